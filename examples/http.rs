@@ -0,0 +1,178 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! A tiny HTTP service that renders QR codes
+//!
+//! Run with `cargo run --example http --features png,embedded-io` and open
+//! `http://localhost:8080/qr?text=Hello&format=svg`. The formats are `svg`, `png` and `txt`.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::num::NonZeroUsize;
+use tiny_qr::render::{HalfBlocks, Renderer, SvgOptions};
+use tiny_qr::{QrCodeBuilder, QrCodeMax};
+
+fn main() -> std::io::Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:8080")?;
+    println!(
+        "Listening on http://{}/qr?text=Hello",
+        listener.local_addr()?
+    );
+    for stream in listener.incoming() {
+        if let Err(error) = handle(stream?) {
+            eprintln!("Request failed: {}", error);
+        }
+    }
+    Ok(())
+}
+
+fn handle(mut stream: TcpStream) -> std::io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+
+    let target = match request_line.strip_prefix("GET ") {
+        Some(rest) => rest.split(' ').next().unwrap_or_default(),
+        None => {
+            return respond(
+                &mut stream,
+                "405 Method Not Allowed",
+                "text/plain",
+                b"GET only",
+            )
+        }
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    if path != "/qr" {
+        return respond(&mut stream, "404 Not Found", "text/plain", b"Not found");
+    }
+
+    let mut text = String::new();
+    let mut format = String::from("svg");
+    for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+        match key {
+            "text" => text = percent_decode(value),
+            "format" => format = percent_decode(value),
+            _ => {}
+        }
+    }
+
+    let qr_code = match QrCodeBuilder::new().with_text(&text).try_build() {
+        Ok(qr_code) => qr_code,
+        Err(error) => {
+            return respond(
+                &mut stream,
+                "422 Unprocessable Entity",
+                "text/plain",
+                error.to_string().as_bytes(),
+            )
+        }
+    };
+    match format.as_str() {
+        "svg" => stream_text(
+            &mut stream,
+            "image/svg+xml",
+            &qr_code,
+            &SvgOptions::new().with_module_size(NonZeroUsize::new(8).unwrap()),
+        ),
+        "png" => respond(
+            &mut stream,
+            "200 OK",
+            "image/png",
+            &qr_code.to_png(NonZeroUsize::new(8).unwrap(), 4),
+        ),
+        "txt" => stream_text(
+            &mut stream,
+            "text/plain; charset=utf-8",
+            &qr_code,
+            &HalfBlocks,
+        ),
+        _ => respond(
+            &mut stream,
+            "415 Unsupported Media Type",
+            "text/plain",
+            b"The format must be svg, png or txt",
+        ),
+    }
+}
+
+/// Streams the rendered text into the connection, the closed connection ends the body
+fn stream_text<R: Renderer>(
+    stream: &mut TcpStream,
+    content_type: &str,
+    qr_code: &QrCodeMax,
+    renderer: &R,
+) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nConnection: close\r\n\r\n",
+        content_type
+    )?;
+    qr_code
+        .render_io(renderer, &mut IoStream(stream))
+        .map_err(|error| std::io::Error::other(format!("{:?}", error)))
+}
+
+/// Passes the `embedded_io` writes of `render_io` to a standard stream
+struct IoStream<'a>(&'a mut TcpStream);
+
+impl embedded_io::ErrorType for IoStream<'_> {
+    type Error = embedded_io::ErrorKind;
+}
+
+impl embedded_io::Write for IoStream<'_> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.0.write(buf).map_err(|_| embedded_io::ErrorKind::Other)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.0.flush().map_err(|_| embedded_io::ErrorKind::Other)
+    }
+}
+
+fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body)
+}
+
+/// Decodes `+` and `%XX` escapes of a query string value
+fn percent_decode(value: &str) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut input = value.bytes();
+    while let Some(byte) = input.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [input.next().unwrap_or(b'0'), input.next().unwrap_or(b'0')];
+                let hex = std::str::from_utf8(&hex).unwrap_or("00");
+                bytes.push(u8::from_str_radix(hex, 16).unwrap_or(b'?'));
+            }
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
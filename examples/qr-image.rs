@@ -15,9 +15,9 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-use bmp::{px, Image, Pixel};
 use std::env;
-use tiny_qr::{Color, QrCodeBuilder};
+use std::num::NonZeroUsize;
+use tiny_qr::QrCodeBuilder;
 
 fn main() {
     let args: Vec<String> = env::args().skip(1).collect();
@@ -25,22 +25,13 @@ fn main() {
 
     let qr_code = QrCodeBuilder::new().with_text(data.as_str()).build();
 
-    let iter = qr_code.draw_iter();
-
-    let mut img = Image::new(iter.width() as u32, iter.height() as u32);
-    for module in iter {
-        img.set_pixel(
-            module.x as u32,
-            module.y as u32,
-            match module.color {
-                Color::White => px!(255, 255, 255),
-                Color::Black => px!(0, 0, 0),
-            },
-        )
-    }
+    let mut image = Vec::new();
+    qr_code
+        .write_bmp(&mut image, NonZeroUsize::new(8).unwrap(), 4)
+        .unwrap();
 
     let filename = "img.bmp";
-    let result = img.save(filename);
+    let result = std::fs::write(filename, image);
     if let Err(err) = result {
         eprintln!("Unable to write to file: {}", err);
         std::process::exit(1);
@@ -16,14 +16,35 @@
  */
 
 use std::env;
+use tiny_qr::render::{TerminalCapabilities, TerminalRenderer, TerminalStyle};
 use tiny_qr::QrCodeBuilder;
 
 fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    // Sixel graphics can't be detected from the environment, so they are opt-in
+    let sixel = args.first().is_some_and(|arg| arg == "--sixel");
+    if sixel {
+        args.remove(0);
+    }
     let data = args.join(" ");
     println!("QR code for {}", data);
 
     let qr_code = QrCodeBuilder::new().with_text(data.as_str()).build();
 
-    println!("{}", qr_code);
+    let lang = env::var("LC_ALL")
+        .or_else(|_| env::var("LC_CTYPE"))
+        .or_else(|_| env::var("LANG"))
+        .ok();
+    let term = env::var("TERM").ok();
+    let capabilities = TerminalCapabilities::detect(lang.as_deref(), term.as_deref());
+
+    let style = if sixel {
+        TerminalStyle::Sixel
+    } else {
+        TerminalStyle::Auto
+    };
+    print!(
+        "{}",
+        TerminalRenderer::new(&qr_code, capabilities).with_style(style)
+    );
 }
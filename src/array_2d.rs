@@ -15,6 +15,11 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::vec;
+use core::marker::PhantomData;
 use core::ops::{Index, IndexMut};
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
@@ -36,23 +41,29 @@ impl From<(usize, usize)> for Coordinate {
     }
 }
 
+/// The rows of an [`Array2D`], an array in place or rows on the heap
+pub trait Storage<T, const N: usize>: AsRef<[[T; N]]> + AsMut<[[T; N]]> {}
+
+impl<T, S: AsRef<[[T; N]]> + AsMut<[[T; N]]>, const N: usize> Storage<T, N> for S {}
+
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
-pub struct Array2D<T, const N: usize> {
-    data: [[T; N]; N],
+pub struct Array2D<T, const N: usize, S = [[T; N]; N]> {
+    data: S,
     size: Coordinate,
+    element: PhantomData<T>,
 }
 
-impl<T, const N: usize> Index<Coordinate> for Array2D<T, N> {
+impl<T, const N: usize, S: Storage<T, N>> Index<Coordinate> for Array2D<T, N, S> {
     type Output = T;
 
     fn index(&self, index: Coordinate) -> &Self::Output {
-        &self.data[index.x][index.y]
+        &self.data.as_ref()[index.x][index.y]
     }
 }
 
-impl<T, const N: usize> IndexMut<Coordinate> for Array2D<T, N> {
+impl<T, const N: usize, S: Storage<T, N>> IndexMut<Coordinate> for Array2D<T, N, S> {
     fn index_mut(&mut self, index: Coordinate) -> &mut Self::Output {
-        &mut self.data[index.x][index.y]
+        &mut self.data.as_mut()[index.x][index.y]
     }
 }
 
@@ -64,21 +75,33 @@ where
         Self {
             data: [[T::default(); N]; N],
             size: Coordinate::new(N, N),
+            element: PhantomData,
         }
     }
 }
 
-impl<T, const N: usize> Array2D<T, N> {
-    pub fn size(&self) -> Coordinate {
-        self.size
+#[cfg(feature = "alloc")]
+impl<T, const N: usize> Array2D<T, N, Box<[[T; N]]>>
+where
+    T: Default + Copy,
+{
+    /// Allocates the rows on the heap, so a large array never passes through the stack
+    pub fn new_boxed() -> Self {
+        Self {
+            data: vec![[T::default(); N]; N].into_boxed_slice(),
+            size: Coordinate::new(N, N),
+            element: PhantomData,
+        }
     }
+}
 
-    pub fn rows(&self) -> Rows<'_, T, N> {
-        Rows { data: &self, x: 0 }
+impl<T, const N: usize, S: Storage<T, N>> Array2D<T, N, S> {
+    pub fn size(&self) -> Coordinate {
+        self.size
     }
 
-    pub fn columns(&self) -> Columns<'_, T, N> {
-        Columns { data: &self, y: 0 }
+    pub fn rows(&self) -> Rows<'_, T, N, S> {
+        Rows { data: self, x: 0 }
     }
 
     pub fn set_size(&mut self, size: Coordinate) {
@@ -89,14 +112,14 @@ impl<T, const N: usize> Array2D<T, N> {
 }
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
-pub struct Rows<'a, T, const N: usize> {
-    data: &'a Array2D<T, N>,
+pub struct Rows<'a, T, const N: usize, S> {
+    data: &'a Array2D<T, N, S>,
     x: usize,
 }
 
-impl<'a, T, const N: usize> Iterator for Rows<'a, T, N> {
+impl<'a, T, const N: usize, S: Storage<T, N>> Iterator for Rows<'a, T, N, S> {
     // we will be counting with usize
-    type Item = Row<'a, T, N>;
+    type Item = Row<'a, T, N, S>;
 
     // next() is the only required method
     fn next(&mut self) -> Option<Self::Item> {
@@ -116,13 +139,13 @@ impl<'a, T, const N: usize> Iterator for Rows<'a, T, N> {
 }
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
-pub struct Row<'a, T, const N: usize> {
-    data: &'a Array2D<T, N>,
+pub struct Row<'a, T, const N: usize, S> {
+    data: &'a Array2D<T, N, S>,
     x: usize,
     y: usize,
 }
 
-impl<'a, T, const N: usize> Iterator for Row<'a, T, N> {
+impl<'a, T, const N: usize, S: Storage<T, N>> Iterator for Row<'a, T, N, S> {
     // we will be counting with usize
     type Item = &'a T;
 
@@ -138,54 +161,3 @@ impl<'a, T, const N: usize> Iterator for Row<'a, T, N> {
         }
     }
 }
-
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
-pub struct Columns<'a, T, const N: usize> {
-    data: &'a Array2D<T, N>,
-    y: usize,
-}
-
-impl<'a, T, const N: usize> Iterator for Columns<'a, T, N> {
-    // we will be counting with usize
-    type Item = Column<'a, T, N>;
-
-    // next() is the only required method
-    fn next(&mut self) -> Option<Self::Item> {
-        let size = self.data.size();
-        if self.y < size.y {
-            let result = Column {
-                data: self.data,
-                x: 0,
-                y: self.y,
-            };
-            self.y += 1;
-            Some(result)
-        } else {
-            None
-        }
-    }
-}
-
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
-pub struct Column<'a, T, const N: usize> {
-    data: &'a Array2D<T, N>,
-    x: usize,
-    y: usize,
-}
-
-impl<'a, T, const N: usize> Iterator for Column<'a, T, N> {
-    // we will be counting with usize
-    type Item = &'a T;
-
-    // next() is the only required method
-    fn next(&mut self) -> Option<Self::Item> {
-        let size = self.data.size();
-        if self.x < size.x {
-            let result = &self.data[(self.x, self.y).into()];
-            self.x += 1;
-            Some(result)
-        } else {
-            None
-        }
-    }
-}
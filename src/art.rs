@@ -0,0 +1,336 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Designer QR codes whose modules resemble a target image
+//!
+//! The bits after the terminator are ignored by readers, so they can be chosen freely. Reed-Solomon
+//! codes are linear over GF(2), so every free bit flips a fixed set of bits in the error correction
+//! codewords as well. Gaussian elimination finds the free bits that set as many of the targeted
+//! modules as possible, in order of priority. Optionally a few codewords per block are damaged on
+//! purpose to match even more modules, using up part of the error correction.
+
+use crate::array_2d::{Array2D, Coordinate};
+use crate::blocks::BlockLengthIterator;
+use crate::buffer::Buffer;
+use crate::encoding::{EncodeOptions, EncodedData, EncodingPlan, MaskPolicy};
+use crate::error_correction::{
+    add_error_correction, write_error_correction, ErrorCorrectedData, ErrorCorrectionLevel,
+};
+use crate::logo::misdecode_protection;
+use crate::mask::{mask_condition, Mask};
+use crate::matrix::{Color, Matrix, ModuleKind, PositionIterator};
+use crate::qr_version::Version;
+use crate::qrcode::{QrCode, MAX_MODULE_SIZE};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Bits of all codewords in block order, most significant bit of each codeword first
+type Bits = Vec<u64>;
+
+fn get(bits: &[u64], index: usize) -> bool {
+    bits[index / 64] & (1 << (index % 64)) != 0
+}
+
+fn flip(bits: &mut [u64], index: usize) {
+    bits[index / 64] ^= 1 << (index % 64);
+}
+
+fn xor(bits: &mut [u64], other: &[u64]) {
+    for (word, other) in bits.iter_mut().zip(other) {
+        *word ^= other;
+    }
+}
+
+fn to_bits(codewords: &[u8]) -> Bits {
+    let mut bits = vec![0; (8 * codewords.len()).div_ceil(64)];
+    for (index, &codeword) in codewords.iter().enumerate() {
+        for bit in 0..8 {
+            if codeword & (0x80 >> bit) != 0 {
+                flip(&mut bits, 8 * index + bit);
+            }
+        }
+    }
+    bits
+}
+
+/// A module that holds a codeword bit and the color the image wants there
+struct Target {
+    position: Coordinate,
+    /// The index in [`Bits`], `None` for the remainder bits
+    bit: Option<usize>,
+    color: Color,
+}
+
+pub(crate) fn build_art(
+    options: &EncodeOptions,
+    encoded_data: EncodedData,
+    plan: &EncodingPlan,
+    target: impl Fn(usize, usize) -> Option<Color>,
+    error_budget: usize,
+) -> QrCode<MAX_MODULE_SIZE> {
+    let version = encoded_data.version;
+    let error_correction = encoded_data.error_correction;
+    let data_len = version.data_codeword_count(error_correction);
+    let total_len = version.total_codeword_count();
+    let corrected = add_error_correction(encoded_data);
+    let codewords = to_bits(&corrected.buffer.data()[..total_len]);
+
+    // Every free bit after the terminator, with the bits it flips when set
+    let terminator_len = (8 * data_len - plan.data_bit_len).min(4);
+    let variables: Vec<Bits> = (plan.data_bit_len + terminator_len..8 * data_len)
+        .map(|bit| {
+            let mut unit = vec![0; total_len];
+            unit[bit / 8] = 0x80 >> (bit % 8);
+            let (data, ecc) = unit.split_at_mut(data_len);
+            write_error_correction(version, error_correction, data, ecc);
+            to_bits(&unit)
+        })
+        .collect();
+
+    let targets = targets(version, error_correction, &target);
+    let references = match options.mask_policy {
+        MaskPolicy::Fixed(mask) => mask.reference()..mask.reference() + 1,
+        MaskPolicy::Automatic => 0..8,
+    };
+    // The mask that matches the most modules, the first one on a tie
+    let (mut codewords, reference) = references
+        .map(|reference| {
+            let codewords = solve(&codewords, variables.clone(), &targets, reference);
+            (codewords, reference)
+        })
+        .rev()
+        .max_by_key(|(codewords, reference)| matched(codewords, &targets, *reference))
+        .unwrap();
+
+    damage(
+        &mut codewords,
+        &targets,
+        reference,
+        version,
+        error_correction,
+        error_budget,
+    );
+
+    let mut buffer = Buffer::new();
+    for index in 0..total_len {
+        let codeword = (0..8).fold(0, |codeword, bit| {
+            (codeword << 1) | u8::from(get(&codewords, 8 * index + bit))
+        });
+        buffer.append_bytes(&[codeword]);
+    }
+    let data = ErrorCorrectedData {
+        version,
+        error_correction,
+        buffer,
+    };
+    let masked =
+        Matrix::from_data(Array2D::new(), &data).apply_mask(Mask::ALL[usize::from(reference)]);
+    let mut qr_code = QrCode::from(masked, None);
+    // The remainder bits are ignored, so they simply take the color of the image
+    for target in targets.iter().filter(|target| target.bit.is_none()) {
+        qr_code.data.set(target.position, target.color);
+    }
+    qr_code
+}
+
+/// The targeted data modules, the ones closest to the center first
+fn targets(
+    version: Version,
+    error_correction: ErrorCorrectionLevel,
+    target: &impl Fn(usize, usize) -> Option<Color>,
+) -> Vec<Target> {
+    let total_bit_len = 8 * version.total_codeword_count();
+    let order = placement_order(version, error_correction);
+    let width = version.width();
+    let mut targets: Vec<Target> = PositionIterator::new(Coordinate::new(width, width))
+        .filter(|pos| ModuleKind::of(version, pos.x, pos.y) == ModuleKind::Data)
+        .enumerate()
+        .filter_map(|(index, position)| {
+            let color = target(position.y, position.x)?;
+            let bit = (index < total_bit_len).then(|| 8 * order[index / 8] + index % 8);
+            Some(Target {
+                position,
+                bit,
+                color,
+            })
+        })
+        .collect();
+    let distance = |i: usize| (2 * i + 1).abs_diff(width).pow(2);
+    targets.sort_by_key(|target| distance(target.position.x) + distance(target.position.y));
+    targets
+}
+
+/// The index in block order of each codeword in placement order, following the interleaving
+fn placement_order(version: Version, error_correction: ErrorCorrectionLevel) -> Vec<usize> {
+    let blocks = || BlockLengthIterator::new(version, error_correction);
+    let max_data_len = blocks().map(|block| block.data_len).max().unwrap();
+    let ecc_len = blocks().next().unwrap().ecc_len;
+    let data_positions = (0..max_data_len).flat_map(|offset| {
+        blocks()
+            .filter(move |block| offset < block.data_len)
+            .map(move |block| block.data_pos + offset)
+    });
+    let ecc_positions =
+        (0..ecc_len).flat_map(|offset| blocks().map(move |block| block.ecc_pos + offset));
+    data_positions.chain(ecc_positions).collect()
+}
+
+/// Whether the bit has to be set for the module to get its color under the mask
+fn wanted(target: &Target, reference: u8) -> bool {
+    let dark = target.color == Color::Black;
+    dark != mask_condition(reference)(target.position.x, target.position.y)
+}
+
+/// Sets the free bits so the targets are met in order of priority
+///
+/// Each target takes a variable that flips its bit as pivot and eliminates that bit from the
+/// other variables. Later pivots then leave the earlier targets alone.
+fn solve(codewords: &[u64], mut variables: Vec<Bits>, targets: &[Target], reference: u8) -> Bits {
+    let mut codewords = codewords.to_vec();
+    for target in targets {
+        let bit = match target.bit {
+            Some(bit) => bit,
+            None => continue,
+        };
+        if let Some(index) = variables.iter().position(|variable| get(variable, bit)) {
+            let pivot = variables.swap_remove(index);
+            for variable in variables.iter_mut().filter(|variable| get(variable, bit)) {
+                xor(variable, &pivot);
+            }
+            if get(&codewords, bit) != wanted(target, reference) {
+                xor(&mut codewords, &pivot);
+            }
+        }
+    }
+    codewords
+}
+
+fn matched(codewords: &[u64], targets: &[Target], reference: u8) -> usize {
+    targets
+        .iter()
+        .filter_map(|target| target.bit.map(|bit| (target, bit)))
+        .filter(|&(target, bit)| get(codewords, bit) == wanted(target, reference))
+        .count()
+}
+
+/// Flips the mismatching bits of the codewords with the most mismatches, up to the budget per
+/// block and at most what the block can correct
+fn damage(
+    codewords: &mut Bits,
+    targets: &[Target],
+    reference: u8,
+    version: Version,
+    error_correction: ErrorCorrectionLevel,
+    error_budget: usize,
+) {
+    let total_len = version.total_codeword_count();
+    let mut mismatches = vec![0usize; total_len];
+    for target in targets {
+        if let Some(bit) = target.bit {
+            if get(codewords, bit) != wanted(target, reference) {
+                mismatches[bit / 8] += 1;
+            }
+        }
+    }
+    let protection = misdecode_protection(version, error_correction);
+    for block in BlockLengthIterator::new(version, error_correction) {
+        let budget = error_budget.min((block.ecc_len - protection) / 2);
+        let mut indices: Vec<usize> = (block.data_pos..block.data_pos + block.data_len)
+            .chain(block.ecc_pos..block.ecc_pos + block.ecc_len)
+            .filter(|&index| mismatches[index] > 0)
+            .collect();
+        indices.sort_by_key(|&index| core::cmp::Reverse(mismatches[index]));
+        for &index in indices.iter().take(budget) {
+            for target in targets {
+                if let Some(bit) = target.bit.filter(|bit| bit / 8 == index) {
+                    if get(codewords, bit) != wanted(target, reference) {
+                        flip(codewords, bit);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decoder::decode;
+    use crate::{Color, ErrorCorrectionLevel, Mask, QrCodeBuilder, QrCodeMax};
+
+    /// A dark disc in the center of the 33 modules of version 4
+    fn disc(x: usize, y: usize) -> Option<Color> {
+        let distance = (2 * x).abs_diff(32).pow(2) + (2 * y).abs_diff(32).pow(2);
+        Some(if distance < 14 * 14 {
+            Color::Black
+        } else {
+            Color::White
+        })
+    }
+
+    fn matching(qr_code: &QrCodeMax) -> usize {
+        qr_code
+            .modules()
+            .filter(|&(x, y, color)| {
+                (9..24).contains(&x) && (9..24).contains(&y) && Some(color) == disc(x, y)
+            })
+            .count()
+    }
+
+    #[test]
+    fn decodes_and_resembles_image() {
+        let builder = QrCodeBuilder::new()
+            .with_specific_version(4)
+            .with_specific_error_correction_level(ErrorCorrectionLevel::Low)
+            .with_text("ART");
+        let plain = builder.build();
+        let art = builder.try_build_art(disc, 0).unwrap();
+
+        let decoded = decode(&art).unwrap();
+        assert_eq!(decoded.text(), "ART");
+        assert_eq!(decoded.corrected_codewords(), 0);
+        // Every module in the center of the symbol follows the disc
+        assert_eq!(matching(&art), 15 * 15);
+        assert!(matching(&plain) < 15 * 15 * 2 / 3);
+    }
+
+    #[test]
+    fn error_budget() {
+        let builder = QrCodeBuilder::new()
+            .with_specific_version(4)
+            .with_specific_error_correction_level(ErrorCorrectionLevel::High)
+            .with_text("ART WITH DAMAGE");
+        let clean = builder.try_build_art(disc, 0).unwrap();
+        let damaged = builder.try_build_art(disc, 3).unwrap();
+
+        let decoded = decode(&damaged).unwrap();
+        assert_eq!(decoded.text(), "ART WITH DAMAGE");
+        assert!(decoded.corrected_codewords() > 0);
+        assert!(matching(&damaged) > matching(&clean));
+    }
+
+    #[test]
+    fn fixed_mask() {
+        let mask = Mask::ALL[5];
+        let art = QrCodeBuilder::new()
+            .with_mask_reference(mask)
+            .with_text("ART")
+            .try_build_art(|_, _| Some(Color::White), 0)
+            .unwrap();
+        assert_eq!(art.mask_reference(), mask);
+        assert_eq!(decode(&art).unwrap().text(), "ART");
+    }
+}
@@ -0,0 +1,108 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::array_2d::Coordinate;
+use crate::matrix::Color;
+use crate::qrcode::MAX_MODULE_SIZE;
+use core::ops::Index;
+
+/// The number of bytes that hold the modules of a single column
+const BAND_COUNT: usize = MAX_MODULE_SIZE.div_ceil(8);
+
+/// A two dimensional array of colors, stored as one bit per module
+///
+/// Each byte holds eight vertically adjacent modules of a column, the least significant bit is the
+/// top one.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
+pub struct BitArray2D<const N: usize> {
+    bands: [[u8; N]; BAND_COUNT],
+    size: Coordinate,
+}
+
+impl<const N: usize> BitArray2D<N> {
+    /// Creates an array of `N` by `N` white modules
+    pub fn new() -> Self {
+        assert!(N <= BAND_COUNT * 8);
+        Self {
+            bands: [[0; N]; BAND_COUNT],
+            size: Coordinate::new(N, N),
+        }
+    }
+
+    pub fn size(&self) -> Coordinate {
+        self.size
+    }
+
+    pub fn set_size(&mut self, size: Coordinate) {
+        assert!(size.x <= N);
+        assert!(size.y <= N);
+        self.size = size;
+    }
+
+    pub fn set(&mut self, pos: Coordinate, color: Color) {
+        let byte = &mut self.bands[pos.x / 8][pos.y];
+        let mask = 1 << (pos.x % 8);
+        match color {
+            Color::Black => *byte |= mask,
+            Color::White => *byte &= !mask,
+        }
+    }
+}
+
+impl<const N: usize> Index<Coordinate> for BitArray2D<N> {
+    type Output = Color;
+
+    fn index(&self, pos: Coordinate) -> &Self::Output {
+        if self.bands[pos.x / 8][pos.y] & (1 << (pos.x % 8)) != 0 {
+            &Color::Black
+        } else {
+            &Color::White
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::array_2d::Coordinate;
+    use crate::bit_array_2d::BitArray2D;
+    use crate::matrix::Color;
+    use core::mem::size_of;
+
+    #[test]
+    fn set_and_get() {
+        let mut array = BitArray2D::<33>::new();
+        array.set(Coordinate::new(9, 32), Color::Black);
+        array.set(Coordinate::new(32, 0), Color::Black);
+        assert_eq!(array[Coordinate::new(9, 32)], Color::Black);
+        assert_eq!(array[Coordinate::new(32, 0)], Color::Black);
+        assert_eq!(array[Coordinate::new(8, 32)], Color::White);
+        let positions = (0..33).flat_map(|x| (0..33).map(move |y| Coordinate::new(x, y)));
+        assert_eq!(
+            positions.filter(|&pos| array[pos] == Color::Black).count(),
+            2
+        );
+
+        array.set(Coordinate::new(9, 32), Color::White);
+        assert_eq!(array[Coordinate::new(9, 32)], Color::White);
+    }
+
+    #[test]
+    fn packed_size() {
+        // An array of colors for a version 4 symbol takes 1089 bytes
+        assert!(size_of::<BitArray2D<33>>() < 200);
+    }
+}
@@ -16,21 +16,23 @@
  */
 
 use crate::error_correction::{ErrorCorrectedData, ErrorCorrectionLevel};
-use crate::qr_version::Version;
+use crate::qr_version::{BlockStructure, Version};
 use core::iter::Chain;
 
 pub struct BlockIterator<'a> {
     iter: Chain<BlockDataIterator<'a>, BlockEccIterator<'a>>,
+    remaining: usize,
 }
 
 impl<'a> BlockIterator<'a> {
-    pub fn new(data: &'a ErrorCorrectedData) -> Self {
+    pub fn new<S: AsRef<[u8]> + AsMut<[u8]>>(data: &'a ErrorCorrectedData<S>) -> Self {
         let data_iter =
             BlockDataIterator::new(data.buffer.data(), data.version, data.error_correction);
         let ecc_iter =
             BlockEccIterator::new(data.buffer.data(), data.version, data.error_correction);
         Self {
             iter: data_iter.chain(ecc_iter),
+            remaining: data.version.total_codeword_count(),
         }
     }
 }
@@ -39,10 +41,22 @@ impl<'a> Iterator for BlockIterator<'a> {
     type Item = &'a u8;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next()
+        let next = self.iter.next();
+        if next.is_some() {
+            self.remaining -= 1;
+        } else {
+            assert_eq!(self.remaining, 0, "interleaving ended early");
+        }
+        next
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
     }
 }
 
+impl ExactSizeIterator for BlockIterator<'_> {}
+
 #[derive(Copy, Clone)]
 pub struct BlockDataIterator<'a> {
     data: &'a [u8],
@@ -99,7 +113,7 @@ pub struct BlockEccIterator<'a> {
 impl<'a> BlockEccIterator<'a> {
     pub fn new(data: &'a [u8], version: Version, error_correction: ErrorCorrectionLevel) -> Self {
         Self {
-            data: &data,
+            data,
             blocks: BlockLengthIterator::new(version, error_correction),
             ecc_offset: 0,
         }
@@ -142,17 +156,14 @@ pub struct BlockLength {
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
 pub struct BlockLengthIterator {
-    //TODO: Combine version and error correction
-    version: Version,
-    error_correction: ErrorCorrectionLevel,
+    structure: BlockStructure,
     last: Option<BlockLength>,
 }
 
 impl BlockLengthIterator {
     pub fn new(version: Version, error_correction: ErrorCorrectionLevel) -> BlockLengthIterator {
         BlockLengthIterator {
-            version,
-            error_correction,
+            structure: version.block_structure(error_correction),
             last: None,
         }
     }
@@ -166,36 +177,29 @@ impl Iterator for BlockLengthIterator {
     type Item = BlockLength;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let data_len = self.version.data_codeword_count(self.error_correction);
-        if self.last.is_none() {
-            let (ecc_len, blocks) = self
-                .version
-                .error_correction_codeword_blocks_count(self.error_correction);
-            assert_eq!(ecc_len % blocks, 0);
-
-            self.last = Some(BlockLength {
-                block_number: 0,
-                block_count: blocks,
-                data_pos: 0,
-                data_len: data_len / blocks,
-                ecc_pos: data_len,
-                ecc_len: ecc_len / blocks,
-            });
-            self.last
-        } else {
-            let next = self.last.as_mut().unwrap();
+        let [first_group, second_group] = self.structure.groups;
+        if let Some(next) = self.last.as_mut() {
             next.block_number += 1;
             if next.block_number < next.block_count {
                 next.data_pos += next.data_len;
                 next.ecc_pos += next.ecc_len;
-                // If data_len % blocks != 0, then the first blocks are smaller
-                if next.block_number == data_len % next.block_count {
-                    next.data_len += 1
+                if next.block_number == first_group.count as usize {
+                    next.data_len = second_group.data_len as usize;
                 }
                 self.last
             } else {
                 None
             }
+        } else {
+            self.last = Some(BlockLength {
+                block_number: 0,
+                block_count: self.structure.block_count(),
+                data_pos: 0,
+                data_len: first_group.data_len as usize,
+                ecc_pos: self.structure.data_codeword_count(),
+                ecc_len: self.structure.ecc_len as usize,
+            });
+            self.last
         }
     }
 }
@@ -210,7 +214,7 @@ mod tests {
     #[test]
     fn block_len_iter_5q() {
         let mut iter =
-            BlockLengthIterator::new(Version { version: 5 }, ErrorCorrectionLevel::Quartile);
+            BlockLengthIterator::new(Version::new(5).unwrap(), ErrorCorrectionLevel::Quartile);
         assert_eq!(
             iter.next(),
             Some(BlockLength {
@@ -272,7 +276,7 @@ mod tests {
 
     #[test]
     fn block_iter_5q() {
-        let mut buffer = Buffer::new();
+        let mut buffer = Buffer::<[u8; 134]>::default();
         buffer.append_bytes(&[
             67, 85, 70, 134, 87, 38, 85, 194, 119, 50, 6, 18, 6, 103, 38, 246, 246, 66, 7, 118,
             134, 242, 7, 38, 86, 22, 198, 199, 146, 6, 182, 230, 247, 119, 50, 7, 118, 134, 87, 38,
@@ -286,11 +290,12 @@ mod tests {
 
         let data = ErrorCorrectedData {
             buffer,
-            version: Version { version: 5 },
+            version: Version::new(5).unwrap(),
             error_correction: ErrorCorrectionLevel::Quartile,
         };
 
         let iter = BlockIterator::new(&data);
+        assert_eq!(iter.len(), 134);
 
         assert!(iter.eq([
             67, 246, 182, 70, 85, 246, 230, 247, 70, 66, 247, 118, 134, 7, 119, 86, 87, 118, 50,
@@ -15,14 +15,35 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-pub struct Buffer {
-    data: [u8; 1024],
+use crate::qr_version::Version;
+use crate::qrcode::MAX_VERSION;
+
+/// The default capacity, enough for all codewords of the largest supported version
+pub const BUFFER_CAPACITY: usize = Version {
+    version: MAX_VERSION,
+}
+.total_codeword_count();
+
+/// Bits written after each other, in an array or in memory provided by the caller
+pub struct Buffer<S = [u8; BUFFER_CAPACITY]> {
+    data: S,
     bit_len: usize,
 }
 
-impl Default for Buffer {
+impl<const N: usize> Default for Buffer<[u8; N]> {
+    /// Creates a new empty buffer of any capacity
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::buffer::Buffer;
+    ///     let buffer = Buffer::<[u8; 26]>::default();
+    ///     assert_eq!(buffer.capacity(), 26);
+    ///```
     fn default() -> Self {
-        Self::new()
+        Buffer {
+            data: [0; N],
+            bit_len: 0,
+        }
     }
 }
 
@@ -38,11 +59,36 @@ impl Buffer {
     ///         assert_eq!(buffer.data(), [1,2,3]);
     ///```
     pub fn new() -> Buffer {
+        Self::default()
+    }
+}
+
+impl<'a> Buffer<&'a mut [u8]> {
+    /// Creates a new empty buffer that writes into the slice, which is cleared first
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::buffer::Buffer;
+    ///     let mut scratch = [0xff; 4];
+    ///     let mut buffer = Buffer::from_slice(&mut scratch);
+    ///     buffer.append_number(0b101, 3);
+    ///     assert_eq!(buffer.data(), [0b1010_0000]);
+    ///     assert_eq!(scratch, [0b1010_0000, 0, 0, 0]);
+    ///```
+    pub fn from_slice(slice: &'a mut [u8]) -> Self {
+        slice.fill(0);
         Buffer {
-            data: [0; 1024],
+            data: slice,
             bit_len: 0,
         }
     }
+}
+
+impl<S: AsRef<[u8]> + AsMut<[u8]>> Buffer<S> {
+    /// Returns the number of bytes that fit in the buffer
+    pub fn capacity(&self) -> usize {
+        self.data.as_ref().len()
+    }
 
     /// Return the amount of bytes and bits written to the buffer
     ///
@@ -78,7 +124,7 @@ impl Buffer {
         let (byte_len, bit_len) = self.byte_bit_len();
         if bit {
             let mask = 1 << (7 - bit_len);
-            self.data[byte_len] |= mask;
+            self.data.as_mut()[byte_len] |= mask;
         }
         self.bit_len += 1;
     }
@@ -112,14 +158,10 @@ impl Buffer {
     pub fn append_byte(&mut self, byte: u8) {
         let (byte_len, bit_len) = self.byte_bit_len();
         if bit_len == 0 {
-            self.data[byte_len] = byte;
+            self.data.as_mut()[byte_len] = byte;
             self.bit_len += 8;
         } else {
-            for index in (0..8).rev() {
-                let mask = 1 << index;
-                let bit = byte & mask != 0;
-                self.append_bit(bit);
-            }
+            self.append_number(byte as u32, 8);
         }
     }
 
@@ -133,8 +175,14 @@ impl Buffer {
     ///         assert_eq!(buffer.data(), [1,2,3]);
     ///```
     pub fn append_bytes(&mut self, bytes: &[u8]) {
-        for byte in bytes {
-            self.append_byte(*byte)
+        let (byte_len, bit_len) = self.byte_bit_len();
+        if bit_len == 0 {
+            self.data.as_mut()[byte_len..byte_len + bytes.len()].copy_from_slice(bytes);
+            self.bit_len += bytes.len() * 8;
+        } else {
+            for byte in bytes {
+                self.append_number(*byte as u32, 8)
+            }
         }
     }
 
@@ -150,9 +198,17 @@ impl Buffer {
     ///         assert_eq!(buffer.data(), [0b0011_1111, 0b0010_0000]);
     ///```
     pub fn append_number(&mut self, number: u32, bit_len: usize) {
-        for index in (0..bit_len).rev() {
-            let mask = 1 << index;
-            self.append_bit(number & mask != 0)
+        assert!(bit_len <= 32);
+        let mut remaining = bit_len;
+        while remaining > 0 {
+            let (byte_len, used) = self.byte_bit_len();
+            let free = 8 - used;
+            let count = free.min(remaining);
+            // Take the most significant of the remaining bits and place them after the used bits
+            let bits = (number >> (remaining - count)) & ((1 << count) - 1);
+            self.data.as_mut()[byte_len] |= (bits << (free - count)) as u8;
+            self.bit_len += count;
+            remaining -= count;
         }
     }
 
@@ -168,9 +224,49 @@ impl Buffer {
     pub fn data(&self) -> &[u8] {
         let (byte_len, bit_len) = self.byte_bit_len();
         if bit_len == 0 {
-            &self.data[0..byte_len]
+            &self.data.as_ref()[0..byte_len]
         } else {
-            &self.data[0..byte_len + 1]
+            &self.data.as_ref()[0..byte_len + 1]
+        }
+    }
+
+    /// Extends the buffer with `len` zero bytes, returns the previously written data and the new bytes
+    pub(crate) fn split_append(&mut self, len: usize) -> (&[u8], &mut [u8]) {
+        let (byte_len, bit_len) = self.byte_bit_len();
+        assert_eq!(bit_len, 0, "buffer must be byte aligned");
+        self.bit_len += len * 8;
+        let (written, remaining) = self.data.as_mut().split_at_mut(byte_len);
+        (written, &mut remaining[..len])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::Buffer;
+
+    #[test]
+    fn unaligned_writes_match_bitwise_writes() {
+        for offset in 0..8 {
+            let mut buffer = Buffer::new();
+            let mut expected = Buffer::new();
+            for _ in 0..offset {
+                buffer.append_bit(true);
+                expected.append_bit(true);
+            }
+
+            buffer.append_number(0x2_a5f3, 18);
+            buffer.append_bytes(&[0x81, 0x7e]);
+            buffer.append_number(0x5, 3);
+
+            let bits = [0x2_a5f3 << 14, 0x817e << 16, 0x5 << 29];
+            for (number, bit_len) in bits.iter().zip([18, 16, 3]) {
+                for index in 0..bit_len {
+                    expected.append_bit(number & (1 << (31 - index)) != 0);
+                }
+            }
+
+            assert_eq!(buffer.bit_len(), expected.bit_len());
+            assert_eq!(buffer.data(), expected.data(), "offset {}", offset);
         }
     }
 }
@@ -0,0 +1,611 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Reads the data back from the modules of a symbol, to verify generated QR codes
+//!
+//! There is no image sampling: the input is the grid of modules, either a [`Symbol`] like a
+//! [`QrCode`](crate::QrCode) or a slice of booleans. All QR code versions whose codewords fit the buffer are supported, see
+//! [`decode_modules_sized`] for the larger versions. Micro QR codes are not supported.
+//!
+//! # Example
+//!```
+//!     use tiny_qr::decoder::decode;
+//!     use tiny_qr::QrCodeBuilder;
+//!     let qr_code = QrCodeBuilder::new().with_text("Hello, world!").build();
+//!     let decoded = decode(&qr_code).unwrap();
+//!     assert_eq!(decoded.text(), "Hello, world!");
+//!```
+
+use crate::array_2d::Coordinate;
+use crate::blocks::BlockLengthIterator;
+use crate::buffer::BUFFER_CAPACITY;
+use crate::encoding::EncodingMode;
+use crate::error_correction::{correct_errors, ErrorCorrectionLevel};
+use crate::mask::{mask_condition, Mask};
+use crate::matrix::{read_format, Color, ModuleKind, PositionIterator};
+use crate::qr_version::Version;
+use crate::symbol::Symbol;
+use core::convert::TryFrom;
+use core::fmt::{self, Display, Formatter};
+#[cfg(feature = "kanji")]
+use encoding_rs::{DecoderResult, SHIFT_JIS};
+
+/// The errors that can occur while decoding a symbol
+#[derive(Clone, Copy, Debug, Ord, PartialOrd, Eq, PartialEq)]
+pub enum DecodeError {
+    /// The width isn't that of a supported QR code version, or the grid isn't square
+    InvalidWidth { width: usize },
+    /// Neither copy of the format information could be read
+    UnreadableFormat,
+    /// More codewords are damaged than the error correction can repair
+    TooManyErrors,
+    /// The data contains a mode indicator that the decoder doesn't support
+    UnsupportedMode { mode: u8 },
+    /// The byte data uses a character set that the decoder doesn't support
+    UnsupportedEci { assignment: u32 },
+    /// The data ends in the middle of a segment or contains invalid characters
+    InvalidData,
+    /// The symbol decodes, but not to what was expected, see [`QrCode::verify`](crate::QrCode::verify)
+    Mismatch,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidWidth { width } => write!(f, "invalid width {}", width),
+            DecodeError::UnreadableFormat => write!(f, "unreadable format information"),
+            DecodeError::TooManyErrors => write!(f, "too many errors"),
+            DecodeError::UnsupportedMode { mode } => write!(f, "unsupported mode {:#06b}", mode),
+            DecodeError::UnsupportedEci { assignment } => {
+                write!(f, "unsupported ECI assignment {}", assignment)
+            }
+            DecodeError::InvalidData => write!(f, "invalid data"),
+            DecodeError::Mismatch => write!(f, "decoded symbol doesn't match"),
+        }
+    }
+}
+
+/// The text and symbol properties read from the modules of a symbol of at most `C` codewords
+#[derive(Clone, Copy)]
+pub struct Decoded<const C: usize = BUFFER_CAPACITY> {
+    version: Version,
+    error_correction: ErrorCorrectionLevel,
+    mask: Mask,
+    corrected_codewords: usize,
+    /// A codeword holds at most three bytes of UTF-8 text, in numeric mode
+    text: [[u8; C]; 3],
+    text_len: usize,
+}
+
+impl<const C: usize> Decoded<C> {
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn error_correction(&self) -> ErrorCorrectionLevel {
+        self.error_correction
+    }
+
+    pub fn mask(&self) -> Mask {
+        self.mask
+    }
+
+    /// The number of damaged codewords that the error correction repaired
+    pub fn corrected_codewords(&self) -> usize {
+        self.corrected_codewords
+    }
+
+    /// The text of all segments joined together
+    pub fn text(&self) -> &str {
+        // Only complete characters are pushed
+        core::str::from_utf8(&self.text.as_flattened()[..self.text_len]).unwrap()
+    }
+
+    fn push(&mut self, character: char) -> Result<(), DecodeError> {
+        let len = character.len_utf8();
+        let text = self
+            .text
+            .as_flattened_mut()
+            .get_mut(self.text_len..self.text_len + len)
+            .ok_or(DecodeError::InvalidData)?;
+        character.encode_utf8(text);
+        self.text_len += len;
+        Ok(())
+    }
+}
+
+impl<const C: usize> fmt::Debug for Decoded<C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Decoded")
+            .field("version", &self.version)
+            .field("error_correction", &self.error_correction)
+            .field("mask", &self.mask)
+            .field("corrected_codewords", &self.corrected_codewords)
+            .field("text", &self.text())
+            .finish()
+    }
+}
+
+/// Decodes the modules of the QR code
+pub fn decode(qr_code: &impl Symbol) -> Result<Decoded, DecodeError> {
+    decode_with(qr_code.width(), |row, column| {
+        qr_code.module(column, row) == Color::Black
+    })
+}
+
+/// Decodes a square grid of modules, given row by row with `true` for dark modules
+///
+/// # Example
+///```
+///     use tiny_qr::decoder::decode_modules;
+///     use tiny_qr::{Color, QrCodeBuilder};
+///     let qr_code = QrCodeBuilder::new().with_text("01234567").build();
+///     let modules: Vec<bool> = qr_code.modules().map(|(_, _, color)| color == Color::Black).collect();
+///     let decoded = decode_modules(qr_code.width(), &modules).unwrap();
+///     assert_eq!(decoded.text(), "01234567");
+///```
+pub fn decode_modules(width: usize, modules: &[bool]) -> Result<Decoded, DecodeError> {
+    decode_modules_sized(width, modules)
+}
+
+/// Decodes a square grid of modules like [`decode_modules`], for symbols of at most `C` codewords
+///
+/// The buffers on the stack grow with `C`, version 40 needs 3706 codewords.
+///
+/// # Example
+///```
+///     use tiny_qr::decoder::{decode_modules_sized, Decoded};
+///     use tiny_qr::{Color, QrCodeBuilder};
+///     let qr_code = QrCodeBuilder::new().with_text("01234567").build();
+///     let modules: Vec<bool> = qr_code.modules().map(|(_, _, color)| color == Color::Black).collect();
+///     let decoded: Decoded<3706> = decode_modules_sized(qr_code.width(), &modules).unwrap();
+///     assert_eq!(decoded.text(), "01234567");
+///```
+pub fn decode_modules_sized<const C: usize>(
+    width: usize,
+    modules: &[bool],
+) -> Result<Decoded<C>, DecodeError> {
+    if modules.len() != width * width {
+        return Err(DecodeError::InvalidWidth { width });
+    }
+    decode_with(width, |row, column| modules[row * width + column])
+}
+
+pub(crate) fn decode_with<const C: usize>(
+    width: usize,
+    dark: impl Fn(usize, usize) -> bool,
+) -> Result<Decoded<C>, DecodeError> {
+    let version = version_of_width::<C>(width).ok_or(DecodeError::InvalidWidth { width })?;
+    let size = Coordinate::new(width, width);
+
+    let format = read_format(size, |pos| {
+        if dark(pos.x, pos.y) {
+            Color::Black
+        } else {
+            Color::White
+        }
+    });
+    let (error_correction, mask) = format
+        .decode_first_copy()
+        .or_else(|| format.decode_second_copy())
+        .ok_or(DecodeError::UnreadableFormat)?;
+
+    // Read the codewords in placement order, leaving out the remainder bits
+    let condition = mask_condition(mask.reference());
+    let mut placed = [0; C];
+    let total_bit_len = 8 * version.total_codeword_count();
+    let data_positions = PositionIterator::new(size)
+        .filter(|pos| ModuleKind::of(version, pos.x, pos.y) == ModuleKind::Data)
+        .take(total_bit_len);
+    for (index, pos) in data_positions.enumerate() {
+        if dark(pos.x, pos.y) != condition(pos.x, pos.y) {
+            placed[index / 8] |= 0x80 >> (index % 8);
+        }
+    }
+
+    let mut codewords = [0; C];
+    deinterleave(version, error_correction, &placed, &mut codewords);
+    let total_len = version.total_codeword_count();
+    let corrected_codewords =
+        correct_errors(version, error_correction, &mut codewords[..total_len], &[])
+            .ok_or(DecodeError::TooManyErrors)?;
+
+    let mut decoded = Decoded {
+        version,
+        error_correction,
+        mask,
+        corrected_codewords,
+        text: [[0; C]; 3],
+        text_len: 0,
+    };
+    let data_len = version.data_codeword_count(error_correction);
+    parse_segments(version, &codewords[..data_len], &mut decoded)?;
+    Ok(decoded)
+}
+
+/// The version of a symbol of the width, when its codewords fit a buffer of `C` codewords
+fn version_of_width<const C: usize>(width: usize) -> Option<Version> {
+    let extra = width.checked_sub(17)?;
+    if extra % 4 != 0 || extra == 0 {
+        return None;
+    }
+    let version = Version::new(u8::try_from(extra / 4).ok()?).ok()?;
+    (version.total_codeword_count() <= C).then_some(version)
+}
+
+/// Puts the interleaved codewords back in block order, the inverse of `BlockIterator`
+fn deinterleave(
+    version: Version,
+    error_correction: ErrorCorrectionLevel,
+    placed: &[u8],
+    codewords: &mut [u8],
+) {
+    let blocks = || BlockLengthIterator::new(version, error_correction);
+    let max_data_len = blocks().map(|block| block.data_len).max().unwrap();
+    let data_positions = (0..max_data_len).flat_map(|offset| {
+        blocks()
+            .filter(move |block| offset < block.data_len)
+            .map(move |block| block.data_pos + offset)
+    });
+    let ecc_len = blocks().next().unwrap().ecc_len;
+    let ecc_positions =
+        (0..ecc_len).flat_map(|offset| blocks().map(move |block| block.ecc_pos + offset));
+    for (position, &codeword) in data_positions.chain(ecc_positions).zip(placed) {
+        codewords[position] = codeword;
+    }
+}
+
+/// Reads numbers of up to 32 bits from the data, most significant bit first
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn remaining(&self) -> usize {
+        8 * self.data.len() - self.position
+    }
+
+    fn read(&mut self, bit_len: usize) -> Result<u32, DecodeError> {
+        if bit_len > self.remaining() {
+            return Err(DecodeError::InvalidData);
+        }
+        let mut number = 0;
+        for _ in 0..bit_len {
+            let bit = (self.data[self.position / 8] >> (7 - self.position % 8)) & 1;
+            number = (number << 1) | bit as u32;
+            self.position += 1;
+        }
+        Ok(number)
+    }
+}
+
+const ALPHANUMERIC: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// The ECI assignment numbers that the byte data can be read in
+const ECI_ISO_8859_1: u32 = 3;
+const ECI_UTF8: u32 = 26;
+
+fn parse_segments<const C: usize>(
+    version: Version,
+    data: &[u8],
+    decoded: &mut Decoded<C>,
+) -> Result<(), DecodeError> {
+    let mut reader = BitReader { data, position: 0 };
+    let mut eci = None;
+    // The terminator can be shortened or left out when the data is full
+    while reader.remaining() >= 4 {
+        let mode = reader.read(4)? as u8;
+        let count_bit_len = |mode| version.character_count_indicator_bit_length(mode);
+        match mode {
+            0b0000 => break,
+            0b0001 => {
+                let mut count = reader.read(count_bit_len(EncodingMode::Numeric))? as usize;
+                while count > 0 {
+                    let (digits, bit_len) = match count {
+                        1 => (1, 4),
+                        2 => (2, 7),
+                        _ => (3, 10),
+                    };
+                    let number = reader.read(bit_len)?;
+                    if number >= 10u32.pow(digits) {
+                        return Err(DecodeError::InvalidData);
+                    }
+                    for i in (0..digits).rev() {
+                        let digit = number / 10u32.pow(i) % 10;
+                        decoded.push(char::from(b'0' + digit as u8))?;
+                    }
+                    count -= digits as usize;
+                }
+            }
+            0b0010 => {
+                let mut count = reader.read(count_bit_len(EncodingMode::Alphanumeric))? as usize;
+                let mut push = |value: u32| {
+                    let character = ALPHANUMERIC
+                        .get(value as usize)
+                        .ok_or(DecodeError::InvalidData)?;
+                    decoded.push(char::from(*character))
+                };
+                while count >= 2 {
+                    let pair = reader.read(11)?;
+                    push(pair / 45)?;
+                    push(pair % 45)?;
+                    count -= 2;
+                }
+                if count == 1 {
+                    push(reader.read(6)?)?;
+                }
+            }
+            0b0100 => {
+                let count = reader.read(count_bit_len(EncodingMode::Byte))? as usize;
+                push_bytes(decoded, &mut reader, count, eci)?;
+            }
+            #[cfg(feature = "kanji")]
+            0b1000 => {
+                let count = reader.read(count_bit_len(EncodingMode::Kanji))?;
+                for _ in 0..count {
+                    decoded.push(expand_kanji(reader.read(13)?)?)?;
+                }
+            }
+            0b0111 => eci = Some(read_eci(&mut reader)?),
+            // The structured append header only tells where the symbol is in the sequence
+            0b0011 => {
+                reader.read(16)?;
+            }
+            mode => return Err(DecodeError::UnsupportedMode { mode }),
+        }
+    }
+    Ok(())
+}
+
+fn read_eci(reader: &mut BitReader) -> Result<u32, DecodeError> {
+    let first = reader.read(8)?;
+    if first & 0x80 == 0 {
+        Ok(first)
+    } else if first & 0xc0 == 0x80 {
+        Ok(((first & 0x3f) << 8) | reader.read(8)?)
+    } else if first & 0xe0 == 0xc0 {
+        Ok(((first & 0x1f) << 16) | reader.read(16)?)
+    } else {
+        Err(DecodeError::InvalidData)
+    }
+}
+
+/// Reads byte data in the character set of the ECI
+///
+/// Without an ECI designator the data is ISO 8859-1, like the encoder writes it. UTF-8 written
+/// without a designator for [`EciPolicy::Never`] is therefore read as ISO 8859-1 as well.
+///
+/// [`EciPolicy::Never`]: crate::encoding::EciPolicy::Never
+fn push_bytes<const C: usize>(
+    decoded: &mut Decoded<C>,
+    reader: &mut BitReader,
+    count: usize,
+    eci: Option<u32>,
+) -> Result<(), DecodeError> {
+    match eci {
+        None | Some(ECI_ISO_8859_1) => {
+            for _ in 0..count {
+                decoded.push(char::from(reader.read(8)? as u8))?;
+            }
+            Ok(())
+        }
+        Some(ECI_UTF8) => {
+            // The bytes are the text, so they are read into place and checked afterwards
+            let start = decoded.text_len;
+            let text = decoded
+                .text
+                .as_flattened_mut()
+                .get_mut(start..start + count)
+                .ok_or(DecodeError::InvalidData)?;
+            for byte in text.iter_mut() {
+                *byte = reader.read(8)? as u8;
+            }
+            core::str::from_utf8(text).map_err(|_| DecodeError::InvalidData)?;
+            decoded.text_len += count;
+            Ok(())
+        }
+        Some(assignment) => Err(DecodeError::UnsupportedEci { assignment }),
+    }
+}
+
+/// Converts the 13 bit value of Kanji mode back to a Shift JIS character
+#[cfg(feature = "kanji")]
+fn expand_kanji(value: u32) -> Result<char, DecodeError> {
+    let compact = ((value / 0xc0) << 8) | (value % 0xc0);
+    let shift_jis = if compact < 0x1f00 {
+        compact + 0x8140
+    } else {
+        compact + 0xc140
+    };
+    let mut utf8 = [0; 4];
+    let (result, _, written) = SHIFT_JIS
+        .new_decoder_without_bom_handling()
+        .decode_to_utf8_without_replacement(&(shift_jis as u16).to_be_bytes(), &mut utf8, true);
+    if result != DecoderResult::InputEmpty {
+        return Err(DecodeError::InvalidData);
+    }
+    core::str::from_utf8(&utf8[..written])
+        .ok()
+        .and_then(|text| text.chars().next())
+        .ok_or(DecodeError::InvalidData)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decoder::{decode, decode_modules, DecodeError};
+    use crate::encoding::{EciPolicy, EncodeOptions, EncodingMode, Segment};
+    use crate::error_correction::ErrorCorrectionLevel;
+    use crate::mask::Mask;
+    use crate::matrix::Color;
+    use crate::qr_version::Version;
+    use crate::qrcode::QrCodeBuilder;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn round_trip() {
+        let texts = [
+            "",
+            "01234567",
+            "HELLO WORLD",
+            "Hello, world!",
+            "Grüße",
+            "Ελληνικά",
+            "https://example.com/?q=1234567890",
+        ];
+        for text in texts {
+            for mask in Mask::ALL {
+                let qr_code = QrCodeBuilder::new()
+                    .with_mask_reference(mask)
+                    .with_text(text)
+                    .build();
+                let decoded = decode(&qr_code).unwrap();
+                assert_eq!(decoded.text(), text);
+                assert_eq!(decoded.mask(), mask);
+                assert_eq!(Some(decoded.version()), qr_code.version());
+                assert_eq!(decoded.error_correction(), qr_code.error_correction());
+            }
+        }
+    }
+
+    #[test]
+    fn every_version_and_level() {
+        let levels = [
+            ErrorCorrectionLevel::Low,
+            ErrorCorrectionLevel::Medium,
+            ErrorCorrectionLevel::Quartile,
+            ErrorCorrectionLevel::High,
+        ];
+        for version in 1..=4 {
+            for level in levels {
+                let qr_code = QrCodeBuilder::new()
+                    .with_specific_version(version)
+                    .with_specific_error_correction_level(level)
+                    .with_text("TINY QR 12")
+                    .build();
+                let decoded = decode(&qr_code).unwrap();
+                assert_eq!(decoded.text(), "TINY QR 12");
+                assert_eq!(decoded.version(), Version::new(version).unwrap());
+                assert_eq!(decoded.error_correction(), level);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn large_versions() {
+        use crate::decoder::{decode_modules_sized, Decoded};
+        use crate::dyn_qrcode::BUFFER_CAPACITY;
+
+        let text = "Grüße, 0123456789 ".repeat(10);
+        for version in [10, 27, 40] {
+            let qr_code = QrCodeBuilder::new()
+                .with_specific_version(version)
+                .with_text(&text)
+                .build_dyn();
+            let width = qr_code.width();
+            let modules: Vec<bool> = (0..width)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .map(|(x, y)| qr_code.module(x, y) == Color::Black)
+                .collect();
+            assert_eq!(
+                decode_modules(width, &modules).err(),
+                Some(DecodeError::InvalidWidth { width })
+            );
+            let decoded: Decoded<BUFFER_CAPACITY> = decode_modules_sized(width, &modules).unwrap();
+            assert_eq!(decoded.text(), text);
+            assert_eq!(decoded.version(), Version::new(version).unwrap());
+        }
+    }
+
+    #[test]
+    fn segments_and_eci() {
+        let segments = [
+            Segment::new(EncodingMode::Numeric, b"2024"),
+            Segment::new(EncodingMode::Alphanumeric, b"-ABC"),
+            Segment::new(EncodingMode::Byte, b" ok"),
+        ];
+        let qr_code = QrCodeBuilder::new().with_segments(&segments).build();
+        assert_eq!(decode(&qr_code).unwrap().text(), "2024-ABC ok");
+
+        // Without a designator the bytes are ISO 8859-1, also when they happen to be UTF-8
+        let qr_code = QrCodeBuilder::new().with_text("Ã©").build();
+        assert_eq!(decode(&qr_code).unwrap().text(), "Ã©");
+        assert_eq!(qr_code.verify("Ã©"), Ok(()));
+        let qr_code = QrCodeBuilder::new()
+            .with_options(EncodeOptions::new().with_eci_policy(EciPolicy::Never))
+            .with_text("€5")
+            .build();
+        assert_eq!(decode(&qr_code).unwrap().text(), "â\u{82}¬5");
+        let qr_code = QrCodeBuilder::new().with_text("€5").build();
+        assert_eq!(decode(&qr_code).unwrap().text(), "€5");
+
+        let qr_code = QrCodeBuilder::new().with_utf16_text("A").build();
+        assert_eq!(
+            decode(&qr_code).err(),
+            Some(DecodeError::UnsupportedEci { assignment: 25 })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "kanji")]
+    fn kanji() {
+        let qr_code = QrCodeBuilder::new().with_text("点茗").build();
+        assert_eq!(decode(&qr_code).unwrap().text(), "点茗");
+    }
+
+    #[test]
+    fn modules() {
+        let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+        let mut modules: Vec<bool> = qr_code
+            .modules()
+            .map(|(_, _, color)| color == Color::Black)
+            .collect();
+        assert_eq!(decode_modules(21, &modules).unwrap().text(), "HELLO");
+        assert_eq!(
+            decode_modules(20, &modules).err(),
+            Some(DecodeError::InvalidWidth { width: 20 })
+        );
+
+        // A flipped module damages a codeword, which the error correction repairs
+        modules[20 * 21 + 20] = !modules[20 * 21 + 20];
+        let decoded = decode_modules(21, &modules).unwrap();
+        assert_eq!(decoded.text(), "HELLO");
+        assert_eq!(decoded.corrected_codewords(), 1);
+
+        // The bottom right quarter holds too many codewords
+        for y in 11..21 {
+            for x in 11..21 {
+                modules[y * 21 + x] = !modules[y * 21 + x];
+            }
+        }
+        assert_eq!(
+            decode_modules(21, &modules).err(),
+            Some(DecodeError::TooManyErrors)
+        );
+
+        // Both copies of the format information are cleared
+        let modules: Vec<bool> = qr_code
+            .modules()
+            .map(|(x, y, color)| color == Color::Black && x != 8 && y != 8)
+            .collect();
+        assert_eq!(
+            decode_modules(21, &modules).err(),
+            Some(DecodeError::UnreadableFormat)
+        );
+    }
+}
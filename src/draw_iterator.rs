@@ -16,9 +16,9 @@
  */
 
 use crate::matrix::Color;
-use crate::qrcode::QrCode;
-
-const BORDER_SIZE: usize = 4;
+use crate::qrcode::QUIET_ZONE;
+use crate::symbol::Symbol;
+use core::num::NonZeroUsize;
 
 pub struct CoordinatedColor {
     pub x: usize,
@@ -26,38 +26,185 @@ pub struct CoordinatedColor {
     pub color: Color,
 }
 
-pub struct DrawIterator<'a, const N: usize> {
-    qrcode: &'a QrCode<N>,
+/// The orientation of the symbol in the emitted coordinates, for displays that are mounted
+/// rotated or mirrored
+///
+/// The rotations are clockwise, the mirrored variants flip the symbol left to right before
+/// rotating it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Orientation {
+    Normal,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    Mirror,
+    MirrorRotate90,
+    MirrorRotate180,
+    MirrorRotate270,
+}
+
+pub struct DrawIterator<'a, Q> {
+    qrcode: &'a Q,
     x: usize,
     y: usize,
+    border: usize,
+    orientation: Orientation,
+    inverted: bool,
+    module_width: usize,
+    module_height: usize,
 }
 
-impl<'a, const N: usize> DrawIterator<'a, N> {
-    pub(crate) fn new(qrcode: &'a QrCode<N>) -> Self {
-        DrawIterator { qrcode, x: 0, y: 0 }
+impl<'a, Q: Symbol> DrawIterator<'a, Q> {
+    pub(crate) fn new(qrcode: &'a Q) -> Self {
+        DrawIterator {
+            qrcode,
+            x: 0,
+            y: 0,
+            border: QUIET_ZONE,
+            orientation: Orientation::Normal,
+            inverted: false,
+            module_width: 1,
+            module_height: 1,
+        }
+    }
+
+    /// Draws every module as a rectangle of the given number of pixels
+    ///
+    /// A different width and height compensates for outputs with non-square pixels, like some
+    /// displays and dot-matrix printers.
+    ///
+    /// # Example
+    ///```
+    ///     use core::num::NonZeroUsize;
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+    ///     let iter = qr_code.draw_iter().with_module_size(NonZeroUsize::new(2).unwrap(), NonZeroUsize::new(3).unwrap());
+    ///     assert_eq!((iter.width(), iter.height()), (58, 87));
+    ///```
+    pub fn with_module_size(mut self, width: NonZeroUsize, height: NonZeroUsize) -> Self {
+        self.module_width = width.get();
+        self.module_height = height.get();
+        self
+    }
+
+    /// Draws every module as a square of the given number of pixels
+    ///
+    /// # Example
+    ///```
+    ///     use core::num::NonZeroUsize;
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+    ///     let iter = qr_code.draw_iter().with_scale(NonZeroUsize::new(8).unwrap());
+    ///     assert_eq!((iter.width(), iter.height()), (232, 232));
+    ///```
+    pub fn with_scale(self, pixels: NonZeroUsize) -> Self {
+        self.with_module_size(pixels, pixels)
+    }
+
+    /// Sets the width of the light border around the symbol in modules, the default is the quiet
+    /// zone of four modules
+    ///
+    /// A smaller border only scans reliably when the surrounding area is light as well.
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+    ///     let iter = qr_code.draw_iter().with_border(0);
+    ///     assert_eq!((iter.width(), iter.height()), (21, 21));
+    ///```
+    pub fn with_border(mut self, modules: usize) -> Self {
+        self.border = modules;
+        self
+    }
+
+    /// Emits the modules in the native scan order of a rotated or mirrored display
+    ///
+    /// The module size applies to the emitted coordinates, so it stays the size of the display
+    /// pixels.
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::{Orientation, QrCodeBuilder};
+    ///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+    ///     // A quarter turn moves the bottom-right corner to the bottom-left
+    ///     let corner = qr_code
+    ///         .draw_iter()
+    ///         .with_border(0)
+    ///         .with_orientation(Orientation::Rotate90)
+    ///         .find(|pixel| pixel.x == 0 && pixel.y == 20)
+    ///         .unwrap();
+    ///     assert_eq!(corner.color, qr_code.module(20, 20));
+    ///```
+    pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Swaps the colors, so the symbol is drawn light on a dark background including the border
+    ///
+    /// This suits displays with a black background, like OLED dashboards. Not every scanner reads
+    /// inverted symbols, older scanner apps and many handheld scanners only read dark modules on a
+    /// light background.
+    pub fn with_inverted(mut self, inverted: bool) -> Self {
+        self.inverted = inverted;
+        self
     }
 
     pub fn height(&self) -> usize {
-        let size = self.qrcode.data.size();
-        8 + size.x
+        (2 * self.border + self.qrcode.width()) * self.module_height
     }
 
     pub fn width(&self) -> usize {
-        let size = self.qrcode.data.size();
-        8 + size.y
+        (2 * self.border + self.qrcode.width()) * self.module_width
+    }
+
+    /// Returns the module of the symbol that is shown at the emitted module position
+    fn orient(&self, x: usize, y: usize) -> (usize, usize) {
+        let last = 2 * self.border + self.qrcode.width() - 1;
+        let (x, y, mirrored) = match self.orientation {
+            Orientation::Normal => (x, y, false),
+            Orientation::Rotate90 => (y, last - x, false),
+            Orientation::Rotate180 => (last - x, last - y, false),
+            Orientation::Rotate270 => (last - y, x, false),
+            Orientation::Mirror => (x, y, true),
+            Orientation::MirrorRotate90 => (y, last - x, true),
+            Orientation::MirrorRotate180 => (last - x, last - y, true),
+            Orientation::MirrorRotate270 => (last - y, x, true),
+        };
+        if mirrored {
+            (last - x, y)
+        } else {
+            (x, y)
+        }
     }
 
-    fn is_current_pos_border(&self) -> bool {
-        let data_size = self.qrcode.data.size();
+    fn module_color(&self, module_x: usize, module_y: usize) -> Color {
+        let color = self.symbol_color(module_x, module_y);
+        if self.inverted {
+            color.inverse()
+        } else {
+            color
+        }
+    }
 
-        self.x < 4
-            || self.y < 4
-            || self.x >= data_size.x + BORDER_SIZE
-            || self.y >= data_size.y + BORDER_SIZE
+    fn symbol_color(&self, module_x: usize, module_y: usize) -> Color {
+        let (module_x, module_y) = self.orient(module_x, module_y);
+        let size = self.qrcode.width();
+        if module_x < self.border
+            || module_y < self.border
+            || module_x >= size + self.border
+            || module_y >= size + self.border
+        {
+            Color::White
+        } else {
+            self.qrcode
+                .module(module_x - self.border, module_y - self.border)
+        }
     }
 }
 
-impl<const N: usize> Iterator for DrawIterator<'_, N> {
+impl<Q: Symbol> Iterator for DrawIterator<'_, Q> {
     // we will be counting with usize
     type Item = CoordinatedColor;
 
@@ -65,18 +212,11 @@ impl<const N: usize> Iterator for DrawIterator<'_, N> {
     fn next(&mut self) -> Option<Self::Item> {
         let result = if self.y >= self.height() {
             None
-        } else if self.is_current_pos_border() {
-            Some(CoordinatedColor {
-                x: self.x,
-                y: self.y,
-                color: Color::White,
-            })
         } else {
-            let data_pos = (self.x - BORDER_SIZE, self.y - BORDER_SIZE).into();
             Some(CoordinatedColor {
                 x: self.x,
                 y: self.y,
-                color: self.qrcode.data[data_pos],
+                color: self.module_color(self.x / self.module_width, self.y / self.module_height),
             })
         };
 
@@ -89,3 +229,130 @@ impl<const N: usize> Iterator for DrawIterator<'_, N> {
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::draw_iterator::Orientation;
+    use crate::matrix::Color;
+    use crate::qrcode::QrCodeBuilder;
+    use alloc::vec::Vec;
+    use core::num::NonZeroUsize;
+
+    #[test]
+    fn module_size() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let iter = qr_code
+            .draw_iter()
+            .with_module_size(NonZeroUsize::new(3).unwrap(), NonZeroUsize::new(2).unwrap());
+        assert_eq!(iter.width(), 29 * 3);
+        assert_eq!(iter.height(), 29 * 2);
+
+        let black = |x, y| {
+            qr_code
+                .draw_iter()
+                .with_module_size(NonZeroUsize::new(3).unwrap(), NonZeroUsize::new(2).unwrap())
+                .find(|pixel| pixel.x == x && pixel.y == y)
+                .unwrap()
+                .color
+                == Color::Black
+        };
+        // The top-left corner of the finder pattern is at module (4, 4)
+        assert!(!black(11, 8));
+        assert!(black(12, 8));
+        assert!(black(14, 9));
+        // The top row of the finder pattern is dark, the row below it is light inside
+        assert!(black(15, 9));
+        assert!(!black(15, 10));
+    }
+
+    #[test]
+    fn row_first() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let size = qr_code.data.size();
+        // The symbol isn't symmetric, so a transposed output differs
+        assert!((0..size.x).any(|row| (0..size.y)
+            .any(|col| qr_code.data[(row, col).into()] != qr_code.data[(col, row).into()])));
+        for pixel in qr_code.draw_iter() {
+            if (4..4 + size.y).contains(&pixel.x) && (4..4 + size.x).contains(&pixel.y) {
+                assert!(pixel.color == qr_code.data[(pixel.y - 4, pixel.x - 4).into()]);
+            }
+        }
+    }
+
+    #[test]
+    fn border() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let iter = qr_code.draw_iter().with_border(1);
+        assert_eq!((iter.width(), iter.height()), (23, 23));
+
+        let pixels: Vec<_> = qr_code.draw_iter().with_border(1).collect();
+        assert_eq!(pixels[0].color, Color::White);
+        assert_eq!(pixels[23 + 1].color, Color::Black);
+        assert!(qr_code
+            .draw_iter()
+            .with_border(0)
+            .all(|pixel| pixel.color == qr_code.module(pixel.x, pixel.y)));
+    }
+
+    #[test]
+    fn scale() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let scaled: Vec<_> = qr_code
+            .draw_iter()
+            .with_scale(NonZeroUsize::new(2).unwrap())
+            .collect();
+        assert_eq!(scaled.len(), 58 * 58);
+        for pixel in qr_code.draw_iter() {
+            for (dx, dy) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+                let (x, y) = (2 * pixel.x + dx, 2 * pixel.y + dy);
+                assert_eq!(scaled[y * 58 + x].color, pixel.color);
+            }
+        }
+    }
+
+    #[test]
+    fn orientation() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let pixels = |orientation| -> Vec<Color> {
+            qr_code
+                .draw_iter()
+                .with_border(0)
+                .with_orientation(orientation)
+                .map(|pixel| pixel.color)
+                .collect()
+        };
+        let normal = pixels(Orientation::Normal);
+        let at = |x: usize, y: usize| normal[y * 21 + x];
+
+        for (orientation, source) in [
+            (
+                Orientation::Rotate90,
+                (|x, y| (y, 20 - x)) as fn(usize, usize) -> _,
+            ),
+            (Orientation::Rotate180, |x, y| (20 - x, 20 - y)),
+            (Orientation::Rotate270, |x, y| (20 - y, x)),
+            (Orientation::Mirror, |x, y| (20 - x, y)),
+            (Orientation::MirrorRotate90, |x, y| (20 - y, 20 - x)),
+            (Orientation::MirrorRotate180, |x, y| (x, 20 - y)),
+            (Orientation::MirrorRotate270, |x, y| (y, x)),
+        ] {
+            let oriented = pixels(orientation);
+            for y in 0..21 {
+                for x in 0..21 {
+                    let (source_x, source_y) = source(x, y);
+                    assert_eq!(oriented[y * 21 + x], at(source_x, source_y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn inverted() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let inverted = qr_code.draw_iter().with_inverted(true);
+        assert!(qr_code
+            .draw_iter()
+            .zip(inverted)
+            .all(|(pixel, inverted)| inverted.color == pixel.color.inverse()));
+    }
+}
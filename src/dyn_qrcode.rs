@@ -0,0 +1,371 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! A QR code on the heap, for hosted users
+
+use crate::array_2d::Array2D;
+use crate::decoder::DecodeError;
+use crate::draw_iterator::DrawIterator;
+use crate::encoding::{EncodeOptions, EncodedData};
+use crate::error::QrError;
+use crate::error_correction::ErrorCorrectionLevel;
+use crate::format::FormatInfoCheck;
+use crate::logo::LogoArea;
+use crate::mask::Mask;
+use crate::matrix::{Color, ModuleKind};
+use crate::packed_rows::PackedRows;
+use crate::qr_version::{version_to_size, Version, LAST_VERSION};
+use crate::qrcode::{place_and_mask, QrCode, QUIET_ZONE};
+use crate::render::blocks::write_half_blocks;
+use crate::render::{Blocks, HalfBlocks, Renderer};
+use crate::run_length::RunLengthRows;
+use crate::symbol::{self, Symbol};
+use alloc::boxed::Box;
+use alloc::vec;
+use core::fmt::{Debug, Display, Formatter, Write};
+
+/// The side length of the largest version, the symbol is placed in rows of this size on the heap
+/// before it is stored
+const MODULE_SIZE: usize = version_to_size(LAST_VERSION);
+
+/// The number of codewords of the largest version
+pub(crate) const BUFFER_CAPACITY: usize = Version {
+    version: LAST_VERSION,
+}
+.total_codeword_count();
+
+/// A QR code that stores only the modules of its version on the heap, one bit each
+///
+/// Unlike [`QrCode`] the size isn't part of the type, so any version up to 40 can be built and
+/// symbols of different versions can be stored together. See
+/// [`QrCodeBuilder::try_build_dyn`](crate::QrCodeBuilder::try_build_dyn). The renderers, the draw
+/// iterator and the packed rows work the same as for a [`QrCode`].
+///
+/// # Example
+///```
+///     use tiny_qr::{Color, QrCodeBuilder, Version};
+///     let qr_code = QrCodeBuilder::new().with_text(&"HELLO ".repeat(100)).build_dyn();
+///     assert_eq!(qr_code.version(), Some(Version::new(22).unwrap()));
+///     assert_eq!(qr_code.width(), 105);
+///     assert_eq!(qr_code.module(0, 0), Color::Black);
+///```
+#[derive(Clone, Eq, PartialEq)]
+pub struct DynQrCode {
+    width: usize,
+    version: Option<Version>,
+    error_correction: ErrorCorrectionLevel,
+    mask_reference: Mask,
+    /// The modules row by row, the most significant bit first
+    modules: Box<[u8]>,
+}
+
+impl DynQrCode {
+    pub(crate) fn from_encoded_data<const C: usize>(
+        options: &EncodeOptions,
+        encoded_data: EncodedData<[u8; C]>,
+    ) -> Self {
+        let (masked, _) = place_and_mask(
+            options,
+            encoded_data,
+            Array2D::<_, MODULE_SIZE, _>::new_boxed(),
+        );
+        let data = &masked.matrix.data;
+        Self::from_modules(
+            data.size().y,
+            Some(masked.matrix.version),
+            masked.matrix.error_correction,
+            Mask::ALL[masked.mask_reference as usize],
+            |row, column| data[(row, column).into()].into(),
+        )
+    }
+
+    fn from_modules(
+        width: usize,
+        version: Option<Version>,
+        error_correction: ErrorCorrectionLevel,
+        mask_reference: Mask,
+        color: impl Fn(usize, usize) -> Color,
+    ) -> Self {
+        let mut modules = vec![0; (width * width).div_ceil(8)].into_boxed_slice();
+        for row in 0..width {
+            for column in 0..width {
+                if color(row, column) == Color::Black {
+                    let index = row * width + column;
+                    modules[index / 8] |= 0x80 >> (index % 8);
+                }
+            }
+        }
+        Self {
+            width,
+            version,
+            error_correction,
+            mask_reference,
+            modules,
+        }
+    }
+
+    /// Returns the number of modules per row and column, without the quiet zone
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the version that was selected, `None` for a Micro QR code
+    pub fn version(&self) -> Option<Version> {
+        self.version
+    }
+
+    pub fn error_correction(&self) -> ErrorCorrectionLevel {
+        self.error_correction
+    }
+
+    /// Returns the mask that was applied, in the Micro QR numbering for a Micro QR code
+    pub fn mask_reference(&self) -> Mask {
+        self.mask_reference
+    }
+
+    /// Returns the color of the module at column `x` and row `y`, like [`QrCode::module`]
+    pub fn module(&self, x: usize, y: usize) -> Color {
+        assert!(x < self.width && y < self.width);
+        let index = y * self.width + x;
+        if self.modules[index / 8] & (0x80 >> (index % 8)) != 0 {
+            Color::Black
+        } else {
+            Color::White
+        }
+    }
+
+    /// Returns an iterator over the rows, each row is an iterator over the colors
+    pub fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = Color> + '_> + '_ {
+        (0..self.width).map(move |y| (0..self.width).map(move |x| self.module(x, y)))
+    }
+
+    /// Returns an iterator over the column, row and color of every module, row by row
+    pub fn modules(&self) -> impl Iterator<Item = (usize, usize, Color)> + '_ {
+        symbol::modules(self)
+    }
+
+    /// Returns the function of the module at column `x` and row `y`, like [`QrCode::module_kind`]
+    pub fn module_kind(&self, x: usize, y: usize) -> ModuleKind {
+        symbol::module_kind(self.width, x, y)
+    }
+
+    pub fn draw_iter(&self) -> DrawIterator<'_, Self> {
+        DrawIterator::new(self)
+    }
+
+    /// Returns the rows packed as bytes, for monochrome display drivers
+    pub fn packed_rows(&self) -> PackedRows<'_, Self> {
+        PackedRows::new(self)
+    }
+
+    /// Returns the rows as spans of the same color, for renderers that fill rectangles
+    pub fn run_length_rows(&self) -> RunLengthRows<'_, Self> {
+        RunLengthRows::new(self)
+    }
+
+    /// Writes the QR code with the given renderer, like [`QrCode::render`]
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::render::SvgOptions;
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     let qr_code = QrCodeBuilder::new().with_text(&"HELLO ".repeat(100)).build_dyn();
+    ///     let mut svg = String::new();
+    ///     qr_code.render(&SvgOptions::new(), &mut svg).unwrap();
+    ///     assert!(svg.contains("viewBox=\"0 0 113 113\""));
+    ///```
+    pub fn render<R: Renderer>(&self, renderer: &R, out: &mut impl Write) -> core::fmt::Result {
+        renderer.render(self, out)
+    }
+
+    /// Reads both copies of the format information, useful for checking the symbol
+    pub fn format_info(&self) -> FormatInfoCheck {
+        symbol::format_info(self)
+    }
+
+    /// Decodes the symbol and checks that it holds the text, like [`QrCode::verify`]
+    ///
+    /// The decoder keeps buffers for the codewords of version 40 on the stack, about 18 KiB.
+    pub fn verify(&self, text: &str) -> Result<(), DecodeError> {
+        symbol::verify::<BUFFER_CAPACITY>(self, text)
+    }
+
+    /// Plans a centered logo covering the percentage of the symbol, like [`QrCode::logo_area`]
+    pub fn logo_area(&self, percent: u8) -> Result<LogoArea, QrError> {
+        symbol::logo_area(self, percent)
+    }
+}
+
+impl Symbol for DynQrCode {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn module(&self, x: usize, y: usize) -> Color {
+        DynQrCode::module(self, x, y)
+    }
+
+    fn version(&self) -> Option<Version> {
+        self.version
+    }
+
+    fn error_correction(&self) -> ErrorCorrectionLevel {
+        self.error_correction
+    }
+
+    fn mask_reference(&self) -> Mask {
+        self.mask_reference
+    }
+}
+
+impl<const N: usize> From<QrCode<N>> for DynQrCode {
+    fn from(qr_code: QrCode<N>) -> Self {
+        Self::from(&qr_code)
+    }
+}
+
+impl<const N: usize> From<&QrCode<N>> for DynQrCode {
+    fn from(qr_code: &QrCode<N>) -> Self {
+        Self::from_modules(
+            qr_code.width(),
+            qr_code.version(),
+            qr_code.error_correction(),
+            qr_code.mask_reference(),
+            |row, column| qr_code.module(column, row),
+        )
+    }
+}
+
+impl Debug for DynQrCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.render(&Blocks, f)
+    }
+}
+
+/// Prints the QR code as half blocks, like the `Display` output of [`QrCode`]
+impl Display for DynQrCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        if f.alternate() {
+            write_half_blocks(self, f, QUIET_ZONE)
+        } else {
+            self.render(&HalfBlocks, f)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decoder::{decode_modules_sized, DecodeError, Decoded};
+    use crate::dyn_qrcode::{DynQrCode, BUFFER_CAPACITY};
+    use crate::error::QrError;
+    use crate::error_correction::ErrorCorrectionLevel;
+    use crate::matrix::{Color, ModuleKind};
+    use crate::qr_version::Version;
+    use crate::qrcode::QrCodeBuilder;
+    use crate::render::HalfBlocks;
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use core::mem::size_of_val;
+
+    #[test]
+    fn same_as_fixed() {
+        let qr_code = QrCodeBuilder::new().with_text("01234567").build();
+        let dyn_qr_code = DynQrCode::from(&qr_code);
+        assert_eq!(dyn_qr_code.width(), 21);
+        assert_eq!(dyn_qr_code.rows().count(), 21);
+        assert_eq!(format!("{:?}", dyn_qr_code), format!("{:?}", qr_code));
+        assert_eq!(format!("{:#}", dyn_qr_code), format!("{:#}", qr_code));
+        assert!(dyn_qr_code
+            .packed_rows()
+            .zip(qr_code.packed_rows())
+            .all(|(dyn_row, row)| dyn_row.eq(row)));
+        assert_eq!(dyn_qr_code.format_info(), qr_code.format_info());
+        assert_eq!(dyn_qr_code.module(7, 0), qr_code.module(7, 0));
+        assert_eq!(dyn_qr_code.module(0, 7), qr_code.module(0, 7));
+
+        let built = QrCodeBuilder::new().with_text("01234567").build_dyn();
+        assert!(built == dyn_qr_code);
+    }
+
+    #[test]
+    fn large_version() {
+        let qr_code = QrCodeBuilder::new()
+            .with_specific_version(40)
+            .with_text("HELLO")
+            .build_dyn();
+        assert_eq!(qr_code.version(), Version::new(40).ok());
+        assert_eq!(qr_code.width(), 177);
+        // One bit per module
+        assert_eq!(size_of_val(&*qr_code.modules), 177 * 177 / 8 + 1);
+
+        let result = QrCodeBuilder::new()
+            .with_max_version(40)
+            .with_text(&"1".repeat(7090))
+            .try_build_dyn();
+        assert_eq!(result.err(), Some(QrError::DataTooLong));
+    }
+
+    #[test]
+    fn render_and_verify() {
+        let text = "HELLO ".repeat(100);
+        let qr_code = QrCodeBuilder::new().with_text(&text).build_dyn();
+        assert_eq!(qr_code.width(), 105);
+        assert_eq!(qr_code.verify(&text), Ok(()));
+        assert_eq!(qr_code.verify("HELLO"), Err(DecodeError::Mismatch));
+        assert_eq!(qr_code.module_kind(6, 10), ModuleKind::Timing);
+
+        let mut output = String::new();
+        qr_code.render(&HalfBlocks, &mut output).unwrap();
+        assert_eq!(output, format!("{}", qr_code));
+        assert_eq!(output.lines().count(), 53);
+
+        let dark_count = qr_code
+            .modules()
+            .filter(|&(_, _, color)| color == Color::Black)
+            .count();
+        let dark_pixels = qr_code
+            .draw_iter()
+            .filter(|pixel| pixel.color == Color::Black)
+            .count();
+        assert_eq!(dark_pixels, dark_count);
+        let rows: Vec<Vec<u8>> = qr_code.packed_rows().map(|row| row.collect()).collect();
+        assert_eq!((rows.len(), rows[0].len()), (105, 14));
+        assert_eq!(rows[0][0], 0b1111_1110);
+    }
+
+    #[test]
+    fn covered_logo_decodes() {
+        // Larger than a `QrCode`, from version 7 on an alignment pattern is in the center
+        let qr_code = QrCodeBuilder::new()
+            .with_specific_version(6)
+            .with_specific_error_correction_level(ErrorCorrectionLevel::High)
+            .with_text("LOGO")
+            .build_dyn();
+        let area = qr_code.logo_area(10).unwrap();
+        let inside = |i: usize| (area.start..area.start + area.size).contains(&i);
+        // Cover the area with the inverse of the modules, the worst case for the decoder
+        let modules: Vec<bool> = qr_code
+            .modules()
+            .map(|(x, y, color)| (color == Color::Black) != (inside(x) && inside(y)))
+            .collect();
+        let decoded: Decoded<BUFFER_CAPACITY> =
+            decode_modules_sized(qr_code.width(), &modules).unwrap();
+        assert_eq!(decoded.text(), "LOGO");
+        assert!(decoded.corrected_codewords() > 0);
+    }
+}
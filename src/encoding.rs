@@ -15,9 +15,31 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-use crate::buffer::Buffer;
+use crate::buffer::{Buffer, BUFFER_CAPACITY};
+use crate::error::QrError;
 use crate::error_correction::ErrorCorrectionLevel;
-use crate::qr_version::Version;
+use crate::mask::{Mask, ScoringModel};
+use crate::qr_version::{version_to_size, Version, LAST_VERSION};
+use crate::qrcode::QUIET_ZONE;
+#[cfg(any(
+    feature = "kanji",
+    feature = "shift-jis",
+    feature = "windows-1252",
+    feature = "iso-8859-15"
+))]
+use encoding_rs::EncoderResult;
+#[cfg(any(
+    feature = "shift-jis",
+    feature = "windows-1252",
+    feature = "iso-8859-15"
+))]
+use encoding_rs::Encoding;
+#[cfg(feature = "iso-8859-15")]
+use encoding_rs::ISO_8859_15;
+#[cfg(any(feature = "kanji", feature = "shift-jis"))]
+use encoding_rs::SHIFT_JIS;
+#[cfg(feature = "windows-1252")]
+use encoding_rs::WINDOWS_1252;
 
 #[derive(Clone, Copy, Debug, Ord, PartialOrd, Eq, PartialEq)]
 pub enum VersionRestriction {
@@ -51,57 +73,769 @@ impl ErrorCorrectionRestriction {
     }
 }
 
-fn calculate_encoded_data_bit_length(
-    data_len: usize,
+/// Controls whether an ECI designator is written for text that needs it
+#[derive(Clone, Copy, Debug, Ord, PartialOrd, Eq, PartialEq)]
+pub enum EciPolicy {
+    /// Write the UTF-8 ECI designator when the text can't be represented in ISO 8859-1
+    Automatic,
+    /// Never write an ECI designator; UTF-8 text is written in byte mode as-is
+    Never,
+}
+
+/// Controls which data mask is applied to the symbol
+#[derive(Clone, Copy, Debug, Ord, PartialOrd, Eq, PartialEq)]
+pub enum MaskPolicy {
+    /// Select the mask with the lowest penalty score
+    Automatic,
+    /// Always use the given mask
+    Fixed(Mask),
+}
+
+/// Options for encoding data into a QR code
+///
+/// # Example
+///```
+///     use tiny_qr::encoding::{EncodeOptions, CharacterSet};
+///     use tiny_qr::ErrorCorrectionLevel;
+///     let options = EncodeOptions::new()
+///         .with_max_version(2)
+///         .with_min_error_correction_level(ErrorCorrectionLevel::Quartile)
+///         .with_character_set(CharacterSet::Alphanumeric);
+///```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EncodeOptions {
+    pub(crate) version_restriction: VersionRestriction,
+    pub(crate) error_correction_restriction: ErrorCorrectionRestriction,
+    pub(crate) character_set: Option<CharacterSet>,
+    pub(crate) eci_policy: EciPolicy,
+    pub(crate) mask_policy: MaskPolicy,
+    pub(crate) scoring_model: ScoringModel,
+    pub(crate) optimal_segmentation: bool,
+    pub(crate) error_correction_boost: bool,
+    pub(crate) uppercase_normalization: bool,
+    pub(crate) eci: Option<u32>,
+    pub(crate) structured_append: Option<StructuredAppend>,
+    /// Set by `with_print_area` for a module size of zero
+    pub(crate) zero_module_size: bool,
+    #[cfg(feature = "kanji")]
+    pub(crate) kanji_detection: bool,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EncodeOptions {
+    pub fn new() -> Self {
+        Self {
+            version_restriction: VersionRestriction::MaxVersion(Version {
+                version: LAST_VERSION,
+            }),
+            error_correction_restriction: ErrorCorrectionRestriction::MinErrorCorrection(
+                ErrorCorrectionLevel::Medium,
+            ),
+            character_set: None,
+            eci_policy: EciPolicy::Automatic,
+            mask_policy: MaskPolicy::Automatic,
+            scoring_model: ScoringModel::Iso,
+            optimal_segmentation: false,
+            error_correction_boost: true,
+            uppercase_normalization: false,
+            eci: None,
+            structured_append: None,
+            zero_module_size: false,
+            #[cfg(feature = "kanji")]
+            kanji_detection: true,
+        }
+    }
+
+    /// Limits the version, the default is the largest version that fits the output
+    ///
+    /// A [`QrCode`] holds up to version 4, a higher maximum is lowered to that. Use a
+    /// `DynQrCode` for the larger versions.
+    ///
+    /// [`QrCode`]: crate::QrCode
+    pub fn with_max_version(mut self, max_version: u8) -> Self {
+        self.version_restriction = VersionRestriction::MaxVersion(Version {
+            version: max_version,
+        });
+        self
+    }
+
+    pub fn with_specific_version(mut self, version: u8) -> Self {
+        self.version_restriction = VersionRestriction::SpecificVersion(Version { version });
+        self
+    }
+
+    /// Limits the version to the largest symbol that fits in a square print area
+    ///
+    /// The area and the module size are given in the same unit, for example micrometres or
+    /// printer dots. The quiet zone of 4 modules on each side is part of the area. Encoding
+    /// fails with [`QrError::DataTooLong`] when the data doesn't fit in that version, or with
+    /// [`QrError::InvalidField`] when the module size is zero.
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::encoding::EncodeOptions;
+    ///     // A 15 mm label with modules of 0.5 mm fits 30 modules, so version 1 with its quiet zone
+    ///     let options = EncodeOptions::new().with_print_area(15_000, 500);
+    ///```
+    pub fn with_print_area(mut self, area: u32, module_size: u32) -> Self {
+        self.zero_module_size = module_size == 0;
+        let modules = area.checked_div(module_size).unwrap_or(0) as usize;
+        let version = modules
+            .checked_sub(2 * QUIET_ZONE + version_to_size(0))
+            .map_or(0, |extra| (extra / 4).min(LAST_VERSION as usize));
+        self.with_max_version(version as u8)
+    }
+
+    pub fn with_min_error_correction_level(
+        mut self,
+        min_error_correction_level: ErrorCorrectionLevel,
+    ) -> Self {
+        self.error_correction_restriction =
+            ErrorCorrectionRestriction::MinErrorCorrection(min_error_correction_level);
+        self
+    }
+
+    pub fn with_specific_error_correction_level(
+        mut self,
+        error_correction_level: ErrorCorrectionLevel,
+    ) -> Self {
+        self.error_correction_restriction =
+            ErrorCorrectionRestriction::SpecificErrorCorrection(error_correction_level);
+        self
+    }
+
+    /// Whether the error correction level is raised above the minimum while the data still fits,
+    /// the default
+    ///
+    /// Disable this to keep exactly the minimum level, for example to keep reference images
+    /// stable when the data changes.
+    pub fn with_error_correction_boost(mut self, error_correction_boost: bool) -> Self {
+        self.error_correction_boost = error_correction_boost;
+        self
+    }
+
+    /// Whether lowercase ASCII letters are uppercased when that allows alphanumeric mode, disabled
+    /// by default
+    ///
+    /// Alphanumeric mode only has uppercase letters but needs far fewer bits than byte mode. Enable
+    /// this when the case of the text doesn't matter, for example for URLs. Text with other
+    /// characters outside alphanumeric mode is encoded unchanged.
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::encoding::{encode_text, EncodeOptions};
+    ///     let options = EncodeOptions::new().with_uppercase_normalization(true);
+    ///     let encoded = encode_text(&options, "hello world").unwrap();
+    ///```
+    pub fn with_uppercase_normalization(mut self, uppercase_normalization: bool) -> Self {
+        self.uppercase_normalization = uppercase_normalization;
+        self
+    }
+
+    /// Use the given character set instead of detecting it from the text
+    pub fn with_character_set(mut self, character_set: CharacterSet) -> Self {
+        self.character_set = Some(character_set);
+        self
+    }
+
+    pub fn with_eci_policy(mut self, eci_policy: EciPolicy) -> Self {
+        self.eci_policy = eci_policy;
+        self
+    }
+
+    pub fn with_mask_policy(mut self, mask_policy: MaskPolicy) -> Self {
+        self.mask_policy = mask_policy;
+        self
+    }
+
+    /// Select the rules used to find the best mask when the mask policy is automatic
+    pub fn with_scoring_model(mut self, scoring_model: ScoringModel) -> Self {
+        self.scoring_model = scoring_model;
+        self
+    }
+
+    /// Splits ASCII text into numeric, alphanumeric and byte segments, see [`OptimalSegments`]
+    ///
+    /// This is ignored when a character set is selected.
+    pub fn with_optimal_segmentation(mut self, optimal_segmentation: bool) -> Self {
+        self.optimal_segmentation = optimal_segmentation;
+        self
+    }
+
+    /// Checks the options that can't be checked while setting them, for a symbol of at most the
+    /// version limit
+    ///
+    /// A maximum version above the limit is allowed, since encoding lowers it to the limit.
+    pub(crate) fn validate(&self, limit: Version) -> Result<(), QrError> {
+        if self.zero_module_size {
+            return Err(QrError::InvalidField {
+                field: "module size",
+            });
+        }
+        let version = self.version_restriction.to_version().version;
+        if self.version_restriction == VersionRestriction::MaxVersion(Version { version: 0 }) {
+            // Nothing fits when not even version 1 is allowed, see `with_print_area`
+            return Err(QrError::DataTooLong);
+        }
+        let supported = match self.version_restriction {
+            VersionRestriction::MaxVersion(_) => 1..=LAST_VERSION,
+            VersionRestriction::SpecificVersion(_) => 1..=limit.version,
+        };
+        if !supported.contains(&version) {
+            return Err(QrError::UnsupportedVersion { version });
+        }
+        if let Some(StructuredAppend {
+            position, total, ..
+        }) = self.structured_append
+        {
+            if position >= total || total > 16 {
+                return Err(QrError::InvalidField {
+                    field: "structured append",
+                });
+            }
+        }
+        match self.eci {
+            Some(assignment) if assignment > MAX_ECI_ASSIGNMENT => {
+                Err(QrError::UnsupportedEci { assignment })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Writes an ECI designator with the assignment number before bytes and segments
+    ///
+    /// This tells the reader which character set the byte data uses, for example 3 for
+    /// ISO 8859-1 or 20 for Shift JIS. Text is not affected, see [`EciPolicy`] instead. Encoding
+    /// fails with [`QrError::UnsupportedEci`] when the number is above 999999.
+    pub fn with_eci(mut self, assignment: u32) -> Self {
+        self.eci = Some(assignment);
+        self
+    }
+
+    /// Marks the symbol as one of a sequence, the reader joins the data of all symbols
+    ///
+    /// The position starts at 0 and there can be at most 16 symbols. The parity is the XOR of
+    /// all bytes of the complete data. Only [`crate::encode_split`] sets it. Encoding fails with
+    /// [`QrError::InvalidField`] for a position or total outside that range.
+    pub(crate) fn with_structured_append(mut self, position: u8, total: u8, parity: u8) -> Self {
+        self.structured_append = Some(StructuredAppend {
+            position,
+            total,
+            parity,
+        });
+        self
+    }
+
+    /// Whether text of only Shift JIS double-byte characters is detected as Kanji, the default
+    ///
+    /// When disabled such text is encoded as UTF-8 in byte mode, for readers without Kanji support.
+    #[cfg(feature = "kanji")]
+    pub fn with_kanji_detection(mut self, kanji_detection: bool) -> Self {
+        self.kanji_detection = kanji_detection;
+        self
+    }
+}
+
+/// The number of bits of the structured append mode indicator, position, total and parity
+pub(crate) const STRUCTURED_APPEND_BIT_LEN: usize = 4 + 4 + 4 + 8;
+
+/// The header of a symbol that is part of a sequence
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct StructuredAppend {
+    position: u8,
+    total: u8,
+    parity: u8,
+}
+
+impl StructuredAppend {
+    fn append<S: AsRef<[u8]> + AsMut<[u8]>>(&self, buffer: &mut Buffer<S>) {
+        buffer.append_bits(&[false, false, true, true]);
+        buffer.append_number(self.position as u32, 4);
+        buffer.append_number(self.total as u32 - 1, 4);
+        buffer.append_byte(self.parity);
+    }
+}
+
+/// The largest assignment number that fits in an ECI designator
+const MAX_ECI_ASSIGNMENT: u32 = 999_999;
+
+/// The ECI assignment number of UTF-8
+const ECI_UTF8: u32 = 26;
+
+/// The ECI assignment numbers of UTF-16 in big and little endian byte order
+const ECI_UTF16_BE: u32 = 25;
+const ECI_UTF16_LE: u32 = 33;
+
+/// The ECI assignment number of Shift JIS
+#[cfg(feature = "shift-jis")]
+const ECI_SHIFT_JIS: u32 = 20;
+
+/// The ECI assignment number of ISO 8859-15
+#[cfg(feature = "iso-8859-15")]
+const ECI_ISO_8859_15: u32 = 17;
+
+/// The ECI assignment number of Windows-1252
+#[cfg(feature = "windows-1252")]
+const ECI_WINDOWS_1252: u32 = 21;
+
+/// Returns the number of bits of the ECI mode indicator and designator
+fn eci_bit_len(assignment: u32) -> usize {
+    match assignment {
+        0..=127 => 4 + 8,
+        128..=16383 => 4 + 16,
+        _ => 4 + 24,
+    }
+}
+
+/// Writes the ECI mode indicator and designator
+fn append_eci<S: AsRef<[u8]> + AsMut<[u8]>>(buffer: &mut Buffer<S>, assignment: u32) {
+    buffer.append_bits(&[false, true, true, true]);
+    match assignment {
+        0..=127 => buffer.append_number(assignment, 8),
+        128..=16383 => buffer.append_number(0b10 << 14 | assignment, 16),
+        _ => buffer.append_number(0b110 << 21 | assignment, 24),
+    }
+}
+
+/// Calculates the number of bits a segment occupies when encoded in the given mode
+///
+/// This includes the mode indicator and character count indicator, but excludes the terminator.
+/// In numeric and alphanumeric mode every byte of data is a single character. In Kanji mode the
+/// data is Shift JIS, with two bytes per character.
+///
+/// # Example
+///```
+///     use tiny_qr::encoding::{encoded_bit_length, EncodingMode};
+///     use tiny_qr::Version;
+///     assert_eq!(encoded_bit_length("01234567", EncodingMode::Numeric, Version::new(1).unwrap()), 41);
+///     assert_eq!(encoded_bit_length(&[0u8, 1, 2], EncodingMode::Byte, Version::new(1).unwrap()), 36);
+///```
+pub fn encoded_bit_length<T: AsRef<[u8]> + ?Sized>(
+    data: &T,
+    mode: EncodingMode,
     version: Version,
-    character_set: CharacterSet,
 ) -> usize {
+    segment_bit_length(data.as_ref().len(), mode, version)
+}
+
+/// The number of bits of a segment with data of the given length in bytes
+fn segment_bit_length(data_len: usize, mode: EncodingMode, version: Version) -> usize {
     let mode_bits = 4;
-    let char_count_len =
-        version.character_count_indicator_bit_length(character_set.to_encoding_mode());
-
-    match character_set {
-        CharacterSet::Numeric => {
-            mode_bits
-                + char_count_len
-                + 10 * (data_len / 3)
+    let char_count_len = version.character_count_indicator_bit_length(mode);
+
+    let data_bits = match mode {
+        EncodingMode::Numeric => {
+            10 * (data_len / 3)
                 + match data_len % 3 {
                     0 => 0,
                     1 => 4,
                     2 => 7,
-                    _ => panic!(),
+                    _ => unreachable!(),
+                }
+        }
+        EncodingMode::Alphanumeric => 11 * (data_len / 2) + 6 * (data_len % 2),
+        EncodingMode::Byte => 8 * data_len,
+        EncodingMode::Kanji => 13 * (data_len / 2),
+    };
+    mode_bits + char_count_len + data_bits
+}
+
+/// The maximum number of characters that fit in the version and error correction level when
+/// encoded in a single segment of the mode
+///
+/// Byte mode counts bytes and Kanji mode counts double-byte characters. Headers such as an ECI
+/// designator or structured append reduce the capacity.
+///
+/// # Example
+///```
+///     use tiny_qr::encoding::{capacity, EncodingMode};
+///     use tiny_qr::{ErrorCorrectionLevel, Version};
+///     let version = Version::new(1).unwrap();
+///     assert_eq!(capacity(version, ErrorCorrectionLevel::Low, EncodingMode::Numeric), 41);
+///     assert_eq!(capacity(version, ErrorCorrectionLevel::Low, EncodingMode::Byte), 17);
+///```
+pub fn capacity(
+    version: Version,
+    error_correction: ErrorCorrectionLevel,
+    mode: EncodingMode,
+) -> usize {
+    let header_bit_len = encoded_bit_length(b"", mode, version);
+    let available = version
+        .data_codeword_bit_len(error_correction)
+        .saturating_sub(header_bit_len);
+    let characters = match mode {
+        EncodingMode::Numeric => {
+            3 * (available / 10)
+                + match available % 10 {
+                    0..=3 => 0,
+                    4..=6 => 1,
+                    _ => 2,
                 }
         }
-        CharacterSet::Alphanumeric => {
-            mode_bits + char_count_len + 11 * (data_len / 2) + 6 * (data_len % 2)
+        EncodingMode::Alphanumeric => 2 * (available / 11) + usize::from(available % 11 >= 6),
+        EncodingMode::Byte => available / 8,
+        EncodingMode::Kanji => available / 13,
+    };
+    // The character count indicator limits the length as well
+    let max_count = (1 << version.character_count_indicator_bit_length(mode)) - 1;
+    characters.min(max_count)
+}
+
+pub(crate) fn calculate_encoded_data_bit_length(
+    text: &str,
+    version: Version,
+    character_set: CharacterSet,
+    eci_policy: EciPolicy,
+) -> usize {
+    let segment_bits = match character_set {
+        #[cfg(feature = "kanji")]
+        CharacterSet::Kanji => {
+            4 + version.character_count_indicator_bit_length(EncodingMode::Kanji)
+                + 13 * text.chars().count()
+        }
+        // Each character is a single byte, while non-ASCII characters take two bytes in UTF-8
+        CharacterSet::Iso8859_1 => {
+            segment_bit_length(text.chars().count(), EncodingMode::Byte, version)
         }
-        CharacterSet::Iso8859_1 => mode_bits + char_count_len + 8 * data_len,
-        CharacterSet::Unicode => 4 + 8 + mode_bits + char_count_len + 8 * data_len,
+        _ => encoded_bit_length(text, character_set.to_encoding_mode(), version),
+    };
+    match (character_set, eci_policy) {
+        (CharacterSet::Unicode, EciPolicy::Automatic) => eci_bit_len(ECI_UTF8) + segment_bits,
+        _ => segment_bits,
     }
 }
 
-pub fn encode_text(
-    version_restriction: VersionRestriction,
-    error_correction_restriction: ErrorCorrectionRestriction,
+pub fn encode_text(options: &EncodeOptions, text: &str) -> Result<EncodedData, QrError> {
+    encode_text_sized(options, text)
+}
+
+/// Encodes the text into a buffer of `C` codewords, which limits the version
+pub(crate) fn encode_text_sized<const C: usize>(
+    options: &EncodeOptions,
     text: &str,
-) -> Result<EncodedData, ()> {
-    // Find the character set to encode in
-    let character_set = detect_character_set(text);
+) -> Result<EncodedData<[u8; C]>, QrError> {
+    let mut uppercase = [[0; C]; 2];
+    let text = normalize_case(options, text, uppercase.as_flattened_mut());
+    if options.optimal_segmentation && options.character_set.is_none() && text.is_ascii() {
+        let mut modes = [[0; 3]; C];
+        let (version, error_correction, segments) =
+            plan_optimal_segments(options, text.as_bytes(), &mut modes)?;
+        return Ok(write_segments(options, version, error_correction, segments));
+    }
+    let character_set = select_character_set(options, text)?;
+    let plan = plan_text::<C>(options, character_set, text)?;
+    encode_planned(options, &plan, text)
+}
+
+/// Encodes ISO 8859-1 (Latin-1) text given as bytes, like [`encode_text`]
+///
+/// Each byte is a character, so text from a Latin-1 source doesn't need to be converted to a
+/// string first. Like any text, it is encoded in the smallest mode that contains all characters.
+///
+/// # Example
+///```
+///     use tiny_qr::encoding::{encode_latin1, EncodeOptions};
+///     // "Grüße" in Latin-1
+///     let encoded = encode_latin1(&EncodeOptions::new(), b"Gr\xfc\xdfe").unwrap();
+///```
+pub fn encode_latin1(options: &EncodeOptions, data: &[u8]) -> Result<EncodedData, QrError> {
+    encode_latin1_sized(options, data)
+}
+
+pub(crate) fn encode_latin1_sized<const C: usize>(
+    options: &EncodeOptions,
+    data: &[u8],
+) -> Result<EncodedData<[u8; C]>, QrError> {
+    // Non-ASCII characters take two bytes in UTF-8
+    let mut utf8 = [[0; C]; 2];
+    encode_text_sized(options, latin1_to_str(data, utf8.as_flattened_mut())?)
+}
+
+/// Converts the Latin-1 text to UTF-8, using the buffer of twice the codewords when it isn't ASCII
+fn latin1_to_str<'a>(data: &'a [u8], buffer: &'a mut [u8]) -> Result<&'a str, QrError> {
+    if data.is_ascii() {
+        return Ok(core::str::from_utf8(data).unwrap());
+    }
+    // Non-ASCII text needs at least a byte per character, so it doesn't fit when it is longer
+    if data.len() > buffer.len() / 2 {
+        return Err(QrError::DataTooLong);
+    }
+    let mut len = 0;
+    for &byte in data {
+        len += char::from(byte).encode_utf8(&mut buffer[len..]).len();
+    }
+    Ok(core::str::from_utf8(&buffer[..len]).unwrap())
+}
+
+/// Uppercases the text into the buffer when enabled and that makes it alphanumeric
+///
+/// Alphanumeric mode needs more than 5 bits per character, so text longer than twice the
+/// codewords doesn't fit and is returned unchanged.
+pub(crate) fn normalize_case<'a>(
+    options: &EncodeOptions,
+    text: &'a str,
+    buffer: &'a mut [u8],
+) -> &'a str {
+    if !options.uppercase_normalization
+        || options.character_set.is_some()
+        || text.len() > buffer.len()
+        || !text.bytes().any(|b| b.is_ascii_lowercase())
+        || !text
+            .chars()
+            .all(|c| is_char_alphanumeric(c.to_ascii_uppercase()))
+    {
+        return text;
+    }
+    let buffer = &mut buffer[..text.len()];
+    buffer.copy_from_slice(text.as_bytes());
+    buffer.make_ascii_uppercase();
+    // Only ASCII characters are alphanumeric
+    core::str::from_utf8(buffer).unwrap()
+}
+
+/// The choices that encoding the data would make, see [`QrCodeBuilder::plan`]
+///
+/// [`QrCodeBuilder::plan`]: crate::QrCodeBuilder::plan
+///
+/// # Example
+///```
+///     use tiny_qr::encoding::EncodingMode;
+///     use tiny_qr::{ErrorCorrectionLevel, QrCodeBuilder, Version};
+///     let plan = QrCodeBuilder::new().with_text("HELLO WORLD").plan().unwrap();
+///     assert_eq!(plan.mode, Some(EncodingMode::Alphanumeric));
+///     assert_eq!(plan.version, Version::new(2).unwrap());
+///     assert_eq!(plan.error_correction, ErrorCorrectionLevel::High);
+///     assert_eq!(plan.used_data_codeword_count(), 10);
+///     assert_eq!(plan.data_codeword_count(), 16);
+///     assert_eq!(plan.error_correction_codeword_count(), 28);
+///```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EncodingPlan {
+    /// The mode of the data, `None` when there are no segments or they differ in mode
+    pub mode: Option<EncodingMode>,
+    pub version: Version,
+    pub error_correction: ErrorCorrectionLevel,
+    /// The number of bits before the terminator and padding
+    pub data_bit_len: usize,
+}
+
+impl EncodingPlan {
+    /// The number of data codewords that hold data, the others are padding
+    pub fn used_data_codeword_count(&self) -> usize {
+        self.data_bit_len.div_ceil(8)
+    }
+
+    pub fn data_codeword_count(&self) -> usize {
+        self.version.data_codeword_count(self.error_correction)
+    }
+
+    pub fn error_correction_codeword_count(&self) -> usize {
+        self.version.total_codeword_count() - self.data_codeword_count()
+    }
+}
+
+/// Plans the encoding of the text, without encoding it
+pub(crate) fn plan_encode_text<const C: usize>(
+    options: &EncodeOptions,
+    text: &str,
+) -> Result<EncodingPlan, QrError> {
+    let mut uppercase = [[0; C]; 2];
+    let text = normalize_case(options, text, uppercase.as_flattened_mut());
+    if options.optimal_segmentation && options.character_set.is_none() && text.is_ascii() {
+        let mut modes = [[0; 3]; C];
+        let (version, error_correction, segments) =
+            plan_optimal_segments(options, text.as_bytes(), &mut modes)?;
+        return Ok(segments_plan(options, version, error_correction, segments));
+    }
+    let character_set = select_character_set(options, text)?;
+    let plan = plan_text::<C>(options, character_set, text)?;
+    Ok(EncodingPlan {
+        mode: Some(character_set.to_encoding_mode()),
+        version: plan.version,
+        error_correction: plan.error_correction,
+        data_bit_len: text_bit_len(options, character_set, text, plan.version),
+    })
+}
+
+pub(crate) fn plan_encode_latin1<const C: usize>(
+    options: &EncodeOptions,
+    data: &[u8],
+) -> Result<EncodingPlan, QrError> {
+    let mut utf8 = [[0; C]; 2];
+    plan_encode_text::<C>(options, latin1_to_str(data, utf8.as_flattened_mut())?)
+}
+
+pub(crate) fn plan_encode_number<const C: usize>(
+    options: &EncodeOptions,
+    number: u128,
+) -> Result<EncodingPlan, QrError> {
+    let mut digits = [0; 39];
+    plan_encode_text::<C>(options, format_number(number, &mut digits))
+}
+
+pub(crate) fn plan_encode_bytes<const C: usize>(
+    options: &EncodeOptions,
+    data: &[u8],
+) -> Result<EncodingPlan, QrError> {
+    if data.is_empty() {
+        return plan_encode_segments::<C>(options, &[]);
+    }
+    plan_encode_segments::<C>(options, &[Segment::new(EncodingMode::Byte, data)])
+}
+
+pub(crate) fn plan_encode_segments<const C: usize>(
+    options: &EncodeOptions,
+    segments: &[Segment],
+) -> Result<EncodingPlan, QrError> {
+    validate_segments(segments)?;
+    let (version, error_correction) = plan_bit_len::<C>(options, |version| {
+        segments_bit_len(options, segments.iter().copied(), version)
+    })?;
+    Ok(segments_plan(
+        options,
+        version,
+        error_correction,
+        segments.iter().copied(),
+    ))
+}
+
+/// The choices made before encoding a text
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Plan {
+    pub(crate) character_set: CharacterSet,
+    pub(crate) version: Version,
+    pub(crate) error_correction: ErrorCorrectionLevel,
+}
+
+pub(crate) fn select_character_set(
+    options: &EncodeOptions,
+    text: &str,
+) -> Result<CharacterSet, QrError> {
+    match options.character_set {
+        Some(character_set) => {
+            if let Some((position, character)) = text
+                .chars()
+                .enumerate()
+                .find(|&(_, c)| !character_set.contains(c))
+            {
+                return Err(QrError::InvalidCharacter {
+                    character,
+                    position,
+                });
+            }
+            Ok(character_set)
+        }
+        None => match detect_character_set(text) {
+            #[cfg(feature = "kanji")]
+            CharacterSet::Kanji if !options.kanji_detection => Ok(CharacterSet::Unicode),
+            character_set => Ok(character_set),
+        },
+    }
+}
+
+/// Selects the version and error correction level for the text, in a buffer of `C` codewords
+pub(crate) fn plan_text<const C: usize>(
+    options: &EncodeOptions,
+    character_set: CharacterSet,
+    text: &str,
+) -> Result<Plan, QrError> {
+    let (version, error_correction) = plan_bit_len::<C>(options, |version| {
+        text_bit_len(options, character_set, text, version)
+    })?;
+    Ok(Plan {
+        character_set,
+        version,
+        error_correction,
+    })
+}
+
+/// Whether the plan of a text still holds after characters of its character set were appended
+///
+/// The data only grows, so a smaller version or higher error correction level still doesn't fit.
+/// The plan holds when the text fits its version and doesn't lower a boosted error correction.
+pub(crate) fn plan_holds<const C: usize>(options: &EncodeOptions, plan: &Plan, text: &str) -> bool {
+    let bit_len = |version| text_bit_len(options, plan.character_set, text, version);
+    if bit_len(plan.version) > plan.version.data_codeword_bit_len(plan.error_correction) {
+        return false;
+    }
+    match options.error_correction_restriction {
+        ErrorCorrectionRestriction::MinErrorCorrection(_) if options.error_correction_boost => {
+            let max_version = match options.version_restriction {
+                VersionRestriction::MaxVersion(version) => version.min(version_limit::<C>()),
+                VersionRestriction::SpecificVersion(version) => version,
+            };
+            bit_len(max_version) <= max_version.data_codeword_bit_len(plan.error_correction)
+        }
+        _ => true,
+    }
+}
+
+/// The number of bits of the text in the version, including the header
+fn text_bit_len(
+    options: &EncodeOptions,
+    character_set: CharacterSet,
+    text: &str,
+    version: Version,
+) -> usize {
+    let header_bit_len = options
+        .structured_append
+        .map_or(0, |_| STRUCTURED_APPEND_BIT_LEN);
+    header_bit_len
+        + if text.is_empty() {
+            0
+        } else {
+            calculate_encoded_data_bit_length(text, version, character_set, options.eci_policy)
+        }
+}
+
+/// The largest version of which all codewords fit in a buffer of `C` codewords
+pub(crate) const fn version_limit<const C: usize>() -> Version {
+    let mut version = LAST_VERSION;
+    while version > 1 && (Version { version }).total_codeword_count() > C {
+        version -= 1;
+    }
+    Version { version }
+}
+
+/// Selects the version and error correction level for data of the given length per version
+///
+/// A maximum version above what fits a buffer of `C` codewords is lowered to fit.
+fn plan_bit_len<const C: usize>(
+    options: &EncodeOptions,
+    bit_len: impl Fn(Version) -> usize,
+) -> Result<(Version, ErrorCorrectionLevel), QrError> {
+    let limit = version_limit::<C>();
+    options.validate(limit)?;
+    let version_restriction = match options.version_restriction {
+        VersionRestriction::MaxVersion(version) => {
+            VersionRestriction::MaxVersion(version.min(limit))
+        }
+        restriction => restriction,
+    };
+    let error_correction_restriction = options.error_correction_restriction;
 
     // Check whether the data could fit with the provided restrictions
     let max_version = version_restriction.to_version();
     let min_error_correction = error_correction_restriction.to_error_correction();
-    let bit_len = calculate_encoded_data_bit_length(text.len(), max_version, character_set);
-    if max_version.data_codeword_bit_len(min_error_correction) < bit_len {
-        return Err(());
+    if max_version.data_codeword_bit_len(min_error_correction) < bit_len(max_version) {
+        return Err(QrError::DataTooLong);
     }
 
     // Try to increase the error correction while the data still fits and it is allowed by the restriction
     let selected_error_correction = match error_correction_restriction {
-        ErrorCorrectionRestriction::MinErrorCorrection(min_error_correction) => {
+        ErrorCorrectionRestriction::MinErrorCorrection(min_error_correction)
+            if options.error_correction_boost =>
+        {
             let mut selected_error_correction = min_error_correction;
             while let Some(increased_error_correction) = selected_error_correction.increment() {
-                if max_version.data_codeword_bit_len(increased_error_correction) >= bit_len {
+                if max_version.data_codeword_bit_len(increased_error_correction)
+                    >= bit_len(max_version)
+                {
                     selected_error_correction = increased_error_correction;
                 } else {
                     break;
@@ -109,7 +843,8 @@ pub fn encode_text(
             }
             selected_error_correction
         }
-        ErrorCorrectionRestriction::SpecificErrorCorrection(error_correction) => error_correction,
+        ErrorCorrectionRestriction::MinErrorCorrection(error_correction)
+        | ErrorCorrectionRestriction::SpecificErrorCorrection(error_correction) => error_correction,
     };
 
     // Try to decrease the version while the data still fits and it is allowed by the restriction
@@ -117,7 +852,9 @@ pub fn encode_text(
         VersionRestriction::MaxVersion(max_version) => {
             let mut selected_version = max_version;
             while let Some(decreased_version) = selected_version.decrement() {
-                if decreased_version.data_codeword_bit_len(selected_error_correction) >= bit_len {
+                if decreased_version.data_codeword_bit_len(selected_error_correction)
+                    >= bit_len(decreased_version)
+                {
                     selected_version = decreased_version;
                 } else {
                     break;
@@ -128,37 +865,71 @@ pub fn encode_text(
         VersionRestriction::SpecificVersion(version) => version,
     };
 
+    Ok((selected_version, selected_error_correction))
+}
+
+pub(crate) fn encode_planned<const C: usize>(
+    options: &EncodeOptions,
+    plan: &Plan,
+    text: &str,
+) -> Result<EncodedData<[u8; C]>, QrError> {
+    let selected_version = plan.version;
+    let selected_error_correction = plan.error_correction;
+
     // Encode the data
-    let buffer = match character_set {
-        CharacterSet::Numeric => {
-            let encoder = NumericDataEncoder {
-                version: selected_version,
-                error_correction: selected_error_correction,
-            };
-            encoder.encode(text)
-        }
-        CharacterSet::Alphanumeric => {
-            let encoder = AlphanumericDataEncoder {
-                version: selected_version,
-                error_correction: selected_error_correction,
-            };
-            encoder.encode(text)
-        }
-        CharacterSet::Iso8859_1 => {
-            let encoder = Iso8859_1DataEncoder {
-                version: selected_version,
-                error_correction: selected_error_correction,
-            };
-            encoder.encode(text)
-        }
-        CharacterSet::Unicode => {
-            let encoder = UnicodeDataEncoder {
-                version: selected_version,
-                error_correction: selected_error_correction,
-            };
-            encoder.encode(text)
+    let mut buffer = Buffer::<[u8; C]>::default();
+    if let Some(structured_append) = options.structured_append {
+        structured_append.append(&mut buffer);
+    }
+    if text.is_empty() {
+        // An empty text results in a symbol that only contains the terminator and padding
+        let encoder = NumericDataEncoder {
+            version: selected_version,
+            error_correction: selected_error_correction,
+        };
+        encoder.encode_terminator(&mut buffer);
+        encoder.encode_padding(&mut buffer);
+    } else {
+        match plan.character_set {
+            CharacterSet::Numeric => {
+                let encoder = NumericDataEncoder {
+                    version: selected_version,
+                    error_correction: selected_error_correction,
+                };
+                encoder.encode_into(text, &mut buffer)?
+            }
+            CharacterSet::Alphanumeric => {
+                let encoder = AlphanumericDataEncoder {
+                    version: selected_version,
+                    error_correction: selected_error_correction,
+                };
+                encoder.encode_into(text, &mut buffer)?
+            }
+            CharacterSet::Iso8859_1 => {
+                let encoder = Iso8859_1DataEncoder {
+                    version: selected_version,
+                    error_correction: selected_error_correction,
+                };
+                encoder.encode_into(text, &mut buffer)?
+            }
+            #[cfg(feature = "kanji")]
+            CharacterSet::Kanji => {
+                let encoder = KanjiDataEncoder {
+                    version: selected_version,
+                    error_correction: selected_error_correction,
+                };
+                encoder.encode_into(text, &mut buffer)?
+            }
+            CharacterSet::Unicode => {
+                let encoder = UnicodeDataEncoder {
+                    version: selected_version,
+                    error_correction: selected_error_correction,
+                    eci_designator: options.eci_policy == EciPolicy::Automatic,
+                };
+                encoder.encode_into(text, &mut buffer)?
+            }
         }
-    };
+    }
     Ok(EncodedData {
         version: selected_version,
         error_correction: selected_error_correction,
@@ -166,62 +937,805 @@ pub fn encode_text(
     })
 }
 
-pub struct NumericDataEncoder {
-    // TODO: Combine Version and ErrorCorrectionLevel
-    pub(crate) version: Version,
-    pub(crate) error_correction: ErrorCorrectionLevel,
+/// A part of the data that is encoded in a single mode
+///
+/// In numeric and alphanumeric mode the data must be ASCII characters of that mode. In byte mode
+/// the data is written as-is. In Kanji mode the data is Shift JIS, with two bytes per character.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Segment<'a> {
+    pub mode: EncodingMode,
+    pub data: &'a [u8],
 }
 
-impl NumericDataEncoder {
-    //TODO: Spec contains a formula for calculating the length of the output before encoding it.
+impl<'a> Segment<'a> {
+    pub fn new(mode: EncodingMode, data: &'a [u8]) -> Self {
+        Self { mode, data }
+    }
 
-    fn encode_mode_indicator(&self, buffer: &mut Buffer) {
-        buffer.append_bits(&[false, false, false, true])
+    /// Checks whether every byte is valid for the mode, the position is relative to the segment
+    fn validate(&self) -> Result<(), QrError> {
+        let invalid = match self.mode {
+            EncodingMode::Numeric => self.data.iter().position(|&b| !is_char_numeric(b as char)),
+            EncodingMode::Alphanumeric => self
+                .data
+                .iter()
+                .position(|&b| !is_char_alphanumeric(b as char)),
+            EncodingMode::Byte => None,
+            EncodingMode::Kanji => self
+                .data
+                .chunks(2)
+                .position(|pair| compact_shift_jis(pair).is_none())
+                .map(|position| position * 2),
+        };
+        match invalid {
+            Some(position) => Err(QrError::InvalidCharacter {
+                character: self.data[position] as char,
+                position,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn encode<S: AsRef<[u8]> + AsMut<[u8]>>(&self, version: Version, buffer: &mut Buffer<S>) {
+        let mode_indicator = match self.mode {
+            EncodingMode::Numeric => 0b0001,
+            EncodingMode::Alphanumeric => 0b0010,
+            EncodingMode::Byte => 0b0100,
+            EncodingMode::Kanji => 0b1000,
+        };
+        buffer.append_number(mode_indicator, 4);
+        let character_count = match self.mode {
+            EncodingMode::Kanji => self.data.len() / 2,
+            _ => self.data.len(),
+        };
+        buffer.append_number(
+            character_count as u32,
+            version.character_count_indicator_bit_length(self.mode),
+        );
+        match self.mode {
+            EncodingMode::Numeric => {
+                for digits in self.data.chunks(3) {
+                    let number = digits
+                        .iter()
+                        .fold(0, |number, &digit| number * 10 + (digit - b'0') as u32);
+                    buffer.append_number(number, 1 + 3 * digits.len());
+                }
+            }
+            EncodingMode::Alphanumeric => {
+                let convert =
+                    |&b: &u8| AlphanumericDataEncoder::convert_alphanumeric(b as char).unwrap();
+                for pair in self.data.chunks(2) {
+                    match pair {
+                        [char1, char2] => {
+                            buffer.append_number(45 * convert(char1) + convert(char2), 11)
+                        }
+                        [char1] => buffer.append_number(convert(char1), 6),
+                        _ => unreachable!(),
+                    }
+                }
+            }
+            EncodingMode::Byte => {
+                for &byte in self.data {
+                    buffer.append_byte(byte);
+                }
+            }
+            EncodingMode::Kanji => {
+                for pair in self.data.chunks(2) {
+                    buffer.append_number(compact_shift_jis(pair).unwrap(), 13);
+                }
+            }
+        }
     }
+}
+
+/// Encodes the segments after each other, each in its own mode
+///
+/// # Example
+///```
+///     use tiny_qr::encoding::{encode_segments, EncodeOptions, EncodingMode, Segment};
+///     let segments = [
+///         Segment::new(EncodingMode::Byte, b"serial:"),
+///         Segment::new(EncodingMode::Numeric, b"0123456789012345"),
+///     ];
+///     let encoded = encode_segments(&EncodeOptions::new(), &segments).unwrap();
+///```
+pub fn encode_segments(
+    options: &EncodeOptions,
+    segments: &[Segment],
+) -> Result<EncodedData, QrError> {
+    encode_segments_sized(options, segments)
+}
 
-    fn encode_character_count_indicator(&self, count: u32, buffer: &mut Buffer) {
-        let bit_len = self
-            .version
-            .character_count_indicator_bit_length(EncodingMode::Numeric);
-        buffer.append_number(count, bit_len);
+pub(crate) fn encode_segments_sized<const C: usize>(
+    options: &EncodeOptions,
+    segments: &[Segment],
+) -> Result<EncodedData<[u8; C]>, QrError> {
+    validate_segments(segments)?;
+    let (version, error_correction) = plan_bit_len::<C>(options, |version| {
+        segments_bit_len(options, segments.iter().copied(), version)
+    })?;
+    Ok(write_segments(
+        options,
+        version,
+        error_correction,
+        segments.iter().copied(),
+    ))
+}
+
+/// Checks every segment, the position of an invalid character counts from the first segment
+fn validate_segments(segments: &[Segment]) -> Result<(), QrError> {
+    let mut offset = 0;
+    for segment in segments {
+        segment.validate().map_err(|error| match error {
+            QrError::InvalidCharacter {
+                character,
+                position,
+            } => QrError::InvalidCharacter {
+                character,
+                position: offset + position,
+            },
+            error => error,
+        })?;
+        offset += segment.data.len();
     }
+    Ok(())
+}
 
-    fn encode_data(&self, data: &str, buffer: &mut Buffer) {
-        let mut i = 0;
-        loop {
-            let chars_left = data.len() - i;
-            if chars_left >= 3 {
-                let number = data[i..i + 3].parse::<u32>().unwrap();
-                buffer.append_number(number, 10);
-                i += 3;
-            } else if chars_left == 2 {
-                let number = data[i..i + 2].parse::<u32>().unwrap();
-                buffer.append_number(number, 7);
-                break;
-            } else if chars_left == 1 {
-                let number = data[i..i + 1].parse::<u32>().unwrap();
-                buffer.append_number(number, 3);
-                break;
+/// Encodes binary data in byte mode, without the need for it to be text
+///
+/// # Example
+///```
+///     use tiny_qr::encoding::{encode_bytes, EncodeOptions};
+///     let encoded = encode_bytes(&EncodeOptions::new(), &[0x08, 0x96, 0x01, 0xff]).unwrap();
+///```
+pub fn encode_bytes(options: &EncodeOptions, data: &[u8]) -> Result<EncodedData, QrError> {
+    encode_bytes_sized(options, data)
+}
+
+pub(crate) fn encode_bytes_sized<const C: usize>(
+    options: &EncodeOptions,
+    data: &[u8],
+) -> Result<EncodedData<[u8; C]>, QrError> {
+    if data.is_empty() {
+        // Like an empty text, only the terminator and padding are encoded
+        return encode_segments_sized(options, &[]);
+    }
+    encode_segments_sized(options, &[Segment::new(EncodingMode::Byte, data)])
+}
+
+/// Encodes the text as UTF-16 bytes in byte mode, with an ECI designator of the byte order
+///
+/// The byte order is selected with [`EncodeOptions::with_eci`]: 25 for big endian, the default,
+/// or 33 for little endian. Other assignment numbers result in [`QrError::UnsupportedEci`].
+///
+/// # Example
+///```
+///     use tiny_qr::encoding::{encode_utf16, EncodeOptions};
+///     let options = EncodeOptions::new().with_eci(33);
+///     let encoded = encode_utf16(&options, "Grüße").unwrap();
+///```
+pub fn encode_utf16(options: &EncodeOptions, text: &str) -> Result<EncodedData, QrError> {
+    encode_utf16_sized(options, text)
+}
+
+pub(crate) fn encode_utf16_sized<const C: usize>(
+    options: &EncodeOptions,
+    text: &str,
+) -> Result<EncodedData<[u8; C]>, QrError> {
+    let mut utf16 = [0; C];
+    let (options, data) = transcode_utf16(options, text, &mut utf16)?;
+    encode_bytes_sized(&options, data)
+}
+
+pub(crate) fn plan_encode_utf16<const C: usize>(
+    options: &EncodeOptions,
+    text: &str,
+) -> Result<EncodingPlan, QrError> {
+    let mut utf16 = [0; C];
+    let (options, data) = transcode_utf16(options, text, &mut utf16)?;
+    plan_encode_bytes::<C>(&options, data)
+}
+
+/// Converts the text to UTF-16 in the byte order of the ECI, which defaults to big endian
+fn transcode_utf16<'a>(
+    options: &EncodeOptions,
+    text: &str,
+    buffer: &'a mut [u8],
+) -> Result<(EncodeOptions, &'a [u8]), QrError> {
+    let to_bytes = match options.eci {
+        None | Some(ECI_UTF16_BE) => u16::to_be_bytes,
+        Some(ECI_UTF16_LE) => u16::to_le_bytes,
+        Some(assignment) => return Err(QrError::UnsupportedEci { assignment }),
+    };
+    let mut len = 0;
+    for unit in text.encode_utf16() {
+        let bytes = buffer.get_mut(len..len + 2).ok_or(QrError::DataTooLong)?;
+        bytes.copy_from_slice(&to_bytes(unit));
+        len += 2;
+    }
+    let options = options.with_eci(options.eci.unwrap_or(ECI_UTF16_BE));
+    Ok((options, &buffer[..len]))
+}
+
+/// Encodes the text as Shift JIS bytes in byte mode, with an ECI designator of 20
+///
+/// Some older Japanese readers expect Shift JIS byte data instead of UTF-8. Unlike Kanji mode,
+/// this works for any text that Shift JIS contains, including half-width katakana and ASCII.
+///
+/// # Example
+///```
+///     use tiny_qr::encoding::{encode_shift_jis, EncodeOptions};
+///     let encoded = encode_shift_jis(&EncodeOptions::new(), "ｶﾀｶﾅ and 漢字").unwrap();
+///```
+#[cfg(feature = "shift-jis")]
+pub fn encode_shift_jis(options: &EncodeOptions, text: &str) -> Result<EncodedData, QrError> {
+    encode_shift_jis_sized(options, text)
+}
+
+#[cfg(feature = "shift-jis")]
+pub(crate) fn encode_shift_jis_sized<const C: usize>(
+    options: &EncodeOptions,
+    text: &str,
+) -> Result<EncodedData<[u8; C]>, QrError> {
+    encode_transcoded(options, text, SHIFT_JIS, ECI_SHIFT_JIS)
+}
+
+#[cfg(feature = "shift-jis")]
+pub(crate) fn plan_encode_shift_jis<const C: usize>(
+    options: &EncodeOptions,
+    text: &str,
+) -> Result<EncodingPlan, QrError> {
+    plan_encode_transcoded::<C>(options, text, SHIFT_JIS, ECI_SHIFT_JIS)
+}
+
+/// Encodes the text as Windows-1252 bytes in byte mode, with an ECI designator of 21
+///
+/// Windows-1252 extends ISO 8859-1 with the euro sign and typographic quotes, so readers that
+/// are configured for it show those characters instead of the text in UTF-8.
+///
+/// # Example
+///```
+///     use tiny_qr::encoding::{encode_windows_1252, EncodeOptions};
+///     let encoded = encode_windows_1252(&EncodeOptions::new(), "“5 €”").unwrap();
+///```
+#[cfg(feature = "windows-1252")]
+pub fn encode_windows_1252(options: &EncodeOptions, text: &str) -> Result<EncodedData, QrError> {
+    encode_windows_1252_sized(options, text)
+}
+
+#[cfg(feature = "windows-1252")]
+pub(crate) fn encode_windows_1252_sized<const C: usize>(
+    options: &EncodeOptions,
+    text: &str,
+) -> Result<EncodedData<[u8; C]>, QrError> {
+    encode_transcoded(options, text, WINDOWS_1252, ECI_WINDOWS_1252)
+}
+
+#[cfg(feature = "windows-1252")]
+pub(crate) fn plan_encode_windows_1252<const C: usize>(
+    options: &EncodeOptions,
+    text: &str,
+) -> Result<EncodingPlan, QrError> {
+    plan_encode_transcoded::<C>(options, text, WINDOWS_1252, ECI_WINDOWS_1252)
+}
+
+/// Encodes the text as ISO 8859-15 bytes in byte mode, with an ECI designator of 17
+///
+/// ISO 8859-15 replaces some symbols of ISO 8859-1 with the euro sign and letters like `Œ`.
+///
+/// # Example
+///```
+///     use tiny_qr::encoding::{encode_iso_8859_15, EncodeOptions};
+///     let encoded = encode_iso_8859_15(&EncodeOptions::new(), "Œuvre 5 €").unwrap();
+///```
+#[cfg(feature = "iso-8859-15")]
+pub fn encode_iso_8859_15(options: &EncodeOptions, text: &str) -> Result<EncodedData, QrError> {
+    encode_iso_8859_15_sized(options, text)
+}
+
+#[cfg(feature = "iso-8859-15")]
+pub(crate) fn encode_iso_8859_15_sized<const C: usize>(
+    options: &EncodeOptions,
+    text: &str,
+) -> Result<EncodedData<[u8; C]>, QrError> {
+    encode_transcoded(options, text, ISO_8859_15, ECI_ISO_8859_15)
+}
+
+#[cfg(feature = "iso-8859-15")]
+pub(crate) fn plan_encode_iso_8859_15<const C: usize>(
+    options: &EncodeOptions,
+    text: &str,
+) -> Result<EncodingPlan, QrError> {
+    plan_encode_transcoded::<C>(options, text, ISO_8859_15, ECI_ISO_8859_15)
+}
+
+/// Encodes the text in byte mode in another character set, with its ECI designator
+#[cfg(any(
+    feature = "shift-jis",
+    feature = "windows-1252",
+    feature = "iso-8859-15"
+))]
+fn encode_transcoded<const C: usize>(
+    options: &EncodeOptions,
+    text: &str,
+    encoding: &'static Encoding,
+    assignment: u32,
+) -> Result<EncodedData<[u8; C]>, QrError> {
+    let mut transcoded = [0; C];
+    let data = transcode(text, encoding, &mut transcoded)?;
+    encode_bytes_sized(&options.with_eci(assignment), data)
+}
+
+#[cfg(any(
+    feature = "shift-jis",
+    feature = "windows-1252",
+    feature = "iso-8859-15"
+))]
+fn plan_encode_transcoded<const C: usize>(
+    options: &EncodeOptions,
+    text: &str,
+    encoding: &'static Encoding,
+    assignment: u32,
+) -> Result<EncodingPlan, QrError> {
+    let mut transcoded = [0; C];
+    let data = transcode(text, encoding, &mut transcoded)?;
+    plan_encode_bytes::<C>(&options.with_eci(assignment), data)
+}
+
+/// Converts the text to the character set, which doesn't fit when it is longer than the buffer
+#[cfg(any(
+    feature = "shift-jis",
+    feature = "windows-1252",
+    feature = "iso-8859-15"
+))]
+fn transcode<'a>(
+    text: &str,
+    encoding: &'static Encoding,
+    buffer: &'a mut [u8],
+) -> Result<&'a [u8], QrError> {
+    let (result, read, written) = encoding
+        .new_encoder()
+        .encode_from_utf8_without_replacement(text, buffer, true);
+    match result {
+        EncoderResult::InputEmpty => Ok(&buffer[..written]),
+        EncoderResult::OutputFull => Err(QrError::DataTooLong),
+        EncoderResult::Unmappable(character) => Err(QrError::InvalidCharacter {
+            character,
+            // The unmappable character is included in the read input
+            position: text[..read].chars().count() - 1,
+        }),
+    }
+}
+
+/// Encodes the segments with the header of the options in the selected version
+fn write_segments<'a, const C: usize>(
+    options: &EncodeOptions,
+    version: Version,
+    error_correction: ErrorCorrectionLevel,
+    segments: impl Iterator<Item = Segment<'a>>,
+) -> EncodedData<[u8; C]> {
+    let mut buffer = Buffer::<[u8; C]>::default();
+    if let Some(structured_append) = options.structured_append {
+        structured_append.append(&mut buffer);
+    }
+    if let Some(assignment) = options.eci {
+        append_eci(&mut buffer, assignment);
+    }
+    for segment in segments {
+        segment.encode(version, &mut buffer);
+    }
+    // The framing doesn't depend on the mode
+    let encoder = NumericDataEncoder {
+        version,
+        error_correction,
+    };
+    encoder.encode_terminator(&mut buffer);
+    encoder.encode_padding(&mut buffer);
+    EncodedData {
+        version,
+        error_correction,
+        buffer,
+    }
+}
+
+/// Describes the segments in the selected version, the mode is only known when they all share it
+fn segments_plan<'a>(
+    options: &EncodeOptions,
+    version: Version,
+    error_correction: ErrorCorrectionLevel,
+    segments: impl Iterator<Item = Segment<'a>> + Clone,
+) -> EncodingPlan {
+    let mut modes = segments.clone().map(|segment| segment.mode);
+    let mode = modes
+        .next()
+        .filter(|&first| modes.all(|mode| mode == first));
+    EncodingPlan {
+        mode,
+        version,
+        error_correction,
+        data_bit_len: segments_bit_len(options, segments, version),
+    }
+}
+
+/// The number of bits of the structured append header and ECI designator of the options
+fn header_bit_len(options: &EncodeOptions) -> usize {
+    options
+        .structured_append
+        .map_or(0, |_| STRUCTURED_APPEND_BIT_LEN)
+        + options.eci.map_or(0, eci_bit_len)
+}
+
+/// The number of bits of the segments in the version, including the header
+fn segments_bit_len<'a>(
+    options: &EncodeOptions,
+    segments: impl Iterator<Item = Segment<'a>>,
+    version: Version,
+) -> usize {
+    header_bit_len(options)
+        + segments
+            .map(|segment| encoded_bit_length(segment.data, segment.mode, version))
+            .sum::<usize>()
+}
+
+/// Selects the version for the optimal segments of ASCII data and finds the segments
+///
+/// The segments only depend on the lengths of the character count indicators, so the number of
+/// bits is calculated once for each range of versions that shares them.
+fn plan_optimal_segments<'a, const C: usize>(
+    options: &EncodeOptions,
+    data: &'a [u8],
+    modes: &'a mut [[u8; 3]; C],
+) -> Result<(Version, ErrorCorrectionLevel, OptimalSegments<'a>), QrError> {
+    let bit_lens = [1, 10, 27].map(|version| {
+        OptimalSegments::bit_len(data, Version::new(version).expect("valid version"))
+    });
+    let (version, error_correction) = plan_bit_len::<C>(options, |version| {
+        let class = match version.version {
+            1..=9 => 0,
+            10..=26 => 1,
+            _ => 2,
+        };
+        header_bit_len(options) + bit_lens[class]
+    })?;
+    // The data fits in `C` codewords and each byte takes more than 3 bits, so there is a mode
+    // for every byte
+    let segments = OptimalSegments::new(data, version, modes.as_flattened_mut());
+    Ok((version, error_correction, segments))
+}
+
+/// The modes considered for optimal segmentation
+const SEGMENTATION_MODES: [EncodingMode; 3] = [
+    EncodingMode::Numeric,
+    EncodingMode::Alphanumeric,
+    EncodingMode::Byte,
+];
+
+/// The bits of each character of a group, for the modes of [`SEGMENTATION_MODES`]
+///
+/// Numeric mode packs three digits in 10 bits and alphanumeric mode two characters in 11 bits, so
+/// the first character of a group takes more bits than the others.
+const GROUP_BIT_LENS: [&[usize]; 3] = [&[4, 3, 3], &[6, 5], &[8]];
+
+/// Splits data into the sequence of numeric, alphanumeric and byte segments with the fewest bits
+///
+/// The segments are found by dynamic programming over the bytes, where the state of a byte is its
+/// mode and its position in the character group of that mode. This counts the bits of every
+/// segment exactly. For every byte a backpointer is kept in `modes`, which is then replaced by the
+/// mode of the byte while following the backpointers from the end.
+///
+/// # Example
+///```
+///     use tiny_qr::encoding::{EncodingMode, OptimalSegments, Segment};
+///     use tiny_qr::Version;
+///     let data = b"https://example.com/0123456789012";
+///     let mut modes = [0; 33];
+///     let mut segments = OptimalSegments::new(data, Version::new(1).unwrap(), &mut modes);
+///     assert_eq!(segments.next(), Some(Segment::new(EncodingMode::Byte, b"https://example.com/")));
+///     assert_eq!(segments.next(), Some(Segment::new(EncodingMode::Numeric, b"0123456789012")));
+///     assert_eq!(segments.next(), None);
+///```
+#[derive(Clone, Debug)]
+pub struct OptimalSegments<'a> {
+    data: &'a [u8],
+    modes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> OptimalSegments<'a> {
+    /// Finds the segments for the version, using one byte of `modes` for every byte of data
+    ///
+    /// # Panics
+    /// Panics when `modes` is shorter than `data`.
+    pub fn new(data: &'a [u8], version: Version, modes: &'a mut [u8]) -> Self {
+        let modes = &mut modes[..data.len()];
+        let (_, mut state) = Self::optimize(data, version, Some(&mut *modes));
+        for position in (0..data.len()).rev() {
+            let backpointer = modes[position];
+            let (mode_index, group_index) = state;
+            modes[position] = mode_index as u8;
+            state = if group_index > 0 {
+                (mode_index, group_index - 1)
+            } else if backpointer & (1 << mode_index) != 0 {
+                // The segment starts at this byte
+                (
+                    usize::from((backpointer >> 3) & 0b11),
+                    usize::from(backpointer >> 5),
+                )
             } else {
-                break;
+                (mode_index, GROUP_BIT_LENS[mode_index].len() - 1)
+            };
+        }
+        Self {
+            data,
+            modes,
+            position: 0,
+        }
+    }
+
+    /// The number of bits of the optimal segments in the version, including their mode indicators
+    /// and character count indicators
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::encoding::OptimalSegments;
+    ///     use tiny_qr::Version;
+    ///     let bit_len = OptimalSegments::bit_len(b"ABC0123456789", Version::new(1).unwrap());
+    ///     assert_eq!(bit_len, (4 + 9 + 17) + (4 + 10 + 34));
+    ///```
+    pub fn bit_len(data: &[u8], version: Version) -> usize {
+        Self::optimize(data, version, None).0
+    }
+
+    /// Returns the number of bits of the data and the state of its last byte
+    ///
+    /// The backpointer of a byte has a bit for each mode in which a segment starts at the byte,
+    /// followed by the best state of the byte before it.
+    fn optimize(
+        data: &[u8],
+        version: Version,
+        mut backpointers: Option<&mut [u8]>,
+    ) -> (usize, (usize, usize)) {
+        // The bits of the data up to the byte, for each state of the byte
+        let mut bit_lens = [[None; 3]; 3];
+        let mut best = (0, (0, 0));
+        for (position, &byte) in data.iter().enumerate() {
+            let (best_bit_len, best_state) = best;
+            let mut next: [[Option<usize>; 3]; 3] = [[None; 3]; 3];
+            let mut started = 0;
+            for (mode_index, &mode) in SEGMENTATION_MODES.iter().enumerate() {
+                let fits = match mode {
+                    EncodingMode::Numeric => is_char_numeric(byte as char),
+                    EncodingMode::Alphanumeric => is_char_alphanumeric(byte as char),
+                    _ => true,
+                };
+                if !fits {
+                    continue;
+                }
+                let group = GROUP_BIT_LENS[mode_index];
+                for (group_index, &char_bit_len) in group.iter().enumerate() {
+                    let previous =
+                        bit_lens[mode_index][(group_index + group.len() - 1) % group.len()];
+                    next[mode_index][group_index] = previous.map(|bit_len| bit_len + char_bit_len);
+                }
+                let start = best_bit_len
+                    + 4
+                    + version.character_count_indicator_bit_length(mode)
+                    + group[0];
+                // Prefer continuing the current segment on a tie
+                if start < next[mode_index][0].unwrap_or(usize::MAX) {
+                    next[mode_index][0] = Some(start);
+                    started |= 1 << mode_index;
+                }
+            }
+            if let Some(backpointers) = backpointers.as_deref_mut() {
+                backpointers[position] =
+                    started | (best_state.0 << 3) as u8 | (best_state.1 << 5) as u8;
+            }
+
+            best = (usize::MAX, (0, 0));
+            for (mode_index, group) in next.iter().enumerate() {
+                for (group_index, &bit_len) in group.iter().enumerate() {
+                    match bit_len {
+                        Some(bit_len) if bit_len < best.0 => {
+                            best = (bit_len, (mode_index, group_index))
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            bit_lens = next;
+        }
+        best
+    }
+}
+
+impl<'a> Iterator for OptimalSegments<'a> {
+    type Item = Segment<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.position;
+        let &mode_index = self.modes.get(start)?;
+        let len = self.modes[start..]
+            .iter()
+            .take_while(|&&mode| mode == mode_index)
+            .count();
+        self.position = start + len;
+        Some(Segment::new(
+            SEGMENTATION_MODES[usize::from(mode_index)],
+            &self.data[start..self.position],
+        ))
+    }
+}
+
+/// Encodes a number in numeric mode, without the need to format it as a string first
+///
+/// # Example
+///```
+///     use tiny_qr::encoding::{encode_number, EncodeOptions};
+///     let encoded = encode_number(&EncodeOptions::new(), 1234567890).unwrap();
+///```
+pub fn encode_number(options: &EncodeOptions, number: u128) -> Result<EncodedData, QrError> {
+    encode_number_sized(options, number)
+}
+
+pub(crate) fn encode_number_sized<const C: usize>(
+    options: &EncodeOptions,
+    number: u128,
+) -> Result<EncodedData<[u8; C]>, QrError> {
+    let mut digits = [0; 39];
+    encode_text_sized(options, format_number(number, &mut digits))
+}
+
+/// Encodes decimal digits from an iterator in numeric mode, without collecting them as a string
+///
+/// Every item is the value of a digit, from 0 to 9. The iterator is cloned to count the digits
+/// before encoding them.
+///
+/// # Example
+///```
+///     use tiny_qr::encoding::{encode_digits, EncodeOptions};
+///     let reading = [0u8, 4, 2, 1, 7, 3];
+///     let encoded = encode_digits(&EncodeOptions::new(), reading.iter().copied()).unwrap();
+///```
+pub fn encode_digits<I: Iterator<Item = u8> + Clone>(
+    options: &EncodeOptions,
+    digits: I,
+) -> Result<EncodedData, QrError> {
+    encode_digits_sized(options, digits)
+}
+
+pub(crate) fn encode_digits_sized<const C: usize, I: Iterator<Item = u8> + Clone>(
+    options: &EncodeOptions,
+    digits: I,
+) -> Result<EncodedData<[u8; C]>, QrError> {
+    let plan = plan_encode_digits::<C>(options, digits.clone())?;
+    let encoder = NumericDataEncoder {
+        version: plan.version,
+        error_correction: plan.error_correction,
+    };
+    let mut buffer = Buffer::<[u8; C]>::default();
+    if let Some(structured_append) = options.structured_append {
+        structured_append.append(&mut buffer);
+    }
+    // Like an empty text, no digits only encode the terminator and padding
+    let count = digits.clone().count();
+    if count > 0 {
+        encoder.encode_mode_indicator(&mut buffer);
+        encoder.encode_character_count_indicator(count, &mut buffer);
+        append_digits(digits, &mut buffer);
+    }
+    encoder.encode_terminator(&mut buffer);
+    encoder.encode_padding(&mut buffer);
+    Ok(EncodedData {
+        version: plan.version,
+        error_correction: plan.error_correction,
+        buffer,
+    })
+}
+
+pub(crate) fn plan_encode_digits<const C: usize>(
+    options: &EncodeOptions,
+    digits: impl Iterator<Item = u8>,
+) -> Result<EncodingPlan, QrError> {
+    let mut count = 0;
+    for (position, digit) in digits.enumerate() {
+        if digit > 9 {
+            // The value isn't a digit, so it is reported as the character with that code
+            return Err(QrError::InvalidCharacter {
+                character: char::from(digit),
+                position,
+            });
+        }
+        count += 1;
+    }
+    let bit_len = |version| {
+        let header_bit_len = options
+            .structured_append
+            .map_or(0, |_| STRUCTURED_APPEND_BIT_LEN);
+        header_bit_len
+            + match count {
+                0 => 0,
+                _ => segment_bit_length(count, EncodingMode::Numeric, version),
             }
+    };
+    let (version, error_correction) = plan_bit_len::<C>(options, bit_len)?;
+    Ok(EncodingPlan {
+        mode: Some(EncodingMode::Numeric),
+        version,
+        error_correction,
+        data_bit_len: bit_len(version),
+    })
+}
+
+/// Formats the number as decimal digits at the end of the digits array
+fn format_number(number: u128, digits: &mut [u8; 39]) -> &str {
+    let mut number = number;
+    let mut start = digits.len();
+    loop {
+        start -= 1;
+        digits[start] = b'0' + (number % 10) as u8;
+        number /= 10;
+        if number == 0 {
+            break;
         }
     }
+    core::str::from_utf8(&digits[start..]).unwrap()
+}
+
+/// Encodes data in one of the QR code modes.
+///
+/// Implementors only provide the mode specific parts: the mode indicator, the length of the
+/// character count indicator and the data itself. The framing (character count indicator,
+/// terminator and padding) is shared by all modes. This allows other crates to add modes that
+/// are not supported by this crate.
+pub trait ModeEncoder {
+    /// The data that is accepted by this encoder
+    type Input: ?Sized;
+
+    fn version(&self) -> Version;
+
+    fn error_correction(&self) -> ErrorCorrectionLevel;
+
+    fn encode_mode_indicator<S: AsRef<[u8]> + AsMut<[u8]>>(&self, buffer: &mut Buffer<S>);
+
+    fn character_count_indicator_bit_length(&self) -> usize;
+
+    /// Returns the amount of characters as it is written in the character count indicator
+    fn character_count(&self, data: &Self::Input) -> usize;
+
+    fn encode_data<S: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        data: &Self::Input,
+        buffer: &mut Buffer<S>,
+    ) -> Result<(), QrError>;
+
+    fn encode_character_count_indicator<S: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        count: usize,
+        buffer: &mut Buffer<S>,
+    ) {
+        let bit_len = self.character_count_indicator_bit_length();
+        buffer.append_number(count as u32, bit_len);
+    }
 
-    fn encode_terminator(&self, buffer: &mut Buffer) {
-        let max_data_bit_len = self.version.data_codeword_bit_len(self.error_correction);
+    fn encode_terminator<S: AsRef<[u8]> + AsMut<[u8]>>(&self, buffer: &mut Buffer<S>) {
+        let max_data_bit_len = self
+            .version()
+            .data_codeword_bit_len(self.error_correction());
 
         let buffer_bit_len = buffer.bit_len();
         if max_data_bit_len - buffer_bit_len < 4 {
             buffer.append_number(0, max_data_bit_len - buffer_bit_len)
         } else {
-            let alignment = 8 - ((buffer_bit_len + 4) % 8);
+            let alignment = (8 - ((buffer_bit_len + 4) % 8)) % 8;
             buffer.append_number(0, 4 + alignment)
         }
     }
 
-    fn encode_padding(&self, buffer: &mut Buffer) {
-        let max_data_bit_len = self.version.data_codeword_bit_len(self.error_correction);
+    fn encode_padding<S: AsRef<[u8]> + AsMut<[u8]>>(&self, buffer: &mut Buffer<S>) {
+        let max_data_bit_len = self
+            .version()
+            .data_codeword_bit_len(self.error_correction());
         loop {
             let bit_len_diff = max_data_bit_len - buffer.bit_len();
             if bit_len_diff == 0 {
@@ -236,14 +1750,97 @@ impl NumericDataEncoder {
         }
     }
 
-    pub fn encode(&self, data: &str) -> Buffer {
+    fn encode(&self, data: &Self::Input) -> Result<Buffer, QrError> {
         let mut buffer = Buffer::new();
-        self.encode_mode_indicator(&mut buffer);
-        self.encode_character_count_indicator(data.len() as u32, &mut buffer);
-        self.encode_data(data, &mut buffer);
-        self.encode_terminator(&mut buffer);
-        self.encode_padding(&mut buffer);
-        buffer
+        self.encode_into(data, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Encodes the data after what is already in the buffer, like a structured append header
+    fn encode_into<S: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        data: &Self::Input,
+        buffer: &mut Buffer<S>,
+    ) -> Result<(), QrError> {
+        self.encode_mode_indicator(buffer);
+        self.encode_character_count_indicator(self.character_count(data), buffer);
+        self.encode_data(data, buffer)?;
+        self.encode_terminator(buffer);
+        self.encode_padding(buffer);
+        Ok(())
+    }
+}
+
+pub struct NumericDataEncoder {
+    // TODO: Combine Version and ErrorCorrectionLevel
+    pub(crate) version: Version,
+    pub(crate) error_correction: ErrorCorrectionLevel,
+}
+
+impl ModeEncoder for NumericDataEncoder {
+    type Input = str;
+
+    fn version(&self) -> Version {
+        self.version
+    }
+
+    fn error_correction(&self) -> ErrorCorrectionLevel {
+        self.error_correction
+    }
+
+    fn encode_mode_indicator<S: AsRef<[u8]> + AsMut<[u8]>>(&self, buffer: &mut Buffer<S>) {
+        buffer.append_bits(&[false, false, false, true])
+    }
+
+    fn character_count_indicator_bit_length(&self) -> usize {
+        self.version
+            .character_count_indicator_bit_length(EncodingMode::Numeric)
+    }
+
+    fn character_count(&self, data: &str) -> usize {
+        data.len()
+    }
+
+    fn encode_data<S: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        data: &str,
+        buffer: &mut Buffer<S>,
+    ) -> Result<(), QrError> {
+        if let Some((position, character)) = data
+            .chars()
+            .enumerate()
+            .find(|(_, character)| !character.is_ascii_digit())
+        {
+            return Err(QrError::InvalidCharacter {
+                character,
+                position,
+            });
+        }
+        append_digits(data.bytes().map(|byte| byte - b'0'), buffer);
+        Ok(())
+    }
+}
+
+/// Appends the digits in groups of three, the last group can be shorter
+fn append_digits<S: AsRef<[u8]> + AsMut<[u8]>>(
+    digits: impl Iterator<Item = u8>,
+    buffer: &mut Buffer<S>,
+) {
+    let mut number = 0;
+    let mut digit_count = 0;
+    for digit in digits {
+        number = number * 10 + digit as u32;
+        digit_count += 1;
+        if digit_count == 3 {
+            buffer.append_number(number, 10);
+            number = 0;
+            digit_count = 0;
+        }
+    }
+    match digit_count {
+        2 => buffer.append_number(number, 7),
+        1 => buffer.append_number(number, 4),
+        _ => {}
     }
 }
 
@@ -254,261 +1851,286 @@ pub struct AlphanumericDataEncoder {
 }
 
 impl AlphanumericDataEncoder {
-    //TODO: Spec contains a formula for calculating the length of the output before encoding it.
-
-    fn convert_alphanumeric(c: char) -> u32 {
+    fn convert_alphanumeric(c: char) -> Option<u32> {
         match c {
-            '0' => 0,
-            '1' => 1,
-            '2' => 2,
-            '3' => 3,
-            '4' => 4,
-            '5' => 5,
-            '6' => 6,
-            '7' => 7,
-            '8' => 8,
-            '9' => 9,
-            'A' => 10,
-            'B' => 11,
-            'C' => 12,
-            'D' => 13,
-            'E' => 14,
-            'F' => 15,
-            'G' => 16,
-            'H' => 17,
-            'I' => 18,
-            'J' => 19,
-            'K' => 20,
-            'L' => 21,
-            'M' => 22,
-            'N' => 23,
-            'O' => 24,
-            'P' => 25,
-            'Q' => 26,
-            'R' => 27,
-            'S' => 28,
-            'T' => 29,
-            'U' => 30,
-            'V' => 31,
-            'W' => 32,
-            'X' => 33,
-            'Y' => 34,
-            'Z' => 35,
-            ' ' => 36,
-            '$' => 37,
-            '%' => 38,
-            '*' => 39,
-            '+' => 40,
-            '-' => 41,
-            '.' => 42,
-            '/' => 43,
-            ':' => 44,
-            _ => panic!(),
-        }
-    }
-
-    fn encode_mode_indicator(&self, buffer: &mut Buffer) {
+            '0' => Some(0),
+            '1' => Some(1),
+            '2' => Some(2),
+            '3' => Some(3),
+            '4' => Some(4),
+            '5' => Some(5),
+            '6' => Some(6),
+            '7' => Some(7),
+            '8' => Some(8),
+            '9' => Some(9),
+            'A' => Some(10),
+            'B' => Some(11),
+            'C' => Some(12),
+            'D' => Some(13),
+            'E' => Some(14),
+            'F' => Some(15),
+            'G' => Some(16),
+            'H' => Some(17),
+            'I' => Some(18),
+            'J' => Some(19),
+            'K' => Some(20),
+            'L' => Some(21),
+            'M' => Some(22),
+            'N' => Some(23),
+            'O' => Some(24),
+            'P' => Some(25),
+            'Q' => Some(26),
+            'R' => Some(27),
+            'S' => Some(28),
+            'T' => Some(29),
+            'U' => Some(30),
+            'V' => Some(31),
+            'W' => Some(32),
+            'X' => Some(33),
+            'Y' => Some(34),
+            'Z' => Some(35),
+            ' ' => Some(36),
+            '$' => Some(37),
+            '%' => Some(38),
+            '*' => Some(39),
+            '+' => Some(40),
+            '-' => Some(41),
+            '.' => Some(42),
+            '/' => Some(43),
+            ':' => Some(44),
+            _ => None,
+        }
+    }
+}
+
+impl ModeEncoder for AlphanumericDataEncoder {
+    type Input = str;
+
+    fn version(&self) -> Version {
+        self.version
+    }
+
+    fn error_correction(&self) -> ErrorCorrectionLevel {
+        self.error_correction
+    }
+
+    fn encode_mode_indicator<S: AsRef<[u8]> + AsMut<[u8]>>(&self, buffer: &mut Buffer<S>) {
         buffer.append_bits(&[false, false, true, false])
     }
 
-    fn encode_character_count_indicator(&self, count: u32, buffer: &mut Buffer) {
-        let bit_len = self
-            .version
-            .character_count_indicator_bit_length(EncodingMode::Alphanumeric);
-        buffer.append_number(count, bit_len);
+    fn character_count_indicator_bit_length(&self) -> usize {
+        self.version
+            .character_count_indicator_bit_length(EncodingMode::Alphanumeric)
     }
 
-    fn encode_data(&self, data: &str, buffer: &mut Buffer) {
-        let mut chars = data.chars();
+    fn character_count(&self, data: &str) -> usize {
+        data.len()
+    }
+
+    fn encode_data<S: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        data: &str,
+        buffer: &mut Buffer<S>,
+    ) -> Result<(), QrError> {
+        let convert = |(position, character)| {
+            Self::convert_alphanumeric(character).ok_or(QrError::InvalidCharacter {
+                character,
+                position,
+            })
+        };
+        let mut chars = data.chars().enumerate();
         while let Some(char1) = chars.next() {
             if let Some(char2) = chars.next() {
-                let char1 = Self::convert_alphanumeric(char1);
-                let char2 = Self::convert_alphanumeric(char2);
+                let char1 = convert(char1)?;
+                let char2 = convert(char2)?;
                 buffer.append_number(45 * char1 + char2, 11)
             } else {
-                let char1 = Self::convert_alphanumeric(char1);
+                let char1 = convert(char1)?;
                 buffer.append_number(char1, 6);
             }
         }
+        Ok(())
     }
+}
 
-    fn encode_terminator(&self, buffer: &mut Buffer) {
-        let max_data_bit_len = self.version.data_codeword_bit_len(self.error_correction);
+pub struct Iso8859_1DataEncoder {
+    // TODO: Combine Version and ErrorCorrectionLevel
+    pub(crate) version: Version,
+    pub(crate) error_correction: ErrorCorrectionLevel,
+}
 
-        let buffer_bit_len = buffer.bit_len();
-        if max_data_bit_len - buffer_bit_len < 4 {
-            buffer.append_number(0, max_data_bit_len - buffer_bit_len)
+impl Iso8859_1DataEncoder {
+    fn convert_iso8859_1(c: char) -> Option<u32> {
+        if is_char_iso_8859_1(c) {
+            Some(c as u32)
         } else {
-            let alignment = 8 - ((buffer_bit_len + 4) % 8);
-            buffer.append_number(0, 4 + alignment)
+            None
         }
     }
+}
 
-    fn encode_padding(&self, buffer: &mut Buffer) {
-        let max_data_bit_len = self.version.data_codeword_bit_len(self.error_correction);
-        loop {
-            let bit_len_diff = max_data_bit_len - buffer.bit_len();
-            if bit_len_diff == 0 {
-                break;
-            } else if bit_len_diff >= 16 {
-                buffer.append_number(0b1110_1100_0001_0001, 16);
-            } else if bit_len_diff == 8 {
-                buffer.append_number(0b1110_1100, 8);
-            } else {
-                unreachable!()
-            }
-        }
+impl ModeEncoder for Iso8859_1DataEncoder {
+    type Input = str;
+
+    fn version(&self) -> Version {
+        self.version
     }
 
-    pub fn encode(&self, data: &str) -> Buffer {
-        let mut buffer = Buffer::new();
-        self.encode_mode_indicator(&mut buffer);
-        self.encode_character_count_indicator(data.len() as u32, &mut buffer);
-        self.encode_data(data, &mut buffer);
-        self.encode_terminator(&mut buffer);
-        self.encode_padding(&mut buffer);
-        buffer
+    fn error_correction(&self) -> ErrorCorrectionLevel {
+        self.error_correction
+    }
+
+    fn encode_mode_indicator<S: AsRef<[u8]> + AsMut<[u8]>>(&self, buffer: &mut Buffer<S>) {
+        buffer.append_bits(&[false, true, false, false])
+    }
+
+    fn character_count_indicator_bit_length(&self) -> usize {
+        self.version
+            .character_count_indicator_bit_length(EncodingMode::Byte)
+    }
+
+    fn character_count(&self, data: &str) -> usize {
+        data.chars().count()
+    }
+
+    fn encode_data<S: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        data: &str,
+        buffer: &mut Buffer<S>,
+    ) -> Result<(), QrError> {
+        for (position, character) in data.chars().enumerate() {
+            let char1 = Self::convert_iso8859_1(character).ok_or(QrError::InvalidCharacter {
+                character,
+                position,
+            })?;
+            buffer.append_number(char1, 8);
+        }
+        Ok(())
     }
 }
 
-pub struct Iso8859_1DataEncoder {
+pub struct UnicodeDataEncoder {
     // TODO: Combine Version and ErrorCorrectionLevel
     pub(crate) version: Version,
     pub(crate) error_correction: ErrorCorrectionLevel,
+    pub(crate) eci_designator: bool,
 }
 
-impl Iso8859_1DataEncoder {
-    //TODO: Spec contains a formula for calculating the length of the output before encoding it.
+impl ModeEncoder for UnicodeDataEncoder {
+    type Input = str;
 
-    fn convert_iso8859_1(c: char) -> u32 {
-        assert!(c as u32 <= 0xFF);
-        c as u32
+    fn version(&self) -> Version {
+        self.version
     }
 
-    fn encode_mode_indicator(&self, buffer: &mut Buffer) {
-        buffer.append_bits(&[false, true, false, false])
+    fn error_correction(&self) -> ErrorCorrectionLevel {
+        self.error_correction
     }
 
-    fn encode_character_count_indicator(&self, count: u32, buffer: &mut Buffer) {
-        let bit_len = self
-            .version
-            .character_count_indicator_bit_length(EncodingMode::Byte);
-        buffer.append_number(count, bit_len);
-    }
-
-    fn encode_data(&self, data: &str, buffer: &mut Buffer) {
-        for char1 in data.chars() {
-            let char1 = Self::convert_iso8859_1(char1);
-            buffer.append_number(char1, 8);
+    fn encode_mode_indicator<S: AsRef<[u8]> + AsMut<[u8]>>(&self, buffer: &mut Buffer<S>) {
+        if self.eci_designator {
+            append_eci(buffer, ECI_UTF8);
         }
+        // Byte mode indicator
+        buffer.append_bits(&[false, true, false, false])
     }
 
-    fn encode_terminator(&self, buffer: &mut Buffer) {
-        let max_data_bit_len = self.version.data_codeword_bit_len(self.error_correction);
-
-        let buffer_bit_len = buffer.bit_len();
-        if max_data_bit_len - buffer_bit_len < 4 {
-            buffer.append_number(0, max_data_bit_len - buffer_bit_len)
-        } else {
-            let alignment = 8 - ((buffer_bit_len + 4) % 8);
-            buffer.append_number(0, 4 + alignment)
-        }
+    fn character_count_indicator_bit_length(&self) -> usize {
+        self.version
+            .character_count_indicator_bit_length(EncodingMode::Byte)
     }
 
-    fn encode_padding(&self, buffer: &mut Buffer) {
-        let max_data_bit_len = self.version.data_codeword_bit_len(self.error_correction);
-        loop {
-            let bit_len_diff = max_data_bit_len - buffer.bit_len();
-            if bit_len_diff == 0 {
-                break;
-            } else if bit_len_diff >= 16 {
-                buffer.append_number(0b1110_1100_0001_0001, 16);
-            } else if bit_len_diff == 8 {
-                buffer.append_number(0b1110_1100, 8);
-            } else {
-                unreachable!()
-            }
-        }
+    fn character_count(&self, data: &str) -> usize {
+        data.len()
     }
 
-    pub fn encode(&self, data: &str) -> Buffer {
-        let mut buffer = Buffer::new();
-        self.encode_mode_indicator(&mut buffer);
-        self.encode_character_count_indicator(data.len() as u32, &mut buffer);
-        self.encode_data(data, &mut buffer);
-        self.encode_terminator(&mut buffer);
-        self.encode_padding(&mut buffer);
-        buffer
+    fn encode_data<S: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        data: &str,
+        buffer: &mut Buffer<S>,
+    ) -> Result<(), QrError> {
+        for byte1 in data.bytes() {
+            buffer.append_byte(byte1);
+        }
+        Ok(())
     }
 }
 
-pub struct UnicodeDataEncoder {
-    // TODO: Combine Version and ErrorCorrectionLevel
+/// Encodes Shift JIS double-byte characters in 13 bits each
+#[cfg(feature = "kanji")]
+pub struct KanjiDataEncoder {
     pub(crate) version: Version,
     pub(crate) error_correction: ErrorCorrectionLevel,
 }
 
-impl UnicodeDataEncoder {
-    //TODO: Spec contains a formula for calculating the length of the output before encoding it.
+#[cfg(feature = "kanji")]
+impl KanjiDataEncoder {
+    fn convert_kanji(c: char) -> Option<u32> {
+        let mut utf8 = [0; 4];
+        let mut shift_jis = [0; 2];
+        let (result, _, written) = SHIFT_JIS
+            .new_encoder()
+            .encode_from_utf8_without_replacement(c.encode_utf8(&mut utf8), &mut shift_jis, true);
+        if result != EncoderResult::InputEmpty || written != 2 {
+            return None;
+        }
+        compact_shift_jis(&shift_jis)
+    }
+}
+
+#[cfg(feature = "kanji")]
+impl ModeEncoder for KanjiDataEncoder {
+    type Input = str;
 
-    fn encode_mode_indicator(&self, buffer: &mut Buffer) {
-        // ECI indicator for UTF-8
-        buffer.append_bits(&[false, true, true, true]);
-        buffer.append_byte(26);
-        // Byte mode indicator
-        buffer.append_bits(&[false, true, false, false])
+    fn version(&self) -> Version {
+        self.version
     }
 
-    fn encode_character_count_indicator(&self, count: u32, buffer: &mut Buffer) {
-        let bit_len = self
-            .version
-            .character_count_indicator_bit_length(EncodingMode::Byte);
-        buffer.append_number(count, bit_len);
+    fn error_correction(&self) -> ErrorCorrectionLevel {
+        self.error_correction
     }
 
-    fn encode_data(&self, data: &str, buffer: &mut Buffer) {
-        for byte1 in data.bytes() {
-            buffer.append_byte(byte1);
-        }
+    fn encode_mode_indicator<S: AsRef<[u8]> + AsMut<[u8]>>(&self, buffer: &mut Buffer<S>) {
+        buffer.append_bits(&[true, false, false, false])
     }
 
-    fn encode_terminator(&self, buffer: &mut Buffer) {
-        let max_data_bit_len = self.version.data_codeword_bit_len(self.error_correction);
+    fn character_count_indicator_bit_length(&self) -> usize {
+        self.version
+            .character_count_indicator_bit_length(EncodingMode::Kanji)
+    }
 
-        let buffer_bit_len = buffer.bit_len();
-        if max_data_bit_len - buffer_bit_len < 4 {
-            buffer.append_number(0, max_data_bit_len - buffer_bit_len)
-        } else {
-            let alignment = 8 - ((buffer_bit_len + 4) % 8);
-            buffer.append_number(0, 4 + alignment)
-        }
+    fn character_count(&self, data: &str) -> usize {
+        data.chars().count()
     }
 
-    fn encode_padding(&self, buffer: &mut Buffer) {
-        let max_data_bit_len = self.version.data_codeword_bit_len(self.error_correction);
-        loop {
-            let bit_len_diff = max_data_bit_len - buffer.bit_len();
-            if bit_len_diff == 0 {
-                break;
-            } else if bit_len_diff >= 16 {
-                buffer.append_number(0b1110_1100_0001_0001, 16);
-            } else if bit_len_diff == 8 {
-                buffer.append_number(0b1110_1100, 8);
-            } else {
-                unreachable!()
-            }
+    fn encode_data<S: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        data: &str,
+        buffer: &mut Buffer<S>,
+    ) -> Result<(), QrError> {
+        for (position, character) in data.chars().enumerate() {
+            let value = Self::convert_kanji(character).ok_or(QrError::InvalidCharacter {
+                character,
+                position,
+            })?;
+            buffer.append_number(value, 13);
         }
+        Ok(())
     }
+}
 
-    pub fn encode(&self, data: &str) -> Buffer {
-        let mut buffer = Buffer::new();
-        self.encode_mode_indicator(&mut buffer);
-        self.encode_character_count_indicator(data.len() as u32, &mut buffer);
-        self.encode_data(data, &mut buffer);
-        self.encode_terminator(&mut buffer);
-        self.encode_padding(&mut buffer);
-        buffer
-    }
+/// Converts a Shift JIS double-byte character to the 13 bit value of Kanji mode
+fn compact_shift_jis(pair: &[u8]) -> Option<u32> {
+    let value = match pair {
+        &[byte1, byte2] => u16::from_be_bytes([byte1, byte2]) as u32,
+        _ => return None,
+    };
+    let offset = match value {
+        0x8140..=0x9FFC => value - 0x8140,
+        0xE040..=0xEBBF => value - 0xC140,
+        _ => return None,
+    };
+    Some((offset >> 8) * 0xC0 + (offset & 0xFF))
 }
 
 #[derive(Clone, Copy, Debug, Ord, PartialOrd, Eq, PartialEq)]
@@ -516,23 +2138,42 @@ pub enum EncodingMode {
     Numeric,
     Alphanumeric,
     Byte,
+    Kanji,
 }
 
+/// The smallest set of characters that contains the text, which determines the encoding mode
 #[derive(Clone, Copy, Debug, Ord, PartialOrd, Eq, PartialEq)]
 pub enum CharacterSet {
     Numeric,
     Alphanumeric,
     Iso8859_1,
     Unicode,
+    /// Shift JIS double-byte characters, needs the `kanji` feature
+    #[cfg(feature = "kanji")]
+    Kanji,
 }
 
 impl CharacterSet {
-    fn to_encoding_mode(self) -> EncodingMode {
+    pub(crate) fn contains(self, c: char) -> bool {
+        match self {
+            CharacterSet::Numeric => is_char_numeric(c),
+            CharacterSet::Alphanumeric => is_char_alphanumeric(c),
+            CharacterSet::Iso8859_1 => is_char_iso_8859_1(c),
+            CharacterSet::Unicode => true,
+            #[cfg(feature = "kanji")]
+            CharacterSet::Kanji => KanjiDataEncoder::convert_kanji(c).is_some(),
+        }
+    }
+
+    /// The mode in which text of this character set is encoded
+    pub fn to_encoding_mode(self) -> EncodingMode {
         match self {
             CharacterSet::Numeric => EncodingMode::Numeric,
             CharacterSet::Alphanumeric => EncodingMode::Alphanumeric,
             CharacterSet::Iso8859_1 => EncodingMode::Byte,
             CharacterSet::Unicode => EncodingMode::Byte,
+            #[cfg(feature = "kanji")]
+            CharacterSet::Kanji => EncodingMode::Kanji,
         }
     }
 }
@@ -549,7 +2190,18 @@ fn is_char_iso_8859_1(c: char) -> bool {
     c as u32 <= 0xff
 }
 
-fn detect_character_set(data: &str) -> CharacterSet {
+/// Detects the smallest character set of the text, as the builder does before encoding
+///
+/// Applications can use this to warn about text that needs a less efficient mode.
+///
+/// # Example
+///```
+///     use tiny_qr::encoding::{detect_character_set, CharacterSet, EncodingMode};
+///     assert_eq!(detect_character_set("HELLO WORLD"), CharacterSet::Alphanumeric);
+///     let character_set = detect_character_set("Hello world");
+///     assert_eq!(character_set.to_encoding_mode(), EncodingMode::Byte);
+///```
+pub fn detect_character_set(data: &str) -> CharacterSet {
     if data.chars().all(is_char_numeric) {
         CharacterSet::Numeric
     } else if data.chars().all(is_char_alphanumeric) {
@@ -557,24 +2209,130 @@ fn detect_character_set(data: &str) -> CharacterSet {
     } else if data.chars().all(is_char_iso_8859_1) {
         CharacterSet::Iso8859_1
     } else {
+        #[cfg(feature = "kanji")]
+        if data.chars().all(|c| CharacterSet::Kanji.contains(c)) {
+            return CharacterSet::Kanji;
+        }
         CharacterSet::Unicode
     }
 }
 
-pub struct EncodedData {
+/// Uppercases the case-insensitive parts of a URL, so more of it fits alphanumeric mode
+///
+/// The scheme, the host and the hexadecimal digits of percent-encoded characters are uppercased.
+/// The user info, path, query and fragment are case-sensitive and stay unchanged, so a URL that
+/// only has uppercase letters in those parts becomes alphanumeric. Text without a scheme is left
+/// unchanged.
+///
+/// # Example
+///```
+///     use tiny_qr::encoding::{detect_character_set, optimize_url, CharacterSet};
+///     let mut url = *b"https://example.com/%c3%a9/SALE";
+///     let url = core::str::from_utf8_mut(&mut url).unwrap();
+///     optimize_url(url);
+///     assert_eq!(url, "HTTPS://EXAMPLE.COM/%C3%A9/SALE");
+///     assert_eq!(detect_character_set(url), CharacterSet::Alphanumeric);
+///```
+pub fn optimize_url(url: &mut str) {
+    let scheme_len = match url.find(':') {
+        Some(len) if is_scheme(&url[..len]) => len,
+        _ => return,
+    };
+    url[..scheme_len].make_ascii_uppercase();
+
+    let mut path_start = scheme_len + 1;
+    if url[path_start..].starts_with("//") {
+        let authority_start = path_start + 2;
+        let authority_end = url[authority_start..]
+            .find(['/', '?', '#'])
+            .map_or(url.len(), |len| authority_start + len);
+        let host_start = url[authority_start..authority_end]
+            .rfind('@')
+            .map_or(authority_start, |len| authority_start + len + 1);
+        url[host_start..authority_end].make_ascii_uppercase();
+        path_start = authority_end;
+    }
+
+    // Percent-encoding uses ASCII, so the escapes are on character boundaries
+    let mut position = path_start;
+    while let Some(offset) = url[position..].find('%') {
+        let escape_start = position + offset + 1;
+        let escape_end = escape_start + 2;
+        match url.get_mut(escape_start..escape_end) {
+            Some(escape) if escape.bytes().all(|b| b.is_ascii_hexdigit()) => {
+                escape.make_ascii_uppercase();
+                position = escape_end;
+            }
+            _ => position = escape_start,
+        }
+    }
+}
+
+fn is_scheme(scheme: &str) -> bool {
+    let mut chars = scheme.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+pub struct EncodedData<S = [u8; BUFFER_CAPACITY]> {
     pub(crate) version: Version,
     pub(crate) error_correction: ErrorCorrectionLevel,
-    pub(crate) buffer: Buffer,
+    pub(crate) buffer: Buffer<S>,
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::buffer::{Buffer, BUFFER_CAPACITY};
     use crate::encoding::{
-        detect_character_set, AlphanumericDataEncoder, CharacterSet, Iso8859_1DataEncoder,
-        NumericDataEncoder, UnicodeDataEncoder,
+        capacity, detect_character_set, encode_digits, encode_latin1, encode_number, encode_text,
+        encode_text_sized, encode_utf16, encoded_bit_length, format_number, optimize_url,
+        plan_encode_latin1, plan_encode_text, plan_encode_utf16, AlphanumericDataEncoder,
+        CharacterSet, EciPolicy, EncodeOptions, EncodedData, EncodingMode, Iso8859_1DataEncoder,
+        ModeEncoder, NumericDataEncoder, UnicodeDataEncoder,
     };
-    use crate::error_correction::ErrorCorrectionLevel;
+    use crate::error::QrError;
+    use crate::error_correction::{add_error_correction, ErrorCorrectionLevel};
     use crate::qr_version::Version;
+    use crate::qrcode::MAX_VERSION;
+
+    #[test]
+    fn capacity_table() {
+        use EncodingMode::*;
+        // The capacities of version 4 listed in ISO/IEC 18004
+        let version = Version::new(4).unwrap();
+        for (error_correction, expected) in [
+            (ErrorCorrectionLevel::Low, [187, 114, 78, 48]),
+            (ErrorCorrectionLevel::Medium, [149, 90, 62, 38]),
+            (ErrorCorrectionLevel::Quartile, [111, 67, 46, 28]),
+            (ErrorCorrectionLevel::High, [82, 50, 34, 21]),
+        ] {
+            let capacities = [Numeric, Alphanumeric, Byte, Kanji]
+                .map(|mode| capacity(version, error_correction, mode));
+            assert_eq!(capacities, expected, "{:?}", error_correction);
+        }
+    }
+
+    #[test]
+    fn capacity_fits() {
+        for version in Version::all() {
+            let error_correction = ErrorCorrectionLevel::Medium;
+            let options = EncodeOptions::new()
+                .with_specific_version(version.number())
+                .with_specific_error_correction_level(error_correction);
+            for (mode, character) in [
+                (EncodingMode::Numeric, "1"),
+                (EncodingMode::Alphanumeric, "A"),
+            ] {
+                let capacity = capacity(version, error_correction, mode);
+                let encode = |text: &str| encode_text_sized::<3706>(&options, text);
+                assert!(encode(&character.repeat(capacity)).is_ok());
+                assert_eq!(
+                    encode(&character.repeat(capacity + 1)).err(),
+                    Some(QrError::DataTooLong)
+                );
+            }
+        }
+    }
 
     #[test]
     fn numeric() {
@@ -584,11 +2342,11 @@ mod tests {
         assert_eq!(character_set, CharacterSet::Numeric);
 
         let encoder = NumericDataEncoder {
-            version: Version { version: 1 },
+            version: Version::new(1).unwrap(),
             error_correction: ErrorCorrectionLevel::Medium,
         };
 
-        let buffer = encoder.encode(data);
+        let buffer = encoder.encode(data).unwrap();
         assert_eq!(
             buffer.data(),
             [
@@ -599,18 +2357,43 @@ mod tests {
         )
     }
 
+    #[test]
+    fn numeric_into_slice() {
+        let version = Version::new(1).unwrap();
+        let error_correction = ErrorCorrectionLevel::Medium;
+        let encoder = NumericDataEncoder {
+            version,
+            error_correction,
+        };
+
+        let mut codewords = [0xff; 26];
+        let mut buffer = Buffer::from_slice(&mut codewords);
+        encoder.encode_into("01234567", &mut buffer).unwrap();
+        assert_eq!(buffer.data(), encoder.encode("01234567").unwrap().data());
+
+        let data = add_error_correction(EncodedData {
+            version,
+            error_correction,
+            buffer,
+        });
+        assert_eq!(
+            data.ecc_codewords(),
+            [0xa5, 0x24, 0xd4, 0xc1, 0xed, 0x36, 0xc7, 0x87, 0x2c, 0x55]
+        );
+    }
+
     #[test]
     fn alphanumeric() {
         let data = "HELLO WORLD";
         let encoder = AlphanumericDataEncoder {
-            version: Version { version: 1 },
+            version: Version::new(1).unwrap(),
             error_correction: ErrorCorrectionLevel::Quartile,
         };
 
         let character_set = detect_character_set(data);
         assert_eq!(character_set, CharacterSet::Alphanumeric);
 
-        let buffer = encoder.encode(data);
+        let buffer = encoder.encode(data).unwrap();
         assert_eq!(
             buffer.data(),
             [
@@ -624,23 +2407,260 @@ mod tests {
     fn iso8859_1() {
         let data = "[H@llo wórld]";
         let encoder = Iso8859_1DataEncoder {
-            version: Version { version: 2 },
+            version: Version::new(2).unwrap(),
             error_correction: ErrorCorrectionLevel::Quartile,
         };
 
         let character_set = detect_character_set(data);
         assert_eq!(character_set, CharacterSet::Iso8859_1);
 
-        let buffer = encoder.encode(data);
+        // The character count is 13, while the text is 14 bytes in UTF-8
+        let buffer = encoder.encode(data).unwrap();
         assert_eq!(
             buffer.data(),
             [
-                64, 229, 180, 132, 6, 198, 198, 242, 7, 127, 55, 38, 198, 69, 208, 0, 236, 17, 236,
-                17, 236, 17
+                64, 213, 180, 132, 6, 198, 198, 242, 7, 127, 55, 38, 198, 69, 208, 236, 17, 236,
+                17, 236, 17, 236
             ]
         )
     }
 
+    #[test]
+    #[cfg(feature = "kanji")]
+    fn kanji() {
+        use crate::encoding::KanjiDataEncoder;
+
+        let encoder = KanjiDataEncoder {
+            version: Version::new(1).unwrap(),
+            error_correction: ErrorCorrectionLevel::Low,
+        };
+        assert!(CharacterSet::Kanji.contains('点'));
+        assert!(!CharacterSet::Kanji.contains('a'));
+
+        // Example from the standard: mode, count 2, 0x0D9F and 0x1AAA
+        let buffer = encoder.encode("点茗").unwrap();
+        assert_eq!(
+            buffer.data()[..5],
+            [
+                0b1000_0000,
+                0b0010_0110,
+                0b1100_1111,
+                0b1110_1010,
+                0b1010_1000
+            ]
+        );
+        assert!(matches!(
+            encoder.encode("点a"),
+            Err(QrError::InvalidCharacter {
+                character: 'a',
+                position: 1
+            })
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "kanji")]
+    fn kanji_detection() {
+        use crate::encoding::select_character_set;
+
+        assert_eq!(detect_character_set("点茗"), CharacterSet::Kanji);
+        // Mixed text needs multiple segments, which isn't supported
+        assert_eq!(detect_character_set("点a"), CharacterSet::Unicode);
+
+        let options = EncodeOptions::new();
+        assert_eq!(
+            select_character_set(&options, "点茗"),
+            Ok(CharacterSet::Kanji)
+        );
+        let options = options.with_kanji_detection(false);
+        assert_eq!(
+            select_character_set(&options, "点茗"),
+            Ok(CharacterSet::Unicode)
+        );
+    }
+
+    #[test]
+    fn segments() {
+        use crate::encoding::{encode_segments, Segment};
+
+        // A single segment encodes the same as the text
+        let options =
+            EncodeOptions::new().with_specific_error_correction_level(ErrorCorrectionLevel::Medium);
+        let segments = [Segment::new(EncodingMode::Numeric, b"01234567")];
+        let encoded = encode_segments(&options, &segments).unwrap();
+        let expected = encode_text(&options, "01234567").unwrap();
+        assert_eq!(encoded.version, expected.version);
+        assert_eq!(encoded.buffer.data(), expected.buffer.data());
+
+        // Mixing modes is smaller than encoding everything in byte mode
+        let segments = [
+            Segment::new(EncodingMode::Byte, b"serial:"),
+            Segment::new(EncodingMode::Numeric, b"01234567890123456789"),
+        ];
+        let encoded = encode_segments(&options, &segments).unwrap();
+        assert_eq!(encoded.version, Version::new(2).unwrap());
+        let byte = encode_text(&options, "serial:01234567890123456789").unwrap();
+        assert_eq!(byte.version, Version::new(3).unwrap());
+
+        // The position counts from the start of the first segment
+        let segments = [
+            Segment::new(EncodingMode::Alphanumeric, b"AB"),
+            Segment::new(EncodingMode::Numeric, b"12a"),
+        ];
+        assert!(matches!(
+            encode_segments(&options, &segments),
+            Err(QrError::InvalidCharacter {
+                character: 'a',
+                position: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn optimal_segments() {
+        use crate::encoding::{OptimalSegments, Segment};
+        use EncodingMode::*;
+
+        // Known optimal splits with their number of bits in version 1, the numeric and
+        // alphanumeric groups that aren't complete make the split of the last one differ from
+        // counting a fraction of a group per character
+        type Split<'a> = &'a [(EncodingMode, &'a [u8])];
+        let version = Version::new(1).unwrap();
+        let cases: [(&[u8], Split, usize); 6] = [
+            (b"", &[], 0),
+            (b"0123456789", &[(Numeric, b"0123456789")], 48),
+            (b"ABC123DEF", &[(Alphanumeric, b"ABC123DEF")], 63),
+            (
+                b"a1234567890123ABCDEFGHIJ",
+                &[
+                    (Byte, b"a"),
+                    (Numeric, b"1234567890123"),
+                    (Alphanumeric, b"ABCDEFGHIJ"),
+                ],
+                20 + 58 + 68,
+            ),
+            (
+                b"https://example.com/0123456789012",
+                &[(Byte, b"https://example.com/"), (Numeric, b"0123456789012")],
+                172 + 58,
+            ),
+            (
+                b"aXYZ0123456789Q",
+                &[(Byte, b"a"), (Alphanumeric, b"XYZ0123456789Q")],
+                20 + 90,
+            ),
+        ];
+        for (data, expected, bit_len) in cases {
+            let mut modes = [0; 33];
+            let segments = OptimalSegments::new(data, version, &mut modes);
+            assert!(
+                segments.clone().eq(expected
+                    .iter()
+                    .map(|&(mode, data)| Segment::new(mode, data))),
+                "{:?}",
+                data
+            );
+            assert_eq!(OptimalSegments::bit_len(data, version), bit_len);
+            let encoded_bit_len: usize = segments
+                .map(|segment| encoded_bit_length(segment.data, segment.mode, version))
+                .sum();
+            assert_eq!(encoded_bit_len, bit_len);
+        }
+
+        // Byte segments have a longer character count indicator from version 10
+        let data = b"a0123456a";
+        let mut modes = [0; 9];
+        let segments = OptimalSegments::new(data, Version::new(9).unwrap(), &mut modes);
+        assert!(segments.eq([
+            Segment::new(Byte, b"a"),
+            Segment::new(Numeric, b"0123456"),
+            Segment::new(Byte, b"a"),
+        ]));
+        let mut modes = [0; 9];
+        let segments = OptimalSegments::new(data, Version::new(10).unwrap(), &mut modes);
+        assert!(segments.eq([Segment::new(Byte, data)]));
+
+        // The segmentation drops a version for an URL with a long number
+        let text = "https://example.com/?id=012345678901234567890123456789";
+        let options =
+            EncodeOptions::new().with_specific_error_correction_level(ErrorCorrectionLevel::Low);
+        let encoded = encode_text(&options, text).unwrap();
+        assert_eq!(encoded.version, Version::new(4).unwrap());
+        let options = options.with_optimal_segmentation(true);
+        let encoded = encode_text(&options, text).unwrap();
+        assert_eq!(encoded.version, Version::new(3).unwrap());
+    }
+
+    #[test]
+    fn bytes() {
+        use crate::encoding::encode_bytes;
+
+        let options = EncodeOptions::new();
+        let encoded = encode_bytes(&options, &[0x00, 0xff, 0x80]).unwrap();
+        assert_eq!(
+            &encoded.buffer.data()[..5],
+            [
+                0b0100_0000,
+                0b0011_0000,
+                0b0000_1111,
+                0b1111_1000,
+                0b0000_0000
+            ]
+        );
+
+        let encoded = encode_bytes(&options, &[]).unwrap();
+        assert_eq!(
+            encoded.buffer.data(),
+            encode_text(&options, "").unwrap().buffer.data()
+        );
+    }
+
+    #[test]
+    fn eci() {
+        use crate::buffer::Buffer;
+        use crate::encoding::{append_eci, encode_bytes};
+
+        let mut buffer = Buffer::new();
+        append_eci(&mut buffer, 3);
+        append_eci(&mut buffer, 1000);
+        append_eci(&mut buffer, 999_999);
+        assert_eq!(
+            buffer.data(),
+            [
+                0b0111_0000,
+                0b0011_0111,
+                0b1000_0011,
+                0b1110_1000,
+                0b0111_1100,
+                0b1111_0100,
+                0b0010_0011,
+                0b1111_0000
+            ]
+        );
+
+        // Shift JIS bytes with the matching ECI
+        let options = EncodeOptions::new().with_eci(20);
+        let encoded = encode_bytes(&options, &[0x93, 0x5f]).unwrap();
+        assert_eq!(
+            &encoded.buffer.data()[..5],
+            [
+                0b0111_0001,
+                0b0100_0100,
+                0b0000_0010,
+                0b1001_0011,
+                0b0101_1111
+            ]
+        );
+
+        let options = EncodeOptions::new().with_eci(1_000_000);
+        assert_eq!(
+            encode_bytes(&options, &[0x93, 0x5f]).err(),
+            Some(QrError::UnsupportedEci {
+                assignment: 1_000_000
+            })
+        );
+    }
+
     #[test]
     fn unicode() {
         let data = "I 💓 you";
@@ -649,10 +2669,11 @@ mod tests {
         assert_eq!(character_set, CharacterSet::Unicode);
 
         let encoder = UnicodeDataEncoder {
-            version: Version { version: 1 },
+            version: Version::new(1).unwrap(),
             error_correction: ErrorCorrectionLevel::Quartile,
+            eci_designator: true,
         };
-        let buffer = encoder.encode(data);
+        let buffer = encoder.encode(data).unwrap();
         assert_eq!(
             buffer.data(),
             [
@@ -666,10 +2687,467 @@ mod tests {
                 146,
                 147,
                 32,
-                'y' as u8,
-                'o' as u8,
-                'u' as u8,
+                b'y',
+                b'o',
+                b'u',
             ]
         )
     }
+
+    #[test]
+    fn character_set_override() {
+        let options = EncodeOptions::new()
+            .with_specific_version(1)
+            .with_specific_error_correction_level(ErrorCorrectionLevel::Medium)
+            .with_character_set(CharacterSet::Iso8859_1);
+
+        let encoded = encode_text(&options, "01234567").unwrap();
+        assert_eq!(
+            &encoded.buffer.data()[0..3],
+            [0b0100_0000, 0b1000_0011, 0b0000_0011]
+        );
+    }
+
+    #[test]
+    fn character_set_override_invalid_character() {
+        let options = EncodeOptions::new().with_character_set(CharacterSet::Alphanumeric);
+
+        assert_eq!(
+            encode_text(&options, "HELLO world").err(),
+            Some(QrError::InvalidCharacter {
+                character: 'w',
+                position: 6
+            })
+        );
+    }
+
+    #[test]
+    fn unicode_without_eci() {
+        let options = EncodeOptions::new()
+            .with_specific_version(1)
+            .with_specific_error_correction_level(ErrorCorrectionLevel::Quartile)
+            .with_eci_policy(EciPolicy::Never);
+
+        let encoded = encode_text(&options, "I 💓 you").unwrap();
+        assert_eq!(
+            &encoded.buffer.data()[0..3],
+            [0b0100_0000, 0b1010_0100, 0b1001_0010]
+        );
+    }
+
+    #[test]
+    fn data_too_long() {
+        let options = EncodeOptions::new().with_max_version(1);
+
+        assert_eq!(
+            encode_text(&options, "HTTPS://GITHUB.COM/CASPERMEIJN/TINY-QR").err(),
+            Some(QrError::DataTooLong)
+        );
+    }
+
+    #[test]
+    fn digits() {
+        let options = EncodeOptions::new();
+        for text in ["", "1", "0123456789", "31415926535897932384626433832795"] {
+            let digits = text.bytes().map(|byte| byte - b'0');
+            let encoded = encode_digits(&options, digits).unwrap();
+            let expected = encode_text(&options, text).unwrap();
+            assert_eq!(encoded.buffer.data(), expected.buffer.data(), "{}", text);
+        }
+        assert_eq!(
+            encode_digits(&options, [1, 2, 10].iter().copied()).err(),
+            Some(QrError::InvalidCharacter {
+                character: '\n',
+                position: 2
+            })
+        );
+    }
+
+    #[test]
+    fn utf16() {
+        let options = EncodeOptions::new();
+        let plan = plan_encode_utf16::<BUFFER_CAPACITY>(&options, "Aé😀").unwrap();
+        assert_eq!(plan.mode, Some(EncodingMode::Byte));
+        assert_eq!(plan.data_bit_len, 4 + 8 + 4 + 8 + 8 * 8);
+
+        // ECI 25, then byte mode with the 8 bytes of 0041 00E9 D83D DE00
+        let encoded = encode_utf16(&options, "Aé😀").unwrap();
+        assert_eq!(
+            encoded.buffer.data()[..11],
+            [0x71, 0x94, 0x08, 0x00, 0x41, 0x00, 0xe9, 0xd8, 0x3d, 0xde, 0x00]
+        );
+        let encoded = encode_utf16(&options.with_eci(33), "Aé😀").unwrap();
+        assert_eq!(
+            encoded.buffer.data()[..11],
+            [0x72, 0x14, 0x08, 0x41, 0x00, 0xe9, 0x00, 0x3d, 0xd8, 0x00, 0xde]
+        );
+
+        assert_eq!(
+            encode_utf16(&options.with_eci(26), "A").err(),
+            Some(QrError::UnsupportedEci { assignment: 26 })
+        );
+        assert_eq!(
+            encode_utf16(&options, &"A".repeat(BUFFER_CAPACITY)).err(),
+            Some(QrError::DataTooLong)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "shift-jis")]
+    fn shift_jis() {
+        use crate::encoding::{encode_shift_jis, plan_encode_shift_jis};
+
+        let options = EncodeOptions::new();
+        // Full-width characters take two bytes, half-width katakana a single byte
+        let plan = plan_encode_shift_jis::<BUFFER_CAPACITY>(&options, "点ｱA").unwrap();
+        assert_eq!(plan.mode, Some(EncodingMode::Byte));
+        assert_eq!(plan.data_bit_len, 4 + 8 + 4 + 8 + 4 * 8);
+
+        let encoded = encode_shift_jis(&options, "点ｱA").unwrap();
+        assert_eq!(
+            encoded.buffer.data()[..7],
+            [0x71, 0x44, 0x04, 0x93, 0x5f, 0xb1, 0x41]
+        );
+
+        assert_eq!(
+            encode_shift_jis(&options, "点€").err(),
+            Some(QrError::InvalidCharacter {
+                character: '€',
+                position: 1
+            })
+        );
+        assert_eq!(
+            encode_shift_jis(&options, &"点".repeat(BUFFER_CAPACITY)).err(),
+            Some(QrError::DataTooLong)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "windows-1252")]
+    fn windows_1252() {
+        use crate::encoding::encode_windows_1252;
+
+        let options = EncodeOptions::new();
+        // ECI 21, then byte mode with the 3 bytes of 93 80 94
+        let encoded = encode_windows_1252(&options, "“€”").unwrap();
+        assert_eq!(
+            encoded.buffer.data()[..6],
+            [0x71, 0x54, 0x03, 0x93, 0x80, 0x94]
+        );
+        assert_eq!(
+            encode_windows_1252(&options, "€ Œ ő").err(),
+            Some(QrError::InvalidCharacter {
+                character: 'ő',
+                position: 4
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "iso-8859-15")]
+    fn iso_8859_15() {
+        use crate::encoding::{encode_iso_8859_15, plan_encode_iso_8859_15};
+
+        let options = EncodeOptions::new();
+        let plan = plan_encode_iso_8859_15::<BUFFER_CAPACITY>(&options, "Œ€").unwrap();
+        assert_eq!(plan.data_bit_len, 4 + 8 + 4 + 8 + 2 * 8);
+        // ECI 17, then byte mode with the 2 bytes of BC A4
+        let encoded = encode_iso_8859_15(&options, "Œ€").unwrap();
+        assert_eq!(encoded.buffer.data()[..5], [0x71, 0x14, 0x02, 0xbc, 0xa4]);
+        // The currency sign of ISO 8859-1 is replaced by the euro sign
+        assert_eq!(
+            encode_iso_8859_15(&options, "¤").err(),
+            Some(QrError::InvalidCharacter {
+                character: '¤',
+                position: 0
+            })
+        );
+    }
+
+    #[test]
+    fn latin1() {
+        let options = EncodeOptions::new();
+        let text = "Grüße";
+        let latin1 = b"Gr\xfc\xdfe";
+        assert_eq!(
+            encode_latin1(&options, latin1).unwrap().buffer.data(),
+            encode_text(&options, text).unwrap().buffer.data()
+        );
+        let plan = plan_encode_latin1::<BUFFER_CAPACITY>(&options, latin1).unwrap();
+        assert_eq!(plan.mode, Some(EncodingMode::Byte));
+        // The character count indicator holds the 5 characters, not the 7 bytes of UTF-8
+        assert_eq!(plan.data_bit_len, 4 + 8 + 5 * 8);
+        let encoded = encode_latin1(&options, latin1).unwrap();
+        assert_eq!(encoded.buffer.data()[..2], [0b0100_0000, 0b0101_0100]);
+
+        let plan = plan_encode_latin1::<BUFFER_CAPACITY>(&options, b"0123").unwrap();
+        assert_eq!(plan.mode, Some(EncodingMode::Numeric));
+        assert_eq!(
+            encode_latin1(&options, &[0xe9; BUFFER_CAPACITY + 1]).err(),
+            Some(QrError::DataTooLong)
+        );
+    }
+
+    #[test]
+    fn url() {
+        fn optimized<const N: usize>(url: &[u8; N]) -> [u8; N] {
+            let mut url = *url;
+            optimize_url(core::str::from_utf8_mut(&mut url).unwrap());
+            url
+        }
+        assert_eq!(
+            &optimized(b"https://www.example.com"),
+            b"HTTPS://WWW.EXAMPLE.COM"
+        );
+        assert_eq!(
+            &optimized(b"http://user@example.com:8080/Path?q=a#top"),
+            b"HTTP://user@EXAMPLE.COM:8080/Path?q=a#top"
+        );
+        assert_eq!(
+            &optimized(b"https://x.nl/%2f%zz%e"),
+            b"HTTPS://X.NL/%2F%zz%e"
+        );
+        assert_eq!(&optimized(b"mailto:info@x.nl"), b"MAILTO:info@x.nl");
+        assert_eq!(&optimized(b"example.com/%2f"), b"example.com/%2f");
+        assert_eq!(&optimized(b"1st: place"), b"1st: place");
+
+        let options = EncodeOptions::new();
+        let mut url = *b"https://example.com/";
+        let plain = plan_encode_text::<BUFFER_CAPACITY>(&options, "https://example.com/").unwrap();
+        let url = core::str::from_utf8_mut(&mut url).unwrap();
+        optimize_url(url);
+        let plan = plan_encode_text::<BUFFER_CAPACITY>(&options, url).unwrap();
+        assert_eq!(plan.mode, Some(EncodingMode::Alphanumeric));
+        assert!(plan.data_bit_len < plain.data_bit_len);
+    }
+
+    #[test]
+    fn uppercase_normalization() {
+        let options = EncodeOptions::new().with_error_correction_boost(false);
+        let mixed_case = plan_encode_text::<BUFFER_CAPACITY>(&options, "hello World").unwrap();
+        assert_eq!(mixed_case.mode, Some(EncodingMode::Byte));
+
+        let options = options.with_uppercase_normalization(true);
+        let normalized = plan_encode_text::<BUFFER_CAPACITY>(&options, "hello World").unwrap();
+        assert_eq!(normalized.mode, Some(EncodingMode::Alphanumeric));
+        assert_eq!(
+            encode_text(&options, "hello World").unwrap().buffer.data(),
+            encode_text(&options, "HELLO WORLD").unwrap().buffer.data()
+        );
+
+        // Text that isn't alphanumeric after uppercasing keeps its case
+        let plan = plan_encode_text::<BUFFER_CAPACITY>(&options, "hello world!").unwrap();
+        assert_eq!(plan.mode, Some(EncodingMode::Byte));
+        let options = options.with_character_set(CharacterSet::Iso8859_1);
+        let plan = plan_encode_text::<BUFFER_CAPACITY>(&options, "hello world").unwrap();
+        assert_eq!(plan.mode, Some(EncodingMode::Byte));
+    }
+
+    #[test]
+    fn error_correction_boost() {
+        let options = EncodeOptions::new();
+        let boosted = encode_text(&options, "1").unwrap();
+        assert_eq!(boosted.error_correction, ErrorCorrectionLevel::High);
+
+        let options = options.with_error_correction_boost(false);
+        let encoded = encode_text(&options, "1").unwrap();
+        assert_eq!(encoded.error_correction, ErrorCorrectionLevel::Medium);
+        assert_eq!(encoded.version, Version::new(1).unwrap());
+    }
+
+    #[test]
+    fn print_area() {
+        let options = EncodeOptions::new().with_print_area(29, 1);
+        assert_eq!(
+            options.version_restriction.to_version(),
+            Version::new(1).unwrap()
+        );
+        let options = EncodeOptions::new().with_print_area(36_000, 1_000);
+        assert_eq!(
+            options.version_restriction.to_version(),
+            Version::new(2).unwrap()
+        );
+        // Encoding lowers the version to what fits the buffer
+        let options = EncodeOptions::new().with_print_area(1_000, 10);
+        assert_eq!(
+            options.version_restriction.to_version(),
+            Version::new(18).unwrap()
+        );
+        assert_eq!(
+            encode_text(&options, &"1".repeat(140)).unwrap().version,
+            Version::new(MAX_VERSION).unwrap()
+        );
+
+        let options = EncodeOptions::new().with_print_area(28, 1);
+        assert_eq!(encode_text(&options, "1").err(), Some(QrError::DataTooLong));
+        let options = EncodeOptions::new().with_print_area(29, 1);
+        assert_eq!(
+            encode_text(&options, &"1".repeat(42)).err(),
+            Some(QrError::DataTooLong)
+        );
+        let options = EncodeOptions::new().with_print_area(29, 0);
+        assert_eq!(
+            encode_text(&options, "1").err(),
+            Some(QrError::InvalidField {
+                field: "module size"
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_options() {
+        let options = EncodeOptions::new().with_max_version(41);
+        assert_eq!(
+            encode_text(&options, "1").err(),
+            Some(QrError::UnsupportedVersion { version: 41 })
+        );
+        let options = EncodeOptions::new().with_specific_version(0);
+        assert_eq!(
+            encode_text(&options, "1").err(),
+            Some(QrError::UnsupportedVersion { version: 0 })
+        );
+    }
+
+    #[test]
+    fn number() {
+        let mut digits = [0; 39];
+        assert_eq!(format_number(0, &mut digits), "0");
+        assert_eq!(format_number(1234567, &mut digits), "1234567");
+        assert_eq!(
+            format_number(u128::MAX, &mut digits),
+            "340282366920938463463374607431768211455"
+        );
+
+        let options = EncodeOptions::new();
+        let from_number = encode_number(&options, 1234567).unwrap();
+        let from_text = encode_text(&options, "1234567").unwrap();
+        assert_eq!(from_number.buffer.data(), from_text.buffer.data());
+    }
+
+    #[test]
+    fn alphanumeric_invalid_character() {
+        let encoder = AlphanumericDataEncoder {
+            version: Version::new(1).unwrap(),
+            error_correction: ErrorCorrectionLevel::Quartile,
+        };
+
+        assert_eq!(
+            encoder.encode("HELLO World").err(),
+            Some(QrError::InvalidCharacter {
+                character: 'o',
+                position: 7
+            })
+        );
+    }
+
+    #[test]
+    fn numeric_invalid_character() {
+        let encoder = NumericDataEncoder {
+            version: Version::new(1).unwrap(),
+            error_correction: ErrorCorrectionLevel::Medium,
+        };
+
+        assert_eq!(
+            encoder.encode("0123A").err(),
+            Some(QrError::InvalidCharacter {
+                character: 'A',
+                position: 4
+            })
+        );
+    }
+
+    #[test]
+    fn numeric_single_remaining_digit() {
+        let encoder = NumericDataEncoder {
+            version: Version::new(1).unwrap(),
+            error_correction: ErrorCorrectionLevel::Medium,
+        };
+
+        // 0001 0000000100 0001111011 0111 0000
+        let buffer = encoder.encode("1237").unwrap();
+        assert_eq!(
+            &buffer.data()[0..4],
+            [0b0001_0000, 0b0001_0000, 0b0111_1011, 0b0111_0000]
+        );
+    }
+
+    #[test]
+    fn numeric_single_digit() {
+        let encoder = NumericDataEncoder {
+            version: Version::new(1).unwrap(),
+            error_correction: ErrorCorrectionLevel::Medium,
+        };
+
+        // 0001 0000000001 0111 0000
+        let buffer = encoder.encode("7").unwrap();
+        assert_eq!(
+            &buffer.data()[0..3],
+            [0b0001_0000, 0b0000_0101, 0b1100_0000]
+        );
+    }
+
+    #[test]
+    fn terminator_at_byte_boundary() {
+        let encoder = NumericDataEncoder {
+            version: Version::new(1).unwrap(),
+            error_correction: ErrorCorrectionLevel::Quartile,
+        };
+
+        // 0001 0000000100 0001111011 0100 0000
+        let buffer = encoder.encode("1234").unwrap();
+        assert_eq!(
+            buffer.data(),
+            [
+                0b0001_0000,
+                0b0001_0000,
+                0b0111_1011,
+                0b0100_0000,
+                0b11101100,
+                0b00010001,
+                0b11101100,
+                0b00010001,
+                0b11101100,
+                0b00010001,
+                0b11101100,
+                0b00010001,
+                0b11101100
+            ]
+        );
+    }
+
+    #[test]
+    fn empty() {
+        let encoded = encode_text(&EncodeOptions::new(), "").unwrap();
+        assert_eq!(encoded.version, Version::new(1).unwrap());
+        assert_eq!(encoded.error_correction, ErrorCorrectionLevel::High);
+        assert_eq!(
+            encoded.buffer.data(),
+            [0, 236, 17, 236, 17, 236, 17, 236, 17]
+        );
+    }
+
+    #[test]
+    fn bit_length() {
+        let version = Version::new(1).unwrap();
+        assert_eq!(
+            encoded_bit_length("01234567", EncodingMode::Numeric, version),
+            41
+        );
+        assert_eq!(
+            encoded_bit_length("0123", EncodingMode::Numeric, version),
+            28
+        );
+        assert_eq!(
+            encoded_bit_length("AC-42", EncodingMode::Alphanumeric, version),
+            41
+        );
+        assert_eq!(
+            encoded_bit_length(&[0u8; 3][..], EncodingMode::Byte, version),
+            36
+        );
+        assert_eq!(
+            encoded_bit_length("12345", EncodingMode::Numeric, Version::new(10).unwrap()),
+            33
+        );
+    }
 }
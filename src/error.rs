@@ -0,0 +1,91 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use core::fmt::{Display, Formatter};
+
+/// The errors that can occur while creating a QR code
+#[derive(Clone, Copy, Debug, Ord, PartialOrd, Eq, PartialEq)]
+pub enum QrError {
+    /// No text or other data was given to the builder
+    MissingPayload,
+    /// The data doesn't fit in the largest allowed version
+    DataTooLong,
+    /// The character can't be encoded in the selected character set
+    InvalidCharacter { character: char, position: usize },
+    /// The version isn't supported by this crate
+    UnsupportedVersion { version: u8 },
+    /// There are only eight masks, or four for Micro QR codes
+    InvalidMaskReference { mask_reference: u8 },
+    /// The encoded data doesn't fit in the buffer
+    BufferOverflow,
+    /// The ECI assignment number isn't a character set that the encoder can write
+    UnsupportedEci { assignment: u32 },
+    /// The logo covers function patterns or more codewords than the error correction can repair
+    LogoTooLarge,
+    /// A field of a payload is empty, too long or contains a character it doesn't allow
+    InvalidField { field: &'static str },
+}
+
+impl Display for QrError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            QrError::MissingPayload => write!(f, "missing payload"),
+            QrError::DataTooLong => write!(f, "data too long"),
+            QrError::InvalidCharacter {
+                character,
+                position,
+            } => write!(
+                f,
+                "invalid character {:?} at position {}",
+                character, position
+            ),
+            QrError::UnsupportedVersion { version } => {
+                write!(f, "unsupported version {}", version)
+            }
+            QrError::InvalidMaskReference { mask_reference } => {
+                write!(f, "invalid mask reference {}", mask_reference)
+            }
+            QrError::BufferOverflow => write!(f, "buffer overflow"),
+            QrError::UnsupportedEci { assignment } => {
+                write!(f, "unsupported ECI assignment {}", assignment)
+            }
+            QrError::LogoTooLarge => write!(f, "logo too large"),
+            QrError::InvalidField { field } => write!(f, "invalid {}", field),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::QrError;
+    use alloc::string::ToString;
+
+    #[test]
+    fn display() {
+        let error = QrError::InvalidCharacter {
+            character: 'a',
+            position: 3,
+        };
+        assert_eq!(error.to_string(), "invalid character 'a' at position 3");
+        let error = QrError::UnsupportedVersion { version: 41 };
+        assert_eq!(error.to_string(), "unsupported version 41");
+        let error = QrError::UnsupportedEci { assignment: 35 };
+        assert_eq!(error.to_string(), "unsupported ECI assignment 35");
+        let error = QrError::InvalidField { field: "IBAN" };
+        assert_eq!(error.to_string(), "invalid IBAN");
+    }
+}
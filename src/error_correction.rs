@@ -15,10 +15,13 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-use crate::blocks::BlockLengthIterator;
-use crate::buffer::Buffer;
+use crate::blocks::{BlockLength, BlockLengthIterator};
+use crate::buffer::{Buffer, BUFFER_CAPACITY};
 use crate::encoding::EncodedData;
 use crate::qr_version::Version;
+use crate::reed_solomon;
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
 
 /// Qr codes use Reed–Solomon error correction
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
@@ -44,23 +47,113 @@ impl ErrorCorrectionLevel {
     }
 }
 
-pub struct ErrorCorrectedData {
+/// Formats the level as its standard letter: `L`, `M`, `Q` or `H`
+impl Display for ErrorCorrectionLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let letter = match self {
+            ErrorCorrectionLevel::Low => "L",
+            ErrorCorrectionLevel::Medium => "M",
+            ErrorCorrectionLevel::Quartile => "Q",
+            ErrorCorrectionLevel::High => "H",
+        };
+        f.write_str(letter)
+    }
+}
+
+/// The text isn't one of the letters `L`, `M`, `Q` or `H`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParseErrorCorrectionLevelError;
+
+impl Display for ParseErrorCorrectionLevelError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "error correction level is not one of L, M, Q or H")
+    }
+}
+
+/// Parses the standard letter of the level, in upper or lower case
+///
+/// # Example
+///```
+///     use tiny_qr::ErrorCorrectionLevel;
+///     let level: ErrorCorrectionLevel = "Q".parse().unwrap();
+///     assert_eq!(level, ErrorCorrectionLevel::Quartile);
+///     assert_eq!(level.to_string(), "Q");
+///```
+impl FromStr for ErrorCorrectionLevel {
+    type Err = ParseErrorCorrectionLevelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "L" | "l" => Ok(ErrorCorrectionLevel::Low),
+            "M" | "m" => Ok(ErrorCorrectionLevel::Medium),
+            "Q" | "q" => Ok(ErrorCorrectionLevel::Quartile),
+            "H" | "h" => Ok(ErrorCorrectionLevel::High),
+            _ => Err(ParseErrorCorrectionLevelError),
+        }
+    }
+}
+
+pub struct ErrorCorrectedData<S = [u8; BUFFER_CAPACITY]> {
     pub(crate) version: Version,
     pub(crate) error_correction: ErrorCorrectionLevel,
-    pub(crate) buffer: Buffer,
+    pub(crate) buffer: Buffer<S>,
 }
 
-pub fn add_error_correction(data: EncodedData) -> ErrorCorrectedData {
-    let mut buffer = data.buffer;
+impl<S: AsRef<[u8]> + AsMut<[u8]>> ErrorCorrectedData<S> {
+    /// The data codewords of all blocks, in block order
+    pub fn data_codewords(&self) -> &[u8] {
+        let data_len = self.version.data_codeword_count(self.error_correction);
+        &self.buffer.data()[..data_len]
+    }
 
-    let blocks = BlockLengthIterator::new(data.version, data.error_correction);
-    for block in blocks {
-        let encoder = reed_solomon::Encoder::new(block.ecc_len);
-        let ecc_buffer =
-            encoder.encode(&buffer.data()[block.data_pos..block.data_pos + block.data_len]);
-        buffer.append_bytes(ecc_buffer.ecc());
+    /// The error correction codewords of all blocks, in block order
+    pub fn ecc_codewords(&self) -> &[u8] {
+        let data_len = self.version.data_codeword_count(self.error_correction);
+        &self.buffer.data()[data_len..]
+    }
+
+    pub fn block_count(&self) -> usize {
+        self.version
+            .error_correction_codeword_blocks_count(self.error_correction)
+            .1
+    }
+
+    /// The data codewords of a single block
+    pub fn block_data_codewords(&self, block_number: usize) -> &[u8] {
+        let block = self.block(block_number);
+        &self.buffer.data()[block.data_pos..block.data_pos + block.data_len]
+    }
+
+    /// The error correction codewords of a single block
+    pub fn block_ecc_codewords(&self, block_number: usize) -> &[u8] {
+        let block = self.block(block_number);
+        &self.buffer.data()[block.ecc_pos..block.ecc_pos + block.ecc_len]
     }
 
+    fn block(&self, block_number: usize) -> BlockLength {
+        BlockLengthIterator::new(self.version, self.error_correction)
+            .nth(block_number)
+            .expect("block number out of range")
+    }
+}
+
+pub fn add_error_correction<S: AsRef<[u8]> + AsMut<[u8]>>(
+    data: EncodedData<S>,
+) -> ErrorCorrectedData<S> {
+    let mut buffer = data.buffer;
+
+    let ecc_len = data
+        .version
+        .error_correction_codeword_blocks_count(data.error_correction)
+        .0;
+    let (data_codewords, ecc_codewords) = buffer.split_append(ecc_len);
+    write_error_correction(
+        data.version,
+        data.error_correction,
+        data_codewords,
+        ecc_codewords,
+    );
+
     ErrorCorrectedData {
         version: data.version,
         error_correction: data.error_correction,
@@ -68,12 +161,152 @@ pub fn add_error_correction(data: EncodedData) -> ErrorCorrectedData {
     }
 }
 
+/// Calculates the error correction codewords for the data codewords and writes them into `ecc`
+///
+/// The error correction codewords of all blocks are written in block order, without interleaving.
+/// This allows the caller to place the codewords in memory of its choice, for example directly
+/// after the data codewords.
+///
+/// # Panics
+/// Panics when `data` or `ecc` doesn't have the length required by the version and error
+/// correction level.
+///
+/// # Example
+///```
+///     use tiny_qr::{write_error_correction, ErrorCorrectionLevel, Version};
+///     let mut codewords = [0u8; 26];
+///     codewords[..16].copy_from_slice(&[
+///         0x10, 0x20, 0x0c, 0x56, 0x61, 0x80, 0xec, 0x11, 0xec, 0x11, 0xec, 0x11, 0xec, 0x11, 0xec, 0x11,
+///     ]);
+///     let (data, ecc) = codewords.split_at_mut(16);
+///     write_error_correction(Version::new(1).unwrap(), ErrorCorrectionLevel::Medium, data, ecc);
+///     assert_eq!(ecc, [0xa5, 0x24, 0xd4, 0xc1, 0xed, 0x36, 0xc7, 0x87, 0x2c, 0x55]);
+///```
+pub fn write_error_correction(
+    version: Version,
+    error_correction: ErrorCorrectionLevel,
+    data: &[u8],
+    ecc: &mut [u8],
+) {
+    let (ecc_len, _) = version.error_correction_codeword_blocks_count(error_correction);
+    assert_eq!(data.len(), version.data_codeword_count(error_correction));
+    assert_eq!(ecc.len(), ecc_len);
+
+    let blocks = BlockLengthIterator::new(version, error_correction);
+    for block in blocks {
+        // The ECC positions are relative to the start of the data codewords
+        let ecc_pos = block.ecc_pos - data.len();
+        reed_solomon::encode(
+            &data[block.data_pos..block.data_pos + block.data_len],
+            &mut ecc[ecc_pos..ecc_pos + block.ecc_len],
+        );
+    }
+}
+
+/// Corrects damaged codewords in place, using the error correction codewords of every block
+///
+/// The codewords are the data codewords of all blocks followed by the error correction codewords,
+/// in block order like [`ErrorCorrectedData`]. The erasures are the positions of codewords that are
+/// known to be damaged, for example because the modules couldn't be read. Each block can repair as
+/// many erasures as it has error correction codewords, or half as many errors of unknown position.
+///
+/// Returns the number of corrected codewords, or `None` when a block is damaged beyond repair.
+///
+/// # Panics
+/// Panics when `codewords` doesn't have the length required by the version and error correction
+/// level.
+///
+/// # Example
+///```
+///     use tiny_qr::{correct_errors, write_error_correction, ErrorCorrectionLevel, Version};
+///     let version = Version::new(1).unwrap();
+///     let mut codewords = [0u8; 26];
+///     codewords[..16].copy_from_slice(b"tiny-qr codeword");
+///     let (data, ecc) = codewords.split_at_mut(16);
+///     write_error_correction(version, ErrorCorrectionLevel::Medium, data, ecc);
+///     codewords[3] = 0;
+///     codewords[20] ^= 0xff;
+///     assert_eq!(correct_errors(version, ErrorCorrectionLevel::Medium, &mut codewords, &[]), Some(2));
+///     assert_eq!(&codewords[..16], b"tiny-qr codeword");
+///```
+pub fn correct_errors(
+    version: Version,
+    error_correction: ErrorCorrectionLevel,
+    codewords: &mut [u8],
+    erasures: &[usize],
+) -> Option<usize> {
+    assert_eq!(codewords.len(), version.total_codeword_count());
+
+    let mut corrected = 0;
+    for block in BlockLengthIterator::new(version, error_correction) {
+        let data_range = block.data_pos..block.data_pos + block.data_len;
+        let ecc_range = block.ecc_pos..block.ecc_pos + block.ecc_len;
+        // Gather the block and its erasures, as the codewords of a block aren't adjacent
+        let mut block_codewords = [0; 255];
+        let block_codewords = &mut block_codewords[..block.data_len + block.ecc_len];
+        let (data, ecc) = block_codewords.split_at_mut(block.data_len);
+        data.copy_from_slice(&codewords[data_range.clone()]);
+        ecc.copy_from_slice(&codewords[ecc_range.clone()]);
+
+        let mut block_erasures = [0; 255];
+        let mut erasure_count = 0;
+        for &position in erasures {
+            let block_position = if data_range.contains(&position) {
+                position - block.data_pos
+            } else if ecc_range.contains(&position) {
+                block.data_len + position - block.ecc_pos
+            } else {
+                continue;
+            };
+            // More erasures than error correction codewords can't be corrected anyway
+            *block_erasures.get_mut(erasure_count)? = block_position;
+            erasure_count += 1;
+        }
+
+        corrected += reed_solomon::correct(
+            block_codewords,
+            block.ecc_len,
+            &block_erasures[..erasure_count],
+        )?;
+        let (data, ecc) = block_codewords.split_at(block.data_len);
+        codewords[data_range].copy_from_slice(data);
+        codewords[ecc_range].copy_from_slice(ecc);
+    }
+    Some(corrected)
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::blocks::BlockLengthIterator;
     use crate::buffer::Buffer;
     use crate::encoding::EncodedData;
-    use crate::error_correction::{add_error_correction, ErrorCorrectionLevel};
+    use crate::error_correction::{
+        add_error_correction, correct_errors, ErrorCorrectionLevel, ParseErrorCorrectionLevelError,
+    };
     use crate::qr_version::Version;
+    use alloc::string::ToString;
+
+    #[test]
+    fn letters() {
+        for level in [
+            ErrorCorrectionLevel::Low,
+            ErrorCorrectionLevel::Medium,
+            ErrorCorrectionLevel::Quartile,
+            ErrorCorrectionLevel::High,
+        ] {
+            let letter = level.to_string();
+            assert_eq!(letter.parse(), Ok(level));
+            assert_eq!(letter.to_lowercase().parse(), Ok(level));
+        }
+        assert_eq!(
+            "X".parse::<ErrorCorrectionLevel>(),
+            Err(ParseErrorCorrectionLevelError)
+        );
+        assert_eq!(
+            "".parse::<ErrorCorrectionLevel>(),
+            Err(ParseErrorCorrectionLevelError)
+        );
+    }
 
     #[test]
     fn error_correction_encoding() {
@@ -86,7 +319,7 @@ mod tests {
         ]);
 
         let data = EncodedData {
-            version: Version { version: 1 },
+            version: Version::new(1).unwrap(),
             error_correction: ErrorCorrectionLevel::Medium,
             buffer,
         };
@@ -106,7 +339,7 @@ mod tests {
     #[test]
     fn error_correction_encoding_5q() {
         // Version 1-M with text "01234567"
-        let mut buffer = Buffer::new();
+        let mut buffer = Buffer::<[u8; 134]>::default();
         buffer.append_bytes(&[
             67, 85, 70, 134, 87, 38, 85, 194, 119, 50, 6, 18, 6, 103, 38, 246, 246, 66, 7, 118,
             134, 242, 7, 38, 86, 22, 198, 199, 146, 6, 182, 230, 247, 119, 50, 7, 118, 134, 87, 38,
@@ -115,7 +348,7 @@ mod tests {
         ]);
 
         let data = EncodedData {
-            version: Version { version: 5 },
+            version: Version::new(5).unwrap(),
             error_correction: ErrorCorrectionLevel::Quartile,
             buffer,
         };
@@ -133,6 +366,82 @@ mod tests {
                 189, 10, 108, 240, 192, 141, 235, 159, 5, 173, 24, 147, 59, 33, 106, 40, 255, 172,
                 82, 2, 131, 32, 178, 236,
             ]
-        )
+        );
+        assert_eq!(error_corrected_data.data_codewords().len(), 62);
+        assert_eq!(error_corrected_data.ecc_codewords().len(), 72);
+        assert_eq!(error_corrected_data.block_count(), 4);
+        assert_eq!(
+            error_corrected_data.block_data_codewords(2),
+            [182, 230, 247, 119, 50, 7, 118, 134, 87, 38, 82, 6, 134, 151, 50, 7]
+        );
+        assert_eq!(
+            error_corrected_data.block_ecc_codewords(0),
+            [
+                213, 199, 11, 45, 115, 247, 241, 223, 229, 248, 154, 117, 154, 111, 86, 161, 111,
+                39
+            ]
+        );
+    }
+
+    #[test]
+    fn recovery_capacity() {
+        // The levels allow recovery of about 7, 15, 25 and 30 percent of the codewords
+        let levels = [
+            (ErrorCorrectionLevel::Low, 7),
+            (ErrorCorrectionLevel::Medium, 15),
+            (ErrorCorrectionLevel::Quartile, 25),
+            (ErrorCorrectionLevel::High, 30),
+        ];
+        let version = Version::new(4).unwrap();
+        for (level, percentage) in levels {
+            let mut buffer = Buffer::new();
+            for i in 0..version.data_codeword_count(level) {
+                buffer.append_byte((i * 37) as u8);
+            }
+            let data = EncodedData {
+                version,
+                error_correction: level,
+                buffer,
+            };
+            let original = add_error_correction(data);
+            let mut codewords = [0; 100];
+            codewords.copy_from_slice(original.buffer.data());
+
+            // Damage as many data codewords of every block as half its error correction codewords
+            let mut damaged = 0;
+            for block in BlockLengthIterator::new(version, level) {
+                for i in 0..block.ecc_len / 2 {
+                    codewords[block.data_pos + i] ^= 0xa5;
+                    damaged += 1;
+                }
+            }
+            assert!(100 * damaged >= percentage * version.total_codeword_count());
+            assert_eq!(
+                correct_errors(version, level, &mut codewords, &[]),
+                Some(damaged)
+            );
+            assert_eq!(codewords, original.buffer.data());
+
+            // One more error in a block is too many
+            let block = BlockLengthIterator::new(version, level).next().unwrap();
+            for i in 0..=block.ecc_len / 2 {
+                codewords[block.data_pos + i] ^= 0xa5;
+            }
+            assert_eq!(correct_errors(version, level, &mut codewords, &[]), None);
+
+            // Erased codewords can be corrected up to the full error correction capacity
+            let mut codewords = [0; 100];
+            codewords.copy_from_slice(original.buffer.data());
+            let block = BlockLengthIterator::new(version, level).next().unwrap();
+            let erasures: [usize; 16] = core::array::from_fn(|i| block.ecc_pos + i);
+            for &position in &erasures {
+                codewords[position] = 0;
+            }
+            assert_eq!(
+                correct_errors(version, level, &mut codewords, &erasures),
+                Some(16)
+            );
+            assert_eq!(codewords, original.buffer.data());
+        }
     }
 }
@@ -15,69 +15,173 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
+use crate::array_2d::Storage;
 use crate::error_correction::ErrorCorrectionLevel;
-use crate::mask::Masked;
+use crate::mask::{Mask, Masked};
+use crate::matrix::Module;
 
-pub struct Formatted<const N: usize> {
-    pub masked: Masked<N>,
+/// Both copies of the format information as read from a symbol
+///
+/// # Example
+///```
+///     use tiny_qr::{ErrorCorrectionLevel, Mask, QrCodeBuilder};
+///     let qr_code = QrCodeBuilder::new()
+///         .with_specific_error_correction_level(ErrorCorrectionLevel::Quartile)
+///         .with_mask_reference(Mask::Mask3)
+///         .with_text("HELLO")
+///         .build();
+///     let format_info = qr_code.format_info();
+///     assert!(format_info.copies_agree());
+///     assert!(format_info.matches(ErrorCorrectionLevel::Quartile, Mask::Mask3));
+///```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FormatInfoCheck {
+    /// The copy around the top-left finder pattern
+    pub first_copy: u16,
+    /// The copy split between the top-right and bottom-left finder patterns
+    pub second_copy: u16,
 }
 
-impl<const N: usize> Formatted<N> {
-    pub fn from(masked: Masked<N>) -> Self {
-        let mut masked = masked;
+impl FormatInfoCheck {
+    pub fn copies_agree(&self) -> bool {
+        self.first_copy == self.second_copy
+    }
+
+    /// Decodes the first copy, correcting up to 3 bit errors
+    pub fn decode_first_copy(&self) -> Option<(ErrorCorrectionLevel, Mask)> {
+        decode(self.first_copy)
+    }
+
+    /// Decodes the second copy, correcting up to 3 bit errors
+    pub fn decode_second_copy(&self) -> Option<(ErrorCorrectionLevel, Mask)> {
+        decode(self.second_copy)
+    }
+
+    /// Whether both copies are exactly the encoding of the error correction level and mask
+    pub fn matches(&self, error_correction_level: ErrorCorrectionLevel, mask: Mask) -> bool {
+        let expected = encode(error_correction_level, mask.reference());
+        self.first_copy == expected && self.second_copy == expected
+    }
+}
+
+pub(crate) fn encode(error_correction_level: ErrorCorrectionLevel, mask_reference: u8) -> u16 {
+    let error_correction_level = match error_correction_level {
+        ErrorCorrectionLevel::Low => 0b01,
+        ErrorCorrectionLevel::Medium => 0b00,
+        ErrorCorrectionLevel::Quartile => 0b11,
+        ErrorCorrectionLevel::High => 0b10,
+    };
+    let data = (error_correction_level << 3) + mask_reference;
+    masked_sequence(data)
+}
+
+fn decode(format: u16) -> Option<(ErrorCorrectionLevel, Mask)> {
+    let (data, distance) = (0..32)
+        .map(|data| (data, (masked_sequence(data) ^ format).count_ones()))
+        .min_by_key(|&(_, distance)| distance)
+        .unwrap();
+    if distance > 3 {
+        return None;
+    }
+    let error_correction_level = match data >> 3 {
+        0b01 => ErrorCorrectionLevel::Low,
+        0b00 => ErrorCorrectionLevel::Medium,
+        0b11 => ErrorCorrectionLevel::Quartile,
+        _ => ErrorCorrectionLevel::High,
+    };
+    Some((error_correction_level, Mask::ALL[(data & 0b111) as usize]))
+}
 
-        let format = Self::encode(masked.matrix.error_correction, masked.mask_reference);
-        masked.matrix.place_format(format);
+pub(crate) fn masked_sequence(data_bits: u8) -> u16 {
+    match data_bits {
+        0 => 0x5412,
+        1 => 0x5125,
+        2 => 0x5e7c,
+        3 => 0x5b4b,
+        4 => 0x45f9,
+        5 => 0x40ce,
+        6 => 0x4f97,
+        7 => 0x4aa0,
+        8 => 0x77c4,
+        9 => 0x72f3,
+        10 => 0x7daa,
+        11 => 0x789d,
+        12 => 0x662f,
+        13 => 0x6318,
+        14 => 0x6c41,
+        15 => 0x6976,
+        16 => 0x1689,
+        17 => 0x13be,
+        18 => 0x1ce7,
+        19 => 0x19d0,
+        20 => 0x0762,
+        21 => 0x0255,
+        22 => 0x0d0c,
+        23 => 0x083b,
+        24 => 0x355f,
+        25 => 0x3068,
+        26 => 0x3f31,
+        27 => 0x3a06,
+        28 => 0x24b4,
+        29 => 0x2183,
+        30 => 0x2eda,
+        31 => 0x2bed,
+        _ => panic!(),
+    }
+}
 
+pub struct Formatted<const N: usize, S = [[Module; N]; N]> {
+    pub masked: Masked<N, S>,
+}
+
+impl<const N: usize, S: Storage<Module, N>> Formatted<N, S> {
+    pub fn from(masked: Masked<N, S>) -> Self {
+        let mut masked = masked;
+        masked.place_format();
         Self { masked }
     }
+}
 
-    pub fn encode(error_correction_level: ErrorCorrectionLevel, mask_reference: u8) -> u16 {
-        let error_correction_level = match error_correction_level {
-            ErrorCorrectionLevel::Low => 0b01,
-            ErrorCorrectionLevel::Medium => 0b00,
-            ErrorCorrectionLevel::Quartile => 0b11,
-            ErrorCorrectionLevel::High => 0b10,
-        };
-        let data = (error_correction_level << 3) + mask_reference;
-        Self::masked_sequence(data)
+impl<const N: usize, S: Storage<Module, N>> Masked<N, S> {
+    /// Places the format information of the error correction level and mask reference
+    pub(crate) fn place_format(&mut self) {
+        let format = encode(self.matrix.error_correction, self.mask_reference);
+        self.matrix.place_format(format);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error_correction::ErrorCorrectionLevel;
+    use crate::format::{decode, encode, FormatInfoCheck};
+    use crate::mask::Mask;
+
+    #[test]
+    fn decode_with_errors() {
+        let format = encode(ErrorCorrectionLevel::Medium, 5);
+        assert_eq!(
+            decode(format),
+            Some((ErrorCorrectionLevel::Medium, Mask::Mask5))
+        );
+        assert_eq!(
+            decode(format ^ 0b100_0000_0100_0001),
+            Some((ErrorCorrectionLevel::Medium, Mask::Mask5))
+        );
+        assert_eq!(decode(format ^ 0b111_1000_0000_0000), None);
     }
 
-    fn masked_sequence(data_bits: u8) -> u16 {
-        match data_bits {
-            0 => 0x5412,
-            1 => 0x5125,
-            2 => 0x5e7c,
-            3 => 0x5b4b,
-            4 => 0x45f9,
-            5 => 0x40ce,
-            6 => 0x4f97,
-            7 => 0x4aa0,
-            8 => 0x77c4,
-            9 => 0x72f3,
-            10 => 0x7daa,
-            11 => 0x789d,
-            12 => 0x662f,
-            13 => 0x6318,
-            14 => 0x6c41,
-            15 => 0x6976,
-            16 => 0x1689,
-            17 => 0x13be,
-            18 => 0x1ce7,
-            19 => 0x19d0,
-            20 => 0x0762,
-            21 => 0x0255,
-            22 => 0x0d0c,
-            23 => 0x083b,
-            24 => 0x355f,
-            25 => 0x3068,
-            26 => 0x3f31,
-            27 => 0x3a06,
-            28 => 0x24b4,
-            29 => 0x2183,
-            30 => 0x2eda,
-            31 => 0x2bed,
-            _ => panic!(),
-        }
+    #[test]
+    fn copies_disagree() {
+        let format = encode(ErrorCorrectionLevel::High, 2);
+        let check = FormatInfoCheck {
+            first_copy: format,
+            second_copy: format ^ 1,
+        };
+        assert!(!check.copies_agree());
+        assert!(!check.matches(ErrorCorrectionLevel::High, Mask::Mask2));
+        assert_eq!(
+            check.decode_second_copy(),
+            Some((ErrorCorrectionLevel::High, Mask::Mask2))
+        );
     }
 }
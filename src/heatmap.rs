@@ -0,0 +1,146 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::array_2d::{Array2D, Coordinate};
+use crate::bit_array_2d::BitArray2D;
+use crate::mask::{Penalty, PenaltyRule};
+use crate::matrix::Color;
+use core::fmt;
+
+impl<const N: usize> Penalty for BitArray2D<N> {
+    fn size(&self) -> Coordinate {
+        BitArray2D::size(self)
+    }
+
+    fn color(&self, x: usize, y: usize) -> Color {
+        self[(x, y).into()]
+    }
+}
+
+/// The penalty rules that fired for a single module
+///
+/// The dark module proportion rule applies to the whole symbol and isn't included.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PenaltyRules {
+    /// Part of five or more adjacent modules of the same color in a row or column
+    pub adjacent: bool,
+    /// Part of a 2×2 block of modules of the same color
+    pub block: bool,
+    /// Part of a 1:1:3:1:1 finder-like pattern with four light modules on one side
+    pub finder_like: bool,
+}
+
+impl PenaltyRules {
+    pub fn count(&self) -> usize {
+        self.adjacent as usize + self.block as usize + self.finder_like as usize
+    }
+}
+
+/// Records which penalty rules fired for each module of a symbol
+///
+/// # Example
+///```
+///     use tiny_qr::QrCodeBuilder;
+///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+///     let heatmap = qr_code.penalty_heatmap();
+///     // The finder patterns always contain 2×2 blocks
+///     assert!(heatmap.get(2, 2).block);
+///     let mut pgm = String::new();
+///     heatmap.write_pgm(&mut pgm).unwrap();
+///     assert!(pgm.starts_with("P2\n21 21\n3\n"));
+///```
+pub struct PenaltyHeatmap<const N: usize> {
+    data: Array2D<PenaltyRules, N>,
+}
+
+impl<const N: usize> PenaltyHeatmap<N> {
+    pub(crate) fn from(colors: &BitArray2D<N>) -> Self {
+        let mut data = Array2D::<PenaltyRules, N>::new();
+        data.set_size(colors.size());
+        colors.report_penalties(|rule, x, y| {
+            let rules = &mut data[(x, y).into()];
+            match rule {
+                PenaltyRule::Adjacent => rules.adjacent = true,
+                PenaltyRule::Block => rules.block = true,
+                PenaltyRule::FinderLike => rules.finder_like = true,
+            }
+        });
+        Self { data }
+    }
+
+    /// Returns the penalty rules that fired for the module at the given column and row
+    pub fn get(&self, x: usize, y: usize) -> PenaltyRules {
+        self.data[(y, x).into()]
+    }
+
+    /// The number of modules on each side
+    pub fn size(&self) -> usize {
+        self.data.size().x
+    }
+
+    /// Writes the heatmap as a plain PGM image, modules with more penalty rules are darker
+    pub fn write_pgm(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        let size = self.size();
+        write!(out, "P2\n{} {}\n3\n", size, size)?;
+        for y in 0..size {
+            for x in 0..size {
+                if x > 0 {
+                    out.write_char(' ')?;
+                }
+                write!(out, "{}", 3 - self.get(x, y).count())?;
+            }
+            out.write_char('\n')?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bit_array_2d::BitArray2D;
+    use crate::heatmap::PenaltyHeatmap;
+    use crate::matrix::Color;
+    use alloc::string::String;
+
+    #[test]
+    fn rules() {
+        let mut colors = BitArray2D::<12>::new();
+        // A finder-like pattern followed by four light modules in the first row
+        for y in [0, 2, 3, 4, 6] {
+            colors.set((0, y).into(), Color::Black);
+        }
+        let heatmap = PenaltyHeatmap::from(&colors);
+
+        assert!(heatmap.get(0, 0).finder_like);
+        assert!(heatmap.get(10, 0).finder_like);
+        assert!(!heatmap.get(11, 0).finder_like);
+        assert!(heatmap.get(7, 0).adjacent);
+        assert!(!heatmap.get(6, 0).adjacent);
+        assert!(!heatmap.get(2, 0).block);
+        assert!(heatmap.get(0, 5).block);
+        assert_eq!(heatmap.get(8, 1).count(), 2);
+    }
+
+    #[test]
+    fn pgm() {
+        let colors = BitArray2D::<2>::new();
+        let heatmap = PenaltyHeatmap::from(&colors);
+        let mut pgm = String::new();
+        heatmap.write_pgm(&mut pgm).unwrap();
+        assert_eq!(pgm, "P2\n2 2\n3\n2 2\n2 2\n");
+    }
+}
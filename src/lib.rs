@@ -16,24 +16,68 @@
  */
 
 #![no_std]
+// `is_multiple_of` needs Rust 1.87; the `%` form keeps older compilers working.
+#![allow(clippy::manual_is_multiple_of)]
 
 //TODO: Remove alloc requirement
 extern crate alloc;
 
 mod array_2d;
+#[cfg(feature = "art")]
+mod art;
+mod bit_array_2d;
 mod blocks;
 pub mod buffer;
+pub mod decoder;
 mod draw_iterator;
-mod encoding;
+#[cfg(feature = "alloc")]
+mod dyn_qrcode;
+pub mod encoding;
+mod error;
 mod error_correction;
 mod format;
+mod heatmap;
+mod logo;
 mod mask;
 mod matrix;
+mod micro;
+mod packed_rows;
+pub mod payload;
+mod preview;
 mod qr_version;
 mod qrcode;
+mod reed_solomon;
+pub mod render;
+mod run_length;
+mod structured_append;
+mod symbol;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
-pub use matrix::Color;
-pub use qrcode::QrCodeBuilder;
+pub use draw_iterator::Orientation;
+#[cfg(feature = "alloc")]
+pub use dyn_qrcode::DynQrCode;
+pub use error::QrError;
+pub use error_correction::{
+    add_error_correction, correct_errors, write_error_correction, ErrorCorrectedData,
+    ErrorCorrectionLevel, ParseErrorCorrectionLevelError,
+};
+pub use format::FormatInfoCheck;
+pub use heatmap::{PenaltyHeatmap, PenaltyRules};
+pub use logo::{LogoArea, PixelRect};
+pub use mask::{Mask, ScoringModel};
+pub use matrix::{Color, ModuleKind};
+pub use micro::{MicroMask, MicroQrCodeBuilder, MicroVersion};
+pub use preview::LivePreview;
+pub use qr_version::Version;
+pub use qrcode::{
+    BuildPayload, FormattedText, NoPayload, QrCode, QrCodeBuilder, QrCodeMax, WithDigits,
+    WithPayload,
+};
+pub use structured_append::{encode_split, SplitSymbols};
+pub use symbol::Symbol;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,231 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::array_2d::Coordinate;
+use crate::blocks::BlockLengthIterator;
+use crate::error::QrError;
+use crate::error_correction::ErrorCorrectionLevel;
+use crate::matrix::{ModuleKind, PositionIterator};
+use crate::qr_version::Version;
+
+/// The number of blocks of version 40 at level H, the most of any symbol
+const MAX_BLOCK_COUNT: usize = 81;
+
+/// A square in the center of the symbol that may be covered by a logo
+///
+/// The modules below the logo read as errors, so every block of codewords must be able to correct
+/// the codewords it loses. The area must also stay clear of the function patterns.
+///
+/// # Example
+///```
+///     use tiny_qr::{ErrorCorrectionLevel, LogoArea, Version};
+///     let version = Version::new(4).unwrap();
+///     let area = LogoArea::plan(version, ErrorCorrectionLevel::High, 10).unwrap();
+///     assert_eq!((area.start, area.size), (11, 10));
+///     // Rendered with 10 pixels per module and the quiet zone of 4 modules
+///     let rect = area.pixel_rect(10, 4);
+///     assert_eq!((rect.x, rect.y, rect.width, rect.height), (150, 150, 100, 100));
+///```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LogoArea {
+    /// The first row and column of the square, in modules
+    pub start: usize,
+    /// The side length of the square, in modules
+    pub size: usize,
+    /// The largest number of codewords of a single block below the square
+    pub damaged_codewords: usize,
+    /// The number of erroneous codewords a single block can correct
+    pub correctable_codewords: usize,
+}
+
+/// A rectangle in pixels of the rendered output
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PixelRect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl LogoArea {
+    /// Plans a square covering the percentage of the symbol, or returns
+    /// [`QrError::LogoTooLarge`] when the error correction can't repair the damage
+    ///
+    /// The error correction is fully used up by the logo, so choose a higher level or a smaller
+    /// logo to leave room for damage and blur. Returns [`QrError::InvalidField`] when the
+    /// percentage is above 100.
+    pub fn plan(
+        version: Version,
+        error_correction: ErrorCorrectionLevel,
+        percent: u8,
+    ) -> Result<Self, QrError> {
+        if percent > 100 {
+            return Err(QrError::InvalidField { field: "percent" });
+        }
+        let width = version.width();
+        let area = width * width * usize::from(percent) / 100;
+        let size = (0..=width)
+            .take_while(|size| size * size <= area)
+            .last()
+            .unwrap();
+        let start = (width - size) / 2;
+        let inside = |i: usize| (start..start + size).contains(&i);
+
+        for row in start..start + size {
+            for column in start..start + size {
+                if ModuleKind::of(version, row, column) != ModuleKind::Data {
+                    return Err(QrError::LogoTooLarge);
+                }
+            }
+        }
+
+        let total_bit_len = 8 * version.total_codeword_count();
+        let mut data_positions = PositionIterator::new(Coordinate::new(width, width))
+            .filter(|pos| ModuleKind::of(version, pos.x, pos.y) == ModuleKind::Data)
+            .take(total_bit_len);
+
+        // The block of each codeword in placement order, following the interleaving
+        let blocks = || BlockLengthIterator::new(version, error_correction);
+        let max_data_len = blocks().map(|block| block.data_len).max().unwrap();
+        let ecc_len = blocks().next().unwrap().ecc_len;
+        let placement = || {
+            (0..max_data_len)
+                .flat_map(move |offset| {
+                    blocks()
+                        .filter(move |block| offset < block.data_len)
+                        .map(|block| block.block_number)
+                })
+                .chain((0..ecc_len).flat_map(move |_| blocks().map(|block| block.block_number)))
+        };
+
+        // Count the codewords with a module below the logo per block, the eight bits of a
+        // codeword are placed one after the other
+        let mut damaged = [0; MAX_BLOCK_COUNT];
+        for number in placement() {
+            let covered = data_positions
+                .by_ref()
+                .take(8)
+                .filter(|pos| inside(pos.x) && inside(pos.y))
+                .count();
+            if covered > 0 {
+                damaged[number] += 1;
+            }
+        }
+        let damaged_codewords = *damaged.iter().max().unwrap();
+        let correctable_codewords = (ecc_len - misdecode_protection(version, error_correction)) / 2;
+
+        if damaged_codewords > correctable_codewords {
+            return Err(QrError::LogoTooLarge);
+        }
+        Ok(Self {
+            start,
+            size,
+            damaged_codewords,
+            correctable_codewords,
+        })
+    }
+
+    /// The rectangle in an image with the module size in pixels and the margin in modules
+    pub fn pixel_rect(&self, module_size: usize, margin: usize) -> PixelRect {
+        let offset = (margin + self.start) * module_size;
+        PixelRect {
+            x: offset,
+            y: offset,
+            width: self.size * module_size,
+            height: self.size * module_size,
+        }
+    }
+}
+
+/// The error correction codewords reserved to detect misdecodes, see table 9 of the standard
+pub(crate) fn misdecode_protection(
+    version: Version,
+    error_correction: ErrorCorrectionLevel,
+) -> usize {
+    match (version.number(), error_correction) {
+        (1, ErrorCorrectionLevel::Low) => 3,
+        (1, ErrorCorrectionLevel::Medium) | (2, ErrorCorrectionLevel::Low) => 2,
+        (1, _) | (3, ErrorCorrectionLevel::Low) => 1,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decoder::decode_modules;
+    use crate::logo::LogoArea;
+    use crate::{Color, ErrorCorrectionLevel, QrCodeBuilder, QrError, Version};
+    use alloc::vec::Vec;
+
+    #[test]
+    fn levels() {
+        let version = Version::new(4).unwrap();
+        let plan = |level, percent| LogoArea::plan(version, level, percent);
+        assert_eq!(
+            plan(ErrorCorrectionLevel::Low, 0)
+                .unwrap()
+                .damaged_codewords,
+            0
+        );
+        assert!(plan(ErrorCorrectionLevel::Low, 5).is_ok());
+        assert_eq!(
+            plan(ErrorCorrectionLevel::Low, 8),
+            Err(QrError::LogoTooLarge)
+        );
+        assert!(plan(ErrorCorrectionLevel::Quartile, 8).is_ok());
+        assert_eq!(
+            plan(ErrorCorrectionLevel::High, 30),
+            Err(QrError::LogoTooLarge)
+        );
+        assert_eq!(
+            plan(ErrorCorrectionLevel::High, 101),
+            Err(QrError::InvalidField { field: "percent" })
+        );
+    }
+
+    #[test]
+    fn function_patterns() {
+        // The format information limits the square in version 1 to 3 modules
+        let version = Version::new(1).unwrap();
+        let area = LogoArea::plan(version, ErrorCorrectionLevel::High, 3).unwrap();
+        assert_eq!((area.start, area.size), (9, 3));
+        assert_eq!(
+            LogoArea::plan(version, ErrorCorrectionLevel::High, 5),
+            Err(QrError::LogoTooLarge)
+        );
+    }
+
+    #[test]
+    fn covered_symbol_decodes() {
+        let qr_code = QrCodeBuilder::new()
+            .with_specific_version(4)
+            .with_specific_error_correction_level(ErrorCorrectionLevel::High)
+            .with_text("LOGO")
+            .build();
+        let area = qr_code.logo_area(10).unwrap();
+        let width = qr_code.width();
+        let inside = |i: usize| (area.start..area.start + area.size).contains(&i);
+        // Cover the area with the inverse of the modules, the worst case for the decoder
+        let modules: Vec<bool> = qr_code
+            .modules()
+            .map(|(x, y, color)| (color == Color::Black) != (inside(x) && inside(y)))
+            .collect();
+        let decoded = decode_modules(width, &modules).unwrap();
+        assert_eq!(decoded.text(), "LOGO");
+        assert!(decoded.corrected_codewords() > 0);
+    }
+}
@@ -15,33 +15,35 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-use crate::format::Formatted;
-use crate::matrix::{Color, Matrix, Module};
-use core::iter::Peekable;
-
-pub struct Masked<const N: usize> {
+use crate::array_2d::{Coordinate, Storage};
+use crate::error::QrError;
+use crate::format::{self, Formatted};
+use crate::matrix::{format_bit_index, Color, Matrix, Module};
+use core::convert::TryFrom;
+use core::ops::Range;
+
+pub struct Masked<const N: usize, S = [[Module; N]; N]> {
     pub mask_reference: u8,
-    pub matrix: Matrix<N>,
+    pub matrix: Matrix<N, S>,
 }
 
-impl<const N: usize> Masked<N> {
-    pub fn from(matrix: Matrix<N>, reference: u8) -> Self {
-        let condition = match reference {
-            0b000 => |x, y| (x + y) % 2 == 0,
-            0b001 => |x, _y| x % 2 == 0,
-            0b010 => |_x, y| y % 3 == 0,
-            0b011 => |x, y| (x + y) % 3 == 0,
-            0b100 => |x, y| ((x / 2) + (y / 3)) % 2 == 0,
-            0b101 => |x, y| (x * y) % 2 + (x * y) % 3 == 0,
-            0b110 => |x, y| ((x * y) % 2 + (x * y) % 3) % 2 == 0,
-            0b111 => |x, y| ((x + y) % 2 + (x * y) % 3) % 2 == 0,
-            _ => panic!(),
+impl<const N: usize, S: Storage<Module, N>> Masked<N, S> {
+    pub fn from(matrix: Matrix<N, S>, reference: u8) -> Self {
+        let mut masked = Masked {
+            mask_reference: reference,
+            matrix,
         };
-        let mut masked = matrix;
-        let size = masked.data.size();
+        masked.toggle(reference);
+        masked
+    }
+
+    /// Inverts the data modules selected by the mask, applying it twice restores the matrix
+    fn toggle(&mut self, reference: u8) {
+        let condition = mask_condition(reference);
+        let size = self.matrix.data.size();
         for x in 0..size.x {
             for y in 0..size.y {
-                let module = &mut masked.data[(x, y).into()];
+                let module = &mut self.matrix.data[(x, y).into()];
                 if let Module::Filled(color) = module {
                     if condition(x, y) {
                         *module = Module::Filled(color.inverse())
@@ -49,213 +51,275 @@ impl<const N: usize> Masked<N> {
                 }
             }
         }
+    }
+}
 
-        Masked {
-            mask_reference: reference,
-            matrix: masked,
-        }
+/// One of the eight data masks, named after its mask reference
+///
+/// # Example
+///```
+///     use core::convert::TryFrom;
+///     use tiny_qr::{Mask, QrCodeBuilder};
+///     let qr_code = QrCodeBuilder::new().with_mask_reference(Mask::Mask3).with_text("HELLO").build();
+///     assert_eq!(qr_code.mask_reference(), Mask::Mask3);
+///     assert_eq!(Mask::try_from(3), Ok(Mask::Mask3));
+///```
+#[derive(Clone, Copy, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub enum Mask {
+    Mask0,
+    Mask1,
+    Mask2,
+    Mask3,
+    Mask4,
+    Mask5,
+    Mask6,
+    Mask7,
+}
+
+impl Mask {
+    pub const ALL: [Mask; 8] = [
+        Mask::Mask0,
+        Mask::Mask1,
+        Mask::Mask2,
+        Mask::Mask3,
+        Mask::Mask4,
+        Mask::Mask5,
+        Mask::Mask6,
+        Mask::Mask7,
+    ];
+
+    /// The mask reference as written in the format information
+    pub const fn reference(self) -> u8 {
+        self as u8
+    }
+}
+
+impl TryFrom<u8> for Mask {
+    type Error = QrError;
+
+    fn try_from(mask_reference: u8) -> Result<Self, Self::Error> {
+        Mask::ALL
+            .get(mask_reference as usize)
+            .copied()
+            .ok_or(QrError::InvalidMaskReference { mask_reference })
     }
 }
 
-pub struct ScoreMasked<const N: usize> {
+/// Returns whether the module at row `x` and column `y` is inverted by the mask
+pub(crate) fn mask_condition(reference: u8) -> fn(usize, usize) -> bool {
+    match reference {
+        0b000 => |x, y| (x + y) % 2 == 0,
+        0b001 => |x, _y| x % 2 == 0,
+        0b010 => |_x, y| y % 3 == 0,
+        0b011 => |x, y| (x + y) % 3 == 0,
+        0b100 => |x, y| ((x / 2) + (y / 3)) % 2 == 0,
+        0b101 => |x, y| (x * y) % 2 + (x * y) % 3 == 0,
+        0b110 => |x, y| ((x * y) % 2 + (x * y) % 3) % 2 == 0,
+        0b111 => |x, y| ((x + y) % 2 + (x * y) % 3) % 2 == 0,
+        _ => panic!(),
+    }
+}
+
+/// The rules used to select the best mask
+#[derive(Clone, Copy, Debug, Ord, PartialOrd, Eq, PartialEq)]
+pub enum ScoringModel {
+    /// The penalty rules of ISO/IEC 18004
+    Iso,
+    /// The penalty rules as implemented by ZXing, which treats the area outside the symbol as
+    /// light when looking for finder-like patterns and scores the dark proportion linearly
+    Zxing,
+}
+
+pub struct ScoreMasked<const N: usize, S = [[Module; N]; N]> {
     pub score: usize,
-    pub masked: Masked<N>,
+    pub masked: Masked<N, S>,
+}
+
+impl<const N: usize, S: Storage<Module, N>> Matrix<N, S> {
+    /// Applies the mask and its format information without scoring
+    pub fn apply_mask(self, mask: Mask) -> Masked<N, S> {
+        Formatted::from(Masked::from(self, mask.reference())).masked
+    }
+
+    /// Scores every mask directly on the unmasked matrix and only applies the best one
+    pub fn best_mask(self, scoring_model: ScoringModel) -> ScoreMasked<N, S> {
+        let (score, reference) = (0..8)
+            .map(|reference| {
+                let view = MaskView::new(&self, reference);
+                (view.score_with(scoring_model), reference)
+            })
+            .min_by_key(|&(score, _)| score)
+            .unwrap();
+
+        let mut masked = Masked::from(self, reference);
+        masked.place_format();
+        ScoreMasked { score, masked }
+    }
+}
+
+/// A masked and formatted symbol, of which the colors are computed from the unmasked matrix
+struct MaskView<'a, const N: usize, S> {
+    matrix: &'a Matrix<N, S>,
+    condition: fn(usize, usize) -> bool,
+    format: u16,
 }
 
-impl<const N: usize> ScoreMasked<N> {
-    pub fn from(formatted: Formatted<N>) -> Self {
-        let score = formatted.masked.score();
+impl<'a, const N: usize, S: Storage<Module, N>> MaskView<'a, N, S> {
+    fn new(matrix: &'a Matrix<N, S>, reference: u8) -> Self {
         Self {
-            score,
-            masked: formatted.masked,
+            matrix,
+            condition: mask_condition(reference),
+            format: format::encode(matrix.error_correction, reference),
         }
     }
 }
 
-impl<const N: usize> Matrix<N> {
-    pub fn mask(self, mask_reference: u8) -> ScoreMasked<N> {
-        let masked = Masked::from(self, mask_reference);
-        let formatted = Formatted::from(masked);
-        ScoreMasked::from(formatted)
+impl<const N: usize, S: Storage<Module, N>> Penalty for MaskView<'_, N, S> {
+    fn size(&self) -> Coordinate {
+        self.matrix.data.size()
     }
 
-    pub fn best_mask(self) -> ScoreMasked<N> {
-        (0..8)
-            .map(|reference| {
-                let masked = Masked::from(self, reference);
-                let formatted = Formatted::from(masked);
-                ScoreMasked::from(formatted)
-            })
-            .min_by_key(|x| x.score)
-            .unwrap()
+    fn color(&self, x: usize, y: usize) -> Color {
+        let size = self.size();
+        match self.matrix.data[(x, y).into()] {
+            Module::Filled(color) if (self.condition)(x, y) => color.inverse(),
+            Module::Reserved => match format_bit_index(size, (x, y).into()) {
+                Some(index) if self.format & (1 << index) != 0 => Color::Black,
+                None if (x, y) == (size.y - 8, 8) => Color::Black,
+                _ => Color::White,
+            },
+            module => module.into(),
+        }
     }
 }
 
-impl<const N: usize> Masked<N> {
+impl<const N: usize, S: Storage<Module, N>> Penalty for Masked<N, S> {
+    fn size(&self) -> Coordinate {
+        self.matrix.data.size()
+    }
+
+    fn color(&self, x: usize, y: usize) -> Color {
+        self.matrix.data[(x, y).into()].into()
+    }
+}
+
+/// A penalty rule that fires on the modules of a line or block
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum PenaltyRule {
+    Adjacent,
+    Block,
+    FinderLike,
+}
+
+/// The penalty rules, computed line by line from the color of each module
+pub(crate) trait Penalty {
+    fn size(&self) -> Coordinate;
+
+    fn color(&self, x: usize, y: usize) -> Color;
+
     fn score_adjacent_horizontal(&self) -> usize {
-        self.matrix
-            .data
-            .rows()
-            .map(|row| {
-                AdjacentIterator::new(row)
-                    .filter(|&i| i >= 5)
-                    .map(|i| i - 2)
-                    .sum::<usize>()
-            })
+        let size = self.size();
+        (0..size.x)
+            .map(|x| score_adjacent(size.y, |y| self.color(x, y)))
             .sum()
     }
 
     fn score_adjacent_vertical(&self) -> usize {
-        self.matrix
-            .data
-            .columns()
-            .map(|row| {
-                AdjacentIterator::new(row)
-                    .filter(|&i| i >= 5)
-                    .map(|i| i - 2)
-                    .sum::<usize>()
-            })
+        let size = self.size();
+        (0..size.y)
+            .map(|y| score_adjacent(size.x, |x| self.color(x, y)))
             .sum()
     }
 
+    /// Whether the module is the top left of a 2×2 block of modules of the same color
+    fn is_block(&self, x: usize, y: usize) -> bool {
+        let top_left = self.color(x, y);
+        top_left == self.color(x, y + 1)
+            && top_left == self.color(x + 1, y)
+            && top_left == self.color(x + 1, y + 1)
+    }
+
     fn score_blocks(&self) -> usize {
-        let size = self.matrix.data.size();
+        let size = self.size();
         (0..size.x - 1)
-            .map(|x| {
-                (0..size.y - 1)
-                    .map(|y| {
-                        let top_left: Color = self.matrix.data[(x, y).into()].into();
-                        let top_right: Color = self.matrix.data[(x, y + 1).into()].into();
-                        let bottom_left: Color = self.matrix.data[(x + 1, y).into()].into();
-                        let bottom_right: Color = self.matrix.data[(x + 1, y + 1).into()].into();
-                        if top_left == top_right
-                            && top_left == bottom_left
-                            && top_left == bottom_right
-                        {
-                            3
-                        } else {
-                            0
-                        }
-                    })
-                    .sum::<usize>()
-            })
+            .map(|x| (0..size.y - 1).filter(|&y| self.is_block(x, y)).count() * 3)
             .sum()
     }
 
-    fn score_match_pattern<'a, T>(mut iter: T) -> usize
-    where
-        T: Iterator<Item = &'a Module>,
-    {
-        let match_pattern1 = [
-            Color::Black,
-            Color::White,
-            Color::Black,
-            Color::Black,
-            Color::Black,
-            Color::White,
-            Color::Black,
-            Color::White,
-            Color::White,
-            Color::White,
-            Color::White,
-        ];
-        let match_pattern2 = [
-            Color::White,
-            Color::White,
-            Color::White,
-            Color::White,
-            Color::Black,
-            Color::White,
-            Color::Black,
-            Color::Black,
-            Color::Black,
-            Color::White,
-            Color::Black,
-        ];
-        let initial_pattern = |iter: &mut T| -> [Color; 11] {
-            [
-                (*iter.next().unwrap()).into(),
-                (*iter.next().unwrap()).into(),
-                (*iter.next().unwrap()).into(),
-                (*iter.next().unwrap()).into(),
-                (*iter.next().unwrap()).into(),
-                (*iter.next().unwrap()).into(),
-                (*iter.next().unwrap()).into(),
-                (*iter.next().unwrap()).into(),
-                (*iter.next().unwrap()).into(),
-                (*iter.next().unwrap()).into(),
-                (*iter.next().unwrap()).into(),
-            ]
-        };
-
-        let shift_pattern = |mut pattern: [Color; 11], iter: &mut T| -> Option<[Color; 11]> {
-            if let Some(&next) = iter.next() {
-                for i in 0..10 {
-                    pattern[i] = pattern[i + 1];
-                }
-                pattern[10] = next.into();
-                Some(pattern)
-            } else {
-                None
-            }
-        };
-
-        let mut pattern = initial_pattern(&mut iter);
-        let mut total = if pattern == match_pattern1 || pattern == match_pattern2 {
-            1
-        } else {
-            0
-        };
-        while let Some(shifted_pattern) = shift_pattern(pattern, &mut iter) {
-            pattern = shifted_pattern;
-            if pattern == match_pattern1 || pattern == match_pattern2 {
-                total += 1;
-            }
-        }
-        total
-    }
-
     fn score_pattern_horizontal(&self) -> usize {
-        self.matrix
-            .data
-            .rows()
-            .map(Self::score_match_pattern)
+        let size = self.size();
+        (0..size.x)
+            .map(|x| count_finder_like(size.y, |y| self.color(x, y)))
             .sum::<usize>()
             * 40
     }
 
     fn score_pattern_vertical(&self) -> usize {
-        self.matrix
-            .data
-            .columns()
-            .map(Self::score_match_pattern)
+        let size = self.size();
+        (0..size.y)
+            .map(|y| count_finder_like(size.x, |x| self.color(x, y)))
             .sum::<usize>()
             * 40
     }
 
-    fn score_proportion(&self) -> usize {
-        let black_count: usize = self
-            .matrix
-            .data
-            .rows()
-            .map(|row| {
-                row.filter(|&&module| {
-                    let color: Color = module.into();
-                    color == Color::Black
-                })
-                .count()
+    /// Reports every module on which a rule fires, with the same matching as the ISO score
+    ///
+    /// A module is reported once for every line or block it is part of. The dark module
+    /// proportion applies to the whole symbol and isn't reported.
+    fn report_penalties(&self, mut report: impl FnMut(PenaltyRule, usize, usize)) {
+        let size = self.size();
+        for x in 0..size.x {
+            let color = |y| self.color(x, y);
+            adjacent_runs(size.y, color, |run| {
+                run.for_each(|y| report(PenaltyRule::Adjacent, x, y))
+            });
+            finder_like_ranges(size.y, color, |range| {
+                range.for_each(|y| report(PenaltyRule::FinderLike, x, y))
+            });
+        }
+        for y in 0..size.y {
+            let color = |x| self.color(x, y);
+            adjacent_runs(size.x, color, |run| {
+                run.for_each(|x| report(PenaltyRule::Adjacent, x, y))
+            });
+            finder_like_ranges(size.x, color, |range| {
+                range.for_each(|x| report(PenaltyRule::FinderLike, x, y))
+            });
+        }
+        for x in 0..size.x - 1 {
+            for y in (0..size.y - 1).filter(|&y| self.is_block(x, y)) {
+                for (x, y) in [(x, y), (x, y + 1), (x + 1, y), (x + 1, y + 1)] {
+                    report(PenaltyRule::Block, x, y);
+                }
+            }
+        }
+    }
+
+    fn black_count(&self) -> usize {
+        let size = self.size();
+        (0..size.x)
+            .map(|x| {
+                (0..size.y)
+                    .filter(|&y| self.color(x, y) == Color::Black)
+                    .count()
             })
-            .sum();
-        let size = self.matrix.data.size();
-        let percentage = black_count * 100 / (size.x * size.y);
-        let k = if percentage < 50 {
-            50 - percentage
-        } else {
-            percentage - 50
-        };
+            .sum()
+    }
+
+    fn score_proportion(&self) -> usize {
+        let size = self.size();
+        let percentage = self.black_count() * 100 / (size.x * size.y);
+        let k = percentage.abs_diff(50);
         k / 5 * 10
     }
 
+    fn score_with(&self, scoring_model: ScoringModel) -> usize {
+        match scoring_model {
+            ScoringModel::Iso => self.score(),
+            ScoringModel::Zxing => self.score_zxing(),
+        }
+    }
+
     fn score(&self) -> usize {
         self.score_adjacent_horizontal()
             + self.score_adjacent_vertical()
@@ -264,65 +328,144 @@ impl<const N: usize> Masked<N> {
             + self.score_pattern_vertical()
             + self.score_proportion()
     }
-}
 
-struct AdjacentIterator<'a, T>
-where
-    T: Iterator<Item = &'a Module>,
-{
-    data_iter: Peekable<T>,
+    fn score_zxing(&self) -> usize {
+        let size = self.size();
+        let finder_like_count = (0..size.x)
+            .map(|x| count_finder_like_zxing(size.y, |y| self.color(x, y)))
+            .sum::<usize>()
+            + (0..size.y)
+                .map(|y| count_finder_like_zxing(size.x, |x| self.color(x, y)))
+                .sum::<usize>();
+
+        let total = size.x * size.y;
+        let five_percent_variances = (self.black_count() * 2).abs_diff(total) * 10 / total;
+
+        self.score_adjacent_horizontal()
+            + self.score_adjacent_vertical()
+            + self.score_blocks()
+            + finder_like_count * 40
+            + five_percent_variances * 10
+    }
 }
 
-impl<'a, T> AdjacentIterator<'a, T>
-where
-    T: Iterator<Item = &'a Module>,
-{
-    fn new(data_iter: T) -> Self {
-        Self {
-            data_iter: data_iter.peekable(),
+/// Calls `run` with every range of five or more adjacent modules of the same color in a line
+fn adjacent_runs(len: usize, color: impl Fn(usize) -> Color, mut run: impl FnMut(Range<usize>)) {
+    let mut start = 0;
+    for i in 1..=len {
+        if i == len || color(i) != color(start) {
+            if i - start >= 5 {
+                run(start..i);
+            }
+            start = i;
         }
     }
 }
 
-impl<'a, T> Iterator for AdjacentIterator<'a, T>
-where
-    T: Iterator<Item = &'a Module>,
-{
-    type Item = usize;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if let Some(&first) = self.data_iter.next() {
-            let first: Color = first.into();
-            let mut count = 1;
-            while let Some(&&later) = self.data_iter.peek() {
-                let later: Color = later.into();
-                if first == later {
-                    count += 1;
-                    self.data_iter.next();
-                } else {
-                    break;
-                }
-            }
-            Some(count)
-        } else {
-            None
+/// Scores the runs of five or more modules of the same color in a line
+fn score_adjacent(len: usize, color: impl Fn(usize) -> Color) -> usize {
+    let mut score = 0;
+    adjacent_runs(len, color, |run| score += run.len() - 2);
+    score
+}
+
+/// Calls `pattern` with the start of every 1:1:3:1:1 pattern in a line and whether the four
+/// modules before and after it are light
+///
+/// Only patterns with at least one light side are passed. When `outside_light` is set modules
+/// outside the line count as light, like ZXing does.
+fn finder_like_patterns(
+    len: usize,
+    color: impl Fn(usize) -> Color,
+    outside_light: bool,
+    mut pattern: impl FnMut(usize, bool, bool),
+) {
+    const PATTERN: [Color; 7] = [
+        Color::Black,
+        Color::White,
+        Color::Black,
+        Color::Black,
+        Color::Black,
+        Color::White,
+        Color::Black,
+    ];
+    let is_light = |range: Range<usize>| {
+        (outside_light || range.end <= len)
+            && range
+                .take_while(|&i| i < len)
+                .all(|i| color(i) == Color::White)
+    };
+    for start in (0..len.saturating_sub(6)).filter(|&i| (0..7).all(|j| color(i + j) == PATTERN[j]))
+    {
+        let before = (outside_light || start >= 4) && is_light(start.saturating_sub(4)..start);
+        let after = is_light(start + 7..start + 11);
+        if before || after {
+            pattern(start, before, after);
         }
     }
 }
 
+/// Calls `range` with the modules of every 1:1:3:1:1 pattern and its four light modules on one
+/// side within the line, twice when both sides are light
+fn finder_like_ranges(
+    len: usize,
+    color: impl Fn(usize) -> Color,
+    mut range: impl FnMut(Range<usize>),
+) {
+    finder_like_patterns(len, color, false, |start, before, after| {
+        if before {
+            range(start - 4..start + 7);
+        }
+        if after {
+            range(start..start + 11);
+        }
+    });
+}
+
+/// Counts the 1:1:3:1:1 patterns with four light modules on either side within the line
+fn count_finder_like(len: usize, color: impl Fn(usize) -> Color) -> usize {
+    let mut count = 0;
+    finder_like_patterns(len, color, false, |_, before, after| {
+        count += before as usize + after as usize
+    });
+    count
+}
+
+/// Counts the 1:1:3:1:1 patterns with four light modules on either side, where modules outside
+/// the line count as light
+fn count_finder_like_zxing(len: usize, color: impl Fn(usize) -> Color) -> usize {
+    let mut count = 0;
+    finder_like_patterns(len, color, true, |_, _, _| count += 1);
+    count
+}
+
 #[cfg(test)]
 mod tests {
     use crate::array_2d::Array2D;
     use crate::buffer::Buffer;
-    use crate::error_correction::{ErrorCorrectedData, ErrorCorrectionLevel};
-    use crate::mask::Masked;
+    use crate::encoding::EncodedData;
+    use crate::error_correction::{add_error_correction, ErrorCorrectedData, ErrorCorrectionLevel};
+    use crate::mask::{
+        count_finder_like_zxing, Formatted, Mask, MaskView, Masked, Penalty, ScoreMasked,
+        ScoringModel,
+    };
     use crate::matrix::{Color, Matrix, Module};
     use crate::qr_version::Version;
     use alloc::format;
 
+    /// Applies the mask and its format information and scores the result
+    fn score_mask<const N: usize>(matrix: Matrix<N>, mask_reference: u8) -> ScoreMasked<N> {
+        let masked = Masked::from(matrix, mask_reference);
+        let masked = Formatted::from(masked).masked;
+        ScoreMasked {
+            score: masked.score_with(ScoringModel::Iso),
+            masked,
+        }
+    }
+
     fn new_white_matrix() -> Matrix<21> {
         let mut matrix = Matrix {
-            version: Version { version: 1 },
+            version: Version::new(1).unwrap(),
             error_correction: ErrorCorrectionLevel::Low,
             data: Array2D::new(),
         };
@@ -609,12 +752,12 @@ ___███___███___███___
             0b00110110, 0b11000111, 0b10000111, 0b00101100, 0b01010101,
         ]);
         let data = ErrorCorrectedData {
-            version: Version { version: 1 },
+            version: Version::new(1).unwrap(),
             error_correction: ErrorCorrectionLevel::Quartile,
             buffer,
         };
 
-        let mut matrix = Matrix::<21>::from_data(data);
+        let matrix = Matrix::<21>::from_data(Array2D::new(), &data);
 
         let masked = Masked::from(matrix, 0b010);
 
@@ -662,12 +805,12 @@ ___███▓██__█_█__█____
             156, 0, 46, 15, 180, 122, 16,
         ]);
         let data = ErrorCorrectedData {
-            version: Version { version: 1 },
+            version: Version::new(1).unwrap(),
             error_correction: ErrorCorrectionLevel::Quartile,
             buffer,
         };
 
-        let mut matrix = Matrix::<21>::from_data(data);
+        let matrix = Matrix::<21>::from_data(Array2D::new(), &data);
         let masked = Masked::from(matrix, 0);
 
         let adjacent_horizontal = masked.score_adjacent_horizontal();
@@ -729,36 +872,44 @@ ___███▓██__█_█__█____
             156, 0, 46, 15, 180, 122, 16,
         ]);
         let data = ErrorCorrectedData {
-            version: Version { version: 1 },
+            version: Version::new(1).unwrap(),
             error_correction: ErrorCorrectionLevel::Quartile,
             buffer,
         };
 
-        let mut matrix = Matrix::<21>::from_data(data);
+        let matrix = Matrix::<21>::from_data(Array2D::new(), &data);
 
-        let scored = matrix.mask(0);
+        let scored = score_mask(matrix, 0);
         assert_eq!(scored.score, 347);
 
-        let scored = matrix.mask(1);
+        let scored = score_mask(matrix, 1);
         assert_eq!(scored.score, 470);
 
-        let scored = matrix.mask(2);
+        let scored = score_mask(matrix, 2);
         assert_eq!(scored.score, 506);
 
-        let scored = matrix.mask(3);
+        let scored = score_mask(matrix, 3);
         assert_eq!(scored.score, 441);
 
-        let scored = matrix.mask(4);
+        let scored = score_mask(matrix, 4);
         assert_eq!(scored.score, 539);
 
-        let scored = matrix.mask(5);
+        let scored = score_mask(matrix, 5);
         assert_eq!(scored.score, 516);
 
-        let scored = matrix.mask(6);
+        let scored = score_mask(matrix, 6);
         assert_eq!(scored.score, 314);
 
-        let scored = matrix.mask(7);
+        let scored = score_mask(matrix, 7);
         assert_eq!(scored.score, 558);
+
+        // Masking in place leaves the same symbol as masking a copy
+        let best = matrix.best_mask(ScoringModel::Iso);
+        assert_eq!(best.score, 314);
+        assert_eq!(best.masked.mask_reference, 6);
+        assert!(best.masked.matrix.data == score_mask(matrix, 6).masked.matrix.data);
+        // Applying a mask without scoring leaves the same symbol
+        assert!(matrix.apply_mask(Mask::Mask6).matrix.data == best.masked.matrix.data);
     }
 
     #[test]
@@ -770,14 +921,14 @@ ___███▓██__█_█__█____
             156, 0, 46, 15, 180, 122, 16,
         ]);
         let data = ErrorCorrectedData {
-            version: Version { version: 1 },
+            version: Version::new(1).unwrap(),
             error_correction: ErrorCorrectionLevel::Quartile,
             buffer,
         };
 
-        let mut matrix = Matrix::<21>::from_data(data);
+        let matrix = Matrix::<21>::from_data(Array2D::new(), &data);
 
-        let best_mask = matrix.best_mask();
+        let best_mask = matrix.best_mask(ScoringModel::Iso);
         assert_eq!(best_mask.masked.mask_reference, 6);
         assert_eq!(best_mask.score, 314);
     }
@@ -791,15 +942,62 @@ ___███▓██__█_█__█____
             0b00010001, 0b11101100, 0b00010001, 0b11101100, 0b00010001, 0b11101100, 0b00010001,
             0b11101100, 0b00010001,
         ]);
-        let data = ErrorCorrectedData {
-            version: Version { version: 1 },
+        let data = add_error_correction(EncodedData {
+            version: Version::new(1).unwrap(),
             error_correction: ErrorCorrectionLevel::Medium,
             buffer,
+        });
+
+        let matrix = Matrix::<21>::from_data(Array2D::new(), &data);
+
+        let best_mask = matrix.best_mask(ScoringModel::Iso);
+        assert_eq!(best_mask.masked.mask_reference, 0b000);
+    }
+
+    #[test]
+    fn score_zxing_finder_like_at_edge() {
+        let mut matrix = new_white_matrix();
+        for y in [0, 2, 3, 4, 6, 7] {
+            matrix.data[(0, y).into()] = Module::Filled(Color::Black);
+        }
+        let masked = Masked {
+            mask_reference: 0,
+            matrix,
+        };
+
+        // ISO requires the four light modules within the symbol, ZXing treats the outside as light
+        assert_eq!(masked.score_pattern_horizontal(), 0);
+        assert_eq!(count_finder_like_zxing(21, |y| masked.color(0, y)), 1);
+        assert_eq!(masked.score_zxing(), masked.score() + 40);
+    }
+
+    #[test]
+    fn mask_view() {
+        // "HELLO WORLD" with version 1-Q
+        let mut buffer = Buffer::new();
+        buffer.append_bytes(&[
+            32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 168, 72, 22, 82, 217, 54,
+            156, 0, 46, 15, 180, 122, 16,
+        ]);
+        let data = ErrorCorrectedData {
+            version: Version::new(1).unwrap(),
+            error_correction: ErrorCorrectionLevel::Quartile,
+            buffer,
         };
 
-        let mut matrix = Matrix::<21>::from_data(data);
+        let matrix = Matrix::<21>::from_data(Array2D::new(), &data);
 
-        let best_mask = matrix.best_mask();
-        assert_eq!(best_mask.masked.mask_reference, 0b010);
+        // The view computes the same symbol as masking and formatting a copy
+        for reference in 0..8 {
+            let view = MaskView::new(&matrix, reference);
+            let masked = score_mask(matrix, reference).masked;
+            for x in 0..21 {
+                for y in 0..21 {
+                    assert_eq!(view.color(x, y), masked.color(x, y));
+                }
+            }
+            assert_eq!(view.score(), masked.score());
+            assert_eq!(view.score_zxing(), masked.score_zxing());
+        }
     }
 }
@@ -15,25 +15,21 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-use crate::array_2d::{Array2D, Coordinate};
+use crate::array_2d::{Array2D, Coordinate, Storage};
 use crate::blocks::BlockIterator;
 use crate::error_correction::{ErrorCorrectedData, ErrorCorrectionLevel};
+use crate::format::FormatInfoCheck;
 use crate::qr_version::Version;
 use core::fmt::{Debug, Display, Formatter, Write};
 use core::iter::Peekable;
 
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default, Debug)]
 pub enum Color {
+    #[default]
     White,
     Black,
 }
 
-impl Default for Color {
-    fn default() -> Self {
-        Color::White
-    }
-}
-
 impl Color {
     pub(crate) fn inverse(self) -> Self {
         match self {
@@ -43,11 +39,12 @@ impl Color {
     }
 }
 
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default)]
 pub enum Module {
     /// Part of the encoded region and filled with a specific color
     Filled(Color),
     /// Part of the encoded region, but not yet filled with a color
+    #[default]
     Empty,
     /// Part of the finder pattern and filled with a specific color
     Static(Color),
@@ -55,12 +52,6 @@ pub enum Module {
     Reserved,
 }
 
-impl Default for Module {
-    fn default() -> Self {
-        Module::Empty
-    }
-}
-
 impl From<Module> for Color {
     fn from(module: Module) -> Self {
         match module {
@@ -72,14 +63,61 @@ impl From<Module> for Color {
     }
 }
 
+/// The function of a module in the symbol
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ModuleKind {
+    /// A finder pattern or its light separator
+    Finder,
+    Alignment,
+    Timing,
+    /// The format information, including the dark module next to it
+    Format,
+    Version,
+    /// The encoded data and error correction, including the remainder bits
+    Data,
+}
+
+impl ModuleKind {
+    /// Classifies the module at the row and column from the geometry of the version
+    pub(crate) fn of(version: Version, row: usize, column: usize) -> Self {
+        let size = version.width();
+        let near = |i: usize| i < 8;
+        let far = |i: usize| i + 8 >= size;
+        let version_block = |i: usize, j: usize| i < 6 && j + 11 >= size && !far(j);
+        if (near(row) && near(column)) || (near(row) && far(column)) || (far(row) && near(column)) {
+            ModuleKind::Finder
+        } else if version.information().is_some()
+            && (version_block(row, column) || version_block(column, row))
+        {
+            ModuleKind::Version
+        } else if format_bit_index(Coordinate::new(size, size), Coordinate::new(row, column))
+            .is_some()
+            || (row, column) == (size - 8, 8)
+        {
+            ModuleKind::Format
+        } else if version
+            .alignment_pattern_centers()
+            .any(|(center_row, center_column)| {
+                row.abs_diff(center_row) <= 2 && column.abs_diff(center_column) <= 2
+            })
+        {
+            ModuleKind::Alignment
+        } else if row == 6 || column == 6 {
+            ModuleKind::Timing
+        } else {
+            ModuleKind::Data
+        }
+    }
+}
+
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
-pub struct Matrix<const N: usize> {
+pub struct Matrix<const N: usize, S = [[Module; N]; N]> {
     pub(crate) version: Version,
     pub(crate) error_correction: ErrorCorrectionLevel,
-    pub(crate) data: Array2D<Module, N>,
+    pub(crate) data: Array2D<Module, N, S>,
 }
 
-impl<const N: usize> Matrix<N> {
+impl<const N: usize, S: Storage<Module, N>> Matrix<N, S> {
     fn fill_module(&mut self, pos: Coordinate, data: Module) {
         self.data[pos] = data;
     }
@@ -229,10 +267,8 @@ impl<const N: usize> Matrix<N> {
     }
 
     fn fill_alignment_patterns(&mut self) {
-        let size = self.data.size();
-
-        if size.x > 21 {
-            self.fill_alignment_pattern(Coordinate::new(size.x - 7, size.y - 7));
+        for (row, column) in self.version.alignment_pattern_centers() {
+            self.fill_alignment_pattern(Coordinate::new(row, column));
         }
     }
 
@@ -259,6 +295,16 @@ impl<const N: usize> Matrix<N> {
             Coordinate::new(8, size.y - 1),
             reserved,
         );
+
+        // Version information, left-bottom and right-top
+        if self.version.information().is_some() {
+            for i in 0..6 {
+                for j in size.x - 11..size.x - 8 {
+                    self.fill_module(Coordinate::new(j, i), reserved);
+                    self.fill_module(Coordinate::new(i, j), reserved);
+                }
+            }
+        }
     }
 
     fn fill_timing_pattern(&mut self) {
@@ -283,33 +329,49 @@ impl<const N: usize> Matrix<N> {
         }
     }
 
-    pub fn from_data(error_corrected_data: ErrorCorrectedData) -> Self {
+    /// Places the symbol in the given modules, which are on the heap for large versions
+    pub fn from_data<B: AsRef<[u8]> + AsMut<[u8]>>(
+        data: Array2D<Module, N, S>,
+        error_corrected_data: &ErrorCorrectedData<B>,
+    ) -> Self {
         let mut matrix = Self {
             version: error_corrected_data.version,
             error_correction: error_corrected_data.error_correction,
-            data: Array2D::new(),
+            data,
         };
 
         matrix.set_version(error_corrected_data.version);
         matrix.fill_symbol();
+        matrix.place_version_info();
 
-        let data = BlockIterator::new(&error_corrected_data);
-
-        let data_iter = BitIterator::new(data);
-        let pos_iter = PositionIterator::new(matrix.data.size());
+        let data = BlockIterator::new(error_corrected_data);
+        assert_eq!(
+            data.len(),
+            error_corrected_data.version.total_codeword_count()
+        );
 
-        for bit in data_iter {
-            for pos in pos_iter {
-                if matrix.data[pos] == Module::Empty {
-                    matrix.data[pos] = if bit {
-                        Module::Filled(Color::Black)
-                    } else {
-                        Module::Filled(Color::White)
-                    };
-                    break;
-                }
+        let mut data_iter = BitIterator::new(data);
+        let mut remainder_bits = 0;
+
+        for pos in PositionIterator::new(matrix.data.size()) {
+            if matrix.data[pos] == Module::Empty {
+                // The modules after the last codeword are filled with zero remainder bits
+                let bit = data_iter.next().unwrap_or_else(|| {
+                    remainder_bits += 1;
+                    false
+                });
+                matrix.data[pos] = if bit {
+                    Module::Filled(Color::Black)
+                } else {
+                    Module::Filled(Color::White)
+                };
             }
         }
+        assert!(data_iter.next().is_none());
+        assert_eq!(
+            remainder_bits,
+            error_corrected_data.version.remainder_bit_count()
+        );
 
         matrix
     }
@@ -340,6 +402,25 @@ impl<const N: usize> Matrix<N> {
         );
     }
 
+    /// Places both copies of the version information, which only versions 7 and up have
+    pub fn place_version_info(&mut self) {
+        let information = match self.version.information() {
+            Some(information) => information,
+            None => return,
+        };
+        let size = self.data.size().x;
+        for i in 0..18 {
+            let color = if information & (1 << i) != 0 {
+                Color::Black
+            } else {
+                Color::White
+            };
+            let (short, long) = (i / 3, size - 11 + i % 3);
+            self.fill_module(Coordinate::new(long, short), Module::Static(color));
+            self.fill_module(Coordinate::new(short, long), Module::Static(color));
+        }
+    }
+
     fn set_version(&mut self, version: Version) {
         assert!(version.width() <= N);
         self.data
@@ -347,7 +428,7 @@ impl<const N: usize> Matrix<N> {
     }
 }
 
-impl<const N: usize> Debug for Matrix<N> {
+impl<const N: usize, S: Storage<Module, N>> Debug for Matrix<N, S> {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         self.data.rows().try_for_each(|mut row| {
             row.try_for_each(|module| match module {
@@ -367,7 +448,7 @@ impl<const N: usize> Debug for Matrix<N> {
     }
 }
 
-impl<const N: usize> Display for Matrix<N> {
+impl<const N: usize, S: Storage<Module, N>> Display for Matrix<N, S> {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         let iter1 = self.data.rows().step_by(2);
         let iter2 = self.data.rows().skip(1).step_by(2);
@@ -394,6 +475,27 @@ impl<const N: usize> Display for Matrix<N> {
     }
 }
 
+/// Reads both copies of the format information from the modules
+pub(crate) fn read_format(
+    size: Coordinate,
+    color: impl Fn(Coordinate) -> Color,
+) -> FormatInfoCheck {
+    let mut check = FormatInfoCheck {
+        first_copy: 0,
+        second_copy: 0,
+    };
+    let pos_iter = FormatPositionIterator::new(size);
+    for (index, [pos1, pos2]) in pos_iter.enumerate() {
+        if color(pos1) == Color::Black {
+            check.first_copy |= 1 << index;
+        }
+        if color(pos2) == Color::Black {
+            check.second_copy |= 1 << index;
+        }
+    }
+    check
+}
+
 #[derive(Copy, Clone)]
 struct FormatPositionIterator {
     size: Coordinate,
@@ -434,21 +536,57 @@ impl Iterator for FormatPositionIterator {
     }
 }
 
+/// Returns which bit of the format information is placed at the position, the inverse of
+/// `FormatPositionIterator`
+pub(crate) fn format_bit_index(size: Coordinate, pos: Coordinate) -> Option<usize> {
+    if pos.y == 8 {
+        match pos.x {
+            0..=5 => Some(pos.x),
+            7..=8 => Some(pos.x - 1),
+            x if x + 7 >= size.x => Some(x + 15 - size.x),
+            _ => None,
+        }
+    } else if pos.x == 8 {
+        match pos.y {
+            0..=5 => Some(14 - pos.y),
+            7 => Some(8),
+            y if y + 8 >= size.y => Some(size.y - 1 - y),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
 #[derive(Copy, Clone)]
-struct PositionIterator {
+pub(crate) struct PositionIterator {
     size: Coordinate,
     current_pos: Coordinate,
     next_pos: Option<Coordinate>,
     upwards: bool,
+    finished: bool,
 }
 
 impl PositionIterator {
-    fn new(size: Coordinate) -> PositionIterator {
+    pub(crate) fn new(size: Coordinate) -> PositionIterator {
         PositionIterator {
             size,
             current_pos: Coordinate::new(size.x - 1, size.y - 1),
             next_pos: None,
             upwards: true,
+            finished: false,
+        }
+    }
+
+    fn next_column_pair(&mut self) {
+        if self.current_pos.y < 2 {
+            self.finished = true;
+        } else {
+            self.current_pos.y -= 2;
+            // Skip the vertical timing pattern
+            if self.current_pos.y == 6 {
+                self.current_pos.y -= 1;
+            }
         }
     }
 }
@@ -461,25 +599,21 @@ impl Iterator for PositionIterator {
     fn next(&mut self) -> Option<Self::Item> {
         if self.next_pos.is_some() {
             self.next_pos.take()
+        } else if self.finished {
+            None
         } else {
             let current_pos = self.current_pos;
             self.next_pos = Some(Coordinate::new(current_pos.x, current_pos.y - 1));
             if self.upwards {
                 if self.current_pos.x == 0 {
                     self.upwards = false;
-                    self.current_pos.y -= 2;
-                    if self.current_pos.y == 6 {
-                        self.current_pos.y -= 1;
-                    }
+                    self.next_column_pair();
                 } else {
                     self.current_pos.x -= 1;
                 }
             } else if self.current_pos.x == self.size.x - 1 {
                 self.upwards = true;
-                self.current_pos.y -= 2;
-                if self.current_pos.y == 6 {
-                    self.current_pos.y -= 1;
-                }
+                self.next_column_pair();
             } else {
                 self.current_pos.x += 1;
             }
@@ -537,20 +671,23 @@ where
 #[cfg(test)]
 mod tests {
     use crate::array_2d::Array2D;
+    use crate::array_2d::Coordinate;
     use crate::buffer::Buffer;
     use crate::error_correction::{ErrorCorrectedData, ErrorCorrectionLevel};
-    use crate::matrix::{Matrix, Module};
+    use crate::mask::Mask;
+    use crate::matrix::{
+        format_bit_index, read_format, Color, FormatPositionIterator, Matrix, Module, ModuleKind,
+    };
     use crate::qr_version::Version;
-    use crate::Color;
+
     use alloc::format;
 
     fn new_empty_matrix<const N: usize>() -> Matrix<N> {
-        let mut matrix = Matrix {
-            version: Version { version: 1 },
+        Matrix {
+            version: Version::new(1).unwrap(),
             error_correction: ErrorCorrectionLevel::Low,
             data: Array2D::new(),
-        };
-        matrix
+        }
     }
 
     #[test]
@@ -655,6 +792,7 @@ mod tests {
     #[test]
     fn symbol_version_2() {
         let mut matrix: Matrix<25> = new_empty_matrix();
+        matrix.version = Version::new(2).unwrap();
         matrix.fill_symbol();
 
         assert_eq!(
@@ -699,12 +837,12 @@ mod tests {
             0b00110110, 0b11000111, 0b10000111, 0b00101100, 0b01010101,
         ]);
         let data = ErrorCorrectedData {
-            version: Version { version: 1 },
+            version: Version::new(1).unwrap(),
             error_correction: ErrorCorrectionLevel::Quartile,
             buffer,
         };
 
-        let mut matrix = Matrix::<21>::from_data(data);
+        let matrix = Matrix::<21>::from_data(Array2D::new(), &data);
 
         assert_eq!(
             format!("{:?}", matrix),
@@ -771,7 +909,7 @@ mod tests {
     #[test]
     fn large_matrix_small_pattern() {
         let mut matrix: Matrix<100> = new_empty_matrix();
-        matrix.set_version(Version { version: 1 });
+        matrix.set_version(Version::new(1).unwrap());
         matrix.fill_finder_patterns();
 
         assert_eq!(
@@ -801,4 +939,90 @@ mod tests {
 "
         );
     }
+
+    #[test]
+    fn version_info_version_7() {
+        let mut matrix: Matrix<45> = new_empty_matrix();
+        matrix.version = Version::new(7).unwrap();
+        matrix.set_version(matrix.version);
+        matrix.fill_reserved();
+        matrix.place_version_info();
+
+        let region = |rows: core::ops::Range<usize>, columns: core::ops::Range<usize>| {
+            let mut out = alloc::string::String::new();
+            for x in rows {
+                for y in columns.clone() {
+                    out.push(match matrix.data[(x, y).into()] {
+                        Module::Static(Color::Black) => '#',
+                        Module::Static(Color::White) => '_',
+                        _ => '?',
+                    });
+                }
+                out.push('\n');
+            }
+            out
+        };
+        // Compared with the version 7 symbol of an independent encoder
+        assert_eq!(region(0..6, 34..37), "__#\n_#_\n_#_\n_##\n###\n___\n");
+        assert_eq!(region(34..37, 0..6), "____#_\n_####_\n#__##_\n");
+    }
+
+    #[test]
+    fn place_and_read_format() {
+        let mut matrix = new_empty_matrix::<25>();
+        matrix.set_version(Version::new(2).unwrap());
+        matrix.fill_symbol();
+        matrix.place_format(0x5b4b);
+
+        let check = read_format(matrix.data.size(), |pos| matrix.data[pos].into());
+        assert_eq!(check.first_copy, 0x5b4b);
+        assert!(check.copies_agree());
+        assert_eq!(
+            check.decode_first_copy(),
+            Some((ErrorCorrectionLevel::Medium, Mask::Mask3))
+        );
+    }
+
+    #[test]
+    fn module_kind() {
+        fn check<const N: usize>(version: u8) {
+            let mut matrix = new_empty_matrix::<N>();
+            matrix.version = Version::new(version).unwrap();
+            matrix.set_version(matrix.version);
+            matrix.fill_symbol();
+            matrix.place_version_info();
+            let mut counts = [0; 6];
+            for row in 0..N {
+                for column in 0..N {
+                    let kind = ModuleKind::of(matrix.version, row, column);
+                    let is_data = matrix.data[(row, column).into()] == Module::Empty;
+                    assert_eq!(kind == ModuleKind::Data, is_data, "{} {}", row, column);
+                    counts[kind as usize] += 1;
+                }
+            }
+            let alignment_count = matrix.version.alignment_pattern_centers().count();
+            assert_eq!(counts[ModuleKind::Finder as usize], 3 * 64);
+            assert_eq!(counts[ModuleKind::Alignment as usize], alignment_count * 25);
+            assert_eq!(counts[ModuleKind::Format as usize], 31);
+        }
+        check::<21>(1);
+        check::<25>(2);
+        check::<33>(4);
+        check::<45>(7);
+    }
+
+    #[test]
+    fn format_bit_index_inverse() {
+        for size in [Coordinate::new(21, 21), Coordinate::new(33, 33)] {
+            for (index, pos_list) in FormatPositionIterator::new(size).enumerate() {
+                for pos in pos_list {
+                    assert_eq!(format_bit_index(size, pos), Some(index));
+                }
+            }
+            assert_eq!(format_bit_index(size, Coordinate::new(6, 8)), None);
+            assert_eq!(format_bit_index(size, Coordinate::new(8, 6)), None);
+            assert_eq!(format_bit_index(size, Coordinate::new(size.x - 8, 8)), None);
+            assert_eq!(format_bit_index(size, Coordinate::new(9, 9)), None);
+        }
+    }
 }
@@ -0,0 +1,767 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Micro QR codes, the smaller variant with a single finder pattern
+
+use crate::array_2d::{Array2D, Coordinate};
+use crate::bit_array_2d::BitArray2D;
+use crate::buffer::Buffer;
+#[cfg(feature = "kanji")]
+use crate::encoding::KanjiDataEncoder;
+use crate::encoding::{
+    detect_character_set, AlphanumericDataEncoder, CharacterSet, EncodingMode,
+    ErrorCorrectionRestriction, Iso8859_1DataEncoder, ModeEncoder, NumericDataEncoder,
+    UnicodeDataEncoder,
+};
+use crate::error::QrError;
+use crate::error_correction::ErrorCorrectionLevel;
+use crate::format::masked_sequence;
+use crate::mask::{mask_condition, Mask};
+use crate::matrix::{Color, Module, ModuleKind};
+use crate::qr_version::Version;
+use crate::qrcode::QrCode;
+use crate::reed_solomon;
+use core::convert::TryFrom;
+
+/// One of the four data masks of Micro QR codes, named after its mask reference
+///
+/// These are masks 1, 4, 6 and 7 of regular QR codes, numbered 0 to 3 in the format information.
+///
+/// # Example
+///```
+///     use core::convert::TryFrom;
+///     use tiny_qr::{MicroMask, MicroQrCodeBuilder};
+///     let qr_code = MicroQrCodeBuilder::new()
+///         .with_mask_reference(MicroMask::Mask2)
+///         .with_text("12345")
+///         .build()
+///         .unwrap();
+///     assert_eq!(MicroMask::try_from(2), Ok(MicroMask::Mask2));
+///```
+#[derive(Clone, Copy, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub enum MicroMask {
+    Mask0,
+    Mask1,
+    Mask2,
+    Mask3,
+}
+
+impl MicroMask {
+    pub const ALL: [MicroMask; 4] = [
+        MicroMask::Mask0,
+        MicroMask::Mask1,
+        MicroMask::Mask2,
+        MicroMask::Mask3,
+    ];
+
+    /// The mask reference as written in the format information
+    pub const fn reference(self) -> u8 {
+        self as u8
+    }
+}
+
+impl TryFrom<u8> for MicroMask {
+    type Error = QrError;
+
+    fn try_from(mask_reference: u8) -> Result<Self, Self::Error> {
+        MicroMask::ALL
+            .get(mask_reference as usize)
+            .copied()
+            .ok_or(QrError::InvalidMaskReference { mask_reference })
+    }
+}
+
+/// The number of modules on each side of the largest Micro QR code
+pub(crate) const MICRO_MODULE_SIZE: usize = MicroVersion::M4.width();
+
+/// The size of a Micro QR code, from 11×11 (M1) to 17×17 (M4) modules
+#[derive(Clone, Copy, Debug, Ord, PartialOrd, Eq, PartialEq)]
+pub enum MicroVersion {
+    M1,
+    M2,
+    M3,
+    M4,
+}
+
+impl MicroVersion {
+    const fn number(self) -> usize {
+        match self {
+            MicroVersion::M1 => 1,
+            MicroVersion::M2 => 2,
+            MicroVersion::M3 => 3,
+            MicroVersion::M4 => 4,
+        }
+    }
+
+    pub const fn width(self) -> usize {
+        9 + 2 * self.number()
+    }
+
+    fn increment(self) -> Option<Self> {
+        match self {
+            MicroVersion::M1 => Some(MicroVersion::M2),
+            MicroVersion::M2 => Some(MicroVersion::M3),
+            MicroVersion::M3 => Some(MicroVersion::M4),
+            MicroVersion::M4 => None,
+        }
+    }
+
+    /// The number of data bits, `None` when the error correction level isn't available
+    ///
+    /// M1 and M3-M end with a data codeword of only 4 bits.
+    fn data_bit_len(self, error_correction: ErrorCorrectionLevel) -> Option<usize> {
+        use ErrorCorrectionLevel::*;
+        use MicroVersion::*;
+        match (self, error_correction) {
+            (M1, Low) => Some(20),
+            (M2, Low) => Some(40),
+            (M2, Medium) => Some(32),
+            (M3, Low) => Some(84),
+            (M3, Medium) => Some(68),
+            (M4, Low) => Some(128),
+            (M4, Medium) => Some(112),
+            (M4, Quartile) => Some(80),
+            _ => None,
+        }
+    }
+
+    fn total_codeword_count(self) -> usize {
+        match self {
+            MicroVersion::M1 => 5,
+            MicroVersion::M2 => 10,
+            MicroVersion::M3 => 17,
+            MicroVersion::M4 => 24,
+        }
+    }
+
+    /// The symbol number of the format information
+    fn symbol_number(self, error_correction: ErrorCorrectionLevel) -> u8 {
+        use ErrorCorrectionLevel::*;
+        use MicroVersion::*;
+        match (self, error_correction) {
+            (M1, _) => 0,
+            (M2, Low) => 1,
+            (M2, _) => 2,
+            (M3, Low) => 3,
+            (M3, _) => 4,
+            (M4, Low) => 5,
+            (M4, Medium) => 6,
+            (M4, _) => 7,
+        }
+    }
+
+    /// The length of the character count indicator, `None` when the mode isn't available
+    fn character_count_bit_len(self, mode: EncodingMode) -> Option<usize> {
+        match (self, mode) {
+            (_, EncodingMode::Numeric) => Some(self.number() + 2),
+            (MicroVersion::M1, _) => None,
+            (_, EncodingMode::Alphanumeric) => Some(self.number() + 1),
+            (MicroVersion::M2, _) => None,
+            (_, EncodingMode::Byte) => Some(self.number() + 1),
+            (_, EncodingMode::Kanji) => Some(self.number()),
+        }
+    }
+
+    /// The number of bits of the encoded segment, `None` when the mode isn't available
+    fn segment_bit_len(self, mode: EncodingMode, character_count: usize) -> Option<usize> {
+        let data_bits = match mode {
+            EncodingMode::Numeric => 10 * (character_count / 3) + [0, 4, 7][character_count % 3],
+            EncodingMode::Alphanumeric => 11 * (character_count / 2) + 6 * (character_count % 2),
+            EncodingMode::Byte => 8 * character_count,
+            EncodingMode::Kanji => 13 * character_count,
+        };
+        let mode_bits = self.number() - 1;
+        Some(mode_bits + self.character_count_bit_len(mode)? + data_bits)
+    }
+}
+
+/// Builds a Micro QR code, which fits in much less space than a regular QR code
+///
+/// Micro QR codes support numeric data in all versions, alphanumeric data from M2 and byte data
+/// from M3. M1 only has error detection, which is selected with [`ErrorCorrectionLevel::Low`], and
+/// the highest level is [`ErrorCorrectionLevel::Quartile`] in M4. The smallest version that fits
+/// the text is selected, with the highest error correction level that still fits in that version.
+///
+/// The resulting [`QrCode`] is drawn with a quiet zone of 4 modules, twice the minimum required
+/// for Micro QR codes. [`QrCode::format_info`] and [`QrCode::penalty_heatmap`] only apply to
+/// regular QR codes, while [`QrCode::version`] and [`QrCode::penalty_score`] return `None`.
+///
+/// # Example
+///```
+///     use tiny_qr::{MicroQrCodeBuilder, MicroVersion};
+///     let qr_code = MicroQrCodeBuilder::new().with_text("12345").build().unwrap();
+///     assert_eq!(qr_code.draw_iter().width(), MicroVersion::M1.width() + 8);
+///```
+#[derive(Clone)]
+pub struct MicroQrCodeBuilder<'a> {
+    max_version: MicroVersion,
+    specific_version: bool,
+    error_correction_restriction: ErrorCorrectionRestriction,
+    mask_reference: Option<MicroMask>,
+    text: Option<&'a str>,
+    #[cfg(feature = "kanji")]
+    kanji_detection: bool,
+}
+
+impl Default for MicroQrCodeBuilder<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> MicroQrCodeBuilder<'a> {
+    pub fn new() -> Self {
+        Self {
+            max_version: MicroVersion::M4,
+            specific_version: false,
+            error_correction_restriction: ErrorCorrectionRestriction::MinErrorCorrection(
+                ErrorCorrectionLevel::Low,
+            ),
+            mask_reference: None,
+            text: None,
+            #[cfg(feature = "kanji")]
+            kanji_detection: true,
+        }
+    }
+
+    pub fn with_max_version(mut self, max_version: MicroVersion) -> Self {
+        self.max_version = max_version;
+        self.specific_version = false;
+        self
+    }
+
+    pub fn with_specific_version(mut self, version: MicroVersion) -> Self {
+        self.max_version = version;
+        self.specific_version = true;
+        self
+    }
+
+    pub fn with_min_error_correction_level(
+        mut self,
+        error_correction_level: ErrorCorrectionLevel,
+    ) -> Self {
+        self.error_correction_restriction =
+            ErrorCorrectionRestriction::MinErrorCorrection(error_correction_level);
+        self
+    }
+
+    pub fn with_specific_error_correction_level(
+        mut self,
+        error_correction_level: ErrorCorrectionLevel,
+    ) -> Self {
+        self.error_correction_restriction =
+            ErrorCorrectionRestriction::SpecificErrorCorrection(error_correction_level);
+        self
+    }
+
+    /// Uses one of the four Micro QR masks instead of selecting the best one
+    pub fn with_mask_reference(mut self, mask_reference: MicroMask) -> Self {
+        self.mask_reference = Some(mask_reference);
+        self
+    }
+
+    pub fn with_text(mut self, text: &'a str) -> Self {
+        self.text = Some(text);
+        self
+    }
+
+    /// Opts out of detecting Kanji text, which is only available from M3
+    #[cfg(feature = "kanji")]
+    pub fn with_kanji_detection(mut self, kanji_detection: bool) -> Self {
+        self.kanji_detection = kanji_detection;
+        self
+    }
+
+    pub fn build(&self) -> Result<QrCode<MICRO_MODULE_SIZE>, QrError> {
+        let text = self.text.ok_or(QrError::MissingPayload)?;
+        let character_set = match detect_character_set(text) {
+            #[cfg(feature = "kanji")]
+            CharacterSet::Kanji if !self.kanji_detection => CharacterSet::Unicode,
+            character_set => character_set,
+        };
+        let (version, error_correction) = self.plan(character_set, text)?;
+
+        let mut codewords = [0; 24];
+        let data_len = encode(
+            version,
+            error_correction,
+            character_set,
+            text,
+            &mut codewords,
+        )?;
+        let (data, ecc) = codewords[..version.total_codeword_count()].split_at_mut(data_len);
+        reed_solomon::encode(data, ecc);
+
+        let matrix = place(version, error_correction, &codewords);
+        let mask_reference = match self.mask_reference {
+            Some(mask_reference) => mask_reference.reference(),
+            None => (0..4)
+                .max_by_key(|&mask_reference| score(&apply_mask(&matrix, mask_reference)))
+                .unwrap(),
+        };
+        let mut matrix = apply_mask(&matrix, mask_reference);
+        place_format(&mut matrix, version, error_correction, mask_reference);
+
+        let mut data = BitArray2D::new();
+        data.set_size(matrix.size());
+        for x in 0..version.width() {
+            for y in 0..version.width() {
+                data.set((x, y).into(), matrix[(x, y).into()].into());
+            }
+        }
+        Ok(QrCode {
+            data,
+            version: None,
+            error_correction,
+            mask_reference: Mask::ALL[mask_reference as usize],
+            penalty_score: None,
+        })
+    }
+
+    /// Selects the smallest version that fits and then the highest error correction level
+    fn plan(
+        &self,
+        character_set: CharacterSet,
+        text: &str,
+    ) -> Result<(MicroVersion, ErrorCorrectionLevel), QrError> {
+        let mode = character_set.to_encoding_mode();
+        let character_count = character_count(character_set, text);
+        let (error_correction, specific_error_correction) = match self.error_correction_restriction
+        {
+            ErrorCorrectionRestriction::MinErrorCorrection(level) => (level, false),
+            ErrorCorrectionRestriction::SpecificErrorCorrection(level) => (level, true),
+        };
+        let fits = |version: MicroVersion, error_correction| {
+            let bit_len = if text.is_empty() {
+                Some(0)
+            } else {
+                version.segment_bit_len(mode, character_count)
+            };
+            matches!(
+                (bit_len, version.data_bit_len(error_correction)),
+                (Some(bit_len), Some(capacity)) if bit_len <= capacity
+            )
+        };
+
+        let mut version = if self.specific_version {
+            self.max_version
+        } else {
+            MicroVersion::M1
+        };
+        while !fits(version, error_correction) {
+            version = match version.increment() {
+                Some(version) if version <= self.max_version => version,
+                _ => return Err(QrError::DataTooLong),
+            };
+        }
+
+        let mut selected_error_correction = error_correction;
+        if !specific_error_correction {
+            while let Some(increased) = selected_error_correction.increment() {
+                if fits(version, increased) {
+                    selected_error_correction = increased;
+                } else {
+                    break;
+                }
+            }
+        }
+        Ok((version, selected_error_correction))
+    }
+}
+
+/// The number of characters as written in the character count indicator
+fn character_count(character_set: CharacterSet, text: &str) -> usize {
+    match character_set {
+        CharacterSet::Iso8859_1 => text.chars().count(),
+        #[cfg(feature = "kanji")]
+        CharacterSet::Kanji => text.chars().count(),
+        _ => text.len(),
+    }
+}
+
+/// Writes the data codewords and returns the number of data codewords
+fn encode(
+    version: MicroVersion,
+    error_correction: ErrorCorrectionLevel,
+    character_set: CharacterSet,
+    text: &str,
+    codewords: &mut [u8],
+) -> Result<usize, QrError> {
+    let capacity = version.data_bit_len(error_correction).unwrap();
+    let mut buffer = Buffer::new();
+
+    if !text.is_empty() {
+        let mode = character_set.to_encoding_mode();
+        let mode_bits = version.number() - 1;
+        if mode_bits > 0 {
+            buffer.append_number(mode as u32, mode_bits);
+        }
+        let character_count = character_count(character_set, text);
+        let count_bits = version.character_count_bit_len(mode).unwrap();
+        buffer.append_number(character_count as u32, count_bits);
+
+        // The data itself is encoded the same as in regular QR codes
+        let version = Version { version: 1 };
+        let error_correction = ErrorCorrectionLevel::Low;
+        match character_set {
+            CharacterSet::Numeric => NumericDataEncoder {
+                version,
+                error_correction,
+            }
+            .encode_data(text, &mut buffer)?,
+            CharacterSet::Alphanumeric => AlphanumericDataEncoder {
+                version,
+                error_correction,
+            }
+            .encode_data(text, &mut buffer)?,
+            CharacterSet::Iso8859_1 => Iso8859_1DataEncoder {
+                version,
+                error_correction,
+            }
+            .encode_data(text, &mut buffer)?,
+            #[cfg(feature = "kanji")]
+            CharacterSet::Kanji => KanjiDataEncoder {
+                version,
+                error_correction,
+            }
+            .encode_data(text, &mut buffer)?,
+            CharacterSet::Unicode => UnicodeDataEncoder {
+                version,
+                error_correction,
+                eci_designator: false,
+            }
+            .encode_data(text, &mut buffer)?,
+        }
+    }
+
+    // The terminator is shortened when the symbol is nearly full
+    let terminator_len = (2 * version.number() + 1).min(capacity - buffer.bit_len());
+    buffer.append_number(0, terminator_len);
+    let alignment = ((8 - buffer.bit_len() % 8) % 8).min(capacity - buffer.bit_len());
+    buffer.append_number(0, alignment);
+    for &pad in [0b1110_1100, 0b0001_0001].iter().cycle() {
+        if capacity - buffer.bit_len() < 8 {
+            break;
+        }
+        buffer.append_byte(pad);
+    }
+    // The last codeword of M1 and M3-M is only 4 bits
+    buffer.append_number(0, capacity - buffer.bit_len());
+
+    let data = buffer.data();
+    codewords[..data.len()].copy_from_slice(data);
+    Ok(data.len())
+}
+
+fn place(
+    version: MicroVersion,
+    error_correction: ErrorCorrectionLevel,
+    codewords: &[u8],
+) -> Array2D<Module, MICRO_MODULE_SIZE> {
+    let width = version.width();
+    let mut matrix = Array2D::new();
+    matrix.set_size(Coordinate::new(width, width));
+
+    // Finder pattern with its separator
+    for x in 0..8usize {
+        for y in 0..8usize {
+            // The rings around the center are black, white, black and the white separator
+            let ring = x.abs_diff(3).max(y.abs_diff(3));
+            let color = if ring == 2 || ring == 4 {
+                Color::White
+            } else {
+                Color::Black
+            };
+            matrix[(x, y).into()] = Module::Static(color);
+        }
+    }
+    // Timing patterns along the top and left edges
+    for i in 8..width {
+        let color = if i % 2 == 0 {
+            Color::Black
+        } else {
+            Color::White
+        };
+        matrix[(0, i).into()] = Module::Static(color);
+        matrix[(i, 0).into()] = Module::Static(color);
+    }
+    // Format information
+    for i in 1..=8 {
+        matrix[(8, i).into()] = Module::Reserved;
+        matrix[(i, 8).into()] = Module::Reserved;
+    }
+
+    let data_len = version.data_bit_len(error_correction).unwrap();
+    let data_codeword_count = data_len.div_ceil(8);
+    let mut bits = codewords[..version.total_codeword_count()]
+        .iter()
+        .enumerate()
+        .flat_map(|(index, &codeword)| {
+            let bit_len = if index + 1 == data_codeword_count && data_len % 8 != 0 {
+                4
+            } else {
+                8
+            };
+            (0..bit_len).map(move |bit| codeword & (0x80 >> bit) != 0)
+        });
+
+    // Column pairs from right to left, alternating upwards and downwards
+    let mut upwards = true;
+    for right in (2..width).rev().step_by(2) {
+        for row in 0..width {
+            let x = if upwards { width - 1 - row } else { row };
+            for y in [right, right - 1] {
+                let module = &mut matrix[(x, y).into()];
+                if *module == Module::Empty {
+                    let color = if bits.next().unwrap() {
+                        Color::Black
+                    } else {
+                        Color::White
+                    };
+                    *module = Module::Filled(color);
+                }
+            }
+        }
+        upwards = !upwards;
+    }
+    assert!(bits.next().is_none());
+
+    matrix
+}
+
+/// Classifies the module at the row and column of a Micro QR code with the given width
+pub(crate) fn module_kind(width: usize, row: usize, column: usize) -> ModuleKind {
+    debug_assert!(row < width && column < width);
+    if row < 8 && column < 8 {
+        ModuleKind::Finder
+    } else if row == 0 || column == 0 {
+        ModuleKind::Timing
+    } else if (row == 8 && column <= 8) || (column == 8 && row <= 8) {
+        ModuleKind::Format
+    } else {
+        ModuleKind::Data
+    }
+}
+
+fn apply_mask(
+    matrix: &Array2D<Module, MICRO_MODULE_SIZE>,
+    mask_reference: u8,
+) -> Array2D<Module, MICRO_MODULE_SIZE> {
+    // The Micro QR masks are a subset of the regular QR masks
+    let condition = mask_condition([0b001, 0b100, 0b110, 0b111][mask_reference as usize]);
+    let mut masked = *matrix;
+    let width = matrix.size().x;
+    for x in 0..width {
+        for y in 0..width {
+            if let Module::Filled(color) = masked[(x, y).into()] {
+                if condition(x, y) {
+                    masked[(x, y).into()] = Module::Filled(color.inverse());
+                }
+            }
+        }
+    }
+    masked
+}
+
+/// Scores the dark modules along the right and bottom edge, a higher score is better
+fn score(matrix: &Array2D<Module, MICRO_MODULE_SIZE>) -> usize {
+    let width = matrix.size().x;
+    let dark = |pos: Coordinate| Color::from(matrix[pos]) == Color::Black;
+    let right = (1..width)
+        .filter(|&x| dark(Coordinate::new(x, width - 1)))
+        .count();
+    let bottom = (1..width)
+        .filter(|&y| dark(Coordinate::new(width - 1, y)))
+        .count();
+    right.min(bottom) * 16 + right.max(bottom)
+}
+
+fn place_format(
+    matrix: &mut Array2D<Module, MICRO_MODULE_SIZE>,
+    version: MicroVersion,
+    error_correction: ErrorCorrectionLevel,
+    mask_reference: u8,
+) {
+    let data = version.symbol_number(error_correction) << 2 | mask_reference;
+    // Same BCH code as regular QR codes, but with a different mask pattern
+    let format = masked_sequence(data) ^ 0x5412 ^ 0x4445;
+    for i in 0..15 {
+        let color = if format & (1 << i) != 0 {
+            Color::Black
+        } else {
+            Color::White
+        };
+        let pos = if i < 8 {
+            Coordinate::new(i + 1, 8)
+        } else {
+            Coordinate::new(8, 15 - i)
+        };
+        matrix[pos] = Module::Static(color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encoding::detect_character_set;
+    use crate::error::QrError;
+    use crate::error_correction::ErrorCorrectionLevel;
+    use crate::matrix::{Module, ModuleKind};
+    use crate::micro::{module_kind, place, MicroMask, MicroQrCodeBuilder, MicroVersion};
+    use alloc::format;
+
+    #[test]
+    fn numeric_m1() {
+        let qr_code = MicroQrCodeBuilder::new()
+            .with_specific_version(MicroVersion::M1)
+            .with_mask_reference(MicroMask::Mask1)
+            .with_text("01234")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            format!("{:?}", qr_code),
+            "\
+███████_█_█
+█_____█___█
+█_███_█_███
+█_███_█__█_
+█_███_█____
+█_____█_█_█
+███████_█_█
+________███
+██_____█___
+____███_█_█
+███_█_████_
+"
+        );
+    }
+
+    #[test]
+    fn numeric_m3_m() {
+        let qr_code = MicroQrCodeBuilder::new()
+            .with_specific_version(MicroVersion::M3)
+            .with_specific_error_correction_level(ErrorCorrectionLevel::Medium)
+            .with_mask_reference(MicroMask::Mask2)
+            .with_text("1234567890")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            format!("{:?}", qr_code),
+            "\
+███████_█_█_█_█
+█_____█__█____█
+█_███_█___█_██_
+█_███_█__█___██
+█_███_█___██___
+█_____█_█_██_██
+███████_█_████_
+_________█__█_█
+█___██__█_█_█_█
+_██████_█_█_██_
+██___█__██_██_█
+_███__█__██____
+██___█__████__█
+_____█████_█__█
+██████_█_██_██_
+"
+        );
+    }
+
+    #[test]
+    fn plan() {
+        let plan =
+            |builder: MicroQrCodeBuilder, text| builder.plan(detect_character_set(text), text);
+        let builder = MicroQrCodeBuilder::new();
+        assert_eq!(
+            plan(builder.clone(), "12345"),
+            Ok((MicroVersion::M1, ErrorCorrectionLevel::Low))
+        );
+        // Alphanumeric mode isn't available in M1
+        assert_eq!(
+            plan(builder.clone(), "AB"),
+            Ok((MicroVersion::M2, ErrorCorrectionLevel::Medium))
+        );
+        // Byte mode isn't available in M2
+        assert_eq!(
+            plan(builder.clone(), "ab"),
+            Ok((MicroVersion::M3, ErrorCorrectionLevel::Medium))
+        );
+        assert_eq!(
+            plan(
+                builder
+                    .clone()
+                    .with_min_error_correction_level(ErrorCorrectionLevel::Quartile),
+                "1"
+            ),
+            Ok((MicroVersion::M4, ErrorCorrectionLevel::Quartile))
+        );
+        assert_eq!(
+            plan(builder.clone().with_max_version(MicroVersion::M2), "ab"),
+            Err(QrError::DataTooLong)
+        );
+        assert_eq!(
+            plan(builder, "0123456789012345678901234567890123456"),
+            Err(QrError::DataTooLong)
+        );
+    }
+
+    #[test]
+    fn every_version_and_level() {
+        for (version, level) in [
+            (MicroVersion::M1, ErrorCorrectionLevel::Low),
+            (MicroVersion::M2, ErrorCorrectionLevel::Low),
+            (MicroVersion::M2, ErrorCorrectionLevel::Medium),
+            (MicroVersion::M3, ErrorCorrectionLevel::Low),
+            (MicroVersion::M3, ErrorCorrectionLevel::Medium),
+            (MicroVersion::M4, ErrorCorrectionLevel::Low),
+            (MicroVersion::M4, ErrorCorrectionLevel::Medium),
+            (MicroVersion::M4, ErrorCorrectionLevel::Quartile),
+        ] {
+            // Placement asserts that the codewords exactly fill the symbol
+            let qr_code = MicroQrCodeBuilder::new()
+                .with_specific_version(version)
+                .with_specific_error_correction_level(level)
+                .with_text("1")
+                .build()
+                .unwrap();
+            assert_eq!(qr_code.data.size().x, version.width());
+            assert_eq!(qr_code.error_correction(), level);
+            assert_eq!(qr_code.version(), None);
+        }
+    }
+
+    #[test]
+    fn module_kind_matches_placement() {
+        for version in [
+            MicroVersion::M1,
+            MicroVersion::M2,
+            MicroVersion::M3,
+            MicroVersion::M4,
+        ] {
+            let width = version.width();
+            let matrix = place(version, ErrorCorrectionLevel::Low, &[0; 24]);
+            for row in 0..width {
+                for column in 0..width {
+                    let is_data = matches!(matrix[(row, column).into()], Module::Filled(_));
+                    let kind = module_kind(width, row, column);
+                    assert_eq!(kind == ModuleKind::Data, is_data, "{} {}", row, column);
+                }
+            }
+        }
+    }
+}
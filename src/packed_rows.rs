@@ -0,0 +1,181 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::matrix::Color;
+use crate::symbol::Symbol;
+use core::num::NonZeroUsize;
+
+/// Iterates over the rows of a QR code, each packed as bytes with the leftmost pixel in the most
+/// significant bit and a set bit for a dark pixel
+///
+/// This is the layout that monochrome e-paper and SSD1306-style display drivers consume. The last
+/// byte of a row is padded with light pixels.
+///
+/// # Example
+///```
+///     use core::num::NonZeroUsize;
+///     use tiny_qr::QrCodeBuilder;
+///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+///     let rows = qr_code.packed_rows().with_border(2).with_scale(NonZeroUsize::new(2).unwrap());
+///     assert_eq!((rows.width(), rows.height()), (50, 50));
+///     for row in rows {
+///         let bytes: Vec<u8> = row.collect();
+///         assert_eq!(bytes.len(), 7);
+///     }
+///```
+pub struct PackedRows<'a, Q> {
+    qr_code: &'a Q,
+    border: usize,
+    scale: usize,
+    y: usize,
+}
+
+impl<'a, Q: Symbol> PackedRows<'a, Q> {
+    pub(crate) fn new(qr_code: &'a Q) -> Self {
+        Self {
+            qr_code,
+            border: 0,
+            scale: 1,
+            y: 0,
+        }
+    }
+
+    /// Adds a light border of the given number of modules around the symbol
+    pub fn with_border(mut self, modules: usize) -> Self {
+        self.border = modules;
+        self
+    }
+
+    /// Draws every module as a square of the given number of pixels
+    pub fn with_scale(mut self, pixels: NonZeroUsize) -> Self {
+        self.scale = pixels.get();
+        self
+    }
+
+    /// Returns the number of pixels per row
+    pub fn width(&self) -> usize {
+        (2 * self.border + self.qr_code.width()) * self.scale
+    }
+
+    /// Returns the number of rows
+    pub fn height(&self) -> usize {
+        self.width()
+    }
+}
+
+impl<'a, Q: Symbol> Iterator for PackedRows<'a, Q> {
+    type Item = PackedRow<'a, Q>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.y >= self.height() {
+            return None;
+        }
+        let module_y = (self.y / self.scale)
+            .checked_sub(self.border)
+            .filter(|&module_y| module_y < self.qr_code.width());
+        self.y += 1;
+        Some(PackedRow {
+            qr_code: self.qr_code,
+            border: self.border,
+            scale: self.scale,
+            module_y,
+            x: 0,
+        })
+    }
+}
+
+/// Iterates over the bytes of a single packed row
+pub struct PackedRow<'a, Q> {
+    qr_code: &'a Q,
+    border: usize,
+    scale: usize,
+    /// The row of the symbol, or `None` for a row of the border
+    module_y: Option<usize>,
+    x: usize,
+}
+
+impl<Q: Symbol> PackedRow<'_, Q> {
+    fn is_dark(&self, x: usize) -> bool {
+        let width = self.qr_code.width();
+        match ((x / self.scale).checked_sub(self.border), self.module_y) {
+            (Some(module_x), Some(module_y)) if module_x < width => {
+                self.qr_code.module(module_x, module_y) == Color::Black
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<Q: Symbol> Iterator for PackedRow<'_, Q> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let width = (2 * self.border + self.qr_code.width()) * self.scale;
+        if self.x >= width {
+            return None;
+        }
+        let byte = (0..8)
+            .filter(|&bit| self.x + bit < width && self.is_dark(self.x + bit))
+            .fold(0, |byte, bit| byte | (0x80 >> bit));
+        self.x += 8;
+        Some(byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::Color;
+    use crate::qrcode::QrCodeBuilder;
+    use alloc::vec::Vec;
+    use core::num::NonZeroUsize;
+
+    #[test]
+    fn packed_rows() {
+        let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+        let rows: Vec<Vec<u8>> = qr_code.packed_rows().map(|row| row.collect()).collect();
+        assert_eq!(rows.len(), 21);
+        // The finder pattern, its separator and the padding of the last byte
+        assert_eq!(rows[0][0], 0b1111_1110);
+        assert_eq!(rows[0][2] & 0b0000_0111, 0);
+        for (y, row) in rows.iter().enumerate() {
+            assert_eq!(row.len(), 3);
+            for x in 0..21 {
+                let dark = row[x / 8] & (0x80 >> (x % 8)) != 0;
+                assert_eq!(dark, qr_code.module(x, y) == Color::Black);
+            }
+        }
+    }
+
+    #[test]
+    fn border_and_scale() {
+        let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+        let rows: Vec<Vec<u8>> = qr_code
+            .packed_rows()
+            .with_border(1)
+            .with_scale(NonZeroUsize::new(2).unwrap())
+            .map(|row| row.collect())
+            .collect();
+        assert_eq!(rows.len(), 46);
+        assert_eq!(rows[0], [0; 6]);
+        assert_eq!(rows[1], [0; 6]);
+        // Two light pixels of border, fourteen dark pixels of finder pattern and then the separator
+        assert_eq!(rows[2][..2], [0b0011_1111, 0b1111_1111]);
+        assert_eq!(rows[2][2] & 0b1100_0000, 0);
+        assert_eq!(rows[2], rows[3]);
+        assert_eq!(rows[45], [0; 6]);
+    }
+}
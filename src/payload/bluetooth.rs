@@ -0,0 +1,111 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::error::QrError;
+use crate::payload::Escaped;
+use core::fmt::{self, Display, Formatter};
+
+/// Payload for pairing with a Bluetooth LE device, for example during IoT commissioning
+///
+/// The payload uses the `BT:` prefix with `M` (MAC address), `N` (name) and `P` (passkey) fields,
+/// following the key-value conventions of the Wi-Fi network payload.
+///
+/// # Example
+///```
+///     use tiny_qr::payload::BluetoothPairing;
+///     let payload = BluetoothPairing::new([0x00, 0x1a, 0x7d, 0xda, 0x71, 0x13])
+///         .with_name("Sensor; kitchen")
+///         .with_passkey(12345)?;
+///     assert_eq!(
+///         payload.to_string(),
+///         "BT:M:00:1A:7D:DA:71:13;N:Sensor\\; kitchen;P:012345;;"
+///     );
+///     # Ok::<(), tiny_qr::QrError>(())
+///```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BluetoothPairing<'a> {
+    address: [u8; 6],
+    name: Option<&'a str>,
+    passkey: Option<u32>,
+}
+
+impl<'a> BluetoothPairing<'a> {
+    pub fn new(address: [u8; 6]) -> Self {
+        Self {
+            address,
+            name: None,
+            passkey: None,
+        }
+    }
+
+    pub fn with_name(mut self, name: &'a str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    /// Sets the six digit passkey for passkey entry pairing
+    ///
+    /// Returns [`QrError::InvalidField`] when the passkey has more than six digits.
+    ///
+    /// [`QrError::InvalidField`]: crate::QrError::InvalidField
+    pub fn with_passkey(mut self, passkey: u32) -> Result<Self, QrError> {
+        if passkey > 999_999 {
+            return Err(QrError::InvalidField { field: "passkey" });
+        }
+        self.passkey = Some(passkey);
+        Ok(self)
+    }
+}
+
+impl Display for BluetoothPairing<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.address;
+        write!(
+            f,
+            "BT:M:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X};",
+            a, b, c, d, e, g
+        )?;
+        if let Some(name) = self.name {
+            write!(f, "N:{};", Escaped(name))?;
+        }
+        if let Some(passkey) = self.passkey {
+            write!(f, "P:{:06};", passkey)?;
+        }
+        f.write_str(";")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::QrError;
+    use crate::payload::BluetoothPairing;
+    use alloc::format;
+
+    #[test]
+    fn address_only() {
+        let payload = BluetoothPairing::new([0xff, 0xee, 0xdd, 0xcc, 0xbb, 0x0a]);
+        assert_eq!(format!("{}", payload), "BT:M:FF:EE:DD:CC:BB:0A;;");
+    }
+
+    #[test]
+    fn passkey_too_long() {
+        assert_eq!(
+            BluetoothPairing::new([0; 6]).with_passkey(1_000_000),
+            Err(QrError::InvalidField { field: "passkey" })
+        );
+    }
+}
@@ -0,0 +1,225 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::error::QrError;
+use core::fmt::{self, Display, Formatter, Write};
+
+const BASE38_ALPHABET: &[u8; 38] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ-.";
+/// The packed payload is 88 bits long
+const PACKED_LEN: usize = 11;
+
+/// How the device enters commissioning mode
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CommissioningFlow {
+    /// The device is in commissioning mode when powered on
+    Standard,
+    /// The user needs to perform an action, like pressing a button
+    UserIntent,
+    /// The device needs a vendor specific flow
+    Custom,
+}
+
+/// The transports on which the device can be discovered for commissioning
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DiscoveryCapabilities {
+    pub soft_ap: bool,
+    pub ble: bool,
+    pub on_network: bool,
+}
+
+impl DiscoveryCapabilities {
+    fn bits(&self) -> u8 {
+        self.soft_ap as u8 | (self.ble as u8) << 1 | (self.on_network as u8) << 2
+    }
+}
+
+/// Onboarding payload for commissioning a Matter device
+///
+/// The fields are bit packed and base-38 encoded after the `MT:` prefix.
+///
+/// # Example
+///```
+///     use tiny_qr::payload::MatterOnboarding;
+///     let payload = MatterOnboarding::new(0xfff1, 0x8000, 3840, 20202021)?;
+///     assert_eq!(payload.to_string(), "MT:Y.K9042C00KA0648G00");
+///     # Ok::<(), tiny_qr::QrError>(())
+///```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MatterOnboarding {
+    vendor_id: u16,
+    product_id: u16,
+    commissioning_flow: CommissioningFlow,
+    discovery_capabilities: DiscoveryCapabilities,
+    discriminator: u16,
+    passcode: u32,
+}
+
+impl MatterOnboarding {
+    /// Creates a payload for a device discoverable over BLE with the standard commissioning flow
+    ///
+    /// Returns [`QrError::InvalidField`] when the discriminator doesn't fit in 12 bits or the
+    /// passcode is not allowed by the specification.
+    ///
+    /// [`QrError::InvalidField`]: crate::QrError::InvalidField
+    pub fn new(
+        vendor_id: u16,
+        product_id: u16,
+        discriminator: u16,
+        passcode: u32,
+    ) -> Result<Self, QrError> {
+        if discriminator > 0xfff {
+            return Err(QrError::InvalidField {
+                field: "discriminator",
+            });
+        }
+        if !is_valid_passcode(passcode) {
+            return Err(QrError::InvalidField { field: "passcode" });
+        }
+        Ok(Self {
+            vendor_id,
+            product_id,
+            commissioning_flow: CommissioningFlow::Standard,
+            discovery_capabilities: DiscoveryCapabilities {
+                ble: true,
+                ..DiscoveryCapabilities::default()
+            },
+            discriminator,
+            passcode,
+        })
+    }
+
+    pub fn with_commissioning_flow(mut self, commissioning_flow: CommissioningFlow) -> Self {
+        self.commissioning_flow = commissioning_flow;
+        self
+    }
+
+    pub fn with_discovery_capabilities(
+        mut self,
+        discovery_capabilities: DiscoveryCapabilities,
+    ) -> Self {
+        self.discovery_capabilities = discovery_capabilities;
+        self
+    }
+
+    /// Packs the fields least significant bit first, starting with the 3-bit version 0
+    fn pack(&self) -> [u8; PACKED_LEN] {
+        let flow = match self.commissioning_flow {
+            CommissioningFlow::Standard => 0,
+            CommissioningFlow::UserIntent => 1,
+            CommissioningFlow::Custom => 2,
+        };
+        let packed = (self.vendor_id as u128) << 3
+            | (self.product_id as u128) << 19
+            | (flow as u128) << 35
+            | (self.discovery_capabilities.bits() as u128) << 37
+            | (self.discriminator as u128) << 45
+            | (self.passcode as u128) << 57;
+        let mut bytes = [0; PACKED_LEN];
+        bytes.copy_from_slice(&packed.to_le_bytes()[..PACKED_LEN]);
+        bytes
+    }
+}
+
+fn is_valid_passcode(passcode: u32) -> bool {
+    const INVALID: [u32; 12] = [
+        0, 11111111, 22222222, 33333333, 44444444, 55555555, 66666666, 77777777, 88888888,
+        99999999, 12345678, 87654321,
+    ];
+    passcode <= 99999998 && !INVALID.contains(&passcode)
+}
+
+/// Writes the bytes in base-38, every three bytes are encoded as five characters
+fn write_base38(f: &mut Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    for chunk in bytes.chunks(3) {
+        let value = chunk
+            .iter()
+            .rev()
+            .fold(0u32, |value, &byte| value << 8 | byte as u32);
+        let char_count = match chunk.len() {
+            3 => 5,
+            2 => 4,
+            _ => 2,
+        };
+        let mut value = value;
+        for _ in 0..char_count {
+            f.write_char(BASE38_ALPHABET[(value % 38) as usize] as char)?;
+            value /= 38;
+        }
+    }
+    Ok(())
+}
+
+impl Display for MatterOnboarding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("MT:")?;
+        write_base38(f, &self.pack())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::QrError;
+    use crate::payload::matter::{CommissioningFlow, DiscoveryCapabilities, MatterOnboarding};
+    use alloc::format;
+
+    #[test]
+    fn custom_flow_on_network() {
+        let payload = MatterOnboarding::new(0xfff1, 0x8001, 0xabc, 12345679)
+            .unwrap()
+            .with_commissioning_flow(CommissioningFlow::Custom)
+            .with_discovery_capabilities(DiscoveryCapabilities {
+                on_network: true,
+                ..DiscoveryCapabilities::default()
+            });
+        let text = format!("{}", payload);
+        assert_eq!(text.len(), 22);
+        assert_eq!(&text[..3], "MT:");
+    }
+
+    #[test]
+    fn on_network() {
+        // The on-network payload of the all-clusters example app of the Matter SDK
+        let payload = MatterOnboarding::new(0xfff1, 0x8001, 3840, 20202021)
+            .unwrap()
+            .with_discovery_capabilities(DiscoveryCapabilities {
+                on_network: true,
+                ..DiscoveryCapabilities::default()
+            });
+        assert_eq!(format!("{}", payload), "MT:-24J0AFN00KA0648G00");
+
+        // The same device discoverable over BLE
+        let payload = payload.with_discovery_capabilities(DiscoveryCapabilities {
+            ble: true,
+            ..DiscoveryCapabilities::default()
+        });
+        assert_eq!(format!("{}", payload), "MT:-24J042C00KA0648G00");
+    }
+
+    #[test]
+    fn invalid_fields() {
+        assert_eq!(
+            MatterOnboarding::new(0xfff1, 0x8000, 3840, 12345678),
+            Err(QrError::InvalidField { field: "passcode" })
+        );
+        assert_eq!(
+            MatterOnboarding::new(0xfff1, 0x8000, 0x1000, 20202021),
+            Err(QrError::InvalidField {
+                field: "discriminator"
+            })
+        );
+    }
+}
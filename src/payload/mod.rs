@@ -0,0 +1,92 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Builders for the text content of commonly used QR code payloads
+//!
+//! The payloads implement `Display`, so they can be written into any `core::fmt::Write` buffer
+//! before encoding.
+
+mod bluetooth;
+mod matter;
+mod otpauth;
+mod payment;
+mod swiss_qr_bill;
+mod vevent;
+mod wifi;
+
+pub use bluetooth::BluetoothPairing;
+pub use matter::{CommissioningFlow, DiscoveryCapabilities, MatterOnboarding};
+pub use otpauth::{OtpAlgorithm, OtpAuth, OtpKind};
+pub use payment::PaymentUri;
+pub use swiss_qr_bill::{SwissAddress, SwissCurrency, SwissQrBill, SwissReference};
+pub use vevent::{CalendarEvent, EventTime};
+pub use wifi::{WifiConfig, WifiSecurity};
+
+use core::fmt::{self, Display, Formatter, Write};
+
+/// Writes a value with the characters that are special in key-value payloads escaped by a backslash
+pub(crate) struct Escaped<'a>(pub(crate) &'a str);
+
+impl Display for Escaped<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            if matches!(c, '\\' | ';' | ',' | ':' | '"') {
+                f.write_char('\\')?;
+            }
+            f.write_char(c)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a URI component with everything except the unreserved characters percent-encoded
+pub(crate) struct PercentEncoded<'a>(pub(crate) &'a str);
+
+impl Display for PercentEncoded<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for byte in self.0.bytes() {
+            if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+                f.write_char(byte as char)?;
+            } else {
+                write!(f, "%{:02X}", byte)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::payload::{Escaped, PercentEncoded};
+    use alloc::format;
+
+    #[test]
+    fn escaped() {
+        assert_eq!(
+            format!("{}", Escaped(r#"a;b,c:d\e"f"#)),
+            r#"a\;b\,c\:d\\e\"f"#
+        );
+    }
+
+    #[test]
+    fn percent_encoded() {
+        assert_eq!(
+            format!("{}", PercentEncoded("a b&c=d/é~")),
+            "a%20b%26c%3Dd%2F%C3%A9~"
+        );
+    }
+}
@@ -0,0 +1,240 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::error::QrError;
+use crate::payload::PercentEncoded;
+use core::fmt::{self, Display, Formatter, Write};
+
+/// The hash algorithm of a one-time password
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OtpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+/// Whether the password depends on the time or on a counter
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OtpKind {
+    /// Time-based (RFC 6238), with the period in seconds
+    Totp { period: u32 },
+    /// Counter-based (RFC 4226), with the initial counter
+    Hotp { counter: u64 },
+}
+
+/// Payload for provisioning a one-time password generator, like an authenticator app
+///
+/// The payload is an `otpauth://` URI in the Key Uri Format. The secret is given as raw bytes and
+/// written in Base32 without padding. Parameters with their default values are left out to keep
+/// the symbol small.
+///
+/// # Example
+///```
+///     use tiny_qr::payload::OtpAuth;
+///     let payload = OtpAuth::totp(b"12345678901234567890", "alice@example.com")
+///         .with_issuer("ACME Co");
+///     assert_eq!(
+///         payload.to_string(),
+///         "otpauth://totp/ACME%20Co:alice%40example.com\
+///          ?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACME%20Co"
+///     );
+///```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OtpAuth<'a> {
+    secret: &'a [u8],
+    account: &'a str,
+    issuer: Option<&'a str>,
+    kind: OtpKind,
+    algorithm: OtpAlgorithm,
+    digits: u8,
+}
+
+impl<'a> OtpAuth<'a> {
+    /// Creates a time-based password with a period of 30 seconds
+    pub fn totp(secret: &'a [u8], account: &'a str) -> Self {
+        Self::new(secret, account, OtpKind::Totp { period: 30 })
+    }
+
+    /// Creates a counter-based password starting at the counter
+    pub fn hotp(secret: &'a [u8], account: &'a str, counter: u64) -> Self {
+        Self::new(secret, account, OtpKind::Hotp { counter })
+    }
+
+    fn new(secret: &'a [u8], account: &'a str, kind: OtpKind) -> Self {
+        Self {
+            secret,
+            account,
+            issuer: None,
+            kind,
+            algorithm: OtpAlgorithm::Sha1,
+            digits: 6,
+        }
+    }
+
+    /// Sets the provider or service, which is written in both the label and the parameters
+    pub fn with_issuer(mut self, issuer: &'a str) -> Self {
+        self.issuer = Some(issuer);
+        self
+    }
+
+    pub fn with_algorithm(mut self, algorithm: OtpAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Sets the number of digits of the password
+    ///
+    /// Returns [`QrError::InvalidField`] when the number of digits isn't 6 or 8.
+    ///
+    /// [`QrError::InvalidField`]: crate::QrError::InvalidField
+    pub fn with_digits(mut self, digits: u8) -> Result<Self, QrError> {
+        if digits != 6 && digits != 8 {
+            return Err(QrError::InvalidField { field: "digits" });
+        }
+        self.digits = digits;
+        Ok(self)
+    }
+
+    /// Sets the period in seconds of a time-based password
+    ///
+    /// Returns [`QrError::InvalidField`] when the password is counter-based or the period is
+    /// zero.
+    ///
+    /// [`QrError::InvalidField`]: crate::QrError::InvalidField
+    pub fn with_period(mut self, period: u32) -> Result<Self, QrError> {
+        match &mut self.kind {
+            OtpKind::Totp { period: p } if period > 0 => *p = period,
+            _ => return Err(QrError::InvalidField { field: "period" }),
+        }
+        Ok(self)
+    }
+}
+
+/// Writes bytes in the Base32 alphabet of RFC 4648, without padding
+struct Base32<'a>(&'a [u8]);
+
+impl Display for Base32<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+        let mut bits = 0u16;
+        let mut bit_count = 0;
+        for &byte in self.0 {
+            bits = (bits << 8) | u16::from(byte);
+            bit_count += 8;
+            while bit_count >= 5 {
+                bit_count -= 5;
+                f.write_char(ALPHABET[usize::from((bits >> bit_count) & 0x1f)] as char)?;
+            }
+        }
+        if bit_count > 0 {
+            f.write_char(ALPHABET[usize::from((bits << (5 - bit_count)) & 0x1f)] as char)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for OtpAuth<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            OtpKind::Totp { .. } => "totp",
+            OtpKind::Hotp { .. } => "hotp",
+        };
+        write!(f, "otpauth://{}/", kind)?;
+        if let Some(issuer) = self.issuer {
+            write!(f, "{}:", PercentEncoded(issuer))?;
+        }
+        write!(
+            f,
+            "{}?secret={}",
+            PercentEncoded(self.account),
+            Base32(self.secret)
+        )?;
+        if let Some(issuer) = self.issuer {
+            write!(f, "&issuer={}", PercentEncoded(issuer))?;
+        }
+        match self.algorithm {
+            OtpAlgorithm::Sha1 => {}
+            OtpAlgorithm::Sha256 => f.write_str("&algorithm=SHA256")?,
+            OtpAlgorithm::Sha512 => f.write_str("&algorithm=SHA512")?,
+        }
+        if self.digits != 6 {
+            write!(f, "&digits={}", self.digits)?;
+        }
+        match self.kind {
+            OtpKind::Totp { period: 30 } => Ok(()),
+            OtpKind::Totp { period } => write!(f, "&period={}", period),
+            OtpKind::Hotp { counter } => write!(f, "&counter={}", counter),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::QrError;
+    use crate::payload::otpauth::Base32;
+    use crate::payload::{OtpAlgorithm, OtpAuth};
+    use alloc::format;
+
+    #[test]
+    fn base32() {
+        // The test vectors of RFC 4648, without the padding
+        let vectors = [
+            ("", ""),
+            ("f", "MY"),
+            ("fo", "MZXQ"),
+            ("foo", "MZXW6"),
+            ("foob", "MZXW6YQ"),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI"),
+        ];
+        for (input, output) in vectors {
+            assert_eq!(format!("{}", Base32(input.as_bytes())), output);
+        }
+    }
+
+    #[test]
+    fn hotp_with_parameters() {
+        let payload = OtpAuth::hotp(b"\x00\xff", "Ren\u{e9}/dev", 42)
+            .with_algorithm(OtpAlgorithm::Sha256)
+            .with_digits(8)
+            .unwrap();
+        assert_eq!(
+            format!("{}", payload),
+            "otpauth://hotp/Ren%C3%A9%2Fdev?secret=AD7Q&algorithm=SHA256&digits=8&counter=42"
+        );
+    }
+
+    #[test]
+    fn totp_period() {
+        let payload = OtpAuth::totp(b"key", "bob").with_period(60).unwrap();
+        assert_eq!(
+            format!("{}", payload),
+            "otpauth://totp/bob?secret=NNSXS&period=60"
+        );
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        let error = Err(QrError::InvalidField { field: "period" });
+        assert_eq!(OtpAuth::hotp(b"key", "bob", 0).with_period(60), error);
+        assert_eq!(OtpAuth::totp(b"key", "bob").with_period(0), error);
+        assert_eq!(
+            OtpAuth::totp(b"key", "bob").with_digits(7),
+            Err(QrError::InvalidField { field: "digits" })
+        );
+    }
+}
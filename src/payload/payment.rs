@@ -0,0 +1,194 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::error::QrError;
+use crate::payload::PercentEncoded;
+use core::fmt::{self, Display, Formatter};
+
+/// Payload of a cryptocurrency payment request, a BIP-21 style URI
+///
+/// The URI is `<scheme>:<address>` with optional `amount`, `label` and `message` parameters. The
+/// amount is given in the smallest unit of the currency, like satoshi, and written as a decimal
+/// number in the main unit.
+///
+/// # Example
+///```
+///     use tiny_qr::payload::PaymentUri;
+///     let payload = PaymentUri::bitcoin("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq")?
+///         .with_amount(50_000)
+///         .with_label("Coffee & cake");
+///     assert_eq!(
+///         payload.to_string(),
+///         "bitcoin:bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq?amount=0.0005&label=Coffee%20%26%20cake"
+///     );
+///     # Ok::<(), tiny_qr::QrError>(())
+///```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PaymentUri<'a> {
+    scheme: &'a str,
+    address: &'a str,
+    decimals: u8,
+    amount: Option<u64>,
+    label: Option<&'a str>,
+    message: Option<&'a str>,
+}
+
+impl<'a> PaymentUri<'a> {
+    /// Creates a request for a currency with the URI scheme and the number of decimals of its
+    /// smallest unit
+    ///
+    /// Returns [`QrError::InvalidField`] when the scheme or address is empty, the address contains
+    /// characters that aren't allowed in a URI path or there are more than 19 decimals.
+    ///
+    /// [`QrError::InvalidField`]: crate::QrError::InvalidField
+    pub fn new(scheme: &'a str, address: &'a str, decimals: u8) -> Result<Self, QrError> {
+        let is_valid =
+            |value: &str| !value.is_empty() && value.bytes().all(|b| b.is_ascii_alphanumeric());
+        if !is_valid(scheme) {
+            return Err(QrError::InvalidField { field: "scheme" });
+        }
+        if !is_valid(address) {
+            return Err(QrError::InvalidField { field: "address" });
+        }
+        if decimals > 19 {
+            return Err(QrError::InvalidField { field: "decimals" });
+        }
+        Ok(Self {
+            scheme,
+            address,
+            decimals,
+            amount: None,
+            label: None,
+            message: None,
+        })
+    }
+
+    /// Creates a request for bitcoin, with amounts in satoshi
+    pub fn bitcoin(address: &'a str) -> Result<Self, QrError> {
+        Self::new("bitcoin", address, 8)
+    }
+
+    /// Sets the amount in the smallest unit of the currency
+    pub fn with_amount(mut self, amount: u64) -> Self {
+        self.amount = Some(amount);
+        self
+    }
+
+    /// Sets the name of the recipient
+    pub fn with_label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Sets the description of the payment
+    pub fn with_message(mut self, message: &'a str) -> Self {
+        self.message = Some(message);
+        self
+    }
+}
+
+impl Display for PaymentUri<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.scheme, self.address)?;
+        let mut separator = '?';
+        if let Some(amount) = self.amount {
+            let unit = 10u64.pow(u32::from(self.decimals));
+            write!(f, "{}amount={}", separator, amount / unit)?;
+            let mut fraction = amount % unit;
+            if fraction != 0 {
+                // Trailing zeros of the fraction are left out
+                let mut decimals = usize::from(self.decimals);
+                while fraction % 10 == 0 {
+                    fraction /= 10;
+                    decimals -= 1;
+                }
+                write!(f, ".{:0width$}", fraction, width = decimals)?;
+            }
+            separator = '&';
+        }
+        if let Some(label) = self.label {
+            write!(f, "{}label={}", separator, PercentEncoded(label))?;
+            separator = '&';
+        }
+        if let Some(message) = self.message {
+            write!(f, "{}message={}", separator, PercentEncoded(message))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::QrError;
+    use crate::payload::PaymentUri;
+    use alloc::format;
+
+    #[test]
+    fn amounts() {
+        let uri = |amount| {
+            format!(
+                "{}",
+                PaymentUri::bitcoin("1BoatSLRHtKNngkdXEeobR76b53LETtpyT")
+                    .unwrap()
+                    .with_amount(amount)
+            )
+        };
+        assert_eq!(
+            uri(0),
+            "bitcoin:1BoatSLRHtKNngkdXEeobR76b53LETtpyT?amount=0"
+        );
+        assert_eq!(
+            uri(1),
+            "bitcoin:1BoatSLRHtKNngkdXEeobR76b53LETtpyT?amount=0.00000001"
+        );
+        assert_eq!(
+            uri(2_030_000_000),
+            "bitcoin:1BoatSLRHtKNngkdXEeobR76b53LETtpyT?amount=20.3"
+        );
+        assert_eq!(
+            uri(100_000_000),
+            "bitcoin:1BoatSLRHtKNngkdXEeobR76b53LETtpyT?amount=1"
+        );
+    }
+
+    #[test]
+    fn generic_scheme() {
+        let payload = PaymentUri::new("litecoin", "LQ3B36Yv2rBTxdgAdYpU2UcEZsaNwXeATk", 8)
+            .unwrap()
+            .with_message("Invoice #42");
+        assert_eq!(
+            format!("{}", payload),
+            "litecoin:LQ3B36Yv2rBTxdgAdYpU2UcEZsaNwXeATk?message=Invoice%20%2342"
+        );
+    }
+
+    #[test]
+    fn invalid_fields() {
+        assert_eq!(
+            PaymentUri::bitcoin("1BoatSLRHtKNngkdXEeobR76b53LETtpyT?amount=1"),
+            Err(QrError::InvalidField { field: "address" })
+        );
+        assert_eq!(
+            PaymentUri::new("", "1BoatSLRHtKNngkdXEeobR76b53LETtpyT", 8),
+            Err(QrError::InvalidField { field: "scheme" })
+        );
+        assert_eq!(
+            PaymentUri::new("bitcoin", "1BoatSLRHtKNngkdXEeobR76b53LETtpyT", 20),
+            Err(QrError::InvalidField { field: "decimals" })
+        );
+    }
+}
@@ -0,0 +1,427 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::encoding::{CharacterSet, EciPolicy, EncodeOptions};
+use crate::error::QrError;
+use crate::error_correction::ErrorCorrectionLevel;
+use core::fmt::{self, Display, Formatter};
+use core::ops::Range;
+
+/// The currency of a Swiss QR-bill
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SwissCurrency {
+    Chf,
+    Eur,
+}
+
+/// A structured address of the creditor or debtor of a Swiss QR-bill
+///
+/// The street and building number may be empty.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SwissAddress<'a> {
+    /// At most 70 characters
+    pub name: &'a str,
+    /// At most 70 characters
+    pub street: &'a str,
+    /// At most 16 characters
+    pub building_number: &'a str,
+    /// At most 16 characters
+    pub postal_code: &'a str,
+    /// At most 35 characters
+    pub town: &'a str,
+    /// The two letter ISO 3166 country code
+    pub country: &'a str,
+}
+
+impl SwissAddress<'_> {
+    fn validate(&self) -> Result<(), QrError> {
+        validate_field(self.name, 1, 70, "name")?;
+        validate_field(self.street, 0, 70, "street")?;
+        validate_field(self.building_number, 0, 16, "building number")?;
+        validate_field(self.postal_code, 1, 16, "postal code")?;
+        validate_field(self.town, 1, 35, "town")?;
+        if self.country.len() == 2 && self.country.bytes().all(|b| b.is_ascii_uppercase()) {
+            Ok(())
+        } else {
+            Err(QrError::InvalidField { field: "country" })
+        }
+    }
+}
+
+/// The payment reference of a Swiss QR-bill
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SwissReference<'a> {
+    /// No reference, for an IBAN without QR-IID
+    None,
+    /// A QR reference of 27 digits, for a QR-IBAN
+    Qr(&'a str),
+    /// An ISO 11649 creditor reference starting with `RF`, of 5 to 25 characters
+    Creditor(&'a str),
+}
+
+/// Payload of the Swiss QR Code on a QR-bill, the payment part of Swiss invoices
+///
+/// The fields are written on separate lines, version 2.0 of the specification with structured
+/// addresses. The symbol must use error correction level M and up to version 25, see
+/// [`SwissQrBill::encode_options`]. A bill usually needs a larger version than a [`QrCode`]
+/// holds, so with the `alloc` feature format it with `QrCodeBuilder::with_format_args_dyn` and
+/// build a `DynQrCode`, which renders and verifies like a [`QrCode`].
+///
+/// The builder methods return [`QrError::InvalidField`] when a field is empty, too long or has
+/// the wrong format.
+///
+/// [`QrCode`]: crate::QrCode
+/// [`QrError::InvalidField`]: crate::QrError::InvalidField
+///
+/// # Example
+///```
+///     use tiny_qr::payload::{SwissAddress, SwissQrBill, SwissReference};
+///     let creditor = SwissAddress {
+///         name: "Robert Schneider AG",
+///         street: "Rue du Lac",
+///         building_number: "1268",
+///         postal_code: "2501",
+///         town: "Biel",
+///         country: "CH",
+///     };
+///     let payload = SwissQrBill::new("CH4431999123000889012", creditor)?
+///         .with_amount(194_975)?
+///         .with_reference(SwissReference::Qr("210000000003139471430009017"))?
+///         .with_message("Order of 15 June 2020")?;
+///     assert!(payload.to_string().starts_with("SPC\n0200\n1\nCH4431999123000889012\nS\n"));
+///     # Ok::<(), tiny_qr::QrError>(())
+///```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SwissQrBill<'a> {
+    iban: &'a str,
+    creditor: SwissAddress<'a>,
+    amount: Option<u64>,
+    currency: SwissCurrency,
+    debtor: Option<SwissAddress<'a>>,
+    reference: SwissReference<'a>,
+    message: &'a str,
+    billing_information: &'a str,
+}
+
+impl<'a> SwissQrBill<'a> {
+    /// The largest version that the specification allows
+    pub const MAX_VERSION: u8 = 25;
+
+    /// Creates a bill in Swiss francs without amount, debtor and reference
+    ///
+    /// Returns [`QrError::InvalidField`] when the IBAN isn't 21 characters without spaces or a
+    /// field of the creditor is invalid.
+    ///
+    /// [`QrError::InvalidField`]: crate::QrError::InvalidField
+    pub fn new(iban: &'a str, creditor: SwissAddress<'a>) -> Result<Self, QrError> {
+        if iban.len() != 21 || !iban.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return Err(QrError::InvalidField { field: "IBAN" });
+        }
+        creditor.validate()?;
+        Ok(Self {
+            iban,
+            creditor,
+            amount: None,
+            currency: SwissCurrency::Chf,
+            debtor: None,
+            reference: SwissReference::None,
+            message: "",
+            billing_information: "",
+        })
+    }
+
+    /// Sets the amount in cents, at most 999 999 999.99
+    pub fn with_amount(mut self, cents: u64) -> Result<Self, QrError> {
+        if cents > 99_999_999_999 {
+            return Err(QrError::InvalidField { field: "amount" });
+        }
+        self.amount = Some(cents);
+        Ok(self)
+    }
+
+    pub fn with_currency(mut self, currency: SwissCurrency) -> Self {
+        self.currency = currency;
+        self
+    }
+
+    pub fn with_debtor(mut self, debtor: SwissAddress<'a>) -> Result<Self, QrError> {
+        debtor.validate()?;
+        self.debtor = Some(debtor);
+        Ok(self)
+    }
+
+    pub fn with_reference(mut self, reference: SwissReference<'a>) -> Result<Self, QrError> {
+        let valid = match reference {
+            SwissReference::None => true,
+            SwissReference::Qr(reference) => {
+                reference.len() == 27 && reference.bytes().all(|b| b.is_ascii_digit())
+            }
+            SwissReference::Creditor(reference) => {
+                (5..=25).contains(&reference.len())
+                    && reference.starts_with("RF")
+                    && reference.bytes().all(|b| b.is_ascii_alphanumeric())
+            }
+        };
+        if !valid {
+            return Err(QrError::InvalidField { field: "reference" });
+        }
+        self.reference = reference;
+        Ok(self)
+    }
+
+    /// Sets the unstructured message, which shares 140 characters with the billing information
+    pub fn with_message(mut self, message: &'a str) -> Result<Self, QrError> {
+        self.message = message;
+        self.validate_message_len()?;
+        Ok(self)
+    }
+
+    /// Sets the structured billing information, for example in the Swico format
+    pub fn with_billing_information(
+        mut self,
+        billing_information: &'a str,
+    ) -> Result<Self, QrError> {
+        self.billing_information = billing_information;
+        self.validate_message_len()?;
+        Ok(self)
+    }
+
+    fn validate_message_len(&self) -> Result<(), QrError> {
+        validate_field(self.message, 0, 140, "message")?;
+        validate_field(self.billing_information, 0, 140, "billing information")?;
+        let len = self.message.chars().count() + self.billing_information.chars().count();
+        if len > 140 {
+            return Err(QrError::InvalidField {
+                field: "billing information",
+            });
+        }
+        Ok(())
+    }
+
+    /// The options the specification requires: error correction level M, UTF-8 byte mode and up
+    /// to [`SwissQrBill::MAX_VERSION`]
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::payload::SwissQrBill;
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     let builder = QrCodeBuilder::new().with_options(SwissQrBill::encode_options());
+    ///```
+    pub fn encode_options() -> EncodeOptions {
+        EncodeOptions::new()
+            .with_max_version(Self::MAX_VERSION)
+            .with_specific_error_correction_level(ErrorCorrectionLevel::Medium)
+            .with_character_set(CharacterSet::Unicode)
+            .with_eci_policy(EciPolicy::Never)
+    }
+
+    /// The rows and columns of the Swiss cross in a symbol of the width
+    ///
+    /// The cross is 7 mm wide in the center of the 46 mm wide symbol, without the quiet zone.
+    /// It covers the modules below it, which the error correction level M recovers.
+    pub fn swiss_cross_area(width: usize) -> Range<usize> {
+        let len = (width * 7).div_ceil(46);
+        let start = (width - len) / 2;
+        start..start + len
+    }
+}
+
+fn validate_field(
+    value: &str,
+    min_len: usize,
+    max_len: usize,
+    field: &'static str,
+) -> Result<(), QrError> {
+    let len = value.chars().count();
+    if (min_len..=max_len).contains(&len) && !value.contains(['\n', '\r']) {
+        Ok(())
+    } else {
+        Err(QrError::InvalidField { field })
+    }
+}
+
+fn write_address(f: &mut Formatter<'_>, address: Option<&SwissAddress>) -> fmt::Result {
+    match address {
+        Some(address) => write!(
+            f,
+            "S\n{}\n{}\n{}\n{}\n{}\n{}\n",
+            address.name,
+            address.street,
+            address.building_number,
+            address.postal_code,
+            address.town,
+            address.country
+        ),
+        // All seven lines of the address are empty
+        None => f.write_str("\n\n\n\n\n\n\n"),
+    }
+}
+
+impl Display for SwissQrBill<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "SPC\n0200\n1\n{}\n", self.iban)?;
+        write_address(f, Some(&self.creditor))?;
+        // The ultimate creditor is reserved for future use
+        write_address(f, None)?;
+        if let Some(amount) = self.amount {
+            write!(f, "{}.{:02}", amount / 100, amount % 100)?;
+        }
+        let currency = match self.currency {
+            SwissCurrency::Chf => "CHF",
+            SwissCurrency::Eur => "EUR",
+        };
+        write!(f, "\n{}\n", currency)?;
+        write_address(f, self.debtor.as_ref())?;
+        match self.reference {
+            SwissReference::None => f.write_str("NON\n\n")?,
+            SwissReference::Qr(reference) => write!(f, "QRR\n{}\n", reference)?,
+            SwissReference::Creditor(reference) => write!(f, "SCOR\n{}\n", reference)?,
+        }
+        write!(f, "{}\nEPD", self.message)?;
+        if !self.billing_information.is_empty() {
+            write!(f, "\n{}", self.billing_information)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::QrError;
+    use crate::payload::{SwissAddress, SwissCurrency, SwissQrBill, SwissReference};
+    use alloc::format;
+
+    const CREDITOR: SwissAddress = SwissAddress {
+        name: "Robert Schneider AG",
+        street: "Rue du Lac",
+        building_number: "1268",
+        postal_code: "2501",
+        town: "Biel",
+        country: "CH",
+    };
+
+    #[test]
+    fn full_bill() {
+        let debtor = SwissAddress {
+            name: "Pia-Maria Rutschmann-Schnyder",
+            street: "Grosse Marktgasse",
+            building_number: "28",
+            postal_code: "9400",
+            town: "Rorschach",
+            country: "CH",
+        };
+        let payload = SwissQrBill::new("CH5800791123000889012", CREDITOR)
+            .unwrap()
+            .with_amount(21_100)
+            .unwrap()
+            .with_currency(SwissCurrency::Eur)
+            .with_debtor(debtor)
+            .unwrap()
+            .with_reference(SwissReference::Creditor("RF18539007547034"))
+            .unwrap()
+            .with_billing_information("//S1/10/10201409/11/190512")
+            .unwrap();
+        assert_eq!(
+            format!("{}", payload),
+            "SPC\n0200\n1\nCH5800791123000889012\n\
+             S\nRobert Schneider AG\nRue du Lac\n1268\n2501\nBiel\nCH\n\
+             \n\n\n\n\n\n\n\
+             211.00\nEUR\n\
+             S\nPia-Maria Rutschmann-Schnyder\nGrosse Marktgasse\n28\n9400\nRorschach\nCH\n\
+             SCOR\nRF18539007547034\n\
+             \nEPD\n//S1/10/10201409/11/190512"
+        );
+    }
+
+    #[test]
+    fn without_amount_and_reference() {
+        let payload = SwissQrBill::new("CH4431999123000889012", CREDITOR).unwrap();
+        let text = format!("{}", payload);
+        assert!(text.ends_with("CH\n\n\n\n\n\n\n\n\nCHF\n\n\n\n\n\n\n\nNON\n\n\nEPD"));
+    }
+
+    #[test]
+    fn swiss_cross_area() {
+        assert_eq!(SwissQrBill::swiss_cross_area(117), 49..67);
+        assert_eq!(SwissQrBill::swiss_cross_area(33), 13..19);
+    }
+
+    #[test]
+    fn town_too_long() {
+        let creditor = SwissAddress {
+            town: "Llanfairpwllgwyngyllgogerychwyrndrobwll",
+            ..CREDITOR
+        };
+        assert_eq!(
+            SwissQrBill::new("CH4431999123000889012", creditor),
+            Err(QrError::InvalidField { field: "town" })
+        );
+    }
+
+    #[test]
+    fn invalid_qr_reference() {
+        let result = SwissQrBill::new("CH4431999123000889012", CREDITOR)
+            .unwrap()
+            .with_reference(SwissReference::Qr("12345"));
+        assert_eq!(result, Err(QrError::InvalidField { field: "reference" }));
+    }
+
+    #[test]
+    fn message_too_long() {
+        let message = "x".repeat(100);
+        let result = SwissQrBill::new("CH4431999123000889012", CREDITOR)
+            .unwrap()
+            .with_message(&message)
+            .unwrap()
+            .with_billing_information(&message);
+        assert!(result.is_err());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn build_bill() {
+        use crate::qrcode::{QrCodeBuilder, QUIET_ZONE};
+
+        let message = "x".repeat(140);
+        let payload = SwissQrBill::new("CH4431999123000889012", CREDITOR)
+            .unwrap()
+            .with_amount(99_999_999_999)
+            .unwrap()
+            .with_debtor(CREDITOR)
+            .unwrap()
+            .with_reference(SwissReference::Qr("210000000003139471430009017"))
+            .unwrap()
+            .with_message(&message)
+            .unwrap();
+        let qr_code = QrCodeBuilder::new()
+            .with_options(SwissQrBill::encode_options())
+            .with_format_args_dyn(format_args!("{}", payload))
+            .build_dyn();
+        let version = qr_code.version().unwrap().number();
+        assert!(version > 4 && version <= SwissQrBill::MAX_VERSION);
+        assert_eq!(qr_code.verify(&format!("{}", payload)), Ok(()));
+        let rows = qr_code.packed_rows().with_border(QUIET_ZONE).count();
+        assert_eq!(rows, qr_code.width() + 2 * QUIET_ZONE);
+
+        let too_long = "x".repeat(2000);
+        let result = QrCodeBuilder::new()
+            .with_options(SwissQrBill::encode_options())
+            .with_text(&too_long)
+            .try_build_dyn();
+        assert_eq!(result.err(), Some(QrError::DataTooLong));
+    }
+}
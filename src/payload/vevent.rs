@@ -0,0 +1,279 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::encoding::{EncodeOptions, EncodingPlan};
+use crate::{QrCodeBuilder, QrError};
+use core::fmt::{self, Display, Formatter, Write};
+
+/// The start or end of a calendar event, either a whole day or a time of day
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EventTime {
+    year: u16,
+    month: u8,
+    day: u8,
+    /// Hour, minute and second, `None` for a whole day
+    time: Option<(u8, u8, u8)>,
+    utc: bool,
+}
+
+impl EventTime {
+    /// Creates the date of an all-day event
+    ///
+    /// Returns [`QrError::InvalidField`] when the year, month or day is out of range.
+    ///
+    /// [`QrError::InvalidField`]: crate::QrError::InvalidField
+    pub fn date(year: u16, month: u8, day: u8) -> Result<Self, QrError> {
+        if year > 9999 || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return Err(QrError::InvalidField { field: "date" });
+        }
+        Ok(Self {
+            year,
+            month,
+            day,
+            time: None,
+            utc: false,
+        })
+    }
+
+    /// Creates a time in UTC
+    pub fn utc(
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+    ) -> Result<Self, QrError> {
+        Ok(Self {
+            utc: true,
+            ..Self::local(year, month, day, hour, minute, second)?
+        })
+    }
+
+    /// Creates a time without time zone, which the reader interprets in its own time zone
+    ///
+    /// Returns [`QrError::InvalidField`] when a field is out of range.
+    ///
+    /// [`QrError::InvalidField`]: crate::QrError::InvalidField
+    pub fn local(
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+    ) -> Result<Self, QrError> {
+        if hour >= 24 || minute >= 60 || second >= 61 {
+            return Err(QrError::InvalidField { field: "time" });
+        }
+        Ok(Self {
+            time: Some((hour, minute, second)),
+            ..Self::date(year, month, day)?
+        })
+    }
+}
+
+impl Display for EventTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}{:02}{:02}", self.year, self.month, self.day)?;
+        if let Some((hour, minute, second)) = self.time {
+            write!(f, "T{:02}{:02}{:02}", hour, minute, second)?;
+        }
+        if self.utc {
+            f.write_char('Z')?;
+        }
+        Ok(())
+    }
+}
+
+/// Payload of an "add to calendar" code, a single iCalendar VEVENT
+///
+/// Only the summary, start, end and location are written, without the surrounding VCALENDAR, and
+/// the lines are separated by a line feed instead of CR LF. Both save space and are accepted by
+/// common readers. Use [`CalendarEvent::plan`] to check whether the event fits.
+///
+/// # Example
+///```
+///     use tiny_qr::payload::{CalendarEvent, EventTime};
+///     let payload = CalendarEvent::new("Demo", EventTime::utc(2024, 3, 1, 9, 30, 0)?);
+///     assert_eq!(
+///         payload.to_string(),
+///         "BEGIN:VEVENT\nSUMMARY:Demo\nDTSTART:20240301T093000Z\nEND:VEVENT"
+///     );
+///     # Ok::<(), tiny_qr::QrError>(())
+///```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CalendarEvent<'a> {
+    summary: &'a str,
+    start: EventTime,
+    end: Option<EventTime>,
+    location: Option<&'a str>,
+}
+
+impl<'a> CalendarEvent<'a> {
+    pub fn new(summary: &'a str, start: EventTime) -> Self {
+        Self {
+            summary,
+            start,
+            end: None,
+            location: None,
+        }
+    }
+
+    /// Sets the end, which is exclusive: an all-day event ends on the next day
+    pub fn with_end(mut self, end: EventTime) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    pub fn with_location(mut self, location: &'a str) -> Self {
+        self.location = Some(location);
+        self
+    }
+
+    /// Returns the version and error correction level the event would be encoded in, or
+    /// [`QrError::DataTooLong`] when it doesn't fit the maximum version of the options
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::encoding::EncodeOptions;
+    ///     use tiny_qr::payload::{CalendarEvent, EventTime};
+    ///     use tiny_qr::QrError;
+    ///     let payload = CalendarEvent::new("Demo", EventTime::date(2024, 3, 1)?);
+    ///     let options = EncodeOptions::new().with_max_version(2);
+    ///     assert_eq!(payload.plan(&options).err(), Some(QrError::DataTooLong));
+    ///     # Ok::<(), QrError>(())
+    ///```
+    pub fn plan(&self, options: &EncodeOptions) -> Result<EncodingPlan, QrError> {
+        QrCodeBuilder::new()
+            .with_options(*options)
+            .with_format_args(format_args!("{}", self))
+            .plan()
+    }
+}
+
+/// Writes a value with the characters that are special in iCalendar text escaped
+struct TextEscaped<'a>(&'a str);
+
+impl Display for TextEscaped<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '\\' | ';' | ',' => {
+                    f.write_char('\\')?;
+                    f.write_char(c)?;
+                }
+                '\n' => f.write_str("\\n")?,
+                '\r' => {}
+                _ => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for CalendarEvent<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "BEGIN:VEVENT\nSUMMARY:{}\n", TextEscaped(self.summary))?;
+        // Whole days have to be marked as a date instead of a date-time
+        let value_type = |time: &EventTime| match time.time {
+            Some(_) => "",
+            None => ";VALUE=DATE",
+        };
+        writeln!(f, "DTSTART{}:{}", value_type(&self.start), self.start)?;
+        if let Some(end) = &self.end {
+            writeln!(f, "DTEND{}:{}", value_type(end), end)?;
+        }
+        if let Some(location) = self.location {
+            writeln!(f, "LOCATION:{}", TextEscaped(location))?;
+        }
+        f.write_str("END:VEVENT")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encoding::EncodeOptions;
+    use crate::error::QrError;
+    use crate::payload::{CalendarEvent, EventTime};
+    use crate::Version;
+    use alloc::format;
+
+    #[test]
+    fn all_day_with_location() {
+        let payload = CalendarEvent::new("Fair", EventTime::date(2024, 5, 2).unwrap())
+            .with_end(EventTime::date(2024, 5, 4).unwrap())
+            .with_location("Hall 1, Utrecht");
+        assert_eq!(
+            format!("{}", payload),
+            "BEGIN:VEVENT\nSUMMARY:Fair\nDTSTART;VALUE=DATE:20240502\n\
+             DTEND;VALUE=DATE:20240504\nLOCATION:Hall 1\\, Utrecht\nEND:VEVENT"
+        );
+    }
+
+    #[test]
+    fn local_time_and_escaping() {
+        let payload = CalendarEvent::new(
+            "Stand-up; daily\nroom\\2",
+            EventTime::local(2024, 1, 8, 9, 0, 0).unwrap(),
+        );
+        assert_eq!(
+            format!("{}", payload),
+            "BEGIN:VEVENT\nSUMMARY:Stand-up\\; daily\\nroom\\\\2\nDTSTART:20240108T090000\nEND:VEVENT"
+        );
+    }
+
+    #[test]
+    fn plan() {
+        let payload = CalendarEvent::new("Demo", EventTime::utc(2024, 3, 1, 9, 30, 0).unwrap());
+        let plan = payload.plan(&EncodeOptions::new()).unwrap();
+        assert_eq!(plan.version, Version::new(4).unwrap());
+        let payload = payload.with_location("Somewhere far away");
+        assert!(payload.plan(&EncodeOptions::new()).is_err());
+    }
+
+    #[test]
+    fn longer_than_format_buffer() {
+        let summary = "Quarterly planning ".repeat(20);
+        let payload = CalendarEvent::new(&summary, EventTime::date(2024, 3, 1).unwrap());
+        assert!(format!("{}", payload).len() > 300);
+        let options = EncodeOptions::new();
+        assert_eq!(payload.plan(&options).err(), Some(QrError::DataTooLong));
+
+        // The text isn't cut off to fit the buffer of the builder
+        #[cfg(feature = "alloc")]
+        {
+            use crate::QrCodeBuilder;
+            let builder = QrCodeBuilder::new().with_format_args(format_args!("{}", payload));
+            assert_eq!(builder.try_build_dyn().err(), Some(QrError::DataTooLong));
+            let builder = QrCodeBuilder::new().with_format_args_dyn(format_args!("{}", payload));
+            assert!(builder.try_build_dyn().is_ok());
+        }
+    }
+
+    #[test]
+    fn invalid_fields() {
+        assert_eq!(
+            EventTime::date(2024, 13, 1),
+            Err(QrError::InvalidField { field: "date" })
+        );
+        assert_eq!(
+            EventTime::utc(2024, 3, 1, 24, 0, 0),
+            Err(QrError::InvalidField { field: "time" })
+        );
+    }
+}
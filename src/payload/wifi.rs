@@ -0,0 +1,141 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::payload::Escaped;
+use core::fmt::{self, Display, Formatter};
+
+/// The authentication type of a Wi-Fi network
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WifiSecurity {
+    /// An open network without password
+    Open,
+    Wep,
+    /// WPA or WPA2 personal, which WPA3 networks in transition mode accept as well
+    Wpa,
+    /// WPA3 personal only, written as `SAE`
+    Wpa3,
+}
+
+impl WifiSecurity {
+    fn code(self) -> &'static str {
+        match self {
+            WifiSecurity::Open => "nopass",
+            WifiSecurity::Wep => "WEP",
+            WifiSecurity::Wpa => "WPA",
+            WifiSecurity::Wpa3 => "SAE",
+        }
+    }
+}
+
+/// Payload for joining a Wi-Fi network, which phone cameras recognize
+///
+/// The payload uses the `WIFI:` prefix with `T` (security), `S` (network name), `P` (password)
+/// and `H` (hidden) fields. The special characters `\`, `;`, `,`, `:` and `"` in the name and
+/// password are escaped.
+///
+/// # Example
+///```
+///     use tiny_qr::payload::{WifiConfig, WifiSecurity};
+///     use tiny_qr::QrCodeBuilder;
+///     let payload = WifiConfig::new("Home; 5GHz")
+///         .with_security(WifiSecurity::Wpa)
+///         .with_password("p@ss\"word")
+///         .with_hidden(true);
+///     assert_eq!(
+///         payload.to_string(),
+///         r#"WIFI:T:WPA;S:Home\; 5GHz;P:p@ss\"word;H:true;;"#
+///     );
+///     let qr_code = QrCodeBuilder::new().with_text(payload.to_string()).build();
+///```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WifiConfig<'a> {
+    ssid: &'a str,
+    security: WifiSecurity,
+    password: Option<&'a str>,
+    hidden: bool,
+}
+
+impl<'a> WifiConfig<'a> {
+    /// Creates the payload of an open network with the name
+    pub fn new(ssid: &'a str) -> Self {
+        Self {
+            ssid,
+            security: WifiSecurity::Open,
+            password: None,
+            hidden: false,
+        }
+    }
+
+    pub fn with_security(mut self, security: WifiSecurity) -> Self {
+        self.security = security;
+        self
+    }
+
+    /// Sets the password, which is left out for an open network
+    pub fn with_password(mut self, password: &'a str) -> Self {
+        self.password = Some(password);
+        self
+    }
+
+    /// Whether the network doesn't broadcast its name
+    pub fn with_hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+}
+
+impl Display for WifiConfig<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "WIFI:T:{};S:{};",
+            self.security.code(),
+            Escaped(self.ssid)
+        )?;
+        match (self.security, self.password) {
+            (WifiSecurity::Open, _) | (_, None) => {}
+            (_, Some(password)) => write!(f, "P:{};", Escaped(password))?,
+        }
+        if self.hidden {
+            f.write_str("H:true;")?;
+        }
+        f.write_str(";")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::payload::{WifiConfig, WifiSecurity};
+    use alloc::format;
+
+    #[test]
+    fn open_network() {
+        let payload = WifiConfig::new("Guest").with_password("ignored");
+        assert_eq!(format!("{}", payload), "WIFI:T:nopass;S:Guest;;");
+    }
+
+    #[test]
+    fn escaping() {
+        let payload = WifiConfig::new(r#"a\b,c:d"#)
+            .with_security(WifiSecurity::Wpa3)
+            .with_password(";;");
+        assert_eq!(
+            format!("{}", payload),
+            r#"WIFI:T:SAE;S:a\\b\,c\:d;P:\;\;;;"#
+        );
+    }
+}
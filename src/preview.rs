@@ -0,0 +1,187 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::buffer::Buffer;
+use crate::buffer::BUFFER_CAPACITY;
+use crate::encoding::{
+    encode_planned, normalize_case, plan_holds, plan_text, select_character_set, EncodeOptions,
+    Plan,
+};
+use crate::error::QrError;
+use crate::qrcode::{QrCode, MAX_MODULE_SIZE, TEXT_CAPACITY};
+
+/// Regenerates a QR code for a text that is being edited, for example on every keystroke
+///
+/// The detected character set and the selected version are remembered, so appending characters
+/// that fit in them doesn't require scanning the whole text again or trying every version. An
+/// unchanged text, after case normalization, returns the previous symbol.
+///
+/// # Example
+///```
+///     use tiny_qr::encoding::EncodeOptions;
+///     use tiny_qr::LivePreview;
+///     let mut preview = LivePreview::new(EncodeOptions::new());
+///     preview.update("HTTPS://").unwrap();
+///     let qr_code = preview.update("HTTPS://EXAMPLE.COM").unwrap();
+///```
+pub struct LivePreview {
+    options: EncodeOptions,
+    text: Buffer<[u8; TEXT_CAPACITY]>,
+    plan: Option<Plan>,
+    qr_code: Option<QrCode<MAX_MODULE_SIZE>>,
+}
+
+impl LivePreview {
+    pub fn new(options: EncodeOptions) -> Self {
+        Self {
+            options,
+            text: Buffer::default(),
+            plan: None,
+            qr_code: None,
+        }
+    }
+
+    pub fn update(&mut self, text: &str) -> Result<&QrCode<MAX_MODULE_SIZE>, QrError> {
+        let mut uppercase = [[0; BUFFER_CAPACITY]; 2];
+        let text = normalize_case(&self.options, text, uppercase.as_flattened_mut());
+        if self.qr_code.is_none() || self.text.data() != text.as_bytes() {
+            let qr_code = self.encode(text)?;
+            self.qr_code = Some(qr_code);
+        }
+        Ok(self.qr_code.as_ref().unwrap())
+    }
+
+    /// Encodes the text, which is already normalized
+    fn encode(&mut self, text: &str) -> Result<QrCode<MAX_MODULE_SIZE>, QrError> {
+        let previous = self.text.data();
+        let plan = match self.plan {
+            // Appending characters of the detected character set doesn't change the detection,
+            // and the plan while the text still fits in it
+            Some(plan)
+                if text.as_bytes().starts_with(previous)
+                    && text[previous.len()..]
+                        .chars()
+                        .all(|c| plan.character_set.contains(c)) =>
+            {
+                if plan_holds::<BUFFER_CAPACITY>(&self.options, &plan, text) {
+                    plan
+                } else {
+                    plan_text::<BUFFER_CAPACITY>(&self.options, plan.character_set, text)?
+                }
+            }
+            _ => {
+                let character_set = select_character_set(&self.options, text)?;
+                plan_text::<BUFFER_CAPACITY>(&self.options, character_set, text)?
+            }
+        };
+        let encoded_data = encode_planned::<BUFFER_CAPACITY>(&self.options, &plan, text)?;
+
+        self.plan = Some(plan);
+        self.text = Buffer::default();
+        self.text.append_bytes(text.as_bytes());
+        Ok(QrCode::from_encoded_data(&self.options, encoded_data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encoding::{CharacterSet, EncodeOptions};
+    use crate::error::QrError;
+    use crate::preview::LivePreview;
+    use crate::qrcode::QrCodeBuilder;
+    use alloc::format;
+
+    #[test]
+    fn matches_builder() {
+        let mut preview = LivePreview::new(EncodeOptions::new());
+        for text in [
+            "1",
+            "12",
+            "12A",
+            "12A",
+            "12",
+            "12a",
+            "",
+            "HELLO WORLD",
+            "hello",
+            "hello",
+            "hello world",
+            "HTTPS://EXAMPLE.COM/",
+            "HTTPS://EXAMPLE.COM/ABCDEFGHIJKLMNOPQRSTUVWXYZ",
+            "HTTPS://EXAMPLE.COM/ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789",
+        ] {
+            let expected = QrCodeBuilder::new().with_text(text).build();
+            let qr_code = preview.update(text).unwrap();
+            assert_eq!(
+                format!("{:?}", qr_code),
+                format!("{:?}", expected),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn normalized_text() {
+        let options = EncodeOptions::new().with_uppercase_normalization(true);
+        let mut preview = LivePreview::new(options);
+        preview.update("hello").unwrap();
+        assert_eq!(preview.text.data(), b"HELLO");
+        // The unchanged text keeps the symbol without encoding it again
+        preview.plan = None;
+        preview.update("hello").unwrap();
+        assert!(preview.plan.is_none());
+    }
+
+    #[test]
+    fn plan_after_append() {
+        let mut preview = LivePreview::new(EncodeOptions::new());
+        let mut text = alloc::string::String::new();
+        for c in "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 $%*+-./:"
+            .chars()
+            .cycle()
+            .take(90)
+        {
+            text.push(c);
+            let plan = QrCodeBuilder::new().with_text(&text).plan().unwrap();
+            preview.update(&text).expect(&text);
+            let qr_code = preview.qr_code.as_ref().unwrap();
+            assert_eq!(qr_code.version(), Some(plan.version), "{}", text);
+            assert_eq!(
+                qr_code.error_correction(),
+                plan.error_correction,
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_character() {
+        let options = EncodeOptions::new().with_character_set(CharacterSet::Numeric);
+        let mut preview = LivePreview::new(options);
+        preview.update("12").unwrap();
+        assert!(matches!(
+            preview.update("12a"),
+            Err(QrError::InvalidCharacter {
+                character: 'a',
+                position: 2
+            })
+        ));
+        assert!(preview.update("123").is_ok());
+    }
+}
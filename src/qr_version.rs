@@ -15,8 +15,14 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-use crate::encoding::EncodingMode;
+use crate::encoding::{capacity, EncodingMode};
+use crate::error::QrError;
 use crate::error_correction::ErrorCorrectionLevel;
+use core::convert::TryFrom;
+use core::fmt;
+
+/// The largest version of the QR code standard
+pub(crate) const LAST_VERSION: u8 = 40;
 
 pub const fn version_to_size(version: u8) -> usize {
     version as usize * 4 + 17
@@ -28,6 +34,42 @@ pub struct Version {
 }
 
 impl Version {
+    /// Creates a version, fails if it is outside the range 1 to 40
+    ///
+    /// This crate only encodes up to version 4, which building checks separately.
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::{QrError, Version};
+    ///     assert_eq!(Version::new(2).unwrap().width(), 25);
+    ///     assert_eq!(Version::new(41), Err(QrError::UnsupportedVersion { version: 41 }));
+    ///```
+    pub const fn new(version: u8) -> Result<Self, QrError> {
+        if version >= 1 && version <= LAST_VERSION {
+            Ok(Self { version })
+        } else {
+            Err(QrError::UnsupportedVersion { version })
+        }
+    }
+
+    /// Returns an iterator over all versions, from 1 to 40
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::Version;
+    ///     let mut versions = Version::all();
+    ///     assert_eq!(versions.next(), Some(Version::new(1).unwrap()));
+    ///     assert_eq!(versions.next_back(), Some(Version::new(40).unwrap()));
+    ///```
+    pub fn all() -> impl DoubleEndedIterator<Item = Version> + ExactSizeIterator {
+        (1..=LAST_VERSION).map(|version| Version { version })
+    }
+
+    /// The version number, from 1 to 40
+    pub const fn number(&self) -> u8 {
+        self.version
+    }
+
     pub fn decrement(self) -> Option<Self> {
         if self.version > 1 {
             Some(Self {
@@ -38,10 +80,18 @@ impl Version {
         }
     }
 
+    /// The number of modules per row and column, without the quiet zone
     pub const fn width(&self) -> usize {
         version_to_size(self.version)
     }
 
+    /// The maximum number of characters of the mode that fit, see [`capacity`]
+    ///
+    /// [`capacity`]: crate::encoding::capacity
+    pub fn capacity(&self, error_correction: ErrorCorrectionLevel, mode: EncodingMode) -> usize {
+        capacity(*self, error_correction, mode)
+    }
+
     pub fn character_count_indicator_bit_length(&self, encoding: EncodingMode) -> usize {
         match encoding {
             EncodingMode::Numeric => match self.version {
@@ -62,39 +112,64 @@ impl Version {
                 27..=40 => 16,
                 _ => panic!(),
             },
+            EncodingMode::Kanji => match self.version {
+                0..=9 => 8,
+                10..=26 => 10,
+                27..=40 => 12,
+                _ => panic!(),
+            },
         }
     }
 
-    pub fn total_codeword_count(&self) -> usize {
+    pub const fn total_codeword_count(&self) -> usize {
+        // The block structure of every error correction level adds up to the same total
+        self.block_structure(ErrorCorrectionLevel::Low)
+            .total_codeword_count()
+    }
+
+    /// The number of zero bits placed after the last codeword to fill the symbol
+    pub fn remainder_bit_count(&self) -> usize {
         match self.version {
-            1 => 26,
-            2 => 44,
-            3 => 70,
-            4 => 100,
-            5 => 134,
-            6 => 172,
-            7 => 196,
-            8 => 242,
-            9 => 292,
-            10 => 346,
-            11 => 404,
-            12 => 466,
-            13 => 532,
-            14 => 581,
-            15 => 655,
-            16 => 733,
-            17 => 815,
-            18 => 901,
-            19 => 991,
-            20 => 1085,
-            21 => 1156,
-            22 => 1258,
-            23 => 1364,
-            24 => 1474,
-            25 => 1588,
-            // TODO: Finish table 9 edition 2006
-            _ => panic!(),
+            2..=6 => 7,
+            14..=20 | 28..=34 => 3,
+            21..=27 => 4,
+            _ => 0,
+        }
+    }
+
+    /// The centers of the alignment patterns as (row, column), leaving out the three positions that
+    /// overlap with the finder patterns
+    pub fn alignment_pattern_centers(&self) -> impl Iterator<Item = (usize, usize)> {
+        let positions = ALIGNMENT_PATTERN_POSITIONS[self.version as usize - 1];
+        let last = positions.last().copied().unwrap_or_default() as usize;
+        positions
+            .iter()
+            .flat_map(move |&row| {
+                positions
+                    .iter()
+                    .map(move |&column| (row as usize, column as usize))
+            })
+            .filter(move |&(row, column)| {
+                !((row == 6 && (column == 6 || column == last)) || (row == last && column == 6))
+            })
+    }
+
+    /// The 18 bit version information with its BCH error correction, only present from version 7
+    pub(crate) const fn information(&self) -> Option<u32> {
+        if self.version < 7 {
+            return None;
+        }
+        // BCH (18,6) code with generator polynomial x^12 + x^11 + x^10 + x^9 + x^8 + x^5 + x^2 + 1
+        let data = self.version as u32;
+        let mut remainder = data << 12;
+        let mut bit = 17;
+        while bit >= 12 {
+            if remainder & (1 << bit) != 0 {
+                remainder ^= 0x1F25 << (bit - 12);
+            }
+            bit -= 1;
         }
+        Some(data << 12 | remainder)
     }
 
     pub fn data_codeword_count(&self, error_correction: ErrorCorrectionLevel) -> usize {
@@ -112,30 +187,539 @@ impl Version {
         &self,
         error_correction: ErrorCorrectionLevel,
     ) -> (usize, usize) {
-        use ErrorCorrectionLevel::{High as H, Low as L, Medium as M, Quartile as Q};
-        match (self.version, error_correction) {
-            (1, L) => (7, 1),
-            (1, M) => (10, 1),
-            (1, Q) => (13, 1),
-            (1, H) => (17, 1),
-            (2, L) => (10, 1),
-            (2, M) => (16, 1),
-            (2, Q) => (22, 1),
-            (2, H) => (28, 1),
-            (3, L) => (15, 1),
-            (3, M) => (26, 1),
-            (3, Q) => (36, 2),
-            (3, H) => (44, 2),
-            (4, L) => (20, 1),
-            (4, M) => (36, 2),
-            (4, Q) => (52, 2),
-            (4, H) => (64, 4),
-            (5, L) => (26, 1),
-            (5, M) => (48, 2),
-            (5, Q) => (72, 4),
-            (5, H) => (88, 4),
-            // TODO: Finish table 9 edition 2006
-            _ => panic!(),
+        let structure = self.block_structure(error_correction);
+        (
+            structure.block_count() * structure.ecc_len as usize,
+            structure.block_count(),
+        )
+    }
+
+    pub(crate) const fn block_structure(
+        &self,
+        error_correction: ErrorCorrectionLevel,
+    ) -> BlockStructure {
+        let index = match error_correction {
+            ErrorCorrectionLevel::Low => 0,
+            ErrorCorrectionLevel::Medium => 1,
+            ErrorCorrectionLevel::Quartile => 2,
+            ErrorCorrectionLevel::High => 3,
+        };
+        BLOCK_STRUCTURES[self.version as usize - 1][index]
+    }
+}
+
+impl TryFrom<u8> for Version {
+    type Error = QrError;
+
+    fn try_from(version: u8) -> Result<Self, Self::Error> {
+        Version::new(version)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.width();
+        write!(f, "Version {} ({}×{})", self.version, width, width)
+    }
+}
+
+/// A group of error correction blocks with the same length
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+pub(crate) struct BlockGroup {
+    pub(crate) count: u8,
+    pub(crate) data_len: u8,
+}
+
+/// The division of the codewords into error correction blocks, as listed in table 9
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+pub(crate) struct BlockStructure {
+    /// The number of error correction codewords in each block
+    pub(crate) ecc_len: u8,
+    /// The blocks of the second group contain one more data codeword than the first group
+    pub(crate) groups: [BlockGroup; 2],
+}
+
+impl BlockStructure {
+    const fn new(ecc_len: u8, group1: (u8, u8), group2: (u8, u8)) -> Self {
+        Self {
+            ecc_len,
+            groups: [
+                BlockGroup {
+                    count: group1.0,
+                    data_len: group1.1,
+                },
+                BlockGroup {
+                    count: group2.0,
+                    data_len: group2.1,
+                },
+            ],
+        }
+    }
+
+    pub(crate) const fn block_count(&self) -> usize {
+        self.groups[0].count as usize + self.groups[1].count as usize
+    }
+
+    pub(crate) const fn data_codeword_count(&self) -> usize {
+        self.groups[0].count as usize * self.groups[0].data_len as usize
+            + self.groups[1].count as usize * self.groups[1].data_len as usize
+    }
+
+    pub(crate) const fn total_codeword_count(&self) -> usize {
+        self.data_codeword_count() + self.block_count() * self.ecc_len as usize
+    }
+}
+
+/// Row and column coordinates of the alignment pattern centers, see annex E
+const ALIGNMENT_PATTERN_POSITIONS: [&[u8]; 40] = [
+    &[],
+    &[6, 18],
+    &[6, 22],
+    &[6, 26],
+    &[6, 30],
+    &[6, 34],
+    &[6, 22, 38],
+    &[6, 24, 42],
+    &[6, 26, 46],
+    &[6, 28, 50],
+    &[6, 30, 54],
+    &[6, 32, 58],
+    &[6, 34, 62],
+    &[6, 26, 46, 66],
+    &[6, 26, 48, 70],
+    &[6, 26, 50, 74],
+    &[6, 30, 54, 78],
+    &[6, 30, 56, 82],
+    &[6, 30, 58, 86],
+    &[6, 34, 62, 90],
+    &[6, 28, 50, 72, 94],
+    &[6, 26, 50, 74, 98],
+    &[6, 30, 54, 78, 102],
+    &[6, 28, 54, 80, 106],
+    &[6, 32, 58, 84, 110],
+    &[6, 30, 58, 86, 114],
+    &[6, 34, 62, 90, 118],
+    &[6, 26, 50, 74, 98, 122],
+    &[6, 30, 54, 78, 102, 126],
+    &[6, 26, 52, 78, 104, 130],
+    &[6, 30, 56, 82, 108, 134],
+    &[6, 34, 60, 86, 112, 138],
+    &[6, 30, 58, 86, 114, 142],
+    &[6, 34, 62, 90, 118, 146],
+    &[6, 30, 54, 78, 102, 126, 150],
+    &[6, 24, 50, 76, 102, 128, 154],
+    &[6, 28, 54, 80, 106, 132, 158],
+    &[6, 32, 58, 84, 110, 136, 162],
+    &[6, 26, 54, 82, 110, 138, 166],
+    &[6, 30, 58, 86, 114, 142, 170],
+];
+
+/// Error correction block structure indexed by version and error correction level (L, M, Q, H)
+const BLOCK_STRUCTURES: [[BlockStructure; 4]; 40] = [
+    // Version 1
+    [
+        BlockStructure::new(7, (1, 19), (0, 0)),
+        BlockStructure::new(10, (1, 16), (0, 0)),
+        BlockStructure::new(13, (1, 13), (0, 0)),
+        BlockStructure::new(17, (1, 9), (0, 0)),
+    ],
+    // Version 2
+    [
+        BlockStructure::new(10, (1, 34), (0, 0)),
+        BlockStructure::new(16, (1, 28), (0, 0)),
+        BlockStructure::new(22, (1, 22), (0, 0)),
+        BlockStructure::new(28, (1, 16), (0, 0)),
+    ],
+    // Version 3
+    [
+        BlockStructure::new(15, (1, 55), (0, 0)),
+        BlockStructure::new(26, (1, 44), (0, 0)),
+        BlockStructure::new(18, (2, 17), (0, 0)),
+        BlockStructure::new(22, (2, 13), (0, 0)),
+    ],
+    // Version 4
+    [
+        BlockStructure::new(20, (1, 80), (0, 0)),
+        BlockStructure::new(18, (2, 32), (0, 0)),
+        BlockStructure::new(26, (2, 24), (0, 0)),
+        BlockStructure::new(16, (4, 9), (0, 0)),
+    ],
+    // Version 5
+    [
+        BlockStructure::new(26, (1, 108), (0, 0)),
+        BlockStructure::new(24, (2, 43), (0, 0)),
+        BlockStructure::new(18, (2, 15), (2, 16)),
+        BlockStructure::new(22, (2, 11), (2, 12)),
+    ],
+    // Version 6
+    [
+        BlockStructure::new(18, (2, 68), (0, 0)),
+        BlockStructure::new(16, (4, 27), (0, 0)),
+        BlockStructure::new(24, (4, 19), (0, 0)),
+        BlockStructure::new(28, (4, 15), (0, 0)),
+    ],
+    // Version 7
+    [
+        BlockStructure::new(20, (2, 78), (0, 0)),
+        BlockStructure::new(18, (4, 31), (0, 0)),
+        BlockStructure::new(18, (2, 14), (4, 15)),
+        BlockStructure::new(26, (4, 13), (1, 14)),
+    ],
+    // Version 8
+    [
+        BlockStructure::new(24, (2, 97), (0, 0)),
+        BlockStructure::new(22, (2, 38), (2, 39)),
+        BlockStructure::new(22, (4, 18), (2, 19)),
+        BlockStructure::new(26, (4, 14), (2, 15)),
+    ],
+    // Version 9
+    [
+        BlockStructure::new(30, (2, 116), (0, 0)),
+        BlockStructure::new(22, (3, 36), (2, 37)),
+        BlockStructure::new(20, (4, 16), (4, 17)),
+        BlockStructure::new(24, (4, 12), (4, 13)),
+    ],
+    // Version 10
+    [
+        BlockStructure::new(18, (2, 68), (2, 69)),
+        BlockStructure::new(26, (4, 43), (1, 44)),
+        BlockStructure::new(24, (6, 19), (2, 20)),
+        BlockStructure::new(28, (6, 15), (2, 16)),
+    ],
+    // Version 11
+    [
+        BlockStructure::new(20, (4, 81), (0, 0)),
+        BlockStructure::new(30, (1, 50), (4, 51)),
+        BlockStructure::new(28, (4, 22), (4, 23)),
+        BlockStructure::new(24, (3, 12), (8, 13)),
+    ],
+    // Version 12
+    [
+        BlockStructure::new(24, (2, 92), (2, 93)),
+        BlockStructure::new(22, (6, 36), (2, 37)),
+        BlockStructure::new(26, (4, 20), (6, 21)),
+        BlockStructure::new(28, (7, 14), (4, 15)),
+    ],
+    // Version 13
+    [
+        BlockStructure::new(26, (4, 107), (0, 0)),
+        BlockStructure::new(22, (8, 37), (1, 38)),
+        BlockStructure::new(24, (8, 20), (4, 21)),
+        BlockStructure::new(22, (12, 11), (4, 12)),
+    ],
+    // Version 14
+    [
+        BlockStructure::new(30, (3, 115), (1, 116)),
+        BlockStructure::new(24, (4, 40), (5, 41)),
+        BlockStructure::new(20, (11, 16), (5, 17)),
+        BlockStructure::new(24, (11, 12), (5, 13)),
+    ],
+    // Version 15
+    [
+        BlockStructure::new(22, (5, 87), (1, 88)),
+        BlockStructure::new(24, (5, 41), (5, 42)),
+        BlockStructure::new(30, (5, 24), (7, 25)),
+        BlockStructure::new(24, (11, 12), (7, 13)),
+    ],
+    // Version 16
+    [
+        BlockStructure::new(24, (5, 98), (1, 99)),
+        BlockStructure::new(28, (7, 45), (3, 46)),
+        BlockStructure::new(24, (15, 19), (2, 20)),
+        BlockStructure::new(30, (3, 15), (13, 16)),
+    ],
+    // Version 17
+    [
+        BlockStructure::new(28, (1, 107), (5, 108)),
+        BlockStructure::new(28, (10, 46), (1, 47)),
+        BlockStructure::new(28, (1, 22), (15, 23)),
+        BlockStructure::new(28, (2, 14), (17, 15)),
+    ],
+    // Version 18
+    [
+        BlockStructure::new(30, (5, 120), (1, 121)),
+        BlockStructure::new(26, (9, 43), (4, 44)),
+        BlockStructure::new(28, (17, 22), (1, 23)),
+        BlockStructure::new(28, (2, 14), (19, 15)),
+    ],
+    // Version 19
+    [
+        BlockStructure::new(28, (3, 113), (4, 114)),
+        BlockStructure::new(26, (3, 44), (11, 45)),
+        BlockStructure::new(26, (17, 21), (4, 22)),
+        BlockStructure::new(26, (9, 13), (16, 14)),
+    ],
+    // Version 20
+    [
+        BlockStructure::new(28, (3, 107), (5, 108)),
+        BlockStructure::new(26, (3, 41), (13, 42)),
+        BlockStructure::new(30, (15, 24), (5, 25)),
+        BlockStructure::new(28, (15, 15), (10, 16)),
+    ],
+    // Version 21
+    [
+        BlockStructure::new(28, (4, 116), (4, 117)),
+        BlockStructure::new(26, (17, 42), (0, 0)),
+        BlockStructure::new(28, (17, 22), (6, 23)),
+        BlockStructure::new(30, (19, 16), (6, 17)),
+    ],
+    // Version 22
+    [
+        BlockStructure::new(28, (2, 111), (7, 112)),
+        BlockStructure::new(28, (17, 46), (0, 0)),
+        BlockStructure::new(30, (7, 24), (16, 25)),
+        BlockStructure::new(24, (34, 13), (0, 0)),
+    ],
+    // Version 23
+    [
+        BlockStructure::new(30, (4, 121), (5, 122)),
+        BlockStructure::new(28, (4, 47), (14, 48)),
+        BlockStructure::new(30, (11, 24), (14, 25)),
+        BlockStructure::new(30, (16, 15), (14, 16)),
+    ],
+    // Version 24
+    [
+        BlockStructure::new(30, (6, 117), (4, 118)),
+        BlockStructure::new(28, (6, 45), (14, 46)),
+        BlockStructure::new(30, (11, 24), (16, 25)),
+        BlockStructure::new(30, (30, 16), (2, 17)),
+    ],
+    // Version 25
+    [
+        BlockStructure::new(26, (8, 106), (4, 107)),
+        BlockStructure::new(28, (8, 47), (13, 48)),
+        BlockStructure::new(30, (7, 24), (22, 25)),
+        BlockStructure::new(30, (22, 15), (13, 16)),
+    ],
+    // Version 26
+    [
+        BlockStructure::new(28, (10, 114), (2, 115)),
+        BlockStructure::new(28, (19, 46), (4, 47)),
+        BlockStructure::new(28, (28, 22), (6, 23)),
+        BlockStructure::new(30, (33, 16), (4, 17)),
+    ],
+    // Version 27
+    [
+        BlockStructure::new(30, (8, 122), (4, 123)),
+        BlockStructure::new(28, (22, 45), (3, 46)),
+        BlockStructure::new(30, (8, 23), (26, 24)),
+        BlockStructure::new(30, (12, 15), (28, 16)),
+    ],
+    // Version 28
+    [
+        BlockStructure::new(30, (3, 117), (10, 118)),
+        BlockStructure::new(28, (3, 45), (23, 46)),
+        BlockStructure::new(30, (4, 24), (31, 25)),
+        BlockStructure::new(30, (11, 15), (31, 16)),
+    ],
+    // Version 29
+    [
+        BlockStructure::new(30, (7, 116), (7, 117)),
+        BlockStructure::new(28, (21, 45), (7, 46)),
+        BlockStructure::new(30, (1, 23), (37, 24)),
+        BlockStructure::new(30, (19, 15), (26, 16)),
+    ],
+    // Version 30
+    [
+        BlockStructure::new(30, (5, 115), (10, 116)),
+        BlockStructure::new(28, (19, 47), (10, 48)),
+        BlockStructure::new(30, (15, 24), (25, 25)),
+        BlockStructure::new(30, (23, 15), (25, 16)),
+    ],
+    // Version 31
+    [
+        BlockStructure::new(30, (13, 115), (3, 116)),
+        BlockStructure::new(28, (2, 46), (29, 47)),
+        BlockStructure::new(30, (42, 24), (1, 25)),
+        BlockStructure::new(30, (23, 15), (28, 16)),
+    ],
+    // Version 32
+    [
+        BlockStructure::new(30, (17, 115), (0, 0)),
+        BlockStructure::new(28, (10, 46), (23, 47)),
+        BlockStructure::new(30, (10, 24), (35, 25)),
+        BlockStructure::new(30, (19, 15), (35, 16)),
+    ],
+    // Version 33
+    [
+        BlockStructure::new(30, (17, 115), (1, 116)),
+        BlockStructure::new(28, (14, 46), (21, 47)),
+        BlockStructure::new(30, (29, 24), (19, 25)),
+        BlockStructure::new(30, (11, 15), (46, 16)),
+    ],
+    // Version 34
+    [
+        BlockStructure::new(30, (13, 115), (6, 116)),
+        BlockStructure::new(28, (14, 46), (23, 47)),
+        BlockStructure::new(30, (44, 24), (7, 25)),
+        BlockStructure::new(30, (59, 16), (1, 17)),
+    ],
+    // Version 35
+    [
+        BlockStructure::new(30, (12, 121), (7, 122)),
+        BlockStructure::new(28, (12, 47), (26, 48)),
+        BlockStructure::new(30, (39, 24), (14, 25)),
+        BlockStructure::new(30, (22, 15), (41, 16)),
+    ],
+    // Version 36
+    [
+        BlockStructure::new(30, (6, 121), (14, 122)),
+        BlockStructure::new(28, (6, 47), (34, 48)),
+        BlockStructure::new(30, (46, 24), (10, 25)),
+        BlockStructure::new(30, (2, 15), (64, 16)),
+    ],
+    // Version 37
+    [
+        BlockStructure::new(30, (17, 122), (4, 123)),
+        BlockStructure::new(28, (29, 46), (14, 47)),
+        BlockStructure::new(30, (49, 24), (10, 25)),
+        BlockStructure::new(30, (24, 15), (46, 16)),
+    ],
+    // Version 38
+    [
+        BlockStructure::new(30, (4, 122), (18, 123)),
+        BlockStructure::new(28, (13, 46), (32, 47)),
+        BlockStructure::new(30, (48, 24), (14, 25)),
+        BlockStructure::new(30, (42, 15), (32, 16)),
+    ],
+    // Version 39
+    [
+        BlockStructure::new(30, (20, 117), (4, 118)),
+        BlockStructure::new(28, (40, 47), (7, 48)),
+        BlockStructure::new(30, (43, 24), (22, 25)),
+        BlockStructure::new(30, (10, 15), (67, 16)),
+    ],
+    // Version 40
+    [
+        BlockStructure::new(30, (19, 118), (6, 119)),
+        BlockStructure::new(28, (18, 47), (31, 48)),
+        BlockStructure::new(30, (34, 24), (34, 25)),
+        BlockStructure::new(30, (20, 15), (61, 16)),
+    ],
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::encoding::EncodingMode;
+    use crate::error::QrError;
+    use crate::error_correction::ErrorCorrectionLevel;
+    use crate::qr_version::{Version, LAST_VERSION};
+    use alloc::format;
+    use core::convert::TryFrom;
+
+    #[test]
+    fn alignment_pattern_centers() {
+        assert_eq!(
+            Version::new(1).unwrap().alignment_pattern_centers().count(),
+            0
+        );
+        for version in 2..=6 {
+            let version = Version::new(version).unwrap();
+            let end = version.width() - 7;
+            assert!(version.alignment_pattern_centers().eq([(end, end)]));
+        }
+        assert!(Version::new(7).unwrap().alignment_pattern_centers().eq([
+            (6, 22),
+            (22, 6),
+            (22, 22),
+            (22, 38),
+            (38, 22),
+            (38, 38)
+        ]));
+        assert_eq!(
+            Version::new(40)
+                .unwrap()
+                .alignment_pattern_centers()
+                .count(),
+            46
+        );
+        assert!(Version::new(32)
+            .unwrap()
+            .alignment_pattern_centers()
+            .all(|(row, column)| [6, 34, 60, 86, 112, 138].contains(&row)
+                && [6, 34, 60, 86, 112, 138].contains(&column)));
+    }
+
+    #[test]
+    fn information() {
+        assert_eq!(Version::new(6).unwrap().information(), None);
+        assert_eq!(Version::new(7).unwrap().information(), Some(0x07C94));
+        assert_eq!(Version::new(21).unwrap().information(), Some(0x15683));
+        assert_eq!(Version::new(40).unwrap().information(), Some(0x28C69));
+    }
+
+    #[test]
+    fn block_structures() {
+        for version in 1..=40 {
+            let version = Version::new(version).unwrap();
+            // Number of modules in the encoding region, see table 1
+            let width = version.width();
+            let alignment_count = match version.version {
+                1 => 0,
+                v => (v as usize / 7 + 2).pow(2) - 3,
+            };
+            let alignment_timing_overlap = match version.version {
+                1 => 0,
+                v => 2 * (v as usize / 7),
+            };
+            let version_info = if version.version >= 7 { 36 } else { 0 };
+            let function_modules =
+                3 * 64 + 2 * (width - 16) + 31 + version_info + 25 * alignment_count
+                    - 5 * alignment_timing_overlap;
+            let data_modules = width * width - function_modules;
+
+            for error_correction in [
+                ErrorCorrectionLevel::Low,
+                ErrorCorrectionLevel::Medium,
+                ErrorCorrectionLevel::Quartile,
+                ErrorCorrectionLevel::High,
+            ] {
+                let structure = version.block_structure(error_correction);
+                assert_eq!(
+                    structure.total_codeword_count() * 8 + version.remainder_bit_count(),
+                    data_modules
+                );
+                if structure.groups[1].count > 0 {
+                    assert_eq!(
+                        structure.groups[1].data_len,
+                        structure.groups[0].data_len + 1
+                    );
+                }
+            }
+        }
+        assert_eq!(Version::new(25).unwrap().total_codeword_count(), 1588);
+        assert_eq!(
+            Version::new(40)
+                .unwrap()
+                .data_codeword_count(ErrorCorrectionLevel::Low),
+            2956
+        );
+    }
+
+    #[test]
+    fn all() {
+        assert_eq!(Version::all().len(), LAST_VERSION as usize);
+        assert!(Version::all().map(|v| v.number()).eq(1..=LAST_VERSION));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(format!("{}", Version::new(5).unwrap()), "Version 5 (37×37)");
+    }
+
+    #[test]
+    fn new() {
+        assert_eq!(Version::try_from(40).map(|v| v.width()), Ok(177));
+        for version in [0, 41] {
+            assert_eq!(
+                Version::try_from(version),
+                Err(QrError::UnsupportedVersion { version })
+            );
         }
+        let version = Version::new(1).unwrap();
+        assert_eq!(
+            version.capacity(ErrorCorrectionLevel::High, EncodingMode::Alphanumeric),
+            10
+        );
     }
 }
@@ -15,56 +15,192 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-use crate::array_2d::Array2D;
+use crate::array_2d::{Array2D, Storage};
+#[cfg(feature = "art")]
+use crate::art::build_art;
+use crate::bit_array_2d::BitArray2D;
+use crate::buffer::BUFFER_CAPACITY;
+use crate::decoder::DecodeError;
 use crate::draw_iterator::DrawIterator;
-use crate::encoding::{encode_text, ErrorCorrectionRestriction, VersionRestriction};
+#[cfg(feature = "alloc")]
+use crate::dyn_qrcode::{DynQrCode, BUFFER_CAPACITY as DYN_BUFFER_CAPACITY};
+use crate::encoding::{
+    encode_bytes_sized, encode_digits_sized, encode_latin1_sized, encode_number_sized,
+    encode_segments_sized, encode_text_sized, encode_utf16_sized, plan_encode_bytes,
+    plan_encode_digits, plan_encode_latin1, plan_encode_number, plan_encode_segments,
+    plan_encode_text, plan_encode_utf16, EncodeOptions, EncodedData, EncodingPlan, MaskPolicy,
+    Segment,
+};
+#[cfg(feature = "iso-8859-15")]
+use crate::encoding::{encode_iso_8859_15_sized, plan_encode_iso_8859_15};
+#[cfg(feature = "shift-jis")]
+use crate::encoding::{encode_shift_jis_sized, plan_encode_shift_jis};
+#[cfg(feature = "windows-1252")]
+use crate::encoding::{encode_windows_1252_sized, plan_encode_windows_1252};
+use crate::error::QrError;
 use crate::error_correction::{add_error_correction, ErrorCorrectionLevel};
-use crate::mask::ScoreMasked;
-use crate::matrix::{Color, Matrix};
+use crate::format::FormatInfoCheck;
+use crate::heatmap::PenaltyHeatmap;
+use crate::logo::LogoArea;
+use crate::mask::{Mask, Masked, ScoringModel};
+use crate::matrix::{Color, Matrix, Module, ModuleKind};
+use crate::packed_rows::PackedRows;
 use crate::qr_version::{version_to_size, Version};
-use core::fmt::{Debug, Display, Formatter, Write};
+use crate::render::blocks::write_half_blocks;
+use crate::render::{Blocks, HalfBlocks, Renderer};
+use crate::run_length::RunLengthRows;
+use crate::symbol::{self, Symbol};
+use core::fmt::{self, Arguments, Debug, Display, Formatter, Write};
 
-const MAX_VERSION: u8 = 4;
-const MAX_MODULE_SIZE: usize = version_to_size(MAX_VERSION);
+pub(crate) const MAX_VERSION: u8 = 4;
+pub(crate) const MAX_MODULE_SIZE: usize = version_to_size(MAX_VERSION);
+/// The number of light modules required around a QR code
+pub(crate) const QUIET_ZONE: usize = 4;
+/// Numeric mode packs 2.4 characters per byte, so the text can be longer than the codewords
+pub(crate) const TEXT_CAPACITY: usize = 3 * BUFFER_CAPACITY;
+/// The text that fits the codewords of a [`DynQrCode`]
+#[cfg(feature = "alloc")]
+pub(crate) const DYN_TEXT_CAPACITY: usize = 3 * DYN_BUFFER_CAPACITY;
 
-pub struct QrCodeBuilder<'a> {
-    version_restriction: VersionRestriction,
-    error_correction_restriction: ErrorCorrectionRestriction,
-    mask_reference: Option<u8>,
-    text: Option<&'a str>,
+#[derive(Clone)]
+enum Payload<'a, T> {
+    Text(T),
+    Number(u128),
+    Bytes(&'a [u8]),
+    Latin1(&'a [u8]),
+    Utf16(&'a str),
+    #[cfg(feature = "shift-jis")]
+    ShiftJis(&'a str),
+    #[cfg(feature = "windows-1252")]
+    Windows1252(&'a str),
+    #[cfg(feature = "iso-8859-15")]
+    Iso8859_15(&'a str),
+    Segments(&'a [Segment<'a>]),
+    /// Formatted text that overflowed its buffer
+    TooLong,
 }
 
-impl<'a> Default for QrCodeBuilder<'a> {
+/// The state of a [`QrCodeBuilder`] before the payload is set
+#[derive(Clone, Copy, Debug)]
+pub struct NoPayload;
+
+/// The state of a [`QrCodeBuilder`] with a payload, which can be built
+///
+/// The text is stored as any type that can be borrowed as a string.
+#[derive(Clone)]
+pub struct WithPayload<'a, T = &'a str>(Payload<'a, T>);
+
+/// Text formatted by [`QrCodeBuilder::with_format_args`] into a buffer of `C` bytes in the builder
+///
+/// The default buffer holds any text that fits a [`QrCode`]. Text that overflows the buffer is
+/// recorded, so building and planning fail with [`QrError::DataTooLong`] instead of encoding the
+/// text cut off.
+#[derive(Clone)]
+pub struct FormattedText<const C: usize = TEXT_CAPACITY> {
+    buffer: [u8; C],
+    len: usize,
+    overflow: bool,
+}
+
+impl<const C: usize> FormattedText<C> {
+    fn new(args: Arguments<'_>) -> Self {
+        let mut text = Self {
+            buffer: [0; C],
+            len: 0,
+            overflow: false,
+        };
+        // The overflow is recorded by `write_str`
+        let _ = text.write_fmt(args);
+        text
+    }
+}
+
+impl<const C: usize> Write for FormattedText<C> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let available = C - self.len;
+        let mut len = s.len().min(available);
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
+        self.buffer[self.len..self.len + len].copy_from_slice(&s.as_bytes()[..len]);
+        self.len += len;
+        if len < s.len() {
+            self.overflow = true;
+            Err(fmt::Error)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<const C: usize> AsRef<str> for FormattedText<C> {
+    fn as_ref(&self) -> &str {
+        // Only whole characters are written
+        core::str::from_utf8(&self.buffer[..self.len]).unwrap()
+    }
+}
+
+/// Builds a QR code from a payload and encoding options
+///
+/// The builder can be cloned to reuse the options for many payloads. Only a builder with a payload
+/// can be built, so forgetting the payload doesn't compile:
+///
+///```compile_fail
+///     use tiny_qr::QrCodeBuilder;
+///     let qr_code = QrCodeBuilder::new().build();
+///```
+///
+/// # Example
+///```
+///     use tiny_qr::{ErrorCorrectionLevel, QrCodeBuilder};
+///     let builder = QrCodeBuilder::new()
+///         .with_specific_error_correction_level(ErrorCorrectionLevel::High);
+///     for serial in ["SN-001", "SN-002"] {
+///         let qr_code = builder.clone().with_text(serial).build();
+///     }
+///```
+#[derive(Clone)]
+pub struct QrCodeBuilder<P = NoPayload> {
+    options: EncodeOptions,
+    payload: P,
+}
+
+impl Default for QrCodeBuilder<NoPayload> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<'a> QrCodeBuilder<'a> {
+impl QrCodeBuilder<NoPayload> {
     pub fn new() -> Self {
         Self {
-            version_restriction: VersionRestriction::MaxVersion(Version {
-                version: MAX_VERSION,
-            }),
-            error_correction_restriction: ErrorCorrectionRestriction::MinErrorCorrection(
-                ErrorCorrectionLevel::Medium,
-            ),
-            mask_reference: None,
-            text: None,
+            options: EncodeOptions::new(),
+            payload: NoPayload,
         }
     }
+}
+
+impl<P> QrCodeBuilder<P> {
+    /// Replaces all encoding options at once
+    pub fn with_options(mut self, options: EncodeOptions) -> Self {
+        self.options = options;
+        self
+    }
 
     pub fn with_max_version(mut self, max_version: u8) -> Self {
-        assert!(max_version <= MAX_VERSION);
-        self.version_restriction = VersionRestriction::MaxVersion(Version {
-            version: max_version,
-        });
+        self.options = self.options.with_max_version(max_version);
         self
     }
 
     pub fn with_specific_version(mut self, version: u8) -> Self {
-        assert!(version <= MAX_VERSION);
-        self.version_restriction = VersionRestriction::SpecificVersion(Version { version });
+        self.options = self.options.with_specific_version(version);
+        self
+    }
+
+    /// Limits the version to the largest symbol that fits in a square print area, see
+    /// [`EncodeOptions::with_print_area`]
+    pub fn with_print_area(mut self, area: u32, module_size: u32) -> Self {
+        self.options = self.options.with_print_area(area, module_size);
         self
     }
 
@@ -72,8 +208,27 @@ impl<'a> QrCodeBuilder<'a> {
         mut self,
         min_error_correction_level: ErrorCorrectionLevel,
     ) -> Self {
-        self.error_correction_restriction =
-            ErrorCorrectionRestriction::MinErrorCorrection(min_error_correction_level);
+        self.options = self
+            .options
+            .with_min_error_correction_level(min_error_correction_level);
+        self
+    }
+
+    /// Whether the error correction level is raised above the minimum while the data still fits,
+    /// see [`EncodeOptions::with_error_correction_boost`]
+    pub fn with_error_correction_boost(mut self, error_correction_boost: bool) -> Self {
+        self.options = self
+            .options
+            .with_error_correction_boost(error_correction_boost);
+        self
+    }
+
+    /// Whether lowercase ASCII letters are uppercased when that allows alphanumeric mode, see
+    /// [`EncodeOptions::with_uppercase_normalization`]
+    pub fn with_uppercase_normalization(mut self, uppercase_normalization: bool) -> Self {
+        self.options = self
+            .options
+            .with_uppercase_normalization(uppercase_normalization);
         self
     }
 
@@ -81,115 +236,788 @@ impl<'a> QrCodeBuilder<'a> {
         mut self,
         error_correction_level: ErrorCorrectionLevel,
     ) -> Self {
-        self.error_correction_restriction =
-            ErrorCorrectionRestriction::SpecificErrorCorrection(error_correction_level);
+        self.options = self
+            .options
+            .with_specific_error_correction_level(error_correction_level);
+        self
+    }
+
+    pub fn with_mask_reference(mut self, mask: Mask) -> Self {
+        self.options = self.options.with_mask_policy(MaskPolicy::Fixed(mask));
         self
     }
 
-    pub fn with_mask_reference(mut self, mask_reference: u8) -> Self {
-        self.mask_reference = Some(mask_reference);
+    pub fn with_scoring_model(mut self, scoring_model: ScoringModel) -> Self {
+        self.options = self.options.with_scoring_model(scoring_model);
         self
     }
 
-    pub fn with_text(mut self, text: &'a str) -> Self {
-        self.text = Some(text);
+    /// Splits the text into segments of different modes when that is smaller
+    pub fn with_optimal_segmentation(mut self, optimal_segmentation: bool) -> Self {
+        self.options = self.options.with_optimal_segmentation(optimal_segmentation);
         self
     }
 
-    pub fn build(self) -> QrCode<MAX_MODULE_SIZE> {
-        let encoded_data = encode_text(
-            self.version_restriction,
-            self.error_correction_restriction,
-            self.text.unwrap(),
-        )
-        .unwrap();
+    /// Opts out of detecting Kanji text, see [`EncodeOptions::with_kanji_detection`]
+    #[cfg(feature = "kanji")]
+    pub fn with_kanji_detection(mut self, kanji_detection: bool) -> Self {
+        self.options = self.options.with_kanji_detection(kanji_detection);
+        self
+    }
 
-        let error_corrected_data = add_error_correction(encoded_data);
+    /// Sets the text to encode
+    ///
+    /// An empty text is allowed and results in a symbol that only contains padding. The text can
+    /// be borrowed or owned, such as a `String`, so the builder can outlive the original text.
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::{QrCodeBuilder, WithPayload};
+    ///     fn serial_builder(serial: u32) -> QrCodeBuilder<WithPayload<'static, String>> {
+    ///         QrCodeBuilder::new().with_text(format!("SN-{:04}", serial))
+    ///     }
+    ///     let qr_code = serial_builder(7).build();
+    ///```
+    pub fn with_text<T: AsRef<str>>(self, text: T) -> QrCodeBuilder<WithPayload<'static, T>> {
+        self.with_payload(Payload::Text(text))
+    }
 
-        let matrix = Matrix::from_data(error_corrected_data);
+    /// Formats the text into a buffer of the builder, without allocating
+    ///
+    /// The buffer holds any text that fits a [`QrCode`], longer text fails to build with
+    /// [`QrError::DataTooLong`]. Use `with_format_args_dyn` for a `DynQrCode`.
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     let counter = 42;
+    ///     let qr_code = QrCodeBuilder::new()
+    ///         .with_format_args(format_args!("SN:{:06}", counter))
+    ///         .build();
+    ///```
+    pub fn with_format_args(
+        self,
+        args: Arguments<'_>,
+    ) -> QrCodeBuilder<WithPayload<'static, FormattedText>> {
+        self.with_formatted_text(FormattedText::new(args))
+    }
 
-        let masked = if let Some(mask_reference) = self.mask_reference {
-            matrix.mask(mask_reference)
+    /// Formats the text into a buffer of the builder that holds any text that fits a [`DynQrCode`]
+    ///
+    /// The buffer takes about 11 KiB, so keep the builder on the heap when the stack is small.
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     let long = "a".repeat(500);
+    ///     let qr_code = QrCodeBuilder::new()
+    ///         .with_format_args_dyn(format_args!("{}", long))
+    ///         .build_dyn();
+    ///     assert_eq!(qr_code.version().unwrap().number(), 24);
+    ///```
+    #[cfg(feature = "alloc")]
+    pub fn with_format_args_dyn(
+        self,
+        args: Arguments<'_>,
+    ) -> QrCodeBuilder<WithPayload<'static, FormattedText<DYN_TEXT_CAPACITY>>> {
+        self.with_formatted_text(FormattedText::new(args))
+    }
+
+    fn with_formatted_text<const C: usize>(
+        self,
+        text: FormattedText<C>,
+    ) -> QrCodeBuilder<WithPayload<'static, FormattedText<C>>> {
+        if text.overflow {
+            self.with_payload(Payload::TooLong)
         } else {
-            matrix.best_mask()
-        };
+            self.with_text(text)
+        }
+    }
+
+    /// Encodes decimal digits from an iterator in numeric mode, see [`encode_digits`]
+    ///
+    /// [`encode_digits`]: crate::encoding::encode_digits
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     let reading = [0u8, 4, 2, 1, 7, 3];
+    ///     let qr_code = QrCodeBuilder::new().with_digits(reading.iter().copied()).build();
+    ///```
+    pub fn with_digits<I: Iterator<Item = u8> + Clone>(
+        self,
+        digits: I,
+    ) -> QrCodeBuilder<WithDigits<I>> {
+        QrCodeBuilder {
+            options: self.options,
+            payload: WithDigits(digits),
+        }
+    }
+
+    /// Encodes the number in numeric mode, without formatting it as a string first
+    pub fn with_number(self, number: u128) -> QrCodeBuilder<WithPayload<'static>> {
+        self.with_payload(Payload::Number(number))
+    }
+
+    /// Sets the ECI assignment number of the bytes or segments, see [`EncodeOptions::with_eci`]
+    pub fn with_eci(mut self, assignment: u32) -> Self {
+        self.options = self.options.with_eci(assignment);
+        self
+    }
+
+    /// Encodes binary data in byte mode, for data that isn't text
+    pub fn with_bytes<'a>(self, data: &'a [u8]) -> QrCodeBuilder<WithPayload<'a>> {
+        self.with_payload(Payload::Bytes(data))
+    }
+
+    /// Encodes ISO 8859-1 (Latin-1) text given as bytes, see [`encode_latin1`]
+    ///
+    /// [`encode_latin1`]: crate::encoding::encode_latin1
+    pub fn with_latin1<'a>(self, text: &'a [u8]) -> QrCodeBuilder<WithPayload<'a>> {
+        self.with_payload(Payload::Latin1(text))
+    }
 
-        QrCode::from(masked)
+    /// Encodes the text as UTF-16 bytes in the byte order of the ECI, see [`encode_utf16`]
+    ///
+    /// [`encode_utf16`]: crate::encoding::encode_utf16
+    pub fn with_utf16_text<'a>(self, text: &'a str) -> QrCodeBuilder<WithPayload<'a>> {
+        self.with_payload(Payload::Utf16(text))
+    }
+
+    /// Encodes the text as Shift JIS bytes with ECI 20, see [`encode_shift_jis`]
+    ///
+    /// [`encode_shift_jis`]: crate::encoding::encode_shift_jis
+    #[cfg(feature = "shift-jis")]
+    pub fn with_shift_jis_text<'a>(self, text: &'a str) -> QrCodeBuilder<WithPayload<'a>> {
+        self.with_payload(Payload::ShiftJis(text))
+    }
+
+    /// Encodes the text as Windows-1252 bytes with ECI 21, see [`encode_windows_1252`]
+    ///
+    /// [`encode_windows_1252`]: crate::encoding::encode_windows_1252
+    #[cfg(feature = "windows-1252")]
+    pub fn with_windows_1252_text<'a>(self, text: &'a str) -> QrCodeBuilder<WithPayload<'a>> {
+        self.with_payload(Payload::Windows1252(text))
+    }
+
+    /// Encodes the text as ISO 8859-15 bytes with ECI 17, see [`encode_iso_8859_15`]
+    ///
+    /// [`encode_iso_8859_15`]: crate::encoding::encode_iso_8859_15
+    #[cfg(feature = "iso-8859-15")]
+    pub fn with_iso_8859_15_text<'a>(self, text: &'a str) -> QrCodeBuilder<WithPayload<'a>> {
+        self.with_payload(Payload::Iso8859_15(text))
+    }
+
+    /// Encodes each segment in its own mode, for data that mixes modes
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::encoding::{EncodingMode, Segment};
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     let segments = [
+    ///         Segment::new(EncodingMode::Byte, b"serial:"),
+    ///         Segment::new(EncodingMode::Numeric, b"0123456789012345"),
+    ///     ];
+    ///     let qr_code = QrCodeBuilder::new().with_segments(&segments).build();
+    ///```
+    pub fn with_segments<'a>(self, segments: &'a [Segment<'a>]) -> QrCodeBuilder<WithPayload<'a>> {
+        self.with_payload(Payload::Segments(segments))
+    }
+
+    fn with_payload<T>(self, payload: Payload<'_, T>) -> QrCodeBuilder<WithPayload<'_, T>> {
+        QrCodeBuilder {
+            options: self.options,
+            payload: WithPayload(payload),
+        }
+    }
+}
+
+/// The state of a [`QrCodeBuilder`] with digits from an iterator, which can be built
+#[derive(Clone)]
+pub struct WithDigits<I>(I);
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A state of a [`QrCodeBuilder`] with a payload, only implemented by this crate
+pub trait BuildPayload: sealed::Sealed {
+    #[doc(hidden)]
+    fn encode<const C: usize>(
+        &self,
+        options: &EncodeOptions,
+    ) -> Result<EncodedData<[u8; C]>, QrError>;
+
+    #[doc(hidden)]
+    fn plan<const C: usize>(&self, options: &EncodeOptions) -> Result<EncodingPlan, QrError>;
+}
+
+impl<T: AsRef<str>> sealed::Sealed for WithPayload<'_, T> {}
+
+impl<T: AsRef<str>> BuildPayload for WithPayload<'_, T> {
+    fn encode<const C: usize>(
+        &self,
+        options: &EncodeOptions,
+    ) -> Result<EncodedData<[u8; C]>, QrError> {
+        match &self.0 {
+            Payload::Text(text) => encode_text_sized(options, text.as_ref()),
+            Payload::Number(number) => encode_number_sized(options, *number),
+            Payload::Bytes(data) => encode_bytes_sized(options, data),
+            Payload::Latin1(text) => encode_latin1_sized(options, text),
+            Payload::Utf16(text) => encode_utf16_sized(options, text),
+            #[cfg(feature = "shift-jis")]
+            Payload::ShiftJis(text) => encode_shift_jis_sized(options, text),
+            #[cfg(feature = "windows-1252")]
+            Payload::Windows1252(text) => encode_windows_1252_sized(options, text),
+            #[cfg(feature = "iso-8859-15")]
+            Payload::Iso8859_15(text) => encode_iso_8859_15_sized(options, text),
+            Payload::Segments(segments) => encode_segments_sized(options, segments),
+            Payload::TooLong => Err(QrError::DataTooLong),
+        }
+    }
+
+    fn plan<const C: usize>(&self, options: &EncodeOptions) -> Result<EncodingPlan, QrError> {
+        match &self.0 {
+            Payload::Text(text) => plan_encode_text::<C>(options, text.as_ref()),
+            Payload::Number(number) => plan_encode_number::<C>(options, *number),
+            Payload::Bytes(data) => plan_encode_bytes::<C>(options, data),
+            Payload::Latin1(text) => plan_encode_latin1::<C>(options, text),
+            Payload::Utf16(text) => plan_encode_utf16::<C>(options, text),
+            #[cfg(feature = "shift-jis")]
+            Payload::ShiftJis(text) => plan_encode_shift_jis::<C>(options, text),
+            #[cfg(feature = "windows-1252")]
+            Payload::Windows1252(text) => plan_encode_windows_1252::<C>(options, text),
+            #[cfg(feature = "iso-8859-15")]
+            Payload::Iso8859_15(text) => plan_encode_iso_8859_15::<C>(options, text),
+            Payload::Segments(segments) => plan_encode_segments::<C>(options, segments),
+            Payload::TooLong => Err(QrError::DataTooLong),
+        }
+    }
+}
+
+impl<I: Iterator<Item = u8> + Clone> sealed::Sealed for WithDigits<I> {}
+
+impl<I: Iterator<Item = u8> + Clone> BuildPayload for WithDigits<I> {
+    fn encode<const C: usize>(
+        &self,
+        options: &EncodeOptions,
+    ) -> Result<EncodedData<[u8; C]>, QrError> {
+        encode_digits_sized(options, self.0.clone())
+    }
+
+    fn plan<const C: usize>(&self, options: &EncodeOptions) -> Result<EncodingPlan, QrError> {
+        plan_encode_digits::<C>(options, self.0.clone())
+    }
+}
+
+impl<P: BuildPayload> QrCodeBuilder<P> {
+    /// Builds the QR code, panics when it doesn't fit
+    pub fn build(&self) -> QrCode<MAX_MODULE_SIZE> {
+        self.try_build().unwrap()
+    }
+
+    /// Builds the QR code, or returns why that isn't possible
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::{QrCodeBuilder, QrError};
+    ///     let result = QrCodeBuilder::new().with_max_version(1).with_text(&"1".repeat(50)).try_build();
+    ///     assert_eq!(result.err(), Some(QrError::DataTooLong));
+    ///```
+    pub fn try_build(&self) -> Result<QrCode<MAX_MODULE_SIZE>, QrError> {
+        let encoded_data = self.payload.encode::<BUFFER_CAPACITY>(&self.options)?;
+
+        Ok(QrCode::from_encoded_data(&self.options, encoded_data))
+    }
+
+    /// Builds the QR code on the heap, panics when it doesn't fit
+    #[cfg(feature = "alloc")]
+    pub fn build_dyn(&self) -> DynQrCode {
+        self.try_build_dyn().unwrap()
+    }
+
+    /// Builds the QR code on the heap, which allows any version up to 40
+    ///
+    /// The symbol is placed and masked on the heap and only the modules of the selected version
+    /// are kept. Just the codewords for version 40 are buffered on the stack.
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     let qr_code = QrCodeBuilder::new().with_text(&"1".repeat(1000)).try_build_dyn().unwrap();
+    ///     assert_eq!(qr_code.width(), 105);
+    ///```
+    #[cfg(feature = "alloc")]
+    pub fn try_build_dyn(&self) -> Result<DynQrCode, QrError> {
+        let encoded_data = self.payload.encode::<DYN_BUFFER_CAPACITY>(&self.options)?;
+        Ok(DynQrCode::from_encoded_data(&self.options, encoded_data))
+    }
+
+    /// Builds a QR code whose modules resemble the target image, while it still decodes
+    ///
+    /// The target gives the wanted color of the module at the column and row, or `None` when it
+    /// doesn't matter. The bits after the terminator are chosen so the modules closest to the
+    /// center match first; a lower error correction level and shorter data leave more bits to
+    /// choose. Up to `error_budget` codewords per block are also damaged on purpose to match more
+    /// modules, at most what the error correction can repair. That makes the symbol less robust,
+    /// so keep the budget well below the capacity of the level.
+    ///
+    /// The mask is the one that matches the most modules, unless a mask reference is given.
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::{Color, QrCodeBuilder};
+    ///     // A dark square in the center
+    ///     let image = |x: usize, y: usize| Some(if (8..17).contains(&x) && (8..17).contains(&y) {
+    ///         Color::Black
+    ///     } else {
+    ///         Color::White
+    ///     });
+    ///     let qr_code = QrCodeBuilder::new().with_text("HELLO").try_build_art(image, 0).unwrap();
+    ///     assert_eq!(qr_code.verify("HELLO"), Ok(()));
+    ///```
+    #[cfg(feature = "art")]
+    pub fn try_build_art(
+        &self,
+        target: impl Fn(usize, usize) -> Option<Color>,
+        error_budget: usize,
+    ) -> Result<QrCode<MAX_MODULE_SIZE>, QrError> {
+        let plan = self.payload.plan::<BUFFER_CAPACITY>(&self.options)?;
+        let encoded_data = self.payload.encode::<BUFFER_CAPACITY>(&self.options)?;
+        Ok(build_art(
+            &self.options,
+            encoded_data,
+            &plan,
+            target,
+            error_budget,
+        ))
+    }
+
+    /// Returns the mode, version and error correction level that building would select, without
+    /// building the QR code
+    ///
+    /// This is cheap enough to show whether the text fits while it is being typed.
+    pub fn plan(&self) -> Result<EncodingPlan, QrError> {
+        self.payload.plan::<BUFFER_CAPACITY>(&self.options)
     }
 }
 
+/// A QR code symbol of at most `N` by `N` modules
+///
+/// The parameter is the side length instead of the version, so only plain const generics are
+/// needed and the crate builds on stable Rust.
+/// The modules are stored as one bit each.
 pub struct QrCode<const N: usize> {
-    pub(crate) data: Array2D<Color, N>,
+    pub(crate) data: BitArray2D<N>,
+    /// `None` for Micro QR codes, which have their own versions
+    pub(crate) version: Option<Version>,
+    pub(crate) error_correction: ErrorCorrectionLevel,
+    pub(crate) mask_reference: Mask,
+    /// `None` for Micro QR codes and fixed masks, which aren't scored
+    pub(crate) penalty_score: Option<usize>,
 }
 
+/// A QR code that fits every version supported by this crate
+pub type QrCodeMax = QrCode<MAX_MODULE_SIZE>;
+
 impl<const N: usize> QrCode<N> {
-    pub fn draw_iter(&self) -> DrawIterator<N> {
+    pub fn draw_iter(&self) -> DrawIterator<'_, Self> {
         DrawIterator::new(self)
     }
 
-    fn from(scored: ScoreMasked<N>) -> Self {
-        let data = scored.masked.matrix.data;
+    /// Returns the rows packed as bytes, for monochrome display drivers
+    pub fn packed_rows(&self) -> PackedRows<'_, Self> {
+        PackedRows::new(self)
+    }
+
+    /// Returns the rows as spans of the same color, for renderers that fill rectangles
+    pub fn run_length_rows(&self) -> RunLengthRows<'_, Self> {
+        RunLengthRows::new(self)
+    }
+
+    /// Writes the QR code with the given renderer
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::render::{HalfBlocks, Renderer};
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+    ///     let mut output = String::new();
+    ///     qr_code.render(&HalfBlocks, &mut output).unwrap();
+    ///     assert_eq!(output, qr_code.to_string());
+    ///```
+    pub fn render<R: Renderer>(&self, renderer: &R, out: &mut impl Write) -> core::fmt::Result {
+        renderer.render(self, out)
+    }
+
+    /// Returns the version that was selected, `None` for a Micro QR code
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::{ErrorCorrectionLevel, QrCodeBuilder, Version};
+    ///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+    ///     assert_eq!(qr_code.version(), Some(Version::new(1).unwrap()));
+    ///     assert_eq!(qr_code.error_correction(), ErrorCorrectionLevel::High);
+    ///```
+    pub fn version(&self) -> Option<Version> {
+        self.version
+    }
+
+    pub fn error_correction(&self) -> ErrorCorrectionLevel {
+        self.error_correction
+    }
+
+    /// Returns the mask that was applied, in the Micro QR numbering for a Micro QR code
+    pub fn mask_reference(&self) -> Mask {
+        self.mask_reference
+    }
+
+    /// Returns the penalty score of the selected mask, `None` for a Micro QR code or when the mask
+    /// was given, since scoring is skipped then
+    ///
+    /// The score is computed with the scoring model of the options, a lower score is better.
+    pub fn penalty_score(&self) -> Option<usize> {
+        self.penalty_score
+    }
+
+    /// Returns the number of modules per row and column, without the quiet zone
+    pub fn width(&self) -> usize {
+        self.data.size().x
+    }
+
+    /// Returns the color of the module at column `x` and row `y`
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::{Color, QrCodeBuilder};
+    ///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+    ///     assert_eq!(qr_code.width(), 21);
+    ///     assert_eq!(qr_code.module(0, 0), Color::Black);
+    ///     assert_eq!(qr_code.module(7, 0), Color::White);
+    ///```
+    pub fn module(&self, x: usize, y: usize) -> Color {
+        assert!(x < self.width() && y < self.width());
+        // The data is indexed by row first
+        self.data[(y, x).into()]
+    }
+
+    /// Returns an iterator over the column, row and color of every module, row by row
+    pub fn modules(&self) -> impl Iterator<Item = (usize, usize, Color)> + '_ {
+        symbol::modules(self)
+    }
+
+    /// Returns the function of the module at column `x` and row `y`
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::{ModuleKind, QrCodeBuilder};
+    ///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+    ///     assert_eq!(qr_code.module_kind(0, 0), ModuleKind::Finder);
+    ///     assert_eq!(qr_code.module_kind(10, 6), ModuleKind::Timing);
+    ///     assert_eq!(qr_code.module_kind(20, 20), ModuleKind::Data);
+    ///```
+    pub fn module_kind(&self, x: usize, y: usize) -> ModuleKind {
+        symbol::module_kind(self.width(), x, y)
+    }
+
+    /// Reads both copies of the format information, useful for checking the symbol
+    pub fn format_info(&self) -> FormatInfoCheck {
+        symbol::format_info(self)
+    }
+
+    /// Decodes the symbol and checks that it holds the text, before it is printed
+    ///
+    /// Besides the text, the version, error correction level and mask are compared, both copies
+    /// of the format information must be exact and no codeword may need correction. Micro QR codes can't be decoded, so they result in
+    /// [`DecodeError::InvalidWidth`].
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::decoder::DecodeError;
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     let qr_code = QrCodeBuilder::new().with_text("Invoice 2024-001").build();
+    ///     assert_eq!(qr_code.verify("Invoice 2024-001"), Ok(()));
+    ///     assert_eq!(qr_code.verify("Invoice 2024-002"), Err(DecodeError::Mismatch));
+    ///```
+    pub fn verify(&self, text: &str) -> Result<(), DecodeError> {
+        symbol::verify::<BUFFER_CAPACITY>(self, text)
+    }
+
+    /// Plans a centered logo covering the percentage of the symbol, see [`LogoArea::plan`]
+    ///
+    /// Micro QR codes are too small for a logo and return [`QrError::LogoTooLarge`].
+    pub fn logo_area(&self, percent: u8) -> Result<LogoArea, QrError> {
+        symbol::logo_area(self, percent)
+    }
+
+    /// Shows which penalty rules fired where, useful for understanding the mask selection
+    pub fn penalty_heatmap(&self) -> PenaltyHeatmap<N> {
+        PenaltyHeatmap::from(&self.data)
+    }
+
+    pub(crate) fn from_encoded_data<const C: usize>(
+        options: &EncodeOptions,
+        encoded_data: EncodedData<[u8; C]>,
+    ) -> Self {
+        let (masked, penalty_score) = place_and_mask(options, encoded_data, Array2D::new());
+        QrCode::from(masked, penalty_score)
+    }
+
+    pub(crate) fn from(masked: Masked<N>, penalty_score: Option<usize>) -> Self {
+        let data = masked.matrix.data;
         let size = data.size();
 
-        let mut out = Array2D::new();
+        let mut out = BitArray2D::new();
         out.set_size(data.size());
         for x in 0..size.x {
             for y in 0..size.y {
                 let pos = (x, y).into();
-                out[pos] = data[pos].into();
+                out.set(pos, data[pos].into());
             }
         }
 
-        Self { data: out }
+        Self {
+            data: out,
+            version: Some(masked.matrix.version),
+            error_correction: masked.matrix.error_correction,
+            mask_reference: Mask::ALL[masked.mask_reference as usize],
+            penalty_score,
+        }
+    }
+}
+
+/// Adds the error correction, places the codewords in the modules and applies the mask of the
+/// options
+///
+/// Returns the penalty score when the mask was selected by scoring.
+pub(crate) fn place_and_mask<const N: usize, S: Storage<Module, N>, const C: usize>(
+    options: &EncodeOptions,
+    encoded_data: EncodedData<[u8; C]>,
+    modules: Array2D<Module, N, S>,
+) -> (Masked<N, S>, Option<usize>) {
+    let error_corrected_data = add_error_correction(encoded_data);
+
+    let matrix = Matrix::from_data(modules, &error_corrected_data);
+
+    match options.mask_policy {
+        // Scoring is the slowest step, so it is skipped when there is nothing to select
+        MaskPolicy::Fixed(mask) => (matrix.apply_mask(mask), None),
+        MaskPolicy::Automatic => {
+            let scored = matrix.best_mask(options.scoring_model);
+            (scored.masked, Some(scored.score))
+        }
+    }
+}
+
+impl<const N: usize> Symbol for QrCode<N> {
+    fn width(&self) -> usize {
+        QrCode::width(self)
+    }
+
+    fn module(&self, x: usize, y: usize) -> Color {
+        QrCode::module(self, x, y)
+    }
+
+    fn version(&self) -> Option<Version> {
+        self.version
+    }
+
+    fn error_correction(&self) -> ErrorCorrectionLevel {
+        self.error_correction
+    }
+
+    fn mask_reference(&self) -> Mask {
+        self.mask_reference
     }
 }
 
 impl<const N: usize> Debug for QrCode<N> {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        self.data.rows().try_for_each(|mut row| {
-            row.try_for_each(|color| {
-                f.write_char(match color {
-                    Color::Black => '\u{2588}',
-                    Color::White => '_',
-                })
-            })?;
-            f.write_char('\n')
-        })
+        self.render(&Blocks, f)
     }
 }
 
+/// Prints two rows of modules per line as half blocks, the alternate flag `{:#}` adds the quiet
+/// zone of four modules around the symbol
+///
+/// Without the quiet zone the symbol often doesn't scan from a terminal with a dark background.
 impl<const N: usize> Display for QrCode<N> {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        let iter1 = self.data.rows().step_by(2);
-        let iter2 = self.data.rows().skip(1).step_by(2);
-        iter1.zip(iter2).try_for_each(|rows| {
-            rows.0.zip(rows.1).try_for_each(|(&up, &down)| {
-                f.write_char(match (up, down) {
-                    (Color::Black, Color::Black) => '\u{2588}',
-                    (Color::Black, Color::White) => '\u{2580}',
-                    (Color::White, Color::Black) => '\u{2584}',
-                    (Color::White, Color::White) => ' ',
-                })
-            })?;
-            f.write_char('\n')
-        })?;
-
-        let mut last_row = self.data.rows().last().unwrap();
-        last_row.try_for_each(|&up| {
-            f.write_char(match up {
-                Color::Black => '\u{2580}',
-                Color::White => ' ',
-            })
-        })?;
-        f.write_char('\n')
+        if f.alternate() {
+            write_half_blocks(self, f, QUIET_ZONE)
+        } else {
+            self.render(&HalfBlocks, f)
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::decoder::DecodeError;
+    use crate::encoding::{EncodingMode, Segment};
+    use crate::error::QrError;
     use crate::error_correction::ErrorCorrectionLevel;
-    use crate::qrcode::QrCodeBuilder;
+    use crate::mask::Mask;
+    use crate::matrix::Color;
+    use crate::micro::MicroQrCodeBuilder;
+    use crate::qr_version::Version;
+    #[cfg(feature = "alloc")]
+    use crate::qrcode::DYN_TEXT_CAPACITY;
+    use crate::qrcode::{FormattedText, QrCodeBuilder, TEXT_CAPACITY};
     use alloc::format;
+    use alloc::string::String;
+    use core::convert::TryFrom;
+
+    #[test]
+    fn try_build() {
+        assert_eq!(
+            Mask::try_from(8),
+            Err(QrError::InvalidMaskReference { mask_reference: 8 })
+        );
+        assert!(QrCodeBuilder::new().with_text("HELLO").try_build().is_ok());
+    }
+
+    #[test]
+    fn owned_text() {
+        let builder = {
+            let text = String::from("HELLO WORLD");
+            QrCodeBuilder::new().with_text(text)
+        };
+        let expected = QrCodeBuilder::new().with_text("HELLO WORLD").build();
+        assert_eq!(format!("{:?}", builder.build()), format!("{:?}", expected));
+    }
+
+    #[test]
+    fn format_args() {
+        let serial = 42;
+        let builder = QrCodeBuilder::new().with_format_args(format_args!("SN:{:06}", serial));
+        let expected = QrCodeBuilder::new().with_text("SN:000042").build();
+        assert_eq!(format!("{:?}", builder.build()), format!("{:?}", expected));
+
+        // Only whole characters are written, the overflow is recorded
+        let long = "€".repeat(TEXT_CAPACITY);
+        let text: FormattedText = FormattedText::new(format_args!("{}", long));
+        assert_eq!(text.as_ref().len(), TEXT_CAPACITY - TEXT_CAPACITY % 3);
+        assert!(text.overflow);
+        let digits = "1".repeat(TEXT_CAPACITY + 1);
+        let builder = QrCodeBuilder::new().with_format_args(format_args!("{}", digits));
+        assert_eq!(builder.try_build().err(), Some(QrError::DataTooLong));
+        assert_eq!(builder.plan().err(), Some(QrError::DataTooLong));
+        #[cfg(feature = "alloc")]
+        assert_eq!(builder.try_build_dyn().err(), Some(QrError::DataTooLong));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn format_args_dyn() {
+        let long = "a".repeat(500);
+        let builder = QrCodeBuilder::new().with_format_args_dyn(format_args!("{}", long));
+        let expected = QrCodeBuilder::new().with_text(&long).build_dyn();
+        assert_eq!(
+            format!("{:?}", builder.build_dyn()),
+            format!("{:?}", expected)
+        );
+
+        let long = "1".repeat(DYN_TEXT_CAPACITY + 1);
+        let builder = QrCodeBuilder::new().with_format_args_dyn(format_args!("{}", long));
+        assert_eq!(builder.try_build_dyn().err(), Some(QrError::DataTooLong));
+    }
+
+    #[test]
+    fn replace_payload() {
+        let builder = QrCodeBuilder::new().with_bytes(b"hello").with_number(42);
+        assert_eq!(builder.plan().unwrap().mode, Some(EncodingMode::Numeric));
+    }
+
+    #[test]
+    fn modules() {
+        let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+        assert_eq!(qr_code.modules().count(), 21 * 21);
+        assert_eq!(
+            qr_code.modules().nth(21 + 1),
+            Some((1, 1, qr_code.module(1, 1)))
+        );
+        // The timing pattern is on row 6 and column 6
+        assert_eq!(qr_code.module(8, 6), Color::Black);
+        assert_eq!(qr_code.module(9, 6), Color::White);
+        assert_eq!(qr_code.module(6, 9), Color::White);
+        assert!(qr_code
+            .modules()
+            .all(|(x, y, color)| color == qr_code.data[(y, x).into()]));
+    }
+
+    #[test]
+    fn metadata() {
+        let qr_code = QrCodeBuilder::new()
+            .with_text("HELLO WORLD")
+            .with_specific_error_correction_level(ErrorCorrectionLevel::Quartile)
+            .build();
+        assert_eq!(qr_code.version(), Some(Version::new(1).unwrap()));
+        assert_eq!(qr_code.error_correction(), ErrorCorrectionLevel::Quartile);
+        assert_eq!(
+            qr_code.format_info().decode_first_copy(),
+            Some((ErrorCorrectionLevel::Quartile, qr_code.mask_reference()))
+        );
+
+        assert!(qr_code.penalty_score().is_some());
+        for mask in Mask::ALL {
+            let fixed = QrCodeBuilder::new()
+                .with_text("HELLO WORLD")
+                .with_specific_error_correction_level(ErrorCorrectionLevel::Quartile)
+                .with_mask_reference(mask)
+                .build();
+            assert_eq!(fixed.mask_reference(), mask);
+            // A given mask isn't scored
+            assert_eq!(fixed.penalty_score(), None);
+        }
+    }
+
+    #[test]
+    fn plan_matches_build() {
+        let segments = [
+            Segment::new(EncodingMode::Byte, b"serial:"),
+            Segment::new(EncodingMode::Numeric, b"0123456789012345"),
+        ];
+        let builders = [
+            QrCodeBuilder::new().with_text(""),
+            QrCodeBuilder::new().with_text("HELLO WORLD"),
+            QrCodeBuilder::new().with_text("https://example.com/0123456789012"),
+            QrCodeBuilder::new()
+                .with_text("https://example.com/0123456789012")
+                .with_optimal_segmentation(true),
+            QrCodeBuilder::new().with_number(1234567890),
+            QrCodeBuilder::new().with_bytes(&[0x08, 0x96, 0x01, 0xff]),
+            QrCodeBuilder::new().with_segments(&segments),
+        ];
+        for builder in builders {
+            let plan = builder.plan().unwrap();
+            let qr_code = builder.build();
+            assert_eq!(Some(plan.version), qr_code.version());
+            assert_eq!(plan.error_correction, qr_code.error_correction());
+            assert!(plan.used_data_codeword_count() <= plan.data_codeword_count());
+        }
+
+        let plan = QrCodeBuilder::new().with_segments(&segments).plan();
+        assert_eq!(plan.unwrap().mode, None);
+        let text = "1".repeat(50);
+        let too_long = QrCodeBuilder::new().with_max_version(1).with_text(&text);
+        assert_eq!(too_long.plan().err(), Some(QrError::DataTooLong));
+    }
+
+    #[test]
+    fn display_quiet_zone() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let plain = format!("{}", qr_code);
+        let quiet = format!("{:#}", qr_code);
+        assert_eq!(plain.lines().count(), 11);
+        assert_eq!(quiet.lines().count(), 15);
+        assert!(quiet.lines().all(|line| line.chars().count() == 29));
+        assert_eq!(quiet.lines().next(), Some(" ".repeat(29).as_str()));
+        // The quiet zone is four modules, so two lines and four characters
+        for (quiet_line, plain_line) in quiet.lines().skip(2).zip(plain.lines()) {
+            assert_eq!(quiet_line, format!("    {}    ", plain_line));
+        }
+    }
 
     #[test]
     fn numeric_specific_version_1() {
@@ -197,7 +1025,7 @@ mod tests {
             .with_text("01234567")
             .with_specific_version(1)
             .with_specific_error_correction_level(ErrorCorrectionLevel::Medium)
-            .with_mask_reference(0b010)
+            .with_mask_reference(Mask::Mask2)
             .build();
 
         assert_eq!(
@@ -234,7 +1062,7 @@ ________█_█████__██__
             .with_text("01234567")
             .with_max_version(1)
             .with_min_error_correction_level(ErrorCorrectionLevel::Medium)
-            .with_mask_reference(0b010)
+            .with_mask_reference(Mask::Mask2)
             .build();
 
         assert_eq!(
@@ -302,7 +1130,7 @@ ________█____█_█_████
         let qr_code = QrCodeBuilder::new()
             .with_max_version(1)
             .with_min_error_correction_level(ErrorCorrectionLevel::Quartile)
-            .with_mask_reference(0b110)
+            .with_mask_reference(Mask::Mask6)
             .with_text("HELLO WORLD")
             .build();
 
@@ -339,7 +1167,7 @@ ________█___█__█_█___
         let qr_code = QrCodeBuilder::new()
             .with_max_version(2)
             .with_min_error_correction_level(ErrorCorrectionLevel::Quartile)
-            .with_mask_reference(0b110)
+            .with_mask_reference(Mask::Mask6)
             .with_text("HTTPS://CASPERMEIJN.NL")
             .build();
 
@@ -359,10 +1187,10 @@ _█_████_███████████_██_█_
 ███_█_█_██_██_██__█_____█
 █______███_██_█_█_█_████_
 ██_█__██_█_████_█__█_███_
-____██_████_██___█_█_█___
-______███_█__███_███_█___
-_____█_██______██___█_███
-__██__█_█_██_██_█████_███
+█___██_████_██___█_█_█___
+██____███_█__███_███_█___
+█____█_██______██___█_███
+█_██__█_█_██_██_█████_███
 ________█___█___█___██___
 ███████__█__██__█_█_█_███
 █_____█_█_█____██___████_
@@ -380,7 +1208,7 @@ ________█___█___█___██___
         let qr_code = QrCodeBuilder::new()
             .with_max_version(4)
             .with_min_error_correction_level(ErrorCorrectionLevel::High)
-            .with_mask_reference(0b110)
+            .with_mask_reference(Mask::Mask6)
             .with_text("HTTPS://GITHUB.COM/CASPERMEIJN/TINY-QR")
             .build();
 
@@ -408,10 +1236,10 @@ __█___█_____██_███_____█_█__███_█
 ____█_████__█_████__█_█_____█_█_█
 _███_█__█_███_█__███_██__█_██____
 __█_█_█_█_███_████_█_███__███__██
-__█_██____████_████_█___█_███████
-___█_█████__█_█_█████_██_██_█__█_
-__█_██_____██___█__█___██_████___
-__███████___█__██_██__███████____
+█_█_██____████_████_█___█_███████
+█__█_█████__█_█_█████_██_██_█__█_
+█_█_██_____██___█__█___██_████___
+█████████___█__██_██__███████____
 ________█__██_██_███_█_██___█_█_█
 ███████_█_█_██_███_██__██_█_███__
 █_____█__█__█__███___██_█___█_███
@@ -423,4 +1251,63 @@ ________█__██_██_███_█_██___█_█_█
 "
         );
     }
+
+    #[test]
+    fn number_version_1() {
+        let from_number = QrCodeBuilder::new().with_number(1234567890).build();
+        let from_text = QrCodeBuilder::new().with_text("1234567890").build();
+
+        assert_eq!(format!("{:?}", from_number), format!("{:?}", from_text));
+    }
+
+    #[test]
+    fn reuse_builder() {
+        let builder = QrCodeBuilder::new()
+            .with_specific_version(2)
+            .with_mask_reference(Mask::Mask3);
+        let first = builder.clone().with_text("FIRST").build();
+        let second_builder = builder.with_text("SECOND");
+        let second = second_builder.build();
+
+        let expected = QrCodeBuilder::new()
+            .with_specific_version(2)
+            .with_mask_reference(Mask::Mask3)
+            .with_text("FIRST")
+            .build();
+        assert_eq!(format!("{:?}", first), format!("{:?}", expected));
+        // Building doesn't consume the builder
+        assert_eq!(
+            format!("{:?}", second),
+            format!("{:?}", second_builder.build())
+        );
+    }
+
+    #[test]
+    fn verify() {
+        let qr_code = QrCodeBuilder::new().with_text("Grüße 123").build();
+        assert_eq!(qr_code.verify("Grüße 123"), Ok(()));
+        assert_eq!(qr_code.verify("Grüße 124"), Err(DecodeError::Mismatch));
+
+        // A damaged module is corrected by the decoder, but fails the check
+        let last = qr_code.width() - 1;
+        let mut damaged = qr_code;
+        let pos = (last, last).into();
+        damaged.data.set(pos, damaged.data[pos].inverse());
+        assert_eq!(damaged.verify("Grüße 123"), Err(DecodeError::Mismatch));
+
+        // The same for a damaged bit of the format information
+        let mut damaged = QrCodeBuilder::new().with_text("Grüße 123").build();
+        let pos = (8, 0).into();
+        damaged.data.set(pos, damaged.data[pos].inverse());
+        assert!(!damaged.format_info().copies_agree());
+        assert_eq!(damaged.verify("Grüße 123"), Err(DecodeError::Mismatch));
+
+        let micro = MicroQrCodeBuilder::new().with_text("1").build().unwrap();
+        assert_eq!(
+            micro.verify("1"),
+            Err(DecodeError::InvalidWidth {
+                width: micro.width()
+            })
+        );
+    }
 }
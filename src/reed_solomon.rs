@@ -0,0 +1,297 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Reed-Solomon error correction over GF(256), with the tables computed at compile time
+
+/// The primitive polynomial x^8 + x^4 + x^3 + x^2 + 1 of the QR code field
+const PRIMITIVE: u16 = 0x11d;
+
+/// The largest number of error correction codewords in a block of any QR code version
+const MAX_ECC_LEN: usize = 30;
+
+/// Powers of the generator element, twice so that a sum of two logarithms needs no modulo
+const EXP: [u8; 512] = {
+    let mut exp = [0; 512];
+    let mut value: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = value as u8;
+        exp[i + 255] = value as u8;
+        value <<= 1;
+        if value & 0x100 != 0 {
+            value ^= PRIMITIVE;
+        }
+        i += 1;
+    }
+    exp
+};
+
+/// Logarithms of all non-zero elements, the logarithm of 0 is unused
+const LOG: [u8; 256] = {
+    let mut log = [0; 256];
+    let mut i = 0;
+    while i < 255 {
+        log[EXP[i] as usize] = i as u8;
+        i += 1;
+    }
+    log
+};
+
+const fn multiply(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        0
+    } else {
+        EXP[LOG[a as usize] as usize + LOG[b as usize] as usize]
+    }
+}
+
+/// The generator polynomials (x - α^0)...(x - α^(n-1)) for every length n
+///
+/// The coefficients are stored from the highest degree down and the leading 1 is left out, so
+/// `GENERATORS[n][..n]` are the coefficients for n error correction codewords.
+const GENERATORS: [[u8; MAX_ECC_LEN]; MAX_ECC_LEN + 1] = {
+    let mut generators = [[0; MAX_ECC_LEN]; MAX_ECC_LEN + 1];
+    // Multiply the polynomial of the previous length by (x - α^(n-1)), the leading 1 included
+    let mut polynomial = [0u8; MAX_ECC_LEN + 1];
+    polynomial[0] = 1;
+    let mut n = 1;
+    while n <= MAX_ECC_LEN {
+        let root = EXP[n - 1];
+        let mut i = n;
+        while i > 0 {
+            polynomial[i] ^= multiply(polynomial[i - 1], root);
+            i -= 1;
+        }
+        let mut j = 0;
+        while j < n {
+            generators[n][j] = polynomial[j + 1];
+            j += 1;
+        }
+        n += 1;
+    }
+    generators
+};
+
+/// Calculates the error correction codewords of a single block
+///
+/// The length of `ecc` selects the generator polynomial.
+pub(crate) fn encode(data: &[u8], ecc: &mut [u8]) {
+    let generator = &GENERATORS[ecc.len()][..ecc.len()];
+    ecc.fill(0);
+    for &byte in data {
+        let factor = byte ^ ecc[0];
+        ecc.copy_within(1.., 0);
+        let last = ecc.len() - 1;
+        ecc[last] = 0;
+        if factor != 0 {
+            let log_factor = LOG[factor as usize] as usize;
+            for (ecc, &coefficient) in ecc.iter_mut().zip(generator) {
+                if coefficient != 0 {
+                    *ecc ^= EXP[LOG[coefficient as usize] as usize + log_factor];
+                }
+            }
+        }
+    }
+}
+
+const fn inverse(a: u8) -> u8 {
+    EXP[255 - LOG[a as usize] as usize]
+}
+
+/// A polynomial with the coefficient of x^i at index i
+type Polynomial = [u8; MAX_ECC_LEN + 1];
+
+fn evaluate(polynomial: &[u8], x: u8) -> u8 {
+    polynomial
+        .iter()
+        .rev()
+        .fold(0, |value, &coefficient| multiply(value, x) ^ coefficient)
+}
+
+/// Corrects errors and erasures in a block of data codewords followed by `ecc_len` error
+/// correction codewords
+///
+/// The erasures are the positions in the block that are known to be damaged. Up to `ecc_len`
+/// damaged codewords can be corrected, where an error of unknown position counts twice. Returns
+/// the number of corrected codewords, or `None` when the block is damaged beyond repair.
+pub(crate) fn correct(block: &mut [u8], ecc_len: usize, erasures: &[usize]) -> Option<usize> {
+    let len = block.len();
+    assert!(ecc_len <= MAX_ECC_LEN && ecc_len <= len && len <= 255);
+    // The codeword at position i is the coefficient of x^(len - 1 - i)
+    let locator_of = |position: usize| EXP[len - 1 - position];
+
+    // The block is a multiple of the generator, so it is zero at every root α^j
+    let mut syndromes: Polynomial = [0; MAX_ECC_LEN + 1];
+    for (j, syndrome) in syndromes[..ecc_len].iter_mut().enumerate() {
+        *syndrome = evaluate_block(block, EXP[j]);
+    }
+    if syndromes.iter().all(|&syndrome| syndrome == 0) {
+        return Some(0);
+    }
+    if erasures.len() > ecc_len || erasures.iter().any(|&position| position >= len) {
+        return None;
+    }
+
+    // Berlekamp–Massey, starting from the locator of the erasures
+    let mut locator: Polynomial = [0; MAX_ECC_LEN + 1];
+    locator[0] = 1;
+    for &position in erasures {
+        let x = locator_of(position);
+        for i in (1..=MAX_ECC_LEN).rev() {
+            locator[i] ^= multiply(locator[i - 1], x);
+        }
+    }
+    let mut previous = locator;
+    let mut degree = erasures.len();
+    for r in erasures.len()..ecc_len {
+        let discrepancy =
+            (0..=degree.min(r)).fold(0, |sum, i| sum ^ multiply(locator[i], syndromes[r - i]));
+        // previous = x * previous
+        previous.copy_within(..MAX_ECC_LEN, 1);
+        previous[0] = 0;
+        if discrepancy != 0 {
+            let mut next = locator;
+            for (next, &previous) in next.iter_mut().zip(&previous) {
+                *next ^= multiply(discrepancy, previous);
+            }
+            if 2 * degree <= r + erasures.len() {
+                degree = r + 1 + erasures.len() - degree;
+                let factor = inverse(discrepancy);
+                for (previous, &locator) in previous.iter_mut().zip(&locator) {
+                    *previous = multiply(locator, factor);
+                }
+            }
+            locator = next;
+        }
+    }
+    if 2 * degree > ecc_len + erasures.len() || locator[degree + 1..].iter().any(|&c| c != 0) {
+        return None;
+    }
+
+    // The evaluator is the product of the syndromes and the locator, modulo x^ecc_len
+    let mut evaluator: Polynomial = [0; MAX_ECC_LEN + 1];
+    for i in 0..ecc_len {
+        evaluator[i] = (0..=i).fold(0, |sum, j| sum ^ multiply(syndromes[j], locator[i - j]));
+    }
+    // The formal derivative only keeps the odd powers in a field of characteristic 2
+    let mut derivative: Polynomial = [0; MAX_ECC_LEN + 1];
+    for i in (1..=MAX_ECC_LEN).step_by(2) {
+        derivative[i - 1] = locator[i];
+    }
+
+    // Find the roots of the locator and correct them with Forney's algorithm
+    let mut corrected = 0;
+    for (position, codeword) in block.iter_mut().enumerate() {
+        let x = locator_of(position);
+        let x_inverse = inverse(x);
+        if evaluate(&locator[..=degree], x_inverse) != 0 {
+            continue;
+        }
+        let denominator = evaluate(&derivative, x_inverse);
+        if denominator == 0 {
+            return None;
+        }
+        let magnitude = multiply(
+            multiply(x, evaluate(&evaluator, x_inverse)),
+            inverse(denominator),
+        );
+        *codeword ^= magnitude;
+        corrected += 1;
+    }
+    if corrected != degree || (0..ecc_len).any(|j| evaluate_block(block, EXP[j]) != 0) {
+        return None;
+    }
+    Some(corrected)
+}
+
+/// Evaluates the block as a polynomial with the first codeword as the highest coefficient
+fn evaluate_block(block: &[u8], x: u8) -> u8 {
+    block
+        .iter()
+        .fold(0, |value, &codeword| multiply(value, x) ^ codeword)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reed_solomon::{correct, encode, multiply, GENERATORS};
+
+    #[test]
+    fn field() {
+        assert_eq!(multiply(0x80, 2), 0x1d);
+        assert_eq!(multiply(0, 0x53), 0);
+        assert_eq!(multiply(0x53, 1), 0x53);
+    }
+
+    #[test]
+    fn generator_7() {
+        // Annex A lists the exponents 87, 229, 146, 149, 238, 102, 21
+        assert_eq!(GENERATORS[7][..7], [127, 122, 154, 164, 11, 68, 117]);
+    }
+
+    #[test]
+    fn version_1_m() {
+        let data = [
+            0x10, 0x20, 0x0c, 0x56, 0x61, 0x80, 0xec, 0x11, 0xec, 0x11, 0xec, 0x11, 0xec, 0x11,
+            0xec, 0x11,
+        ];
+        let mut ecc = [0; 10];
+        encode(&data, &mut ecc);
+        assert_eq!(
+            ecc,
+            [0xa5, 0x24, 0xd4, 0xc1, 0xed, 0x36, 0xc7, 0x87, 0x2c, 0x55]
+        );
+    }
+
+    #[test]
+    fn correct_errors_and_erasures() {
+        let mut block = [0; 26];
+        block[..16].copy_from_slice(&[
+            0x10, 0x20, 0x0c, 0x56, 0x61, 0x80, 0xec, 0x11, 0xec, 0x11, 0xec, 0x11, 0xec, 0x11,
+            0xec, 0x11,
+        ]);
+        let (data, ecc) = block.split_at_mut(16);
+        encode(data, ecc);
+        let original = block;
+        assert_eq!(correct(&mut block, 10, &[]), Some(0));
+
+        // Up to 5 errors can be found in 10 error correction codewords
+        for (i, position) in [0, 3, 15, 16, 25].iter().enumerate() {
+            block[*position] ^= 0x11 * (i as u8 + 1);
+        }
+        assert_eq!(correct(&mut block, 10, &[]), Some(5));
+        assert_eq!(block, original);
+
+        // Erasures count once, so 10 can be corrected, or 4 erasures and 3 errors
+        let erasures = [1, 2, 4, 6, 8, 10, 12, 14, 20, 24];
+        for &position in &erasures {
+            block[position] = 0;
+        }
+        assert_eq!(correct(&mut block, 10, &erasures), Some(10));
+        assert_eq!(block, original);
+        for position in [0, 5, 7, 9, 11, 13, 22] {
+            block[position] ^= 0xff;
+        }
+        assert_eq!(correct(&mut block, 10, &[0, 5, 7, 9]), Some(7));
+        assert_eq!(block, original);
+
+        // Six errors are too many
+        for position in [0, 1, 2, 3, 4, 5] {
+            block[position] ^= 0x5a;
+        }
+        assert_eq!(correct(&mut block, 10, &[]), None);
+    }
+}
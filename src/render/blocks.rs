@@ -0,0 +1,85 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::matrix::Color;
+use crate::render::Renderer;
+use crate::symbol::Symbol;
+use core::fmt::{self, Write};
+
+/// Renders every module as a full block or an underscore, as the `Debug` output of a QR code
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Blocks;
+
+impl Renderer for Blocks {
+    fn render<Q: Symbol>(&self, qr_code: &Q, out: &mut impl Write) -> fmt::Result {
+        let width = qr_code.width();
+        (0..width).try_for_each(|y| {
+            (0..width).try_for_each(|x| {
+                out.write_char(match qr_code.module(x, y) {
+                    Color::Black => '\u{2588}',
+                    Color::White => '_',
+                })
+            })?;
+            out.write_char('\n')
+        })
+    }
+}
+
+/// Renders two rows of modules as one line of half blocks, as the `Display` output of a QR code
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct HalfBlocks;
+
+impl Renderer for HalfBlocks {
+    fn render<Q: Symbol>(&self, qr_code: &Q, out: &mut impl Write) -> fmt::Result {
+        write_half_blocks(qr_code, out, 0)
+    }
+}
+
+/// Writes half blocks with a light border of the given number of modules
+pub(crate) fn write_half_blocks<Q: Symbol>(
+    qr_code: &Q,
+    out: &mut impl Write,
+    border: usize,
+) -> fmt::Result {
+    let size = qr_code.width();
+    let width = size + 2 * border;
+    let module = |x: usize, y: usize| {
+        if x < border || y < border || x >= size + border || y >= size + border {
+            Color::White
+        } else {
+            qr_code.module(x - border, y - border)
+        }
+    };
+    for y in (0..width).step_by(2) {
+        for x in 0..width {
+            // The last line has only a top half when the width is odd
+            let down = if y + 1 < width {
+                module(x, y + 1)
+            } else {
+                Color::White
+            };
+            out.write_char(match (module(x, y), down) {
+                (Color::Black, Color::Black) => '\u{2588}',
+                (Color::Black, Color::White) => '\u{2580}',
+                (Color::White, Color::Black) => '\u{2584}',
+                (Color::White, Color::White) => ' ',
+            })?;
+        }
+        out.write_char('\n')?;
+    }
+    Ok(())
+}
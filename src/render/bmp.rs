@@ -0,0 +1,132 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::qrcode::QrCode;
+use core::num::NonZeroUsize;
+use embedded_io::Write;
+
+/// The size of the file header, the info header and the palette of two colors
+const HEADER_LEN: u32 = 14 + 40 + 2 * 4;
+
+impl<const N: usize> QrCode<N> {
+    /// Writes the QR code as a 1 bit per pixel BMP image into an `embedded_io` sink
+    ///
+    /// Every module is `scale` by `scale` pixels and the symbol is surrounded by `margin` light
+    /// modules. The image is streamed row by row, so no memory for the image is needed.
+    ///
+    /// # Example
+    ///```
+    ///     use core::num::NonZeroUsize;
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+    ///     let mut buffer = [0u8; 1024];
+    ///     let mut sink = &mut buffer[..];
+    ///     qr_code.write_bmp(&mut sink, NonZeroUsize::new(1).unwrap(), 4).unwrap();
+    ///     assert!(buffer.starts_with(b"BM"));
+    ///```
+    pub fn write_bmp<W: Write>(
+        &self,
+        out: &mut W,
+        scale: NonZeroUsize,
+        margin: usize,
+    ) -> Result<(), W::Error> {
+        let rows = || self.packed_rows().with_border(margin).with_scale(scale);
+        let width = rows().width() as u32;
+        let height = rows().height() as u32;
+        // Rows are padded to a multiple of four bytes, which is 32 pixels
+        let row_len = width.div_ceil(32) * 4;
+        let image_len = row_len * height;
+
+        out.write_all(b"BM")?;
+        out.write_all(&(HEADER_LEN + image_len).to_le_bytes())?;
+        out.write_all(&[0; 4])?;
+        out.write_all(&HEADER_LEN.to_le_bytes())?;
+
+        out.write_all(&40u32.to_le_bytes())?;
+        out.write_all(&width.to_le_bytes())?;
+        out.write_all(&height.to_le_bytes())?;
+        // One plane, 1 bit per pixel and no compression
+        out.write_all(&1u16.to_le_bytes())?;
+        out.write_all(&1u16.to_le_bytes())?;
+        out.write_all(&0u32.to_le_bytes())?;
+        out.write_all(&image_len.to_le_bytes())?;
+        // 2835 pixels per meter is 72 DPI
+        out.write_all(&2835u32.to_le_bytes())?;
+        out.write_all(&2835u32.to_le_bytes())?;
+        out.write_all(&2u32.to_le_bytes())?;
+        out.write_all(&0u32.to_le_bytes())?;
+        // A clear bit is white and a set bit is black, as in the packed rows
+        out.write_all(&[0xff, 0xff, 0xff, 0, 0, 0, 0, 0])?;
+
+        // The bottom row comes first
+        for y in (0..height as usize).rev() {
+            let mut len = 0;
+            for byte in rows().nth(y).unwrap() {
+                out.write_all(&[byte])?;
+                len += 1;
+            }
+            while len < row_len {
+                out.write_all(&[0])?;
+                len += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::Color;
+    use crate::qrcode::QrCodeBuilder;
+    use alloc::vec::Vec;
+    use core::num::NonZeroUsize;
+
+    #[test]
+    fn write_bmp() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let mut data = Vec::new();
+        qr_code
+            .write_bmp(&mut data, NonZeroUsize::new(3).unwrap(), 2)
+            .unwrap();
+
+        let image = ::bmp::from_reader(&mut &data[..]).unwrap();
+        let width = (21 + 2 * 2) * 3;
+        assert_eq!((image.get_width(), image.get_height()), (width, width));
+        for y in 0..width {
+            for x in 0..width {
+                let module = |i: u32| (i / 3).checked_sub(2).filter(|&i| i < 21);
+                let color = match (module(x), module(y)) {
+                    (Some(x), Some(y)) => qr_code.module(x as usize, y as usize),
+                    _ => Color::White,
+                };
+                let pixel = image.get_pixel(x, y);
+                let expected = if color == Color::Black { 0 } else { 255 };
+                assert_eq!((pixel.r, pixel.g, pixel.b), (expected, expected, expected));
+            }
+        }
+    }
+
+    #[test]
+    fn sink_full() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let mut buffer = [0u8; 100];
+        let mut sink = &mut buffer[..];
+        assert!(qr_code
+            .write_bmp(&mut sink, NonZeroUsize::new(1).unwrap(), 4)
+            .is_err());
+    }
+}
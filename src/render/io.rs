@@ -0,0 +1,94 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::qrcode::QrCode;
+use crate::render::Renderer;
+use core::fmt;
+use embedded_io::{Write, WriteFmtError};
+
+/// Passes text to an `embedded_io` sink and keeps the error it returns
+struct IoWriter<'a, W: Write> {
+    out: &'a mut W,
+    error: Option<W::Error>,
+}
+
+impl<W: Write> fmt::Write for IoWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.out.write_all(s.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
+    }
+}
+
+impl<const N: usize> QrCode<N> {
+    /// Streams the QR code with the given renderer into an `embedded_io` sink, like a UART or a
+    /// printer, without building the text in memory
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::render::Pbm;
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+    ///     let mut buffer = [0u8; 1024];
+    ///     let mut sink = &mut buffer[..];
+    ///     qr_code.render_io(&Pbm, &mut sink).unwrap();
+    ///     assert!(buffer.starts_with(b"P1\n29 29\n"));
+    ///```
+    pub fn render_io<R: Renderer, W: Write>(
+        &self,
+        renderer: &R,
+        out: &mut W,
+    ) -> Result<(), WriteFmtError<W::Error>> {
+        let mut writer = IoWriter { out, error: None };
+        match renderer.render(self, &mut writer) {
+            Ok(()) => Ok(()),
+            Err(fmt::Error) => Err(match writer.error {
+                Some(error) => WriteFmtError::Other(error),
+                None => WriteFmtError::FmtError,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::qrcode::QrCodeBuilder;
+    use crate::render::HalfBlocks;
+    use alloc::string::String;
+    use embedded_io::{SliceWriteError, WriteFmtError};
+
+    #[test]
+    fn render_io() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let mut expected = String::new();
+        qr_code.render(&HalfBlocks, &mut expected).unwrap();
+
+        let mut buffer = [0u8; 2048];
+        let mut sink = &mut buffer[..];
+        qr_code.render_io(&HalfBlocks, &mut sink).unwrap();
+        let unused = sink.len();
+        assert_eq!(&buffer[..buffer.len() - unused], expected.as_bytes());
+
+        let mut buffer = [0u8; 16];
+        let mut sink = &mut buffer[..];
+        assert!(matches!(
+            qr_code.render_io(&HalfBlocks, &mut sink),
+            Err(WriteFmtError::Other(SliceWriteError::Full))
+        ));
+    }
+}
@@ -0,0 +1,282 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::draw_iterator::CoordinatedColor;
+use crate::matrix::Color;
+use crate::qrcode::QUIET_ZONE;
+use crate::render::font::{glyph_pixel, GLYPH_HEIGHT, GLYPH_WIDTH};
+#[cfg(feature = "svg")]
+use crate::render::svg::{write_square, write_svg_start};
+use crate::symbol::Symbol;
+#[cfg(feature = "svg")]
+use core::fmt;
+use core::num::NonZeroUsize;
+
+/// A QR code with a line of caption text below it, for example the payload or a serial number
+///
+/// # Example
+///```
+///     use core::num::NonZeroUsize;
+///     use tiny_qr::QrCodeBuilder;
+///     use tiny_qr::render::Label;
+///     let qr_code = QrCodeBuilder::new().with_text("SN 1234").build();
+///     let label = Label::new(&qr_code, "SN 1234").with_module_size(NonZeroUsize::new(2).unwrap());
+///     assert_eq!(label.width(), 90);
+///     assert_eq!(label.height(), 76);
+///```
+pub struct Label<'a, Q> {
+    qr_code: &'a Q,
+    caption: &'a str,
+    module_size: usize,
+    text_scale: usize,
+}
+
+impl<'a, Q: Symbol> Label<'a, Q> {
+    pub fn new(qr_code: &'a Q, caption: &'a str) -> Self {
+        Self {
+            qr_code,
+            caption,
+            module_size: 4,
+            text_scale: 2,
+        }
+    }
+
+    /// Sets the size of a QR code module in pixels
+    pub fn with_module_size(mut self, module_size: NonZeroUsize) -> Self {
+        self.module_size = module_size.get();
+        self
+    }
+
+    /// Sets the size of a font pixel in pixels
+    pub fn with_text_scale(mut self, text_scale: NonZeroUsize) -> Self {
+        self.text_scale = text_scale.get();
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        let text_width = self.text_width() + 2 * self.text_margin();
+        self.qr_width().max(text_width)
+    }
+
+    pub fn height(&self) -> usize {
+        if self.caption.is_empty() {
+            self.qr_width()
+        } else {
+            self.qr_width() + GLYPH_HEIGHT * self.text_scale + self.text_margin()
+        }
+    }
+
+    /// Returns the color of the pixel at the given column and row
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        let qr_offset = (self.width() - self.qr_width()) / 2;
+        if y < self.qr_width() {
+            if x < qr_offset || x >= qr_offset + self.qr_width() {
+                return Color::White;
+            }
+            let module_x = (x - qr_offset) / self.module_size;
+            let module_y = y / self.module_size;
+            return self.module(module_x, module_y);
+        }
+
+        let text_offset = (self.width() - self.text_width()) / 2;
+        if x < text_offset || x >= text_offset + self.text_width() {
+            return Color::White;
+        }
+        let x = (x - text_offset) / self.text_scale;
+        let y = (y - self.qr_width()) / self.text_scale;
+        let advance = GLYPH_WIDTH + 1;
+        match self.caption.chars().nth(x / advance) {
+            Some(character) if glyph_pixel(character, x % advance, y) => Color::Black,
+            _ => Color::White,
+        }
+    }
+
+    /// Iterates over all pixels, row by row
+    pub fn draw_iter(&self) -> LabelIterator<'_, 'a, Q> {
+        LabelIterator {
+            label: self,
+            x: 0,
+            y: 0,
+        }
+    }
+
+    /// Writes the label as an SVG image, using one unit per pixel
+    #[cfg(feature = "svg")]
+    pub fn write_svg(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        let (width, height) = (self.width(), self.height());
+        write_svg_start(out, width, height)?;
+        out.write_char('>')?;
+        write!(
+            out,
+            "<rect width=\"{}\" height=\"{}\" fill=\"#fff\"/>",
+            width, height
+        )?;
+        out.write_str("<path fill=\"#000\" d=\"")?;
+
+        let size = self.qr_code.width();
+        let qr_offset = (width - self.qr_width()) / 2;
+        for module_y in 0..size {
+            for module_x in 0..size {
+                if self.qr_code.module(module_x, module_y) == Color::Black {
+                    let x = qr_offset + (module_x + QUIET_ZONE) * self.module_size;
+                    let y = (module_y + QUIET_ZONE) * self.module_size;
+                    write_square(out, x, y, self.module_size)?;
+                }
+            }
+        }
+
+        let text_offset = (width - self.text_width()) / 2;
+        for (index, character) in self.caption.chars().enumerate() {
+            for glyph_y in 0..GLYPH_HEIGHT {
+                for glyph_x in 0..GLYPH_WIDTH {
+                    if glyph_pixel(character, glyph_x, glyph_y) {
+                        let x =
+                            text_offset + (index * (GLYPH_WIDTH + 1) + glyph_x) * self.text_scale;
+                        let y = self.qr_width() + glyph_y * self.text_scale;
+                        write_square(out, x, y, self.text_scale)?;
+                    }
+                }
+            }
+        }
+        out.write_str("\"/></svg>")
+    }
+
+    fn module(&self, x: usize, y: usize) -> Color {
+        let size = self.qr_code.width();
+        if x < QUIET_ZONE || y < QUIET_ZONE || x >= size + QUIET_ZONE || y >= size + QUIET_ZONE {
+            Color::White
+        } else {
+            self.qr_code.module(x - QUIET_ZONE, y - QUIET_ZONE)
+        }
+    }
+
+    fn qr_width(&self) -> usize {
+        (self.qr_code.width() + 2 * QUIET_ZONE) * self.module_size
+    }
+
+    fn text_width(&self) -> usize {
+        match self.caption.chars().count() {
+            0 => 0,
+            count => (count * (GLYPH_WIDTH + 1) - 1) * self.text_scale,
+        }
+    }
+
+    fn text_margin(&self) -> usize {
+        2 * self.text_scale
+    }
+}
+
+pub struct LabelIterator<'l, 'a, Q> {
+    label: &'l Label<'a, Q>,
+    x: usize,
+    y: usize,
+}
+
+impl<Q: Symbol> Iterator for LabelIterator<'_, '_, Q> {
+    type Item = CoordinatedColor;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.y >= self.label.height() {
+            return None;
+        }
+        let result = CoordinatedColor {
+            x: self.x,
+            y: self.y,
+            color: self.label.pixel(self.x, self.y),
+        };
+
+        self.x += 1;
+        if self.x >= self.label.width() {
+            self.x = 0;
+            self.y += 1;
+        }
+
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::Color;
+    use crate::qrcode::QrCodeBuilder;
+    use crate::render::label::Label;
+    use alloc::string::String;
+    use core::num::NonZeroUsize;
+
+    #[test]
+    fn caption_below_code() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let label = Label::new(&qr_code, "1")
+            .with_module_size(NonZeroUsize::new(1).unwrap())
+            .with_text_scale(NonZeroUsize::new(1).unwrap());
+        assert_eq!(label.width(), 29);
+        assert_eq!(label.height(), 29 + 7 + 2);
+
+        // The top-left finder pattern starts after the quiet zone
+        assert!(label.pixel(3, 3) == Color::White);
+        assert!(label.pixel(4, 4) == Color::Black);
+
+        let mut caption = String::new();
+        for y in 29..36 {
+            for x in 12..17 {
+                caption.push(if label.pixel(x, y) == Color::Black {
+                    '#'
+                } else {
+                    '.'
+                });
+            }
+            caption.push('\n');
+        }
+        assert_eq!(
+            caption,
+            "\
+..#..
+.##..
+..#..
+..#..
+..#..
+..#..
+.###.
+"
+        );
+        assert_eq!(label.draw_iter().count(), 29 * 38);
+    }
+
+    #[test]
+    fn wide_caption() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let label = Label::new(&qr_code, "A LONG CAPTION")
+            .with_module_size(NonZeroUsize::new(1).unwrap())
+            .with_text_scale(NonZeroUsize::new(1).unwrap());
+        assert_eq!(label.width(), 14 * 6 - 1 + 4);
+        assert!(label.pixel(0, 10) == Color::White);
+    }
+
+    #[test]
+    #[cfg(feature = "svg")]
+    fn svg() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let label = Label::new(&qr_code, "").with_module_size(NonZeroUsize::new(1).unwrap());
+        let mut svg = String::new();
+        label.write_svg(&mut svg).unwrap();
+        assert!(svg.starts_with(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"29\" height=\"29\" viewBox=\"0 0 29 29\">"
+        ));
+        assert!(svg.contains("M4 4h1v1h-1z"));
+        assert!(svg.ends_with("\"/></svg>"));
+    }
+}
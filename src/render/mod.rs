@@ -0,0 +1,113 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Renderers for drawing QR codes to common output formats
+//!
+//! Each renderer is behind its own cargo feature, so only the renderers in use are compiled:
+//! - `svg`: [`SvgRenderer`], [`QrCode::write_svg`](crate::QrCode::write_svg) and
+//!   [`Label::write_svg`], enabled by default
+//! - `terminal`: [`TerminalRenderer`] for printing to a terminal
+//! - `label`: [`Label`] for a QR code with a caption, including the [`glyph_pixel`] font
+//! - `raw`: [`RawImage`] for a packed 1 bit per pixel image, as used by embedded-graphics
+//! - `png`: [`QrCode::to_png`](crate::QrCode::to_png) for an uncompressed PNG image and
+//!   [`QrCode::to_data_uri`](crate::QrCode::to_data_uri) to inline it in HTML
+//! - `xbm`: [`QrCode::write_xbm`](crate::QrCode::write_xbm) for an XBM image to embed in C code
+//! - `pbm`: [`Pbm`] for a plain PBM image
+//! - `bmp`: [`QrCode::write_bmp`](crate::QrCode::write_bmp) for a BMP image, streamed into an
+//!   `embedded_io::Write` sink
+//!
+//! Use `default-features = false` to leave out all renderers and only use
+//! [`QrCode::draw_iter`](crate::QrCode::draw_iter).
+//!
+//! The text based renderers implement [`Renderer`], so they can be used through
+//! [`QrCode::render`](crate::QrCode::render) and other crates can add their own. With the
+//! `embedded-io` feature [`QrCode::render_io`](crate::QrCode::render_io) streams their output to an
+//! `embedded_io::Write` sink.
+
+use crate::symbol::Symbol;
+use core::fmt::{self, Write};
+
+pub(crate) mod blocks;
+#[cfg(feature = "bmp")]
+mod bmp;
+#[cfg(feature = "label")]
+mod font;
+#[cfg(feature = "embedded-io")]
+mod io;
+#[cfg(feature = "label")]
+mod label;
+#[cfg(feature = "pbm")]
+mod pbm;
+#[cfg(feature = "png")]
+mod png;
+#[cfg(feature = "raw")]
+mod raw;
+#[cfg(feature = "svg")]
+mod svg;
+#[cfg(feature = "terminal")]
+mod terminal;
+#[cfg(feature = "xbm")]
+mod xbm;
+
+pub use blocks::{Blocks, HalfBlocks};
+#[cfg(feature = "label")]
+pub use font::{glyph_pixel, GLYPH_HEIGHT, GLYPH_WIDTH};
+#[cfg(feature = "label")]
+pub use label::{Label, LabelIterator};
+#[cfg(feature = "pbm")]
+pub use pbm::Pbm;
+#[cfg(feature = "raw")]
+pub use raw::RawImage;
+#[cfg(feature = "svg")]
+pub use svg::{SvgOptions, SvgRenderer, SvgShape};
+#[cfg(feature = "terminal")]
+pub use terminal::{TerminalCapabilities, TerminalRenderer, TerminalStyle};
+
+/// Writes a QR code as text
+///
+/// The modules are read through [`Symbol`], so every renderer works for both
+/// [`QrCode`](crate::QrCode) and `DynQrCode`.
+///
+/// # Example
+///```
+///     use core::fmt::{self, Write};
+///     use tiny_qr::render::Renderer;
+///     use tiny_qr::{Color, QrCodeBuilder, Symbol};
+///
+///     /// Writes the modules as ones and zeros
+///     struct Binary;
+///
+///     impl Renderer for Binary {
+///         fn render<Q: Symbol>(&self, qr_code: &Q, out: &mut impl Write) -> fmt::Result {
+///             for y in 0..qr_code.width() {
+///                 for x in 0..qr_code.width() {
+///                     out.write_char(if qr_code.module(x, y) == Color::Black { '1' } else { '0' })?;
+///                 }
+///                 out.write_char('\n')?;
+///             }
+///             Ok(())
+///         }
+///     }
+///
+///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+///     let mut output = String::new();
+///     qr_code.render(&Binary, &mut output).unwrap();
+///     assert!(output.starts_with("11111110"));
+///```
+pub trait Renderer {
+    fn render<Q: Symbol>(&self, qr_code: &Q, out: &mut impl Write) -> fmt::Result;
+}
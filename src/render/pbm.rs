@@ -0,0 +1,77 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::matrix::Color;
+use crate::qrcode::QUIET_ZONE;
+use crate::render::Renderer;
+use crate::symbol::Symbol;
+use core::fmt::{self, Write};
+
+/// Renders a QR code including its quiet zone as a plain PBM image, a netpbm format that most
+/// image tools read
+///
+/// # Example
+///```
+///     use tiny_qr::render::Pbm;
+///     use tiny_qr::QrCodeBuilder;
+///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+///     let mut pbm = String::new();
+///     qr_code.render(&Pbm, &mut pbm).unwrap();
+///     assert!(pbm.starts_with("P1\n29 29\n"));
+///```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Pbm;
+
+impl Renderer for Pbm {
+    fn render<Q: Symbol>(&self, qr_code: &Q, out: &mut impl Write) -> fmt::Result {
+        let width = qr_code.width() + 2 * QUIET_ZONE;
+        write!(out, "P1\n{} {}\n", width, width)?;
+        for y in 0..width {
+            for x in 0..width {
+                let dark = x >= QUIET_ZONE
+                    && y >= QUIET_ZONE
+                    && x < width - QUIET_ZONE
+                    && y < width - QUIET_ZONE
+                    && qr_code.module(x - QUIET_ZONE, y - QUIET_ZONE) == Color::Black;
+                // A one is a black pixel
+                out.write_char(if dark { '1' } else { '0' })?;
+            }
+            out.write_char('\n')?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::qrcode::QrCodeBuilder;
+    use crate::render::Pbm;
+    use alloc::string::String;
+
+    #[test]
+    fn pbm() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let mut pbm = String::new();
+        qr_code.render(&Pbm, &mut pbm).unwrap();
+        let mut lines = pbm.lines();
+        assert_eq!(lines.next(), Some("P1"));
+        assert_eq!(lines.next(), Some("29 29"));
+        assert_eq!(lines.next(), Some("0".repeat(29).as_str()));
+        assert_eq!(lines.nth(3).map(|line| &line[..12]), Some("000011111110"));
+        assert_eq!(lines.count(), 29 - 5);
+    }
+}
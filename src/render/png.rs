@@ -0,0 +1,215 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::qrcode::QrCode;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::num::NonZeroUsize;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// The largest length of a stored deflate block
+const MAX_STORED_BLOCK: usize = 0xffff;
+
+/// The pixels per module and the margin in modules of a data URI
+const DATA_URI_SCALE: usize = 4;
+const DATA_URI_MARGIN: usize = 4;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+impl<const N: usize> QrCode<N> {
+    /// Encodes the QR code as a black and white PNG image
+    ///
+    /// Every module is `scale` by `scale` pixels and the symbol is surrounded by `margin` light
+    /// modules. The image data is stored uncompressed, so no compression library is needed.
+    ///
+    /// # Example
+    ///```
+    ///     use core::num::NonZeroUsize;
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+    ///     let png = qr_code.to_png(NonZeroUsize::new(4).unwrap(), 4);
+    ///     assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    ///```
+    pub fn to_png(&self, scale: NonZeroUsize, margin: usize) -> Vec<u8> {
+        let rows = self.packed_rows().with_border(margin).with_scale(scale);
+        let width = rows.width() as u32;
+        let height = rows.height() as u32;
+
+        // Every row starts with the filter type, a set bit is a light pixel in grayscale
+        let mut image = Vec::new();
+        for row in rows {
+            image.push(0);
+            image.extend(row.map(|byte| !byte));
+        }
+
+        let mut ihdr = Vec::new();
+        ihdr.extend_from_slice(&width.to_be_bytes());
+        ihdr.extend_from_slice(&height.to_be_bytes());
+        // A bit depth of 1, grayscale, deflate, no filtering and no interlacing
+        ihdr.extend_from_slice(&[1, 0, 0, 0, 0]);
+
+        let mut png = Vec::new();
+        png.extend_from_slice(&SIGNATURE);
+        write_chunk(&mut png, b"IHDR", &ihdr);
+        write_chunk(&mut png, b"IDAT", &zlib_stored(&image));
+        write_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    /// Encodes the QR code as a PNG data URI, to inline in HTML or CSS
+    ///
+    /// Every module is four pixels and the symbol has the quiet zone of four modules.
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+    ///     let html = format!("<img src=\"{}\" alt=\"HELLO\">", qr_code.to_data_uri());
+    ///     assert!(html.starts_with("<img src=\"data:image/png;base64,iVBORw0KGgo"));
+    ///```
+    pub fn to_data_uri(&self) -> String {
+        let mut uri = String::from("data:image/png;base64,");
+        write_base64(
+            &mut uri,
+            &self.to_png(NonZeroUsize::new(DATA_URI_SCALE).unwrap(), DATA_URI_MARGIN),
+        );
+        uri
+    }
+}
+
+/// Appends the data in the standard base64 alphabet with padding
+fn write_base64(out: &mut String, data: &[u8]) {
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, &byte)| {
+            bits | (byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (bits >> (18 - 6 * i)) & 0x3f;
+                out.push(BASE64_ALPHABET[index as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wraps the data in a zlib stream of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // Deflate with a 32 KiB window and the fastest compression level
+    let mut out = Vec::from([0x78, 0x01]);
+    let mut blocks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let is_final = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(is_final as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), &byte| {
+        let a = (a + byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::qrcode::QrCodeBuilder;
+    use crate::render::png::{adler32, crc32, write_base64, zlib_stored};
+    use alloc::string::String;
+    use alloc::vec;
+    use core::num::NonZeroUsize;
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn base64() {
+        for (data, expected) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"\xfb\xff", "+/8="),
+        ] {
+            let mut out = String::new();
+            write_base64(&mut out, data);
+            assert_eq!(out, expected);
+        }
+    }
+
+    #[test]
+    fn stored_blocks() {
+        let data = vec![7; 70000];
+        let stream = zlib_stored(&data);
+        assert_eq!(stream.len(), 2 + 2 * 5 + 70000 + 4);
+        assert_eq!(stream[2..7], [0, 0xff, 0xff, 0, 0]);
+        assert_eq!(stream[2 + 5 + 0xffff], 1);
+    }
+
+    #[test]
+    fn to_png() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let png = qr_code.to_png(NonZeroUsize::new(2).unwrap(), 1);
+        // The IHDR chunk with a width and height of 46 pixels
+        assert_eq!(
+            png[8..29],
+            [0, 0, 0, 13, b'I', b'H', b'D', b'R', 0, 0, 0, 46, 0, 0, 0, 46, 1, 0, 0, 0, 0]
+        );
+        assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]));
+        // The first row after the zlib and block headers is filtered and fully light
+        assert_eq!(png[48..55], [0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+    }
+}
@@ -0,0 +1,158 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::packed_rows::PackedRows;
+use crate::qrcode::QUIET_ZONE;
+use crate::symbol::Symbol;
+use core::num::NonZeroUsize;
+
+/// Renders a QR code including its quiet zone as a packed 1 bit per pixel image
+///
+/// Every row starts at a new byte, the most significant bit is the leftmost pixel and a set bit is
+/// a black module, the rows of [`QrCode::packed_rows`](crate::QrCode::packed_rows) written one after another. This is the layout of `ImageRaw<BinaryColor>` from embedded-graphics, so the
+/// written buffer can be passed to `ImageRaw::new(&buffer, image.width())` directly.
+///
+/// # Example
+///```
+///     use core::num::NonZeroUsize;
+///     use tiny_qr::QrCodeBuilder;
+///     use tiny_qr::render::RawImage;
+///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+///     let image = RawImage::new(&qr_code).with_module_size(NonZeroUsize::new(2).unwrap());
+///     let mut buffer = [0u8; 512];
+///     let data = image.write(&mut buffer);
+///     assert_eq!(image.width(), 58);
+///     assert_eq!(data.len(), 8 * 58);
+///```
+pub struct RawImage<'a, Q> {
+    qr_code: &'a Q,
+    module_size: NonZeroUsize,
+    inverted: bool,
+}
+
+impl<'a, Q: Symbol> RawImage<'a, Q> {
+    pub fn new(qr_code: &'a Q) -> Self {
+        Self {
+            qr_code,
+            module_size: NonZeroUsize::MIN,
+            inverted: false,
+        }
+    }
+
+    /// Sets the number of pixels per module in both directions
+    pub fn with_module_size(mut self, module_size: NonZeroUsize) -> Self {
+        self.module_size = module_size;
+        self
+    }
+
+    /// Swaps the colors for displays with a dark background
+    ///
+    /// Not every scanner reads inverted symbols, older scanner apps and many handheld scanners
+    /// only read dark modules on a light background.
+    pub fn with_inverted(mut self, inverted: bool) -> Self {
+        self.inverted = inverted;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.packed_rows().width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.packed_rows().height()
+    }
+
+    /// Number of bytes per row, including the padding at the end of the row
+    pub fn stride(&self) -> usize {
+        self.width().div_ceil(8)
+    }
+
+    /// Number of bytes needed to store the whole image
+    pub fn byte_len(&self) -> usize {
+        self.stride() * self.height()
+    }
+
+    /// Writes the image to the start of the buffer and returns the written part
+    ///
+    /// Panics when the buffer is smaller than [`RawImage::byte_len`].
+    pub fn write<'b>(&self, buffer: &'b mut [u8]) -> &'b [u8] {
+        let (stride, len) = (self.stride(), self.byte_len());
+        assert!(buffer.len() >= len, "buffer too small for image");
+        let buffer = &mut buffer[..len];
+        // The padding bits at the end of a row stay cleared when inverted
+        let padding = (stride * 8 - self.width()) as u32;
+        let last_byte_mask = 0xffu8.checked_shl(padding).unwrap_or(0);
+        for (row, packed_row) in buffer.chunks_mut(stride).zip(self.packed_rows()) {
+            for (byte, packed) in row.iter_mut().zip(packed_row) {
+                *byte = if self.inverted { !packed } else { packed };
+            }
+            row[stride - 1] &= last_byte_mask;
+        }
+        buffer
+    }
+
+    fn packed_rows(&self) -> PackedRows<'a, Q> {
+        PackedRows::new(self.qr_code)
+            .with_border(QUIET_ZONE)
+            .with_scale(self.module_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::Color;
+    use crate::qrcode::QrCodeBuilder;
+    use crate::render::raw::RawImage;
+    use core::num::NonZeroUsize;
+
+    #[test]
+    fn matches_draw_iter() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let image = RawImage::new(&qr_code);
+        assert_eq!(image.width(), 29);
+        assert_eq!(image.stride(), 4);
+        let mut buffer = [0xffu8; 4 * 29 + 1];
+        let data = image.write(&mut buffer);
+        assert_eq!(data.len(), 4 * 29);
+        for pixel in qr_code.draw_iter() {
+            let bit = data[pixel.y * 4 + pixel.x / 8] & (0x80 >> (pixel.x % 8)) != 0;
+            assert_eq!(bit, pixel.color == Color::Black);
+        }
+        // Padding bits at the end of every row stay cleared
+        assert!(data.chunks(4).all(|row| row[3] & 0x07 == 0));
+        assert_eq!(buffer[4 * 29], 0xff);
+    }
+
+    #[test]
+    fn inverted() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let image = RawImage::new(&qr_code)
+            .with_module_size(NonZeroUsize::new(2).unwrap())
+            .with_inverted(true);
+        let mut buffer = [0u8; 8 * 58];
+        let data = image.write(&mut buffer);
+        for pixel in qr_code
+            .draw_iter()
+            .with_module_size(NonZeroUsize::new(2).unwrap(), NonZeroUsize::new(2).unwrap())
+            .with_inverted(true)
+        {
+            let bit = data[pixel.y * 8 + pixel.x / 8] & (0x80 >> (pixel.x % 8)) != 0;
+            assert_eq!(bit, pixel.color == Color::Black);
+        }
+        assert!(data.chunks(8).all(|row| row[7] & 0x3f == 0));
+    }
+}
@@ -0,0 +1,407 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::matrix::Color;
+use crate::qrcode::{QrCode, QUIET_ZONE};
+use crate::render::Renderer;
+use crate::run_length::RunLengthRows;
+use crate::symbol::Symbol;
+use core::fmt::{self, Display, Formatter, Write};
+use core::num::NonZeroUsize;
+const BLACK: &str = "#000";
+const WHITE: &str = "#fff";
+
+/// How the dark modules are drawn
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SvgShape {
+    /// A single path of squares, the most compact for small module sizes
+    Path,
+    /// A `rect` element for every horizontal run of dark modules, which is easier to style
+    Rects,
+}
+
+/// The layout of an SVG image
+///
+/// # Example
+///```
+///     use core::num::NonZeroUsize;
+///     use tiny_qr::QrCodeBuilder;
+///     use tiny_qr::render::{SvgOptions, SvgShape};
+///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+///     let mut svg = String::new();
+///     let options = SvgOptions::new()
+///         .with_module_size(NonZeroUsize::new(10).unwrap())
+///         .with_margin(2)
+///         .with_shape(SvgShape::Rects);
+///     qr_code.write_svg(&mut svg, options).unwrap();
+///     assert!(svg.contains("viewBox=\"0 0 250 250\""));
+///     assert!(svg.contains("<rect x=\"20\" y=\"20\" width=\"70\" height=\"10\"/>"));
+///```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SvgOptions {
+    module_size: usize,
+    margin: usize,
+    shape: SvgShape,
+    inverted: bool,
+}
+
+impl SvgOptions {
+    pub fn new() -> Self {
+        Self {
+            module_size: 1,
+            margin: QUIET_ZONE,
+            shape: SvgShape::Path,
+            inverted: false,
+        }
+    }
+
+    /// Sets the size of a module in SVG units
+    pub fn with_module_size(mut self, module_size: NonZeroUsize) -> Self {
+        self.module_size = module_size.get();
+        self
+    }
+
+    /// Sets the light margin around the symbol in modules, the default is the quiet zone of four
+    /// modules
+    pub fn with_margin(mut self, margin: usize) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn with_shape(mut self, shape: SvgShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// Draws the symbol light on a dark background
+    ///
+    /// Not every scanner reads inverted symbols, older scanner apps and many handheld scanners
+    /// only read dark modules on a light background.
+    pub fn with_inverted(mut self, inverted: bool) -> Self {
+        self.inverted = inverted;
+        self
+    }
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Renderer for SvgOptions {
+    fn render<Q: Symbol>(&self, qr_code: &Q, out: &mut impl Write) -> fmt::Result {
+        write!(out, "{}", SvgRenderer::new(qr_code).with_options(*self))
+    }
+}
+
+impl<const N: usize> QrCode<N> {
+    /// Writes the QR code as an SVG document
+    pub fn write_svg(&self, out: &mut impl Write, options: SvgOptions) -> fmt::Result {
+        write!(out, "{}", SvgRenderer::new(self).with_options(options))
+    }
+}
+
+/// Renders a QR code including its quiet zone as an SVG image
+///
+/// A title and description can be embedded for screen readers, the image then gets the ARIA `img`
+/// role and is labelled by them. Their ids start with `qr`, give every image in an HTML page its
+/// own prefix with [`SvgRenderer::with_id_prefix`] to keep the ids unique.
+///
+/// # Example
+///```
+///     use tiny_qr::QrCodeBuilder;
+///     use tiny_qr::render::SvgRenderer;
+///     let qr_code = QrCodeBuilder::new().with_text("https://example.com").build();
+///     let svg = SvgRenderer::new(&qr_code)
+///         .with_title("QR code")
+///         .with_description("Link to https://example.com")
+///         .to_string();
+///     assert!(svg.contains("<title id=\"qr-title\">QR code</title>"));
+///```
+pub struct SvgRenderer<'a, Q> {
+    qr_code: &'a Q,
+    options: SvgOptions,
+    title: Option<&'a str>,
+    description: Option<&'a str>,
+    id_prefix: &'a str,
+}
+
+impl<'a, Q: Symbol> SvgRenderer<'a, Q> {
+    pub fn new(qr_code: &'a Q) -> Self {
+        Self {
+            qr_code,
+            options: SvgOptions::new(),
+            title: None,
+            description: None,
+            id_prefix: "qr",
+        }
+    }
+
+    /// Sets the size of a module in SVG units
+    pub fn with_module_size(mut self, module_size: NonZeroUsize) -> Self {
+        self.options = self.options.with_module_size(module_size);
+        self
+    }
+
+    /// Sets the module size, margin and shape at once
+    pub fn with_options(mut self, options: SvgOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets the accessible name of the image, for example "QR code"
+    pub fn with_title(mut self, title: &'a str) -> Self {
+        self.title = Some(title);
+        self
+    }
+
+    /// Sets the accessible description of the image, for example the encoded payload
+    pub fn with_description(mut self, description: &'a str) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    /// Sets the start of the ids of the title and description, `qr` by default
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     use tiny_qr::render::SvgRenderer;
+    ///     let qr_code = QrCodeBuilder::new().with_text("https://example.com").build();
+    ///     let svg = SvgRenderer::new(&qr_code)
+    ///         .with_title("QR code")
+    ///         .with_id_prefix("invoice-qr")
+    ///         .to_string();
+    ///     assert!(svg.contains("aria-labelledby=\"invoice-qr-title\""));
+    ///```
+    pub fn with_id_prefix(mut self, id_prefix: &'a str) -> Self {
+        self.id_prefix = id_prefix;
+        self
+    }
+
+    fn fmt_accessibility(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let prefix = XmlEscaped(self.id_prefix);
+        match (self.title, self.description) {
+            (None, None) => Ok(()),
+            (Some(_), Some(_)) => write!(
+                f,
+                " role=\"img\" aria-labelledby=\"{0}-title {0}-desc\"",
+                prefix
+            ),
+            (Some(_), None) => write!(f, " role=\"img\" aria-labelledby=\"{}-title\"", prefix),
+            (None, Some(_)) => write!(f, " role=\"img\" aria-labelledby=\"{}-desc\"", prefix),
+        }
+    }
+}
+
+impl<Q: Symbol> Display for SvgRenderer<'_, Q> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let SvgOptions {
+            module_size,
+            margin,
+            shape,
+            inverted,
+        } = self.options;
+        let (background, foreground) = if inverted {
+            (BLACK, WHITE)
+        } else {
+            (WHITE, BLACK)
+        };
+        let size = self.qr_code.width();
+        let width = (size + 2 * margin) * module_size;
+        write_svg_start(f, width, width)?;
+        self.fmt_accessibility(f)?;
+        f.write_char('>')?;
+        if let Some(title) = self.title {
+            write!(
+                f,
+                "<title id=\"{}-title\">{}</title>",
+                XmlEscaped(self.id_prefix),
+                XmlEscaped(title)
+            )?;
+        }
+        if let Some(description) = self.description {
+            write!(
+                f,
+                "<desc id=\"{}-desc\">{}</desc>",
+                XmlEscaped(self.id_prefix),
+                XmlEscaped(description)
+            )?;
+        }
+        write!(
+            f,
+            "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+            width, width, background
+        )?;
+
+        match shape {
+            SvgShape::Path => {
+                write!(f, "<path fill=\"{}\" d=\"", foreground)?;
+                let size = self.qr_code.width();
+                for y in 0..size {
+                    for x in 0..size {
+                        if self.qr_code.module(x, y) == Color::Black {
+                            write_square(
+                                f,
+                                (x + margin) * module_size,
+                                (y + margin) * module_size,
+                                module_size,
+                            )?;
+                        }
+                    }
+                }
+                f.write_str("\"/>")?;
+            }
+            SvgShape::Rects => {
+                write!(f, "<g fill=\"{}\">", foreground)?;
+                for (y, row) in RunLengthRows::new(self.qr_code).enumerate() {
+                    let mut x = 0;
+                    for (color, length) in row {
+                        if color == Color::Black {
+                            write!(
+                                f,
+                                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+                                (x + margin) * module_size,
+                                (y + margin) * module_size,
+                                length * module_size,
+                                module_size
+                            )?;
+                        }
+                        x += length;
+                    }
+                }
+                f.write_str("</g>")?;
+            }
+        }
+        f.write_str("</svg>")
+    }
+}
+
+/// Writes the opening `svg` tag without closing it, so attributes can be added
+pub(crate) fn write_svg_start(out: &mut impl Write, width: usize, height: usize) -> fmt::Result {
+    write!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\"",
+        width, height, width, height
+    )
+}
+
+/// Writes a square as path data
+pub(crate) fn write_square(out: &mut impl Write, x: usize, y: usize, size: usize) -> fmt::Result {
+    write!(out, "M{} {}h{}v{}h-{}z", x, y, size, size, size)
+}
+
+/// Writes text with the characters that are special in XML escaped
+struct XmlEscaped<'a>(&'a str);
+
+impl Display for XmlEscaped<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '&' => f.write_str("&amp;")?,
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '"' => f.write_str("&quot;")?,
+                '\'' => f.write_str("&apos;")?,
+                _ => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::Color;
+    use crate::qrcode::QrCodeBuilder;
+    use crate::render::svg::{SvgOptions, SvgRenderer, SvgShape};
+    use alloc::format;
+    use alloc::string::String;
+    use core::num::NonZeroUsize;
+
+    #[test]
+    fn plain() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let svg = format!(
+            "{}",
+            SvgRenderer::new(&qr_code).with_module_size(NonZeroUsize::new(2).unwrap())
+        );
+        assert!(svg.starts_with(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"58\" height=\"58\" viewBox=\"0 0 58 58\"><rect"
+        ));
+        assert!(svg.contains("M8 8h2v2h-2z"));
+        assert!(!svg.contains("aria"));
+    }
+
+    #[test]
+    fn accessible() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let svg = format!(
+            "{}",
+            SvgRenderer::new(&qr_code).with_description("Payload <1> & \"2\"")
+        );
+        assert!(svg.contains(" role=\"img\" aria-labelledby=\"qr-desc\">"));
+        assert!(svg.contains("<desc id=\"qr-desc\">Payload &lt;1&gt; &amp; &quot;2&quot;</desc>"));
+        assert!(!svg.contains("<title"));
+
+        let svg = format!(
+            "{}",
+            SvgRenderer::new(&qr_code)
+                .with_title("Ticket")
+                .with_description("Seat 12")
+                .with_id_prefix("ticket-2")
+        );
+        assert!(svg.contains(" aria-labelledby=\"ticket-2-title ticket-2-desc\">"));
+        assert!(svg.contains("<title id=\"ticket-2-title\">Ticket</title>"));
+        assert!(svg.contains("<desc id=\"ticket-2-desc\">Seat 12</desc>"));
+    }
+
+    #[test]
+    fn write_svg() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let mut svg = String::new();
+        qr_code.write_svg(&mut svg, SvgOptions::new()).unwrap();
+        assert_eq!(svg, format!("{}", SvgRenderer::new(&qr_code)));
+
+        let mut rendered = String::new();
+        qr_code.render(&SvgOptions::new(), &mut rendered).unwrap();
+        assert_eq!(rendered, svg);
+
+        let mut svg = String::new();
+        let options = SvgOptions::new().with_margin(0).with_shape(SvgShape::Rects);
+        qr_code.write_svg(&mut svg, options).unwrap();
+        assert!(svg.contains("viewBox=\"0 0 21 21\""));
+        // The top row starts with the top of the finder pattern
+        assert!(svg.contains("<g fill=\"#000\"><rect x=\"0\" y=\"0\" width=\"7\" height=\"1\"/>"));
+        assert!(svg.ends_with("</g></svg>"));
+
+        let mut inverted = String::new();
+        qr_code
+            .write_svg(&mut inverted, options.with_inverted(true))
+            .unwrap();
+        assert!(
+            inverted.contains("<rect width=\"21\" height=\"21\" fill=\"#000\"/><g fill=\"#fff\">")
+        );
+        let dark_runs = qr_code
+            .run_length_rows()
+            .flatten()
+            .filter(|&(color, _)| color == Color::Black)
+            .count();
+        assert_eq!(svg.matches("<rect x=").count(), dark_runs);
+    }
+}
@@ -0,0 +1,472 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::matrix::Color;
+use crate::qrcode::QUIET_ZONE;
+use crate::render::Renderer;
+use crate::symbol::Symbol;
+use core::fmt::{self, Display, Formatter, Write};
+const RESET: &str = "\x1b[0m";
+/// Black foreground on a white background
+const BLACK_ON_WHITE: &str = "\x1b[30;47m";
+const BLACK_BACKGROUND: &str = "\x1b[40m";
+const WHITE_BACKGROUND: &str = "\x1b[47m";
+/// Starts a sixel image with square pixels
+const SIXEL_START: &str = "\x1bPq";
+const SIXEL_END: &str = "\x1b\\";
+/// Every module is a square of six pixels, which is a single sixel high
+const SIXEL_MODULE_SIZE: usize = 6;
+/// The braille pattern without raised dots, the dots are the bits of the lower byte
+const BRAILLE_BLANK: u32 = 0x2800;
+/// The bit of each dot of a braille character, by row and then column
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// The features supported by the terminal that the QR code is printed on
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TerminalCapabilities {
+    /// The terminal can print UTF-8 block elements
+    pub unicode: bool,
+    /// The terminal supports ANSI color escape codes
+    pub color: bool,
+}
+
+impl TerminalCapabilities {
+    /// Plain ASCII without escape codes, works on any console
+    pub const DUMB: Self = Self {
+        unicode: false,
+        color: false,
+    };
+
+    /// Guesses the capabilities from the values of the `LANG` and `TERM` environment variables
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::render::TerminalCapabilities;
+    ///     let capabilities = TerminalCapabilities::detect(Some("en_US.UTF-8"), Some("xterm-256color"));
+    ///     assert!(capabilities.unicode && capabilities.color);
+    ///     assert_eq!(TerminalCapabilities::detect(None, Some("dumb")), TerminalCapabilities::DUMB);
+    ///```
+    pub fn detect(lang: Option<&str>, term: Option<&str>) -> Self {
+        let unicode = lang.is_some_and(|lang| {
+            let encoding = lang.rsplit('.').next().unwrap_or("");
+            encoding.eq_ignore_ascii_case("UTF-8") || encoding.eq_ignore_ascii_case("utf8")
+        });
+        let color = term.is_some_and(|term| !term.is_empty() && term != "dumb");
+        Self { unicode, color }
+    }
+}
+
+/// Prints with the style chosen from the capabilities
+impl Renderer for TerminalCapabilities {
+    fn render<Q: Symbol>(&self, qr_code: &Q, out: &mut impl Write) -> fmt::Result {
+        write!(out, "{}", TerminalRenderer::new(qr_code, *self))
+    }
+}
+
+/// How the modules are printed
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TerminalStyle {
+    /// Half blocks, color blocks or ASCII art, chosen from the capabilities
+    Auto,
+    /// ASCII art without escape codes, for dumb serial consoles and logs
+    Ascii,
+    /// Two spaces per module with an ANSI background color, which doesn't depend on the font
+    ///
+    /// Several terminal fonts draw half blocks with gaps or overlap, which breaks scanning.
+    ColorBlocks,
+    /// Braille characters of two by four modules, a quarter of the lines of half blocks
+    ///
+    /// The dark modules are raised dots, so the colors are forced to black on white when the
+    /// terminal supports colors.
+    Braille,
+    /// A sixel image, for terminals with sixel graphics like xterm, mlterm and foot
+    ///
+    /// The image shows the actual modules, so it doesn't depend on how the font draws blocks.
+    Sixel,
+}
+
+/// Prints a QR code including its quiet zone to a terminal
+///
+/// With Unicode support two rows of modules are combined into one line of half blocks, otherwise
+/// every module is printed as two characters. With color support the colors are forced to black
+/// on white, so the code scans on terminals with a dark background.
+///
+/// # Example
+///```
+///     use tiny_qr::QrCodeBuilder;
+///     use tiny_qr::render::{TerminalCapabilities, TerminalRenderer};
+///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+///     let output = TerminalRenderer::new(&qr_code, TerminalCapabilities::DUMB).to_string();
+///     assert_eq!(output.lines().count(), 29);
+///```
+pub struct TerminalRenderer<'a, Q> {
+    qr_code: &'a Q,
+    capabilities: TerminalCapabilities,
+    style: TerminalStyle,
+    dark: &'a str,
+    light: &'a str,
+}
+
+impl<'a, Q: Symbol> TerminalRenderer<'a, Q> {
+    pub fn new(qr_code: &'a Q, capabilities: TerminalCapabilities) -> Self {
+        Self {
+            qr_code,
+            capabilities,
+            style: TerminalStyle::Auto,
+            dark: "##",
+            light: "  ",
+        }
+    }
+
+    /// Sets the text of a dark and a light module in ASCII art, the default is `"##"` and `"  "`
+    ///
+    /// Both should have the same width, two characters keep the modules about square.
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     use tiny_qr::render::{TerminalCapabilities, TerminalRenderer};
+    ///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+    ///     let output = TerminalRenderer::new(&qr_code, TerminalCapabilities::DUMB)
+    ///         .with_ascii("@@", "..")
+    ///         .to_string();
+    ///     assert!(output.starts_with(".........."));
+    ///```
+    pub fn with_ascii(mut self, dark: &'a str, light: &'a str) -> Self {
+        self.dark = dark;
+        self.light = light;
+        self
+    }
+
+    /// Overrides the style that is chosen from the capabilities
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     use tiny_qr::render::{TerminalCapabilities, TerminalRenderer, TerminalStyle};
+    ///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+    ///     let output = TerminalRenderer::new(&qr_code, TerminalCapabilities::DUMB)
+    ///         .with_style(TerminalStyle::Sixel)
+    ///         .to_string();
+    ///     assert!(output.starts_with("\x1bPq\"1;1;174;174"));
+    ///```
+    pub fn with_style(mut self, style: TerminalStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    fn width(&self) -> usize {
+        self.qr_code.width() + 2 * QUIET_ZONE
+    }
+
+    fn module(&self, x: usize, y: usize) -> Color {
+        let size = self.qr_code.width();
+        if x < QUIET_ZONE || y < QUIET_ZONE || x >= size + QUIET_ZONE || y >= size + QUIET_ZONE {
+            Color::White
+        } else {
+            self.qr_code.module(x - QUIET_ZONE, y - QUIET_ZONE)
+        }
+    }
+
+    fn fmt_half_blocks(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let width = self.width();
+        for y in (0..width).step_by(2) {
+            if self.capabilities.color {
+                f.write_str(BLACK_ON_WHITE)?;
+            }
+            for x in 0..width {
+                let down = if y + 1 < width {
+                    self.module(x, y + 1)
+                } else {
+                    Color::White
+                };
+                f.write_char(match (self.module(x, y), down) {
+                    (Color::Black, Color::Black) => '\u{2588}',
+                    (Color::Black, Color::White) => '\u{2580}',
+                    (Color::White, Color::Black) => '\u{2584}',
+                    (Color::White, Color::White) => ' ',
+                })?;
+            }
+            if self.capabilities.color {
+                f.write_str(RESET)?;
+            }
+            f.write_char('\n')?;
+        }
+        Ok(())
+    }
+
+    fn fmt_ascii(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let width = self.width();
+        for y in 0..width {
+            for x in 0..width {
+                f.write_str(match self.module(x, y) {
+                    Color::Black => self.dark,
+                    Color::White => self.light,
+                })?;
+            }
+            f.write_char('\n')?;
+        }
+        Ok(())
+    }
+
+    fn fmt_color_blocks(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let width = self.width();
+        for y in 0..width {
+            let mut previous = None;
+            for x in 0..width {
+                // Only changes of color need an escape code
+                let color = self.module(x, y);
+                if previous != Some(color) {
+                    f.write_str(match color {
+                        Color::Black => BLACK_BACKGROUND,
+                        Color::White => WHITE_BACKGROUND,
+                    })?;
+                    previous = Some(color);
+                }
+                f.write_str("  ")?;
+            }
+            f.write_str(RESET)?;
+            f.write_char('\n')?;
+        }
+        Ok(())
+    }
+
+    fn fmt_braille(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let width = self.width();
+        let module = |x: usize, y: usize| {
+            if x < width && y < width {
+                self.module(x, y)
+            } else {
+                Color::White
+            }
+        };
+        for y in (0..width).step_by(4) {
+            if self.capabilities.color {
+                f.write_str(BLACK_ON_WHITE)?;
+            }
+            for x in (0..width).step_by(2) {
+                let mut pattern = BRAILLE_BLANK;
+                for (dy, dots) in BRAILLE_DOTS.iter().enumerate() {
+                    for (dx, &dot) in dots.iter().enumerate() {
+                        if module(x + dx, y + dy) == Color::Black {
+                            pattern |= dot;
+                        }
+                    }
+                }
+                f.write_char(char::from_u32(pattern).unwrap())?;
+            }
+            if self.capabilities.color {
+                f.write_str(RESET)?;
+            }
+            f.write_char('\n')?;
+        }
+        Ok(())
+    }
+
+    fn fmt_sixel(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let width = self.width();
+        let pixels = width * SIXEL_MODULE_SIZE;
+        f.write_str(SIXEL_START)?;
+        // The size in pixels, then color 0 is white and color 1 is black in RGB percentages
+        write!(f, "\"1;1;{};{}", pixels, pixels)?;
+        f.write_str("#0;2;100;100;100#1;2;0;0;0")?;
+        for y in 0..width {
+            for (register, color) in [(0, Color::White), (1, Color::Black)] {
+                write!(f, "#{}", register)?;
+                let mut x = 0;
+                while x < width {
+                    let run = (x..width)
+                        .take_while(|&end| self.module(end, y) == self.module(x, y))
+                        .count();
+                    // A full sixel for the modules of this color, otherwise an empty sixel
+                    let sixel = if self.module(x, y) == color { '~' } else { '?' };
+                    write!(f, "!{}{}", run * SIXEL_MODULE_SIZE, sixel)?;
+                    x += run;
+                }
+                // Return to the start of the line to overlay the next color
+                f.write_char('$')?;
+            }
+            f.write_char('-')?;
+        }
+        f.write_str(SIXEL_END)?;
+        f.write_char('\n')
+    }
+}
+
+impl<Q: Symbol> Display for TerminalRenderer<'_, Q> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.style {
+            TerminalStyle::Sixel => self.fmt_sixel(f),
+            TerminalStyle::Ascii => self.fmt_ascii(f),
+            TerminalStyle::ColorBlocks => self.fmt_color_blocks(f),
+            TerminalStyle::Braille => self.fmt_braille(f),
+            TerminalStyle::Auto if self.capabilities.unicode => self.fmt_half_blocks(f),
+            TerminalStyle::Auto if self.capabilities.color => self.fmt_color_blocks(f),
+            TerminalStyle::Auto => self.fmt_ascii(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::qrcode::QrCodeBuilder;
+    use crate::render::terminal::{TerminalCapabilities, TerminalRenderer, TerminalStyle};
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn ascii() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let output = format!(
+            "{}",
+            TerminalRenderer::new(&qr_code, TerminalCapabilities::DUMB)
+        );
+        let mut lines = output.lines();
+        assert_eq!(lines.next(), Some(" ".repeat(58).as_str()));
+        assert_eq!(
+            lines.nth(3).map(|line| &line[..24]),
+            Some("        ##############  ")
+        );
+        assert_eq!(output.lines().count(), 29);
+        assert!(output.is_ascii());
+
+        let mut rendered = String::new();
+        qr_code
+            .render(&TerminalCapabilities::DUMB, &mut rendered)
+            .unwrap();
+        assert_eq!(rendered, output);
+    }
+
+    #[test]
+    fn configured_ascii() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let capabilities = TerminalCapabilities {
+            unicode: true,
+            color: true,
+        };
+        let output = format!(
+            "{}",
+            TerminalRenderer::new(&qr_code, capabilities)
+                .with_style(TerminalStyle::Ascii)
+                .with_ascii("X", ".")
+        );
+        let mut lines = output.lines();
+        assert_eq!(lines.next(), Some(".".repeat(29).as_str()));
+        assert_eq!(lines.nth(3).map(|line| &line[..12]), Some("....XXXXXXX."));
+        assert_eq!(output.lines().count(), 29);
+        assert!(output.is_ascii());
+    }
+
+    #[test]
+    fn half_blocks_with_color() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let capabilities = TerminalCapabilities {
+            unicode: true,
+            color: true,
+        };
+        let output = format!("{}", TerminalRenderer::new(&qr_code, capabilities));
+        assert_eq!(output.lines().count(), 15);
+        let line = output.lines().nth(2).unwrap();
+        assert!(line.starts_with("\x1b[30;47m    \u{2588}\u{2580}"));
+        assert!(line.ends_with("\x1b[0m"));
+    }
+
+    #[test]
+    fn color_blocks() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let capabilities = TerminalCapabilities {
+            unicode: true,
+            color: true,
+        };
+        let output = format!(
+            "{}",
+            TerminalRenderer::new(&qr_code, capabilities).with_style(TerminalStyle::ColorBlocks)
+        );
+        assert_eq!(output.lines().count(), 29);
+        let line = output.lines().nth(4).unwrap();
+        assert!(line.starts_with(&format!(
+            "\x1b[47m{}\x1b[40m{}\x1b[47m  ",
+            " ".repeat(8),
+            " ".repeat(14)
+        )));
+        assert!(line.ends_with("\x1b[0m"));
+
+        // Without Unicode the colors are used automatically
+        let capabilities = TerminalCapabilities {
+            unicode: false,
+            color: true,
+        };
+        assert_eq!(
+            format!("{}", TerminalRenderer::new(&qr_code, capabilities)),
+            output
+        );
+    }
+
+    #[test]
+    fn braille() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let output = format!(
+            "{}",
+            TerminalRenderer::new(&qr_code, TerminalCapabilities::DUMB)
+                .with_style(TerminalStyle::Braille)
+        );
+        assert_eq!(output.lines().count(), 8);
+        assert!(output.lines().all(|line| line.chars().count() == 15));
+        let mut lines = output.lines();
+        assert_eq!(
+            lines.next().map(|line| line.chars().next()),
+            Some(Some('\u{2800}'))
+        );
+        // Four rows of quiet zone, then the top four rows of the finder pattern
+        let line: Vec<char> = lines.next().unwrap().chars().collect();
+        assert_eq!(
+            line[..6],
+            ['\u{2800}', '\u{2800}', '\u{284f}', '\u{28ed}', '\u{284d}', '\u{2847}']
+        );
+    }
+
+    #[test]
+    fn sixel() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let output = format!(
+            "{}",
+            TerminalRenderer::new(&qr_code, TerminalCapabilities::DUMB)
+                .with_style(TerminalStyle::Sixel)
+        );
+        assert!(output.starts_with("\x1bPq\"1;1;174;174#0;2;100;100;100#1;2;0;0;0"));
+        assert!(output.ends_with("\x1b\\\n"));
+        // Every row of modules is a line of sixels, which ends with a `-`
+        assert_eq!(output.matches('-').count(), 29);
+        // The first line of the symbol has four modules of quiet zone and then the finder pattern
+        let line = output.split('-').nth(4).unwrap();
+        assert!(line.starts_with("#0!24~!42?!6~"));
+        assert!(line.contains("$#1!24?!42~!6?"));
+    }
+
+    #[test]
+    fn detect() {
+        assert_eq!(
+            TerminalCapabilities::detect(Some("C"), Some("vt100")),
+            TerminalCapabilities {
+                unicode: false,
+                color: true
+            }
+        );
+        assert!(TerminalCapabilities::detect(Some("nl_NL.utf8"), None).unicode);
+    }
+}
@@ -0,0 +1,83 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::qrcode::{QrCode, QUIET_ZONE};
+use core::fmt::{self, Write};
+
+/// The number of bytes per line of the bitmap array
+const BYTES_PER_LINE: usize = 12;
+
+impl<const N: usize> QrCode<N> {
+    /// Writes the QR code including its quiet zone as an XBM image, which is C source code
+    ///
+    /// The `name` prefixes the defines and the array, so it must be a valid C identifier. Every
+    /// row starts at a new byte, the least significant bit is the leftmost pixel and a set bit is
+    /// a black module.
+    ///
+    /// # Example
+    ///```
+    ///     use tiny_qr::QrCodeBuilder;
+    ///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+    ///     let mut xbm = String::new();
+    ///     qr_code.write_xbm(&mut xbm, "hello").unwrap();
+    ///     assert!(xbm.starts_with("#define hello_width 29\n#define hello_height 29\n"));
+    ///```
+    pub fn write_xbm(&self, out: &mut impl Write, name: &str) -> fmt::Result {
+        let rows = self.packed_rows().with_border(QUIET_ZONE);
+        writeln!(out, "#define {}_width {}", name, rows.width())?;
+        writeln!(out, "#define {}_height {}", name, rows.height())?;
+        write!(out, "static unsigned char {}_bits[] = {{", name)?;
+        for (index, byte) in rows.flatten().enumerate() {
+            if index > 0 {
+                out.write_char(',')?;
+            }
+            if index % BYTES_PER_LINE == 0 {
+                out.write_str("\n   ")?;
+            }
+            write!(out, " 0x{:02x}", byte.reverse_bits())?;
+        }
+        out.write_str(" };\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::qrcode::QrCodeBuilder;
+    use alloc::string::String;
+
+    #[test]
+    fn write_xbm() {
+        let qr_code = QrCodeBuilder::new().with_text("1").build();
+        let mut xbm = String::new();
+        qr_code.write_xbm(&mut xbm, "qr").unwrap();
+
+        let mut lines = xbm.lines();
+        assert_eq!(lines.next(), Some("#define qr_width 29"));
+        assert_eq!(lines.next(), Some("#define qr_height 29"));
+        assert_eq!(lines.next(), Some("static unsigned char qr_bits[] = {"));
+        assert_eq!(
+            lines.next(),
+            Some("    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,")
+        );
+        // The first row of the symbol starts with four light modules and the finder pattern
+        let line = lines.next().unwrap();
+        assert!(line.starts_with("    0x00, 0x00, 0x00, 0x00, 0xf0, 0x"));
+        assert_eq!(u8::from_str_radix(&line[36..38], 16).unwrap() & 0x0f, 0x07);
+        assert_eq!(lines.count(), 29 * 4 / 12 + 1 - 2);
+        assert!(xbm.ends_with(" 0x00 };\n"));
+    }
+}
@@ -0,0 +1,128 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::matrix::Color;
+use crate::symbol::Symbol;
+
+/// Iterates over the rows of a QR code, each as spans of modules with the same color
+///
+/// # Example
+///```
+///     use tiny_qr::{Color, QrCodeBuilder};
+///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+///     for (y, row) in qr_code.run_length_rows().enumerate() {
+///         let mut x = 0;
+///         for (color, length) in row {
+///             if color == Color::Black {
+///                 // Fill the rectangle from (x, y) with the given length and a height of one
+///             }
+///             x += length;
+///         }
+///         assert_eq!(x, qr_code.width());
+///     }
+///```
+pub struct RunLengthRows<'a, Q> {
+    qr_code: &'a Q,
+    y: usize,
+}
+
+impl<'a, Q: Symbol> RunLengthRows<'a, Q> {
+    pub(crate) fn new(qr_code: &'a Q) -> Self {
+        Self { qr_code, y: 0 }
+    }
+}
+
+impl<'a, Q: Symbol> Iterator for RunLengthRows<'a, Q> {
+    type Item = RunLengthRow<'a, Q>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.y >= self.qr_code.width() {
+            return None;
+        }
+        let row = RunLengthRow {
+            qr_code: self.qr_code,
+            x: 0,
+            y: self.y,
+        };
+        self.y += 1;
+        Some(row)
+    }
+}
+
+/// Iterates over the spans of a single row as the color and the number of modules
+pub struct RunLengthRow<'a, Q> {
+    qr_code: &'a Q,
+    x: usize,
+    y: usize,
+}
+
+impl<Q: Symbol> Iterator for RunLengthRow<'_, Q> {
+    type Item = (Color, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let width = self.qr_code.width();
+        if self.x >= width {
+            return None;
+        }
+        let color = self.qr_code.module(self.x, self.y);
+        let start = self.x;
+        while self.x < width && self.qr_code.module(self.x, self.y) == color {
+            self.x += 1;
+        }
+        Some((color, self.x - start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::Color;
+    use crate::qrcode::QrCodeBuilder;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn run_length_rows() {
+        let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+        assert_eq!(qr_code.run_length_rows().count(), 21);
+
+        // The finder patterns with their separators and the timing pattern in between
+        let row: Vec<(Color, usize)> = qr_code.run_length_rows().nth(6).unwrap().collect();
+        assert_eq!(
+            row,
+            [
+                (Color::Black, 7),
+                (Color::White, 1),
+                (Color::Black, 1),
+                (Color::White, 1),
+                (Color::Black, 1),
+                (Color::White, 1),
+                (Color::Black, 1),
+                (Color::White, 1),
+                (Color::Black, 7),
+            ]
+        );
+
+        for (y, row) in qr_code.run_length_rows().enumerate() {
+            let mut x = 0;
+            for (color, length) in row {
+                assert!(length > 0);
+                assert!((x..x + length).all(|x| qr_code.module(x, y) == color));
+                x += length;
+            }
+            assert_eq!(x, 21);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::buffer::BUFFER_CAPACITY;
+use crate::encoding::{
+    calculate_encoded_data_bit_length, encode_text, select_character_set, version_limit,
+    CharacterSet, EncodeOptions, STRUCTURED_APPEND_BIT_LEN,
+};
+use crate::error::QrError;
+use crate::error_correction::ErrorCorrectionLevel;
+use crate::qr_version::Version;
+use crate::qrcode::{QrCode, MAX_MODULE_SIZE};
+
+/// The maximum number of symbols in a structured append sequence
+const MAX_SYMBOLS: usize = 16;
+
+/// Splits the text over the fewest symbols of at most the version, joined by structured append
+///
+/// All symbols use the character set of the complete text, which may be larger than needed for
+/// a single part. The parity is calculated over the bytes of the text in that character set.
+///
+/// A version above the largest version of a [`QrCode`] is lowered to that, like
+/// [`EncodeOptions::with_max_version`].
+///
+/// # Example
+///```
+///     use tiny_qr::{encode_split, ErrorCorrectionLevel};
+///     let text = "A text that is too long for a single version 1 symbol";
+///     let symbols = encode_split(text, 1, ErrorCorrectionLevel::Low).unwrap();
+///     assert_eq!(symbols.len(), 4);
+///     for qr_code in symbols {
+///         println!("{}", qr_code?);
+///     }
+///     # Ok::<(), tiny_qr::QrError>(())
+///```
+pub fn encode_split(
+    text: &str,
+    max_version: u8,
+    error_correction: ErrorCorrectionLevel,
+) -> Result<SplitSymbols<'_>, QrError> {
+    let options = EncodeOptions::new()
+        .with_max_version(max_version)
+        .with_specific_error_correction_level(error_correction);
+    // Kanji mode would need the parity over the Shift JIS bytes
+    #[cfg(feature = "kanji")]
+    let options = options.with_kanji_detection(false);
+    let limit = version_limit::<BUFFER_CAPACITY>();
+    options.validate(limit)?;
+    let character_set = select_character_set(&options, text)?;
+
+    let mut split = SplitSymbols {
+        options: options.with_character_set(character_set),
+        version: Version::new(max_version.min(limit.number()))?,
+        error_correction,
+        remaining: text,
+        position: 0,
+        total: 0,
+        parity: parity(character_set, text),
+    };
+    let mut remaining = text;
+    while !remaining.is_empty() {
+        if split.total == MAX_SYMBOLS {
+            return Err(QrError::DataTooLong);
+        }
+        remaining = split.split(remaining)?.1;
+        split.total += 1;
+    }
+    Ok(split)
+}
+
+/// The parity of structured append is the XOR of all bytes of the data
+fn parity(character_set: CharacterSet, text: &str) -> u8 {
+    match character_set {
+        CharacterSet::Iso8859_1 => text.chars().fold(0, |parity, c| parity ^ c as u8),
+        _ => text.bytes().fold(0, |parity, byte| parity ^ byte),
+    }
+}
+
+/// The symbols of a split text, in the order of reading
+pub struct SplitSymbols<'a> {
+    options: EncodeOptions,
+    version: Version,
+    error_correction: ErrorCorrectionLevel,
+    remaining: &'a str,
+    position: usize,
+    total: usize,
+    parity: u8,
+}
+
+impl<'a> SplitSymbols<'a> {
+    /// Splits off the longest part of the text that fits in a single symbol
+    fn split(&self, text: &'a str) -> Result<(&'a str, &'a str), QrError> {
+        let capacity = self.version.data_codeword_bit_len(self.error_correction);
+        let character_set = self.options.character_set.unwrap();
+        let fits = |part: &str| {
+            let bit_len = calculate_encoded_data_bit_length(
+                part,
+                self.version,
+                character_set,
+                self.options.eci_policy,
+            );
+            STRUCTURED_APPEND_BIT_LEN + bit_len <= capacity
+        };
+        let end = text
+            .char_indices()
+            .map(|(index, c)| index + c.len_utf8())
+            .take_while(|&end| fits(&text[..end]))
+            .last()
+            .ok_or(QrError::DataTooLong)?;
+        Ok(text.split_at(end))
+    }
+}
+
+impl<'a> Iterator for SplitSymbols<'a> {
+    type Item = Result<QrCode<MAX_MODULE_SIZE>, QrError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining.is_empty() {
+            return None;
+        }
+        let options =
+            self.options
+                .with_structured_append(self.position as u8, self.total as u8, self.parity);
+        let symbol = self.split(self.remaining).and_then(|(part, remaining)| {
+            self.remaining = remaining;
+            let encoded_data = encode_text(&options, part)?;
+            Ok(QrCode::from_encoded_data(&options, encoded_data))
+        });
+        if symbol.is_err() {
+            // Stop after the error, the size hint no longer holds
+            self.remaining = "";
+            self.position = self.total;
+        } else {
+            self.position += 1;
+        }
+        Some(symbol)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.total - self.position;
+        (len, Some(len))
+    }
+}
+
+impl<'a> ExactSizeIterator for SplitSymbols<'a> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::encoding::{encode_text, EncodeOptions};
+    use crate::error::QrError;
+    use crate::error_correction::ErrorCorrectionLevel;
+    use crate::structured_append::encode_split;
+
+    #[test]
+    fn structured_append_header() {
+        let options = EncodeOptions::new().with_structured_append(1, 3, 0xa5);
+        let encoded = encode_text(&options, "1").unwrap();
+        assert_eq!(
+            &encoded.buffer.data()[..4],
+            [0b0011_0001, 0b0010_1010, 0b0101_0001, 0b0000_0000]
+        );
+
+        for (position, total) in [(3, 3), (0, 17)] {
+            let options = EncodeOptions::new().with_structured_append(position, total, 0);
+            assert_eq!(
+                encode_text(&options, "1").err(),
+                Some(QrError::InvalidField {
+                    field: "structured append"
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn split() {
+        // 41 digits fit in a version 1-L symbol, minus the header
+        let text = "0123456789".repeat(7);
+        let symbols = encode_split(&text, 1, ErrorCorrectionLevel::Low).unwrap();
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(symbols.count(), 2);
+
+        let text = "0123456789".repeat(3);
+        let symbols = encode_split(&text, 1, ErrorCorrectionLevel::Low).unwrap();
+        assert_eq!(symbols.len(), 1);
+
+        let text = "0123456789".repeat(100);
+        assert!(encode_split(&text, 1, ErrorCorrectionLevel::High).is_err());
+    }
+
+    #[test]
+    fn max_symbols() {
+        // 35 digits fit in a version 1-L symbol with the header
+        let text = "1".repeat(16 * 35);
+        let symbols = encode_split(&text, 1, ErrorCorrectionLevel::Low).unwrap();
+        assert_eq!(symbols.len(), 16);
+
+        let text = "1".repeat(16 * 35 + 1);
+        assert_eq!(
+            encode_split(&text, 1, ErrorCorrectionLevel::Low).err(),
+            Some(QrError::DataTooLong)
+        );
+    }
+
+    #[test]
+    fn version_above_qr_code() {
+        let text = "A".repeat(300);
+        let symbols = encode_split(&text, 10, ErrorCorrectionLevel::Low).unwrap();
+        let len = symbols.len();
+        assert!(len > 1);
+        let mut count = 0;
+        for qr_code in symbols {
+            assert_eq!(qr_code.unwrap().version().unwrap().number(), 4);
+            count += 1;
+        }
+        assert_eq!(count, len);
+
+        assert_eq!(
+            encode_split("A", 41, ErrorCorrectionLevel::Low).err(),
+            Some(QrError::UnsupportedVersion { version: 41 })
+        );
+        assert_eq!(
+            encode_split("A", 0, ErrorCorrectionLevel::Low).err(),
+            Some(QrError::DataTooLong)
+        );
+    }
+}
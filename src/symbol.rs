@@ -0,0 +1,119 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::array_2d::Coordinate;
+use crate::decoder::{decode_with, DecodeError, Decoded};
+use crate::error::QrError;
+use crate::error_correction::ErrorCorrectionLevel;
+use crate::format::FormatInfoCheck;
+use crate::logo::LogoArea;
+use crate::mask::Mask;
+use crate::matrix::{read_format, Color, ModuleKind};
+use crate::micro;
+use crate::qr_version::{version_to_size, Version};
+
+/// Read access to the modules of a QR code, implemented by [`QrCode`](crate::QrCode) and
+/// `DynQrCode`
+///
+/// The renderers, the draw iterator and the packed rows only read the modules through this trait,
+/// so they work the same for both.
+///
+/// # Example
+///```
+///     use tiny_qr::{Color, QrCodeBuilder, Symbol};
+///
+///     fn dark_count(symbol: &impl Symbol) -> usize {
+///         let width = symbol.width();
+///         (0..width * width)
+///             .filter(|i| symbol.module(i % width, i / width) == Color::Black)
+///             .count()
+///     }
+///
+///     let qr_code = QrCodeBuilder::new().with_text("HELLO").build();
+///     assert!(dark_count(&qr_code) > 0);
+///```
+pub trait Symbol {
+    /// Returns the number of modules per row and column, without the quiet zone
+    fn width(&self) -> usize;
+
+    /// Returns the color of the module at column `x` and row `y`
+    fn module(&self, x: usize, y: usize) -> Color;
+
+    /// Returns the version that was selected, `None` for a Micro QR code
+    fn version(&self) -> Option<Version>;
+
+    fn error_correction(&self) -> ErrorCorrectionLevel;
+
+    /// Returns the mask that was applied, in the Micro QR numbering for a Micro QR code
+    fn mask_reference(&self) -> Mask;
+}
+
+/// Returns an iterator over the column, row and color of every module, row by row
+pub(crate) fn modules<Q: Symbol>(symbol: &Q) -> impl Iterator<Item = (usize, usize, Color)> + '_ {
+    let width = symbol.width();
+    (0..width).flat_map(move |y| (0..width).map(move |x| (x, y, symbol.module(x, y))))
+}
+
+/// Returns the function of the module at column `x` and row `y` of a symbol of the width
+pub(crate) fn module_kind(width: usize, x: usize, y: usize) -> ModuleKind {
+    assert!(x < width && y < width);
+    if width < version_to_size(1) {
+        micro::module_kind(width, y, x)
+    } else {
+        ModuleKind::of(
+            Version {
+                version: ((width - 17) / 4) as u8,
+            },
+            y,
+            x,
+        )
+    }
+}
+
+/// Reads both copies of the format information
+pub(crate) fn format_info(symbol: &impl Symbol) -> FormatInfoCheck {
+    let width = symbol.width();
+    // The positions are a row and a column
+    read_format(Coordinate::new(width, width), |pos| {
+        symbol.module(pos.y, pos.x)
+    })
+}
+
+/// Decodes the symbol with buffers of `C` codewords and compares it with the text, the version,
+/// the error correction level and the mask
+pub(crate) fn verify<const C: usize>(symbol: &impl Symbol, text: &str) -> Result<(), DecodeError> {
+    let decoded: Decoded<C> = decode_with(symbol.width(), |row, column| {
+        symbol.module(column, row) == Color::Black
+    })?;
+    let matches = decoded.text() == text
+        && Some(decoded.version()) == symbol.version()
+        && decoded.error_correction() == symbol.error_correction()
+        && decoded.mask() == symbol.mask_reference()
+        && format_info(symbol).matches(symbol.error_correction(), symbol.mask_reference())
+        && decoded.corrected_codewords() == 0;
+    if matches {
+        Ok(())
+    } else {
+        Err(DecodeError::Mismatch)
+    }
+}
+
+/// Plans a centered logo, Micro QR codes are too small for one
+pub(crate) fn logo_area(symbol: &impl Symbol, percent: u8) -> Result<LogoArea, QrError> {
+    let version = symbol.version().ok_or(QrError::LogoTooLarge)?;
+    LogoArea::plan(version, symbol.error_correction(), percent)
+}
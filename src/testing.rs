@@ -0,0 +1,312 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Known-good reference symbols for testing encoders and renderers
+//!
+//! Every reference has been checked against an independent encoder. Downstream users can build the
+//! symbols with their own configuration and compare the result using [`verify_against_reference`].
+//!
+//! # Example
+//!```
+//!     use tiny_qr::testing::{verify_against_reference, REFERENCE_SYMBOLS};
+//!     for reference in REFERENCE_SYMBOLS {
+//!         assert_eq!(verify_against_reference(reference, &reference.build()), Ok(()));
+//!     }
+//!```
+
+use crate::error_correction::ErrorCorrectionLevel;
+use crate::mask::Mask;
+use crate::matrix::Color;
+use crate::qrcode::{QrCode, QrCodeBuilder, MAX_MODULE_SIZE};
+use crate::symbol::Symbol;
+
+/// A symbol with all encoding parameters fixed and the expected modules
+#[derive(Debug)]
+pub struct ReferenceSymbol {
+    pub text: &'static str,
+    pub version: u8,
+    pub error_correction: ErrorCorrectionLevel,
+    pub mask_reference: Mask,
+    /// One string per row, `█` is a black module and `_` is a white module
+    pub rows: &'static [&'static str],
+}
+
+impl ReferenceSymbol {
+    /// Builds the symbol with the parameters of the reference
+    pub fn build(&self) -> QrCode<MAX_MODULE_SIZE> {
+        QrCodeBuilder::new()
+            .with_specific_version(self.version)
+            .with_specific_error_correction_level(self.error_correction)
+            .with_mask_reference(self.mask_reference)
+            .with_text(self.text)
+            .build()
+    }
+}
+
+/// The first difference between a symbol and a reference
+#[derive(Debug, PartialEq)]
+pub enum Mismatch {
+    Size {
+        expected: usize,
+        actual: usize,
+    },
+    /// A row of the reference doesn't have the width of the symbol
+    RowSize {
+        row: usize,
+        expected: usize,
+        actual: usize,
+    },
+    /// The reference contains a character other than `█` and `_`
+    InvalidCharacter {
+        row: usize,
+        column: usize,
+        character: char,
+    },
+    Module {
+        row: usize,
+        column: usize,
+        expected: Color,
+    },
+}
+
+/// Compares every module of the QR code with the reference
+pub fn verify_against_reference(
+    reference: &ReferenceSymbol,
+    qr_code: &impl Symbol,
+) -> Result<(), Mismatch> {
+    let size = qr_code.width();
+    if reference.rows.len() != size {
+        return Err(Mismatch::Size {
+            expected: reference.rows.len(),
+            actual: size,
+        });
+    }
+    for (row, expected_row) in reference.rows.iter().enumerate() {
+        let row_size = expected_row.chars().count();
+        if row_size != size {
+            return Err(Mismatch::RowSize {
+                row,
+                expected: row_size,
+                actual: size,
+            });
+        }
+        for (column, character) in expected_row.chars().enumerate() {
+            let expected = match character {
+                '█' => Color::Black,
+                '_' => Color::White,
+                character => {
+                    return Err(Mismatch::InvalidCharacter {
+                        row,
+                        column,
+                        character,
+                    })
+                }
+            };
+            if expected != qr_code.module(column, row) {
+                return Err(Mismatch::Module {
+                    row,
+                    column,
+                    expected,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+pub const REFERENCE_SYMBOLS: &[ReferenceSymbol] = &[
+    // Numeric example from ISO/IEC 18004 annex I
+    ReferenceSymbol {
+        text: "01234567",
+        version: 1,
+        error_correction: ErrorCorrectionLevel::Medium,
+        mask_reference: Mask::Mask2,
+        rows: &[
+            "███████__█_██_███████",
+            "█_____█__████_█_____█",
+            "█_███_█_█_____█_███_█",
+            "█_███_█_██____█_███_█",
+            "█_███_█_█_███_█_███_█",
+            "█_____█_█___█_█_____█",
+            "███████_█_█_█_███████",
+            "________█__██________",
+            "█_█████__█__█_█████__",
+            "___█_█_██_█_█__█_██__",
+            "__█___██_█_█_█__█████",
+            "____█____█_____████__",
+            "___██████__█_█__█____",
+            "________█_█████__██__",
+            "███████__██_█_██_____",
+            "█_____█_█_█████___█_█",
+            "█_███_█_█___█__█_██__",
+            "█_███_█_██__█__█_____",
+            "█_███_█_█_██_█__█_█__",
+            "█_____█________██_██_",
+            "███████_████_█__█_█__",
+        ],
+    },
+    // Alphanumeric version 1 example
+    ReferenceSymbol {
+        text: "HELLO WORLD",
+        version: 1,
+        error_correction: ErrorCorrectionLevel::Quartile,
+        mask_reference: Mask::Mask6,
+        rows: &[
+            "███████____█__███████",
+            "█_____█_██__█_█_____█",
+            "█_███_█__█_██_█_███_█",
+            "█_███_█_█████_█_███_█",
+            "█_███_█_██_█__█_███_█",
+            "█_____█__█__█_█_____█",
+            "███████_█_█_█_███████",
+            "________██_██________",
+            "_█_████_██__███_██_█_",
+            "█_████_█____████_███_",
+            "__█_█_██___█__██_____",
+            "█_██_█___█_██___██___",
+            "██_████████_███_█████",
+            "________█___█__█_█___",
+            "███████__██__██__████",
+            "█_____█_█_█__█__█_███",
+            "█_███_█_██_█__█___███",
+            "█_███_█_█_███___█_█__",
+            "█_███_█__█____█____██",
+            "█_____█_███__███__██_",
+            "███████__█_█_______█_",
+        ],
+    },
+    // Alphanumeric version 2, includes an alignment pattern
+    ReferenceSymbol {
+        text: "HTTPS://CASPERMEIJN.NL",
+        version: 2,
+        error_correction: ErrorCorrectionLevel::Quartile,
+        mask_reference: Mask::Mask6,
+        rows: &[
+            "███████____█████__███████",
+            "█_____█_█_█_████__█_____█",
+            "█_███_█__█___██___█_███_█",
+            "█_███_█_██__█_███_█_███_█",
+            "█_███_█_█_██_█_█__█_███_█",
+            "█_____█___█_███_█_█_____█",
+            "███████_█_█_█_█_█_███████",
+            "________█_█___█__________",
+            "_█_████_███████████_██_█_",
+            "█_█_█__█_████_█_█_█████__",
+            "███_█_█_██_██_██__█_____█",
+            "█______███_██_█_█_█_████_",
+            "██_█__██_█_████_█__█_███_",
+            "█___██_████_██___█_█_█___",
+            "██____███_█__███_███_█___",
+            "█____█_██______██___█_███",
+            "█_██__█_█_██_██_█████_███",
+            "________█___█___█___██___",
+            "███████__█__██__█_█_█_███",
+            "█_____█_█_█____██___████_",
+            "█_███_█_███__████████_█__",
+            "█_███_█_█_____█_█_█___█_█",
+            "█_███_█__█_█___██____████",
+            "█_____█_█_███_█████_█_██_",
+            "███████__██████_____██_██",
+        ],
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::error_correction::ErrorCorrectionLevel;
+    use crate::mask::Mask;
+    use crate::qrcode::QrCodeBuilder;
+    use crate::testing::{verify_against_reference, Mismatch, ReferenceSymbol, REFERENCE_SYMBOLS};
+    use alloc::boxed::Box;
+
+    #[test]
+    fn references_match() {
+        for reference in REFERENCE_SYMBOLS {
+            assert_eq!(reference.rows.len(), 17 + 4 * reference.version as usize);
+            assert_eq!(
+                verify_against_reference(reference, &reference.build()),
+                Ok(())
+            );
+        }
+    }
+
+    #[test]
+    fn detects_mismatch() {
+        let reference = &REFERENCE_SYMBOLS[0];
+        let other_mask = QrCodeBuilder::new()
+            .with_specific_version(1)
+            .with_specific_error_correction_level(ErrorCorrectionLevel::Medium)
+            .with_mask_reference(Mask::Mask3)
+            .with_text(reference.text)
+            .build();
+        assert!(matches!(
+            verify_against_reference(reference, &other_mask),
+            Err(Mismatch::Module { .. })
+        ));
+
+        let other_version = QrCodeBuilder::new()
+            .with_specific_version(2)
+            .with_text(reference.text)
+            .build();
+        assert_eq!(
+            verify_against_reference(reference, &other_version),
+            Err(Mismatch::Size {
+                expected: 21,
+                actual: 25
+            })
+        );
+    }
+
+    #[test]
+    fn detects_malformed_reference() {
+        let reference = &REFERENCE_SYMBOLS[0];
+        let qr_code = reference.build();
+        let malformed = |row: &'static str| {
+            let mut rows = reference.rows.to_vec();
+            rows[3] = row;
+            ReferenceSymbol {
+                rows: Box::leak(rows.into_boxed_slice()),
+                ..*reference
+            }
+        };
+
+        assert_eq!(
+            verify_against_reference(&malformed("█_███_█_██____█_███_"), &qr_code),
+            Err(Mismatch::RowSize {
+                row: 3,
+                expected: 20,
+                actual: 21
+            })
+        );
+        assert_eq!(
+            verify_against_reference(&malformed("█_███_█_██____█_███_█_"), &qr_code),
+            Err(Mismatch::RowSize {
+                row: 3,
+                expected: 22,
+                actual: 21
+            })
+        );
+        assert_eq!(
+            verify_against_reference(&malformed("█_███_█_██__ _█_███_█"), &qr_code),
+            Err(Mismatch::InvalidCharacter {
+                row: 3,
+                column: 12,
+                character: ' '
+            })
+        );
+    }
+}
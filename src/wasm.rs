@@ -0,0 +1,113 @@
+/* Copyright (C) 2021 Casper Meijn <casper@meijn.net>
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! JavaScript bindings, so the crate can power a web page next to the firmware
+//!
+//! Build with `wasm-pack build --features wasm` and import the functions from the generated
+//! package:
+//!
+//! ```js
+//! import { generate, generate_svg } from "tiny-qr";
+//! const modules = generate("HELLO", "M");
+//! const width = Math.sqrt(modules.length);
+//! document.body.innerHTML = generate_svg("HELLO", "M");
+//! ```
+
+use crate::render::SvgOptions;
+use crate::{Color, DynQrCode, ErrorCorrectionLevel, QrCodeBuilder};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use wasm_bindgen::prelude::*;
+
+/// Builds the QR code on the heap, so every version up to 40 is available to the web page
+fn build(text: &str, error_correction: &str) -> Result<DynQrCode, JsValue> {
+    let error_correction: ErrorCorrectionLevel = error_correction
+        .parse()
+        .map_err(|_| JsValue::from_str("invalid error correction level"))?;
+    QrCodeBuilder::new()
+        .with_specific_error_correction_level(error_correction)
+        .with_text(text)
+        .try_build_dyn()
+        .map_err(|error| JsValue::from_str(&error.to_string()))
+}
+
+/// Generates the modules row by row, 1 for dark and 0 for light, as a `Uint8Array`
+///
+/// The error correction level is one of `L`, `M`, `Q` and `H`. The symbol is square, so the width
+/// is the square root of the length.
+#[wasm_bindgen]
+pub fn generate(text: &str, error_correction: &str) -> Result<Vec<u8>, JsValue> {
+    let qr_code = build(text, error_correction)?;
+    Ok(qr_code
+        .modules()
+        .map(|(_, _, color)| u8::from(color == Color::Black))
+        .collect())
+}
+
+/// Generates an SVG document including the quiet zone
+#[wasm_bindgen]
+pub fn generate_svg(text: &str, error_correction: &str) -> Result<String, JsValue> {
+    let qr_code = build(text, error_correction)?;
+    let mut svg = String::new();
+    qr_code
+        .render(&SvgOptions::new(), &mut svg)
+        .map_err(|_| JsValue::from_str("formatting failed"))?;
+    Ok(svg)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::wasm::{generate, generate_svg};
+    use crate::{Color, ErrorCorrectionLevel, QrCodeBuilder};
+    use alloc::vec::Vec;
+
+    // Errors create a `JsValue`, which only works on wasm targets, so only success is tested
+
+    #[test]
+    fn modules() {
+        let modules = generate("HELLO", "Q").unwrap();
+        let expected: Vec<u8> = QrCodeBuilder::new()
+            .with_specific_error_correction_level(ErrorCorrectionLevel::Quartile)
+            .with_text("HELLO")
+            .build()
+            .modules()
+            .map(|(_, _, color)| u8::from(color == Color::Black))
+            .collect();
+        assert_eq!(modules, expected);
+        assert_eq!(modules.len(), 21 * 21);
+    }
+
+    #[test]
+    fn large_version() {
+        let text = "HELLO ".repeat(100);
+        let modules = generate(&text, "M").unwrap();
+        let qr_code = QrCodeBuilder::new()
+            .with_specific_error_correction_level(ErrorCorrectionLevel::Medium)
+            .with_text(&text)
+            .build_dyn();
+        assert!(qr_code.width() > 33);
+        assert_eq!(modules.len(), qr_code.width() * qr_code.width());
+        let svg = generate_svg(&text, "M").unwrap();
+        assert!(svg.starts_with("<svg"));
+    }
+
+    #[test]
+    fn svg() {
+        let svg = generate_svg("HELLO", "l").unwrap();
+        assert!(svg.starts_with("<svg"));
+    }
+}